@@ -0,0 +1,30 @@
+@group(1) @binding(0)
+var texture: texture_2d<f32>;
+@group(1) @binding(1)
+var texture_sampler: sampler;
+// x: scanline intensity, y: vignette strength, z: chromatic aberration, w: time
+@group(1) @binding(2)
+var<uniform> params: vec4<f32>;
+
+@fragment
+fn fragment(
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    // chromatic aberration splits the red and blue channels horizontally
+    let offset = vec2<f32>(params.z * 0.008, 0.0);
+    let r = textureSample(texture, texture_sampler, uv + offset).r;
+    let g = textureSample(texture, texture_sampler, uv).g;
+    let b = textureSample(texture, texture_sampler, uv - offset).b;
+    var color = vec3<f32>(r, g, b);
+
+    // crt scanlines, one dark line every other pixel on a 720px target
+    let scanline = 0.5 + 0.5 * sin((uv.y * 360.0 + params.w * 2.0) * 6.28318);
+    color = color * (1.0 - params.x * scanline);
+
+    // vignette darkens the corners
+    let centered = uv - vec2<f32>(0.5, 0.5);
+    let vignette = 1.0 - params.y * dot(centered, centered) * 2.0;
+    color = color * clamp(vignette, 0.0, 1.0);
+
+    return vec4<f32>(color, 1.0);
+}
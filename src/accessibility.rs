@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::AccessibilitySettings,
+    preload::Preloaded,
+    sfx::{PlaySfx, Sfx},
+};
+
+/// how long a sound cue stays on screen
+const CUE_SECONDS: f32 = 0.8;
+/// how long a menu item is captioned when it can't be spoken
+const ANNOUNCE_SECONDS: f32 = 2.0;
+const CUE_FONT_SIZE: f32 = 18.0;
+const ANNOUNCE_FONT_SIZE: f32 = 24.0;
+
+pub struct AccessibilityPlugin;
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_non_send_resource(ScreenReader::new())
+            .add_startup_system(spawn_cue_container)
+            .add_systems((show_sound_cues, announce_menu_items, fade_sound_cues).chain())
+            .add_system(apply_ui_scale.run_if(resource_changed::<AccessibilitySettings>()));
+    }
+}
+
+/// What a screen reader says for a menu item. It's read out when spawned or
+/// changed while `selected`, so menus should only touch it when the text or
+/// selection really changes.
+#[derive(Component, Clone, Default, Debug, PartialEq, Eq)]
+pub struct MenuLabel {
+    pub text: String,
+    pub selected: bool,
+}
+
+impl MenuLabel {
+    pub fn new(text: impl Into<String>, selected: bool) -> Self {
+        MenuLabel {
+            text: text.into(),
+            selected,
+        }
+    }
+}
+
+/// Speaks through the system's text to speech when built with the `tts`
+/// feature, otherwise menu items are captioned on screen.
+struct ScreenReader {
+    #[cfg(feature = "tts")]
+    tts: Option<tts::Tts>,
+}
+
+impl ScreenReader {
+    fn new() -> Self {
+        ScreenReader {
+            #[cfg(feature = "tts")]
+            tts: tts::Tts::default()
+                .map_err(|e| warn!("text to speech unavailable: {e}"))
+                .ok(),
+        }
+    }
+
+    /// false when there's nothing to speak with
+    fn speak(&mut self, text: &str) -> bool {
+        #[cfg(feature = "tts")]
+        if let Some(tts) = &mut self.tts {
+            return tts.speak(text, true).is_ok();
+        }
+        let _ = text;
+        false
+    }
+}
+
+/// column near the top of the screen captions are added to
+#[derive(Component)]
+struct SoundCueContainer;
+
+/// caption shown while a sound plays, for players who can't hear it
+#[derive(Component)]
+struct SoundCue(Timer);
+
+/// `None` for sounds too frequent to caption
+fn caption(sfx: Sfx) -> Option<&'static str> {
+    match sfx {
+        Sfx::Jump => Some("[jump]"),
+        Sfx::Goal => Some("[goal collected]"),
+        Sfx::Death => Some("[death]"),
+        Sfx::JumpPad => Some("[spring]"),
+        Sfx::BlockFell => Some("[block falling]"),
+        Sfx::Explosion => Some("[explosion]"),
+        Sfx::Land(_) => Some("[thud]"),
+        Sfx::Footstep(_) => None,
+    }
+}
+
+fn spawn_cue_container(mut commands: Commands) {
+    commands.spawn((
+        SoundCueContainer,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(40.0),
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn spawn_caption(
+    commands: &mut Commands,
+    container: Entity,
+    preloaded: &Preloaded,
+    text: &str,
+    seconds: f32,
+    font_size: f32,
+) {
+    let cue = commands
+        .spawn((
+            SoundCue(Timer::from_seconds(seconds, TimerMode::Once)),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: preloaded.get("font"),
+                    font_size,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ),
+        ))
+        .id();
+    commands.entity(container).add_child(cue);
+}
+
+fn show_sound_cues(
+    mut commands: Commands,
+    mut events: EventReader<PlaySfx>,
+    settings: Res<AccessibilitySettings>,
+    preloaded: Res<Preloaded>,
+    container: Query<Entity, With<SoundCueContainer>>,
+) {
+    if !settings.sound_cues {
+        events.clear();
+        return;
+    }
+    let Ok(container) = container.get_single() else {
+        return;
+    };
+
+    for event in &mut events {
+        let Some(caption) = caption(event.sfx) else {
+            continue;
+        };
+        spawn_caption(
+            &mut commands,
+            container,
+            &preloaded,
+            caption,
+            CUE_SECONDS,
+            CUE_FONT_SIZE,
+        );
+    }
+}
+
+fn announce_menu_items(
+    mut commands: Commands,
+    mut reader: NonSendMut<ScreenReader>,
+    settings: Res<AccessibilitySettings>,
+    preloaded: Res<Preloaded>,
+    items: Query<&MenuLabel, Changed<MenuLabel>>,
+    container: Query<Entity, With<SoundCueContainer>>,
+) {
+    if !settings.screen_reader {
+        return;
+    }
+    for item in items.iter().filter(|item| item.selected) {
+        if reader.speak(&item.text) {
+            continue;
+        }
+        let Ok(container) = container.get_single() else {
+            continue;
+        };
+        spawn_caption(
+            &mut commands,
+            container,
+            &preloaded,
+            &item.text,
+            ANNOUNCE_SECONDS,
+            ANNOUNCE_FONT_SIZE,
+        );
+    }
+}
+
+/// scales every UI node and its text, for players who need larger text
+fn apply_ui_scale(settings: Res<AccessibilitySettings>, mut ui_scale: ResMut<UiScale>) {
+    let scale = settings.ui_scale.clamp(0.5, 3.0) as f64;
+    if ui_scale.scale != scale {
+        ui_scale.scale = scale;
+    }
+}
+
+fn fade_sound_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cues: Query<(Entity, &mut SoundCue, &mut Text)>,
+) {
+    for (e, mut cue, mut text) in &mut cues {
+        if cue.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        for section in &mut text.sections {
+            section.style.color.set_a(cue.0.percent_left());
+        }
+    }
+}
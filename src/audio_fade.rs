@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{config::AudioConfig, game_state::GameState, sfx::AudioBuses};
+
+/// sounds that aren't fading are forgotten after this long, bevy's sinks
+/// can't tell when they've finished playing
+const MAX_TRACKED_SECONDS: f32 = 4.0;
+
+pub struct AudioFadePlugin;
+impl Plugin for AudioFadePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioFades>()
+            .add_event::<QuitRequested>()
+            // exit at the start of a frame so everything that reacts to
+            // `AppExit`, like saving, sees it before the app closes
+            .add_system(finish_quit.in_base_set(CoreSet::PreUpdate))
+            .add_systems((fade_on_state_exit, start_quit_fade, step_fades).chain());
+    }
+}
+
+/// Send to leave the game. Audio fades out before the app actually exits.
+pub struct QuitRequested;
+
+struct TrackedSink {
+    sink: Handle<AudioSink>,
+    /// the sound fades out when this state is exited
+    owner: GameState,
+    /// volume the sound was started at
+    volume: f32,
+    lifetime: Timer,
+    fade: Option<Timer>,
+}
+
+/// sounds that should fade out rather than be cut off
+#[derive(Resource, Default)]
+pub struct AudioFades {
+    sinks: Vec<TrackedSink>,
+    quitting: Option<Timer>,
+}
+
+impl AudioFades {
+    /// fade `sink` out when `owner` is exited
+    pub fn track(&mut self, sink: Handle<AudioSink>, owner: GameState, volume: f32) {
+        self.sinks.push(TrackedSink {
+            sink,
+            owner,
+            volume,
+            lifetime: Timer::from_seconds(MAX_TRACKED_SECONDS, TimerMode::Once),
+            fade: None,
+        });
+    }
+
+    fn fade_out(&mut self, duration: Duration, exited: impl Fn(&GameState) -> bool) {
+        for tracked in &mut self.sinks {
+            if tracked.fade.is_none() && exited(&tracked.owner) {
+                tracked.fade = Some(Timer::new(duration, TimerMode::Once));
+            }
+        }
+    }
+}
+
+fn fade_seconds(config: &AudioConfig) -> Duration {
+    Duration::from_secs_f32(config.fade_seconds.max(0.0))
+}
+
+fn fade_on_state_exit(
+    state: Res<State<GameState>>,
+    config: Res<AudioConfig>,
+    mut fades: ResMut<AudioFades>,
+) {
+    if state.is_changed() {
+        fades.fade_out(fade_seconds(&config), |owner| *owner != state.0);
+    }
+}
+
+fn start_quit_fade(
+    config: Res<AudioConfig>,
+    mut fades: ResMut<AudioFades>,
+    mut quit: EventReader<QuitRequested>,
+) {
+    let requested = !quit.is_empty();
+    quit.clear();
+    if !requested || fades.quitting.is_some() {
+        return;
+    }
+
+    let duration = fade_seconds(&config);
+    fades.fade_out(duration, |_| true);
+    fades.quitting = Some(Timer::new(duration, TimerMode::Once));
+}
+
+/// Turns down the tracked sounds, and everything on the buses while quitting.
+/// Uses the raw delta so slowed or paused time doesn't hold up a fade.
+fn step_fades(
+    time: Res<Time>,
+    mut fades: ResMut<AudioFades>,
+    mut buses: ResMut<AudioBuses>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    fades.sinks.retain_mut(|tracked| {
+        let Some(sink) = audio_sinks.get(&tracked.sink) else {
+            return false;
+        };
+        let Some(fade) = tracked.fade.as_mut() else {
+            return !tracked.lifetime.tick(time.raw_delta()).finished();
+        };
+        if fade.tick(time.raw_delta()).finished() {
+            sink.stop();
+            return false;
+        }
+        sink.set_volume(tracked.volume * fade.percent_left());
+        true
+    });
+
+    if let Some(quitting) = fades.quitting.as_mut() {
+        quitting.tick(time.raw_delta());
+        buses.fade = quitting.percent_left();
+    }
+}
+
+fn finish_quit(fades: Res<AudioFades>, mut exit: EventWriter<AppExit>) {
+    if fades.quitting.as_ref().is_some_and(Timer::finished) {
+        exit.send(AppExit);
+    }
+}
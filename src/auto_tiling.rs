@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::LayerDefinition;
+
+/// pattern value matching any int grid value
+const ANY_VALUE: i32 = 1000001;
+
+/// tile an auto layer rule places in a cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoTile {
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+#[derive(Clone)]
+struct Rule {
+    /// `size` x `size` int grid values, rows from the top like in ldtk
+    pattern: Vec<i32>,
+    size: i32,
+    tile: u32,
+    flip_x: bool,
+    flip_y: bool,
+    /// value cells outside the level count as, `None` and the rule can't match there
+    out_of_bounds: Option<i32>,
+}
+
+/// Picks tiles the way ldtk's auto layer rules do, so ground changed while
+/// playing matches what ldtk drew. Rules are tried in order and the first one
+/// that matches wins. Chance, modulo and perlin settings aren't mirrored, a
+/// rule with several tiles always places its first.
+pub struct AutoTiler {
+    rules: Vec<Rule>,
+}
+
+impl AutoTiler {
+    pub fn from_layer(layer: &LayerDefinition) -> Self {
+        let rules = layer
+            .auto_rule_groups
+            .iter()
+            .filter(|group| group.active)
+            .flat_map(|group| &group.rules)
+            .filter(|rule| rule.active)
+            .filter_map(|rule| {
+                Some(Rule {
+                    pattern: rule.pattern.clone(),
+                    size: rule.size,
+                    tile: u32::try_from(*rule.tile_ids.first()?).ok()?,
+                    flip_x: rule.flip_x,
+                    flip_y: rule.flip_y,
+                    out_of_bounds: rule.out_of_bounds_value,
+                })
+            })
+            .collect();
+        AutoTiler { rules }
+    }
+
+    /// furthest from a cell any rule looks, cells this close to a change may
+    /// need a different tile
+    pub fn reach(&self) -> i32 {
+        self.rules
+            .iter()
+            .map(|rule| rule.size / 2)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Tile for `cell`, `None` if no rule places one. `value` gives the int
+    /// grid value of a cell, 0 for empty and `None` outside the level. Cells
+    /// are grid coords, so y is up.
+    pub fn resolve(&self, cell: IVec2, value: impl Fn(IVec2) -> Option<i32>) -> Option<AutoTile> {
+        self.rules.iter().find_map(|rule| {
+            // ldtk tries the rule as it is first, then mirrored
+            let flips = [(false, false), (true, false), (false, true), (true, true)];
+            flips
+                .into_iter()
+                .filter(|&(x, y)| (!x || rule.flip_x) && (!y || rule.flip_y))
+                .find(|&(x, y)| rule.matches(cell, x, y, &value))
+                .map(|(flip_x, flip_y)| AutoTile {
+                    index: rule.tile,
+                    flip_x,
+                    flip_y,
+                })
+        })
+    }
+}
+
+impl Rule {
+    fn matches(
+        &self,
+        cell: IVec2,
+        flip_x: bool,
+        flip_y: bool,
+        value: &impl Fn(IVec2) -> Option<i32>,
+    ) -> bool {
+        let radius = self.size / 2;
+        self.pattern.iter().enumerate().all(|(i, &expected)| {
+            if expected == 0 {
+                return true;
+            }
+            let mut offset = IVec2::new(i as i32 % self.size, i as i32 / self.size) - radius;
+            if flip_x {
+                offset.x = -offset.x;
+            }
+            if flip_y {
+                offset.y = -offset.y;
+            }
+            // pattern rows go down, grid coords go up
+            let neighbor = cell + IVec2::new(offset.x, -offset.y);
+            let Some(actual) = value(neighbor).or(self.out_of_bounds) else {
+                return false;
+            };
+            match expected {
+                ANY_VALUE => actual != 0,
+                v if v == -ANY_VALUE => actual == 0,
+                v if v > 0 => actual == v,
+                v => actual != -v,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy::prelude::*;
+
+    use super::{AutoTile, AutoTiler, Rule};
+
+    fn rule(pattern: [i32; 9], tile: u32) -> Rule {
+        Rule {
+            pattern: pattern.to_vec(),
+            size: 3,
+            tile,
+            flip_x: false,
+            flip_y: false,
+            out_of_bounds: Some(1),
+        }
+    }
+
+    fn tile(index: u32) -> Option<AutoTile> {
+        Some(AutoTile {
+            index,
+            flip_x: false,
+            flip_y: false,
+        })
+    }
+
+    /// a 4x2 level, `#` is ground and the first row is the top
+    fn value(rows: [&str; 2]) -> impl Fn(IVec2) -> Option<i32> {
+        let mut ground = HashSet::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    ground.insert(IVec2::new(x as i32, 1 - y as i32));
+                }
+            }
+        }
+        move |cell| {
+            let inside = cell.cmpge(IVec2::ZERO).all() && cell.cmplt(IVec2::new(4, 2)).all();
+            inside.then(|| ground.contains(&cell) as i32)
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let tiler = AutoTiler {
+            rules: vec![
+                // ground with nothing above
+                rule([0, -1, 0, 0, 1, 0, 0, 0, 0], 1),
+                rule([0, 0, 0, 0, 1, 0, 0, 0, 0], 2),
+            ],
+        };
+        let value = value(["#...", "##.."]);
+        assert_eq!(tiler.resolve(IVec2::new(0, 1), &value), tile(2));
+        assert_eq!(tiler.resolve(IVec2::new(1, 0), &value), tile(1));
+        assert_eq!(tiler.resolve(IVec2::new(2, 0), &value), None);
+    }
+
+    #[test]
+    fn out_of_bounds_value() {
+        // ground with nothing to the left
+        let mut edge = rule([0, 0, 0, -1, 1, 0, 0, 0, 0], 1);
+        let value = value(["....", "#.#."]);
+        let resolve = |edge: &Rule, cell| {
+            let rules = vec![edge.clone()];
+            AutoTiler { rules }.resolve(cell, &value)
+        };
+        // the level's edge counts as ground
+        assert_eq!(resolve(&edge, IVec2::new(0, 0)), None);
+        assert_eq!(resolve(&edge, IVec2::new(2, 0)), tile(1));
+
+        edge.out_of_bounds = Some(0);
+        assert_eq!(resolve(&edge, IVec2::new(0, 0)), tile(1));
+    }
+
+    #[test]
+    fn mirrored_rules_flip_the_tile() {
+        let mut right_edge = rule([0, 0, 0, 0, 1, -1, 0, 0, 0], 1);
+        right_edge.flip_x = true;
+        let tiler = AutoTiler {
+            rules: vec![right_edge],
+        };
+        let value = value(["....", ".##."]);
+        assert_eq!(tiler.resolve(IVec2::new(2, 0), &value), tile(1));
+        assert_eq!(
+            tiler.resolve(IVec2::new(1, 0), &value),
+            Some(AutoTile {
+                index: 1,
+                flip_x: true,
+                flip_y: false,
+            })
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    collisions::{CollisionData, CollisionEvents, CollisionSets, RectBundle},
+    constants::CollisionTypes,
+    floating_text::FloatingText,
+    game_state::GameState,
+    gravity_zones::{GravityZone, ZoneSize},
+    ground::drop_falling_block,
+    level_registry::field,
+    physics::{GravityDirection, PhysicsSettings, Velocity},
+    player::{DeathCause, Player, PlayerDeath},
+    preload::Preloaded,
+    turrets::{spawn_projectile, PROJECTILE_SIZE, PROJECTILE_SPEED},
+};
+
+const DEFAULT_HEALTH: u32 = 6;
+/// how long the boss flashes and can't be hurt again after a hit
+const HIT_SECONDS: f32 = 0.6;
+/// how far above the player dropped blocks start
+const DROP_HEIGHT: f32 = 200.0;
+/// size and lifetime of the zone that stops the player's gravity turning
+const LOCK_ZONE_SIZE: f32 = 120.0;
+const LOCK_ZONE_SECONDS: f32 = 3.0;
+const HEALTH_BAR_WIDTH: f32 = 300.0;
+/// the boss's shots are slower than a turret's since they're aimed
+const SHOT_SPEED: f32 = PROJECTILE_SPEED * 0.75;
+
+pub struct BossPlugin;
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<BossBundle>("Boss")
+            .add_systems(
+                (clear_attacks, after_boss_spawned).in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(boss_contact.in_set(CollisionSets::Consume))
+            .add_systems(
+                (
+                    boss_attacks,
+                    flash_boss,
+                    expire_lock_zones,
+                    update_health_bar,
+                )
+                    .in_set(GameState::Playing),
+            );
+    }
+}
+
+/// something the boss does to the player
+#[derive(Clone, Copy, Debug)]
+enum Attack {
+    /// drop a falling block on the player
+    DropBlock,
+    /// stop the player's gravity turning for a while
+    LockZone,
+    /// fire a projectile at where the player is
+    Shoot,
+}
+
+struct Phase {
+    /// the phase lasts while health is above this fraction of the maximum
+    above_health: f32,
+    attack_seconds: f32,
+    /// attacks used in order, looping
+    pattern: &'static [Attack],
+}
+
+static PHASES: [Phase; 3] = [
+    Phase {
+        above_health: 2.0 / 3.0,
+        attack_seconds: 3.0,
+        pattern: &[Attack::DropBlock, Attack::Shoot],
+    },
+    Phase {
+        above_health: 1.0 / 3.0,
+        attack_seconds: 2.5,
+        pattern: &[Attack::DropBlock, Attack::Shoot, Attack::LockZone],
+    },
+    Phase {
+        above_health: 0.0,
+        attack_seconds: 2.0,
+        pattern: &[
+            Attack::DropBlock,
+            Attack::Shoot,
+            Attack::LockZone,
+            Attack::Shoot,
+        ],
+    },
+];
+
+/// A large enemy that has to be landed on `max_health` times to finish the
+/// level. Touching it any other way kills the player.
+#[derive(Component)]
+pub struct Boss {
+    pub health: u32,
+    pub max_health: u32,
+    phase: usize,
+    next_attack: usize,
+    attack: Timer,
+    hit: Timer,
+}
+
+impl Default for Boss {
+    fn default() -> Self {
+        Boss::new(DEFAULT_HEALTH)
+    }
+}
+
+impl Boss {
+    fn new(health: u32) -> Self {
+        let mut hit = Timer::from_seconds(HIT_SECONDS, TimerMode::Once);
+        hit.tick(hit.duration());
+        Boss {
+            health,
+            max_health: health,
+            phase: 0,
+            next_attack: 0,
+            attack: Timer::from_seconds(PHASES[0].attack_seconds, TimerMode::Repeating),
+            hit,
+        }
+    }
+
+    fn current_phase(&self) -> &'static Phase {
+        &PHASES[self.phase]
+    }
+}
+
+/// full size of the boss, taken from the ldtk entity
+#[derive(Component, Default)]
+struct BossSize(Vec2);
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct BossBundle {
+    #[with(boss_from_instance)]
+    boss: Boss,
+    #[with(size_from_instance)]
+    size: BossSize,
+    #[sprite_bundle("boss.png")]
+    sprite: SpriteBundle,
+}
+
+fn boss_from_instance(instance: &EntityInstance) -> Boss {
+    match field(&instance.field_instances, "Health") {
+        Some(FieldValue::Int(Some(health))) if *health > 0 => Boss::new(*health as u32),
+        _ => Boss::default(),
+    }
+}
+
+fn size_from_instance(instance: &EntityInstance) -> BossSize {
+    BossSize(Vec2::new(instance.width as f32, instance.height as f32))
+}
+
+#[derive(Component)]
+struct HealthBar;
+
+#[derive(Component)]
+struct HealthBarFill;
+
+/// gravity zone spawned by an attack, removed when the timer runs out
+#[derive(Component)]
+struct LockZone(Timer);
+
+/// anything an attack spawned, cleared when the level restarts
+#[derive(Component)]
+pub struct FromAttack;
+
+fn clear_attacks(mut commands: Commands, q: Query<Entity, With<FromAttack>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn after_boss_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &BossSize, &mut Sprite), Added<Boss>>,
+    bars: Query<Entity, With<HealthBar>>,
+) {
+    for (e, size, mut sprite) in &mut q {
+        // a restarted level spawns a new boss
+        for bar in &bars {
+            commands.entity(bar).despawn_recursive();
+        }
+
+        sprite.custom_size = Some(size.0);
+        commands
+            .entity(e)
+            .insert((
+                CollisionTypes::Boss,
+                CollisionEvents::<CollisionTypes>::new(),
+            ))
+            .with_children(|children| {
+                children.spawn(RectBundle::new(size.0));
+            });
+
+        commands
+            .spawn((
+                HealthBar,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            top: Val::Px(16.0),
+                            left: Val::Px((720.0 - HEALTH_BAR_WIDTH) / 2.0),
+                            ..default()
+                        },
+                        size: Size::new(Val::Px(HEALTH_BAR_WIDTH), Val::Px(12.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                    ..default()
+                },
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    HealthBarFill,
+                    NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.8, 0.2, 0.3).into(),
+                        ..default()
+                    },
+                ));
+            });
+    }
+}
+
+/// landing on the boss hurts it, any other contact kills the player
+fn boss_contact(
+    mut commands: Commands,
+    mut player: Query<
+        (
+            &CollisionEvents<CollisionTypes>,
+            &GravityDirection,
+            &mut Velocity,
+        ),
+        With<Player>,
+    >,
+    mut bosses: Query<(&mut Boss, &GlobalTransform)>,
+    settings: Res<PhysicsSettings>,
+    mut deaths: EventWriter<PlayerDeath>,
+    mut popups: EventWriter<FloatingText>,
+) {
+    let Ok((events, g, mut v)) = player.get_single_mut() else {
+        return;
+    };
+
+    for event in &events.buffer {
+        let Ok((mut boss, boss_t)) = bosses.get_mut(event.entity) else {
+            continue;
+        };
+        let CollisionData::Aabb(ref sweep) = event.data else {
+            continue;
+        };
+        // same test ground detection uses for a floor
+        if sweep.normal.angle_between(g.reverse().as_vec2()) != 0.0 {
+            deaths.send(PlayerDeath {
+                cause: DeathCause::Enemy,
+            });
+            continue;
+        }
+
+        // bounce off either way so the player doesn't sink into it
+        v.0 = -g.as_vec2() * settings.initial_jump_speed;
+        if !boss.hit.finished() {
+            continue;
+        }
+        boss.hit.reset();
+        boss.health = boss.health.saturating_sub(1);
+        popups.send(
+            FloatingText::new("-1", boss_t.translation().truncate())
+                .with_color(Color::rgb(1.0, 0.35, 0.3)),
+        );
+        if boss.health == 0 {
+            commands.entity(event.entity).despawn_recursive();
+            continue;
+        }
+
+        let fraction = boss.health as f32 / boss.max_health as f32;
+        let phase = PHASES
+            .iter()
+            .position(|phase| fraction > phase.above_health)
+            .unwrap_or(PHASES.len() - 1);
+        if phase != boss.phase {
+            boss.phase = phase;
+            boss.next_attack = 0;
+            boss.attack =
+                Timer::from_seconds(boss.current_phase().attack_seconds, TimerMode::Repeating);
+        }
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bosses: Query<(&mut Boss, &BossSize, &Transform)>,
+    player: Query<(&Transform, &GravityDirection), With<Player>>,
+    preloaded: Res<Preloaded>,
+) {
+    let Ok((player_t, g)) = player.get_single() else {
+        return;
+    };
+
+    for (mut boss, size, boss_t) in &mut bosses {
+        if !boss.attack.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let pattern = boss.current_phase().pattern;
+        let attack = pattern[boss.next_attack % pattern.len()];
+        boss.next_attack += 1;
+
+        let target = player_t.translation.truncate();
+        match attack {
+            Attack::DropBlock => {
+                let start = target - g.as_vec2() * DROP_HEIGHT;
+                let block = drop_falling_block(
+                    &mut commands,
+                    preloaded.get("falling_block"),
+                    start.extend(boss_t.translation.z),
+                    g.0,
+                );
+                commands.entity(block).insert(FromAttack);
+            }
+            Attack::LockZone => {
+                commands.spawn((
+                    GravityZone::Locked,
+                    ZoneSize(Vec2::splat(LOCK_ZONE_SIZE)),
+                    FromAttack,
+                    LockZone(Timer::from_seconds(LOCK_ZONE_SECONDS, TimerMode::Once)),
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(0.5, 0.3, 0.7, 0.25),
+                            custom_size: Some(Vec2::splat(LOCK_ZONE_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(target.extend(boss_t.translation.z)),
+                        ..default()
+                    },
+                ));
+            }
+            Attack::Shoot => {
+                let from = boss_t.translation.truncate();
+                let aim = (target - from)
+                    .try_normalize()
+                    .unwrap_or(g.reverse().as_vec2());
+                // start clear of the boss so the shot isn't inside it
+                let start = from + aim * (size.0.max_element() / 2.0 + PROJECTILE_SIZE);
+                let shot = spawn_projectile(
+                    &mut commands,
+                    start.extend(boss_t.translation.z),
+                    aim * SHOT_SPEED,
+                    0,
+                );
+                commands.entity(shot).insert(FromAttack);
+            }
+        }
+    }
+}
+
+fn flash_boss(time: Res<Time>, mut bosses: Query<(&mut Boss, &mut Sprite)>) {
+    for (mut boss, mut sprite) in &mut bosses {
+        boss.hit.tick(time.delta());
+        sprite.color = if boss.hit.finished() {
+            Color::WHITE
+        } else {
+            Color::rgb(1.0, 0.4, 0.4)
+        };
+    }
+}
+
+fn expire_lock_zones(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut zones: Query<(Entity, &mut LockZone, &mut Sprite)>,
+) {
+    for (e, mut zone, mut sprite) in &mut zones {
+        if zone.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(0.25 * zone.0.percent_left());
+    }
+}
+
+fn update_health_bar(
+    mut commands: Commands,
+    bosses: Query<&Boss>,
+    bars: Query<Entity, With<HealthBar>>,
+    mut fills: Query<&mut Style, With<HealthBarFill>>,
+) {
+    let Some(boss) = bosses.iter().next() else {
+        for bar in &bars {
+            commands.entity(bar).despawn_recursive();
+        }
+        return;
+    };
+
+    let fraction = boss.health as f32 / boss.max_health as f32;
+    for mut style in &mut fills {
+        style.size.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collisions::{CollisionEvent, ContactResponse, Sweep};
+
+    /// a player touching `boss` from the side `normal` points to
+    fn world_with_contact(normal: Vec2) -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<PhysicsSettings>();
+        world.init_resource::<Events<PlayerDeath>>();
+        world.init_resource::<Events<FloatingText>>();
+        let boss = world
+            .spawn((Boss::default(), GlobalTransform::default()))
+            .id();
+        let mut events = CollisionEvents::new();
+        events.buffer.push(CollisionEvent {
+            entity: boss,
+            user_type: CollisionTypes::Boss,
+            data: CollisionData::Aabb(Sweep {
+                position: Vec2::ZERO,
+                time: 0.0,
+                normal,
+            }),
+            ignored: false,
+            response: ContactResponse::SOLID,
+        });
+        world.spawn((
+            Player,
+            events,
+            GravityDirection::default(),
+            Velocity(Vec2::new(0.0, -300.0)),
+        ));
+        let mut schedule = Schedule::new();
+        schedule.add_system(boss_contact);
+        schedule.run(&mut world);
+        (world, boss)
+    }
+
+    #[test]
+    fn stomping_hurts_the_boss_and_not_the_player() {
+        let (world, boss) = world_with_contact(Vec2::Y);
+        assert!(world.resource::<Events<PlayerDeath>>().is_empty());
+        assert_eq!(world.get::<Boss>(boss).unwrap().health, DEFAULT_HEALTH - 1);
+    }
+
+    #[test]
+    fn touching_its_side_kills_the_player() {
+        let (world, boss) = world_with_contact(Vec2::X);
+        assert_eq!(world.resource::<Events<PlayerDeath>>().len(), 1);
+        assert_eq!(world.get::<Boss>(boss).unwrap().health, DEFAULT_HEALTH);
+    }
+}
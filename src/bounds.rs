@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LdtkLevel;
+
+use crate::game_state::GameState;
+
+/// how far past the level edges an entity can drift before it's despawned
+const DESPAWN_MARGIN: f32 = 200.0;
+
+pub struct BoundsPlugin;
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelBounds>()
+            .add_system(update_level_bounds.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(despawn_out_of_bounds.in_base_set(CoreSet::PostUpdate));
+    }
+}
+
+/// world space rectangle covered by the current level
+#[derive(Resource)]
+pub struct LevelBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for LevelBounds {
+    fn default() -> Self {
+        LevelBounds {
+            min: Vec2::ZERO,
+            max: Vec2::new(720.0, 720.0),
+        }
+    }
+}
+
+impl LevelBounds {
+    /// whether `point` is inside the level grown by `margin` on every side
+    pub fn contains(&self, point: Vec2, margin: f32) -> bool {
+        point.cmpge(self.min - margin).all() && point.cmple(self.max + margin).all()
+    }
+}
+
+/// entities with this component are despawned once they leave the level
+#[derive(Component, Default)]
+pub struct DespawnOutOfBounds;
+
+pub fn update_level_bounds(
+    mut bounds: ResMut<LevelBounds>,
+    levels: Query<(&Handle<LdtkLevel>, &GlobalTransform)>,
+    level_assets: Res<Assets<LdtkLevel>>,
+) {
+    for (handle, t) in &levels {
+        let Some(level) = level_assets.get(handle) else {
+            continue;
+        };
+        let min = t.translation().truncate();
+        *bounds = LevelBounds {
+            min,
+            max: min + Vec2::new(level.level.px_wid as f32, level.level.px_hei as f32),
+        };
+    }
+}
+
+fn despawn_out_of_bounds(
+    mut commands: Commands,
+    bounds: Res<LevelBounds>,
+    q: Query<(Entity, &GlobalTransform), With<DespawnOutOfBounds>>,
+) {
+    for (e, t) in &q {
+        if !bounds.contains(t.translation().truncate(), DESPAWN_MARGIN) {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
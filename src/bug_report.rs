@@ -0,0 +1,418 @@
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+use bevy_turborand::GlobalRng;
+
+use crate::{
+    collisions::{Ray, Rect},
+    game_state::GameState,
+    level::LevelSequence,
+    mutators::Mutators,
+    physics::{set_gravity_direction, Direction, GravityDirection, Velocity},
+    player::Player,
+    preload::Preloaded,
+    run_mode::{plan_seeded_run, reset_run_mode, DailyChallenge, RngSeed, RogueliteRun, RunMode},
+};
+
+pub struct BugReportPlugin;
+impl Plugin for BugReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(read_restore_arg)
+            // once the font it's drawn with is loaded
+            .add_system(spawn_debug_panel.in_schedule(OnExit(GameState::Preload)))
+            .add_systems((toggle_debug_panel, update_debug_panel, copy_bug_report).chain())
+            .add_system(
+                start_restore
+                    .run_if(resource_exists::<PendingRestore>())
+                    .after(reset_run_mode)
+                    .in_schedule(OnEnter(GameState::StartMenu)),
+            )
+            // after the seeded run is planned so the restored level isn't replaced
+            .add_system(
+                restore_level
+                    .after(plan_seeded_run)
+                    .in_schedule(OnExit(GameState::LoadLevel)),
+            )
+            // the player can spawn a few frames into the level
+            .add_system(
+                restore_player
+                    .run_if(resource_exists::<PendingRestore>())
+                    .in_set(GameState::Playing),
+            );
+    }
+}
+
+/// Everything needed to reproduce where the player was. Formatted as
+/// `version;level;x,y;vx,vy;gravity;seed;mode;mutators` so it can be pasted into
+/// bug reports and restored with `--restore=<report>`. Mutators are written as
+/// one digit each, in the order they're listed on `Mutators`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BugReport {
+    pub version: String,
+    pub level: usize,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub gravity: Direction,
+    /// the daily's day, the roguelite run's seed, or the `RngSeed` otherwise
+    pub seed: u64,
+    pub mode: RunMode,
+    pub mutators: Mutators,
+}
+
+impl fmt::Display for BugReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{};{};{:.1},{:.1};{:.1},{:.1};{:?};{};{:?};{}",
+            self.version,
+            self.level,
+            self.position.x,
+            self.position.y,
+            self.velocity.x,
+            self.velocity.y,
+            self.gravity,
+            self.seed,
+            self.mode,
+            [
+                self.mutators.mirrored,
+                self.mutators.double_gravity,
+                self.mutators.fragile_goals,
+                self.mutators.icy_floors,
+            ]
+            .map(|on| if on { '1' } else { '0' })
+            .iter()
+            .collect::<String>()
+        )
+    }
+}
+
+impl FromStr for BugReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn vec2(s: &str) -> Result<Vec2, String> {
+            let (x, y) = s.split_once(',').ok_or(format!("expected x,y got {s}"))?;
+            Ok(Vec2::new(
+                x.parse().map_err(|e| format!("{e}"))?,
+                y.parse().map_err(|e| format!("{e}"))?,
+            ))
+        }
+
+        let parts: Vec<&str> = s.trim().split(';').collect();
+        let [version, level, position, velocity, gravity, seed, mode, mutators] = parts[..] else {
+            return Err(format!("expected 8 fields got {}", parts.len()));
+        };
+        let gravity = match gravity {
+            "Up" => Direction::Up,
+            "Down" => Direction::Down,
+            "Left" => Direction::Left,
+            "Right" => Direction::Right,
+            _ => return Err(format!("unknown gravity direction {gravity}")),
+        };
+        let mode = match mode {
+            "Standard" => RunMode::Standard,
+            "Daily" => RunMode::Daily,
+            "Roguelite" => RunMode::Roguelite,
+            "Practice" => RunMode::Practice,
+            "MinimalFlips" => RunMode::MinimalFlips,
+            _ => return Err(format!("unknown run mode {mode}")),
+        };
+        let flags: Vec<bool> = mutators.chars().map(|c| c == '1').collect();
+        let [mirrored, double_gravity, fragile_goals, icy_floors] = flags[..] else {
+            return Err(format!("expected 4 mutators got {mutators}"));
+        };
+
+        Ok(BugReport {
+            version: version.to_string(),
+            level: level.parse().map_err(|e| format!("{e}"))?,
+            position: vec2(position)?,
+            velocity: vec2(velocity)?,
+            gravity,
+            seed: seed.parse().map_err(|e| format!("{e}"))?,
+            mode,
+            mutators: Mutators {
+                mirrored,
+                double_gravity,
+                fragile_goals,
+                icy_floors,
+            },
+        })
+    }
+}
+
+/// report passed on the command line, applied once its level has loaded
+#[derive(Resource)]
+struct PendingRestore(BugReport);
+
+#[derive(Component)]
+struct DebugPanel;
+
+fn spawn_debug_panel(mut commands: Commands, preloaded: Res<Preloaded>) {
+    commands
+        .spawn((
+            DebugPanel,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: preloaded.get("font"),
+                    font_size: 16.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        ))
+        .insert(Visibility::Hidden);
+}
+
+fn toggle_debug_panel(
+    keyboard: Res<Input<KeyCode>>,
+    mut q: Query<&mut Visibility, With<DebugPanel>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    for mut visibility in &mut q {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn current_report(
+    level: &LevelSelection,
+    mode: RunMode,
+    mutators: Mutators,
+    daily: &DailyChallenge,
+    roguelite: &RogueliteRun,
+    rng_seed: &RngSeed,
+    player: (&Transform, &Velocity, &GravityDirection),
+) -> BugReport {
+    let (t, v, g) = player;
+    BugReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        level: match level {
+            LevelSelection::Index(index) => *index,
+            _ => 0,
+        },
+        position: t.translation.truncate(),
+        velocity: v.0,
+        gravity: g.0,
+        seed: match mode {
+            RunMode::Standard | RunMode::Practice | RunMode::MinimalFlips => rng_seed.0,
+            RunMode::Daily => daily.day,
+            RunMode::Roguelite => roguelite.seed,
+        },
+        mode,
+        mutators,
+    }
+}
+
+fn update_debug_panel(
+    level: Res<LevelSelection>,
+    mode: Res<RunMode>,
+    mutators: Res<Mutators>,
+    daily: Res<DailyChallenge>,
+    roguelite: Res<RogueliteRun>,
+    rng_seed: Res<RngSeed>,
+    player: Query<(&Transform, &Velocity, &GravityDirection), With<Player>>,
+    mut panel: Query<(&mut Text, &Visibility), With<DebugPanel>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+
+    for (mut text, visibility) in &mut panel {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let report = current_report(
+            &level, *mode, *mutators, &daily, &roguelite, &rng_seed, player,
+        );
+        text.sections[0].value = format!(
+            "level {}\npos {:.1}, {:.1}\nvel {:.1}, {:.1}\ngravity {:?}\nseed {}\nv{}\nF4 to copy",
+            report.level,
+            report.position.x,
+            report.position.y,
+            report.velocity.x,
+            report.velocity.y,
+            report.gravity,
+            report.seed,
+            report.version,
+        );
+    }
+}
+
+fn copy_bug_report(
+    keyboard: Res<Input<KeyCode>>,
+    level: Res<LevelSelection>,
+    mode: Res<RunMode>,
+    mutators: Res<Mutators>,
+    daily: Res<DailyChallenge>,
+    roguelite: Res<RogueliteRun>,
+    rng_seed: Res<RngSeed>,
+    player: Query<(&Transform, &Velocity, &GravityDirection), With<Player>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F4) {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+
+    let report = current_report(
+        &level, *mode, *mutators, &daily, &roguelite, &rng_seed, player,
+    )
+    .to_string();
+    info!("bug report: {report}");
+    copy_to_clipboard(report);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(report: String) {
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut c| c.set_text(report)) {
+        warn!("failed to copy bug report to the clipboard: {e}");
+    }
+}
+
+// browsers only allow clipboard writes from input event handlers, so on the web the
+// report is only logged to the console
+#[cfg(target_arch = "wasm32")]
+fn copy_to_clipboard(_report: String) {}
+
+/// read `--restore=<report>` if it was passed, the level is started once
+/// everything has loaded
+fn read_restore_arg(mut commands: Commands) {
+    let Some(arg) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--restore=").map(String::from))
+    else {
+        return;
+    };
+
+    match arg.parse::<BugReport>() {
+        Ok(report) => {
+            if report.version != env!("CARGO_PKG_VERSION") {
+                warn!("restoring a report from version {}", report.version);
+            }
+            commands.insert_resource(GlobalRng::with_seed(report.seed));
+            commands.insert_resource(RngSeed(report.seed));
+            commands.insert_resource(PendingRestore(report));
+        }
+        Err(e) => error!("could not parse --restore: {e}"),
+    }
+}
+
+/// go straight from the start menu into the restored level, in the run it was from
+fn start_restore(
+    restore: Res<PendingRestore>,
+    mut mode: ResMut<RunMode>,
+    mut mutators: ResMut<Mutators>,
+    mut daily: ResMut<DailyChallenge>,
+    mut roguelite: ResMut<RogueliteRun>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let report = &restore.0;
+    *mode = report.mode;
+    *mutators = report.mutators;
+    match report.mode {
+        RunMode::Daily => daily.day = report.seed,
+        RunMode::Roguelite => *roguelite = RogueliteRun::new(report.seed),
+        RunMode::Standard | RunMode::Practice | RunMode::MinimalFlips => {}
+    }
+    state.set(GameState::LoadLevel);
+}
+
+fn restore_level(
+    restore: Option<Res<PendingRestore>>,
+    mut sequence: ResMut<LevelSequence>,
+    mut level: ResMut<LevelSelection>,
+) {
+    let Some(restore) = restore else {
+        return;
+    };
+
+    // seeded runs carry on through the rest of their sequence
+    if !sequence.levels.contains(&restore.0.level) {
+        sequence.levels.clear();
+    }
+    sequence.start_at(restore.0.level);
+    *level = LevelSelection::Index(restore.0.level);
+}
+
+fn restore_player(
+    mut commands: Commands,
+    restore: Option<Res<PendingRestore>>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut GravityDirection,
+            &Children,
+        ),
+        With<Player>,
+    >,
+    mut aabb_colliders: Query<&mut Rect>,
+    mut rays: Query<&mut Ray>,
+) {
+    let Some(restore) = restore else {
+        return;
+    };
+    if player.is_empty() {
+        return;
+    }
+
+    for (mut t, mut v, mut g_dir, children) in &mut player {
+        set_gravity_direction(
+            restore.0.gravity,
+            &mut g_dir,
+            &mut t,
+            children,
+            &mut aabb_colliders,
+            &mut rays,
+        );
+        t.translation = restore.0.position.extend(t.translation.z);
+        v.0 = restore.0.velocity;
+    }
+    commands.remove_resource::<PendingRestore>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_round_trip() {
+        let report = BugReport {
+            version: "0.1.0".to_string(),
+            level: 4,
+            position: Vec2::new(120.5, -32.0),
+            velocity: Vec2::new(0.0, 85.5),
+            gravity: Direction::Left,
+            seed: 19_500,
+            mode: RunMode::Daily,
+            mutators: Mutators {
+                mirrored: true,
+                icy_floors: true,
+                ..default()
+            },
+        };
+        let text = report.to_string();
+        assert_eq!(text, "0.1.0;4;120.5,-32.0;0.0,85.5;Left;19500;Daily;1001");
+        assert_eq!(text.parse::<BugReport>(), Ok(report));
+
+        assert!("0.1.0;4;0,0;0,0;Left;0;Daily".parse::<BugReport>().is_err());
+        assert!("0.1.0;4;0,0;0,0;Left;0;Weekly;0000"
+            .parse::<BugReport>()
+            .is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{AccessibilitySettings, AssistConfig, CameraConfig},
+    game_state::GameState,
+    mutators::Mutators,
+    physics::GravityDirection,
+    player::Player,
+    render_layers::CAMERA_Z,
+};
+
+pub struct CameraPlugin;
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRoll>()
+            .add_startup_system(spawn_camera)
+            .add_system(mirror_camera)
+            .add_system(roll_with_gravity)
+            .add_system(apply_camera_config.run_if(resource_changed::<CameraConfig>()));
+    }
+}
+
+/// marker for the camera that renders the game world
+#[derive(Component)]
+pub struct MainCamera;
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        MainCamera,
+        Camera2dBundle {
+            transform: Transform::from_xyz(360.0, 360.0, CAMERA_Z),
+            ..default()
+        },
+    ));
+}
+
+/// flip the view horizontally while a mirrored run is in progress
+fn mirror_camera(
+    mutators: Res<Mutators>,
+    state: Res<State<GameState>>,
+    mut q: Query<&mut Transform, With<MainCamera>>,
+) {
+    let in_run = !matches!(
+        state.0,
+        GameState::Preload | GameState::StartMenu | GameState::LevelSelect | GameState::WinScreen
+    );
+    let scale_x = if mutators.mirrored && in_run {
+        -1.0
+    } else {
+        1.0
+    };
+    for mut t in &mut q {
+        t.scale.x = scale_x;
+    }
+}
+
+/// How far the camera is turned, in radians. Only non zero with the
+/// `rotate_camera` assist, which turns it to keep the player's gravity down.
+#[derive(Resource, Default)]
+pub struct CameraRoll(pub f32);
+
+fn roll_with_gravity(
+    time: Res<Time>,
+    assist: Res<AssistConfig>,
+    config: Res<CameraConfig>,
+    accessibility: Res<AccessibilitySettings>,
+    player: Query<&GravityDirection, With<Player>>,
+    mut roll: ResMut<CameraRoll>,
+    mut q: Query<&mut Transform, With<MainCamera>>,
+) {
+    let target = match player.get_single() {
+        Ok(g_dir) if assist.rotate_camera => g_dir.angle(),
+        _ => 0.0,
+    };
+    // turn the short way round
+    let remaining = (target - roll.0 + PI).rem_euclid(TAU) - PI;
+    if accessibility.reduced_motion {
+        roll.0 = target;
+    } else {
+        // real time so the game speed assist doesn't slow the camera down
+        let step = config.rotation_speed * time.raw_delta_seconds();
+        roll.0 = (roll.0 + remaining.clamp(-step, step)).rem_euclid(TAU);
+    }
+
+    for mut t in &mut q {
+        t.rotation = Quat::from_rotation_z(roll.0);
+    }
+}
+
+fn apply_camera_config(
+    config: Res<CameraConfig>,
+    mut q: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    for (mut t, mut projection) in &mut q {
+        t.translation.x = config.center.0;
+        t.translation.y = config.center.1;
+        projection.scale = config.zoom;
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
+
+use crate::{camera::MainCamera, config::CameraConfig, game_state::ShouldSimulate};
+
+const PAUSED_BY: &str = "camera script";
+
+pub struct CameraScriptPlugin;
+impl Plugin for CameraScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayCameraScript>()
+            .add_startup_systems((load_camera_scripts, read_camera_script_arg))
+            .add_systems((start_camera_script, play_camera_script).chain());
+    }
+}
+
+/// Named camera moves loaded from trailer.camera.ron, for cutscenes and
+/// capturing trailer footage
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "6f1d2b84-3c5e-4a97-b0d2-8e4f7a1c9b35"]
+pub struct CameraScripts {
+    pub scripts: HashMap<String, CameraScript>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CameraScript {
+    /// keyframes sorted by time, the camera holds the last one until the script ends
+    pub keys: Vec<CameraKey>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CameraKey {
+    /// seconds from the start of the script
+    pub time: f32,
+    /// world position the camera looks at
+    pub center: (f32, f32),
+    /// orthographic scale, larger values zoom out
+    pub zoom: f32,
+    /// how the camera moves from the previous key to this one
+    #[serde(default)]
+    pub ease: Ease,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    In,
+    Out,
+    InOut,
+}
+
+impl Ease {
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::In => t * t,
+            Ease::Out => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::InOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl CameraScript {
+    fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    /// camera center and zoom `time` seconds into the script
+    fn sample(&self, time: f32) -> Option<(Vec2, f32)> {
+        let (from, to) = match self.keys.iter().position(|key| key.time > time) {
+            Some(0) => return self.keys.first().map(|key| (key.center.into(), key.zoom)),
+            Some(i) => (&self.keys[i - 1], &self.keys[i]),
+            None => return self.keys.last().map(|key| (key.center.into(), key.zoom)),
+        };
+
+        let span = (to.time - from.time).max(f32::EPSILON);
+        let t = to.ease.apply(((time - from.time) / span).clamp(0.0, 1.0));
+        let center = Vec2::from(from.center).lerp(to.center.into(), t);
+        let zoom = from.zoom + (to.zoom - from.zoom) * t;
+        Some((center, zoom))
+    }
+}
+
+/// Play a script from trailer.camera.ron by name. This is what a console
+/// command or cutscene sends, `--camera-script=<name>` sends it on startup.
+pub struct PlayCameraScript {
+    pub name: String,
+    /// stop gameplay while the script plays
+    pub freeze: bool,
+}
+
+#[derive(Resource)]
+struct CameraScriptsHandle(Handle<CameraScripts>);
+
+#[derive(Resource)]
+struct ActiveCameraScript {
+    script: CameraScript,
+    elapsed: f32,
+    freeze: bool,
+}
+
+fn load_camera_scripts(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CameraScriptsHandle(asset_server.load("trailer.camera.ron")));
+}
+
+/// `--camera-script=<name>` plays alongside gameplay, `--camera-script=<name>:freeze`
+/// stops the game while it plays
+fn read_camera_script_arg(mut events: EventWriter<PlayCameraScript>) {
+    let Some(arg) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--camera-script=").map(String::from))
+    else {
+        return;
+    };
+
+    let (name, freeze) = match arg.strip_suffix(":freeze") {
+        Some(name) => (name.to_string(), true),
+        None => (arg, false),
+    };
+    events.send(PlayCameraScript { name, freeze });
+}
+
+fn start_camera_script(
+    mut commands: Commands,
+    mut events: EventReader<PlayCameraScript>,
+    mut pending: Local<Vec<(String, bool)>>,
+    handle: Res<CameraScriptsHandle>,
+    scripts: Res<Assets<CameraScripts>>,
+    mut simulate: ResMut<ShouldSimulate>,
+) {
+    // requests made before the file loads wait for it
+    pending.extend(events.iter().map(|e| (e.name.clone(), e.freeze)));
+    let Some(scripts) = scripts.get(&handle.0) else {
+        return;
+    };
+
+    for (name, freeze) in pending.drain(..) {
+        let Some(script) = scripts.scripts.get(&name) else {
+            warn!("no camera script named {name}");
+            continue;
+        };
+        if freeze {
+            simulate.pause(PAUSED_BY);
+        }
+        commands.insert_resource(ActiveCameraScript {
+            script: script.clone(),
+            elapsed: 0.0,
+            freeze,
+        });
+    }
+}
+
+fn play_camera_script(
+    mut commands: Commands,
+    active: Option<ResMut<ActiveCameraScript>>,
+    time: Res<Time>,
+    camera_config: Res<CameraConfig>,
+    mut simulate: ResMut<ShouldSimulate>,
+    mut q: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let Some(mut active) = active else {
+        return;
+    };
+
+    // real time so camera moves aren't slowed down by the game speed assist
+    active.elapsed += time.raw_delta_seconds();
+    let finished = active.elapsed > active.script.duration();
+    let (center, zoom) = if finished {
+        commands.remove_resource::<ActiveCameraScript>();
+        if active.freeze {
+            simulate.resume(PAUSED_BY);
+        }
+        (camera_config.center.into(), camera_config.zoom)
+    } else {
+        let Some(sample) = active.script.sample(active.elapsed) else {
+            return;
+        };
+        sample
+    };
+
+    for (mut t, mut projection) in &mut q {
+        t.translation.x = center.x;
+        t.translation.y = center.y;
+        projection.scale = zoom;
+    }
+}
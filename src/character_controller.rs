@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::EntityInstance;
+
+use crate::{
+    collisions::{CollisionEvents, PositionDelta, RayBundle, RectBundle},
+    constants::CollisionTypes,
+    game_state::GameState,
+    physics::{
+        Acceleration, ColliderSizes, Gravity, GravityDirection, GroundFriction, GroundRay,
+        GroundSurface, GroundVelocity, Momentum, OnGround, PhysicsSettings, SizedCollider,
+        Velocity,
+    },
+};
+
+/// how far below the hit box ground rays reach
+const GROUND_RAY_LENGTH: f32 = 15.0;
+
+/// Gives every `KinematicBody` the colliders the physics resolves it with.
+/// Falling, landing and sliding along walls are then handled for it by
+/// `ground_detection` and `falling_detection`, the player only adds controls.
+pub struct CharacterControllerPlugin;
+impl Plugin for CharacterControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_body_colliders.in_schedule(OnEnter(GameState::SpawnLevel)))
+            // bodies can also be dropped in while playing, like falling blocks
+            .add_system(add_body_colliders.in_set(GameState::Playing));
+    }
+}
+
+/// Something moved by gravity and stopped by ground. Gets a hit box the size
+/// of `collider`, and rays on its bottom corners if `ground_rays` is set.
+#[derive(Component, Clone, Copy)]
+pub struct KinematicBody {
+    pub collider: SizedCollider,
+    /// Without them it stays on the ground until something else says it's
+    /// falling, like falling blocks waiting for the player to jump off.
+    pub ground_rays: bool,
+}
+
+impl KinematicBody {
+    /// cat sized and walks off ledges, for the player and anything copying their controls
+    pub const CHARACTER: KinematicBody = KinematicBody {
+        collider: SizedCollider::Player,
+        ground_rays: true,
+    };
+}
+
+impl Default for KinematicBody {
+    fn default() -> Self {
+        KinematicBody::CHARACTER
+    }
+}
+
+/// what the physics needs to move a `KinematicBody`
+#[derive(Bundle, Default)]
+pub struct KinematicBodyBundle {
+    pub body: KinematicBody,
+    pub velocity: Velocity,
+    pub acceleration: Acceleration,
+    pub g_dir: GravityDirection,
+    pub gravity: Gravity,
+    pub on_ground: OnGround,
+    pub ground_surface: GroundSurface,
+    pub ground_velocity: GroundVelocity,
+    pub ground_friction: GroundFriction,
+    pub momentum: Momentum,
+}
+
+impl KinematicBodyBundle {
+    pub fn new(body: KinematicBody) -> Self {
+        KinematicBodyBundle { body, ..default() }
+    }
+}
+
+/// for `#[with(...)]` on ldtk bundles of player sized characters
+pub fn character_body(_: &EntityInstance) -> KinematicBodyBundle {
+    KinematicBodyBundle::new(KinematicBody::CHARACTER)
+}
+
+fn add_body_colliders(
+    mut commands: Commands,
+    // ones already set up by the run on entering `SpawnLevel` have a `PositionDelta`
+    q: Query<
+        (Entity, &KinematicBody, &GravityDirection, &Transform),
+        (Added<KinematicBody>, Without<PositionDelta>),
+    >,
+    settings: Res<PhysicsSettings>,
+) {
+    for (e, body, g_dir, t) in &q {
+        commands
+            .entity(e)
+            .insert((
+                CollisionEvents::<CollisionTypes>::new(),
+                PositionDelta {
+                    origin: t.translation.truncate(),
+                    ray: Vec2::ZERO,
+                },
+            ))
+            .with_children(|children| {
+                spawn_body_colliders(children, body, *g_dir, &settings.colliders)
+            });
+    }
+}
+
+/// The hit box, and ground rays pointing along gravity from its bottom
+/// corners. Bodies spawned with sideways gravity get them turned to match,
+/// like `set_gravity_direction` does.
+fn spawn_body_colliders(
+    children: &mut ChildBuilder,
+    body: &KinematicBody,
+    g_dir: GravityDirection,
+    sizes: &ColliderSizes,
+) {
+    let size = sizes.get(body.collider);
+    if body.ground_rays {
+        // offsets are in the body's space, which is turned with its gravity
+        for x in [-size.x / 2., size.x / 2.] {
+            children.spawn((
+                RayBundle::new(
+                    g_dir.as_vec2() * GROUND_RAY_LENGTH,
+                    Vec2::new(x, -size.y / 2.),
+                ),
+                GroundRay,
+            ));
+        }
+    }
+    // rects are axis aligned, so they're stored swapped while gravity is sideways
+    let rect = if g_dir.as_vec2().x != 0.0 {
+        Vec2::new(size.y, size.x)
+    } else {
+        size
+    };
+    children.spawn((RectBundle::new(rect), body.collider));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collisions::Ray;
+
+    fn sizes() -> ColliderSizes {
+        ColliderSizes {
+            player: (16.0, 20.0),
+            ground: (24.0, 24.0),
+            falling_block: (24.0, 24.0),
+            walker: (16.0, 20.0),
+        }
+    }
+
+    #[test]
+    fn ground_rays_are_marked_for_the_debug_pass() {
+        let mut world = World::new();
+        let body = world
+            .spawn_empty()
+            .with_children(|children| {
+                spawn_body_colliders(
+                    children,
+                    &KinematicBody::CHARACTER,
+                    GravityDirection::default(),
+                    &sizes(),
+                )
+            })
+            .id();
+
+        // the same shape `draw_collision_shapes` looks rays up with
+        let mut rays = world.query::<(&Ray, Option<&Parent>, Option<&GroundRay>)>();
+        let rays: Vec<_> = rays.iter(&world).collect();
+        assert_eq!(rays.len(), 2);
+        for (ray, parent, ground_ray) in rays {
+            assert!(ground_ray.is_some());
+            assert_eq!(parent.map(Parent::get), Some(body));
+            assert_eq!(ray.0, Vec2::new(0.0, -GROUND_RAY_LENGTH));
+        }
+    }
+
+    #[test]
+    fn bodies_without_ground_rays_get_none() {
+        let mut world = World::new();
+        let body = KinematicBody {
+            ground_rays: false,
+            ..KinematicBody::CHARACTER
+        };
+        world.spawn_empty().with_children(|children| {
+            spawn_body_colliders(children, &body, GravityDirection::default(), &sizes())
+        });
+
+        let mut ground_rays = world.query_filtered::<(), With<GroundRay>>();
+        assert_eq!(ground_rays.iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    collisions::{Collided, CollisionEvents, CollisionSets, Ray, Rect, RectBundle},
+    constants::CollisionTypes,
+    crates::{spawn_crate, Pushable},
+    enemies::{spawn_walker, Walker},
+    flips::{AirFlips, FlipCount},
+    game_state::GameState,
+    goals::{spawn_goal, Goal},
+    ground::{spawn_falling_block, FallingGround},
+    physics::{set_gravity_direction, Acceleration, Direction, GravityDirection, Velocity},
+    player::{handle_player_death, Invulnerable, Player, INVULNERABLE_SECONDS},
+};
+
+const CHECKPOINT_SIZE: Vec2 = Vec2::new(8.0, 24.0);
+const CHECKPOINT_COLOR: Color = Color::rgb(0.45, 0.45, 0.5);
+/// the checkpoint the player will come back to
+const ACTIVE_CHECKPOINT_COLOR: Color = Color::rgb(0.4, 0.9, 1.0);
+
+/// Checkpoints placed in ldtk. Once the player has touched one, dying puts
+/// them back at it instead of restarting the level. Falling blocks go back
+/// to where the level placed them, crates and walkers to where they were at
+/// the checkpoint, and goals collected since the checkpoint come back.
+pub struct CheckpointPlugin;
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveCheckpoint>()
+            .add_event::<CheckpointRespawn>()
+            .register_ldtk_entity::<CheckpointBundle>("Checkpoint")
+            .add_systems(
+                (
+                    clear_checkpoint,
+                    remember_falling_blocks,
+                    after_checkpoint_spawned,
+                )
+                    .chain()
+                    .in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(touch_checkpoint.in_set(CollisionSets::Consume))
+            .add_system(color_checkpoints.in_set(GameState::Playing))
+            .add_system(
+                respawn_at_checkpoint
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(handle_player_death),
+            );
+    }
+}
+
+/// send to put the player back at the active checkpoint and reset the level around it
+pub struct CheckpointRespawn;
+
+#[derive(Component, Default)]
+pub struct Checkpoint;
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct CheckpointBundle {
+    checkpoint: Checkpoint,
+    sprite: SpriteBundle,
+}
+
+/// where the player comes back to, kept until the level is spawned again
+#[derive(Resource, Default)]
+pub struct ActiveCheckpoint {
+    reached: Option<Reached>,
+    /// goals left when the checkpoint was touched
+    goals: Vec<SavedGoal>,
+    /// crates and walkers when the checkpoint was touched
+    bodies: Vec<SavedBody>,
+    /// falling blocks as the level placed them
+    falling_blocks: Vec<SavedBlock>,
+}
+
+struct Reached {
+    checkpoint: Entity,
+    position: Vec2,
+    gravity: Direction,
+    /// flips used when it was touched, so a par limit can't run out for good
+    flips: u32,
+}
+
+struct SavedGoal {
+    entity: Entity,
+    layer: Entity,
+    transform: Transform,
+    instance: Option<EntityInstance>,
+}
+
+/// a crate, or a walker with its ldtk instance
+struct SavedBody {
+    layer: Entity,
+    transform: Transform,
+    gravity: Direction,
+    walker: Option<EntityInstance>,
+}
+
+struct SavedBlock {
+    layer: Entity,
+    transform: Transform,
+    sprite: Handle<Image>,
+}
+
+impl ActiveCheckpoint {
+    pub fn is_reached(&self) -> bool {
+        self.reached.is_some()
+    }
+}
+
+fn clear_checkpoint(mut active: ResMut<ActiveCheckpoint>) {
+    *active = ActiveCheckpoint::default();
+}
+
+/// Blocks dropped while playing, like the boss's, have no ldtk instance and
+/// are left out.
+fn remember_falling_blocks(
+    mut active: ResMut<ActiveCheckpoint>,
+    blocks: Query<
+        (&Transform, &Parent, &Handle<Image>),
+        (Added<FallingGround>, With<EntityInstance>),
+    >,
+) {
+    for (t, parent, sprite) in &blocks {
+        active.falling_blocks.push(SavedBlock {
+            layer: parent.get(),
+            transform: *t,
+            sprite: sprite.clone(),
+        });
+    }
+}
+
+fn after_checkpoint_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Sprite), Added<Checkpoint>>,
+) {
+    for (e, mut sprite) in &mut q {
+        sprite.custom_size = Some(CHECKPOINT_SIZE);
+        commands
+            .entity(e)
+            .insert((
+                CollisionTypes::Checkpoint,
+                CollisionEvents::<CollisionTypes>::new(),
+            ))
+            .with_children(|children| {
+                children.spawn(RectBundle::new(CHECKPOINT_SIZE));
+            });
+    }
+}
+
+/// The player comes back facing the gravity they touched the checkpoint with.
+/// Goals are saved here so only the ones collected after it come back, crates
+/// and walkers so they come back to where they were.
+fn touch_checkpoint(
+    mut collisions: EventReader<Collided<CollisionTypes>>,
+    checkpoints: Query<&Transform, With<Checkpoint>>,
+    player: Query<&GravityDirection, With<Player>>,
+    goals: Query<(Entity, &Transform, &Parent, Option<&EntityInstance>), With<Goal>>,
+    crates: Query<(&Transform, &Parent, &GravityDirection), With<Pushable>>,
+    walkers: Query<(&Transform, &Parent, &GravityDirection, &EntityInstance), With<Walker>>,
+    flips: Res<FlipCount>,
+    mut active: ResMut<ActiveCheckpoint>,
+) {
+    for collided in &mut collisions {
+        if collided.event.user_type != CollisionTypes::Player {
+            continue;
+        }
+        let e = collided.receiver;
+        let Ok(t) = checkpoints.get(e) else {
+            continue;
+        };
+        let Ok(g_dir) = player.get_single() else {
+            continue;
+        };
+        if matches!(active.reached, Some(Reached { checkpoint, .. }) if checkpoint == e) {
+            continue;
+        }
+
+        active.reached = Some(Reached {
+            checkpoint: e,
+            position: t.translation.truncate(),
+            gravity: g_dir.0,
+            flips: flips.0,
+        });
+        active.goals = goals
+            .iter()
+            .map(|(entity, t, parent, instance)| SavedGoal {
+                entity,
+                layer: parent.get(),
+                transform: *t,
+                instance: instance.cloned(),
+            })
+            .collect();
+        let crates = crates.iter().map(|(t, parent, g_dir)| SavedBody {
+            layer: parent.get(),
+            transform: *t,
+            gravity: g_dir.0,
+            walker: None,
+        });
+        let walkers = walkers
+            .iter()
+            .map(|(t, parent, g_dir, instance)| SavedBody {
+                layer: parent.get(),
+                transform: *t,
+                gravity: g_dir.0,
+                walker: Some(instance.clone()),
+            });
+        active.bodies = crates.chain(walkers).collect();
+    }
+}
+
+fn color_checkpoints(
+    active: Res<ActiveCheckpoint>,
+    mut checkpoints: Query<(Entity, &mut Sprite), With<Checkpoint>>,
+) {
+    for (e, mut sprite) in &mut checkpoints {
+        let color = match active.reached {
+            Some(Reached { checkpoint, .. }) if checkpoint == e => ACTIVE_CHECKPOINT_COLOR,
+            _ => CHECKPOINT_COLOR,
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// The player's and checkpoint's transforms are both in the entity layer, so
+/// the saved position can be used as is.
+fn respawn_at_checkpoint(
+    mut commands: Commands,
+    mut events: EventReader<CheckpointRespawn>,
+    mut active: ResMut<ActiveCheckpoint>,
+    mut player: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut GravityDirection,
+            &Children,
+        ),
+        With<Player>,
+    >,
+    mut aabb_colliders: Query<&mut Rect>,
+    mut rays: Query<&mut Ray>,
+    falling_blocks: Query<Entity, With<FallingGround>>,
+    bodies: Query<Entity, Or<(With<Pushable>, With<Walker>)>>,
+    goals: Query<(), With<Goal>>,
+    mut flips: ResMut<FlipCount>,
+    mut air: ResMut<AirFlips>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    let active = &mut *active;
+    let Some(reached) = &active.reached else {
+        return;
+    };
+
+    flips.0 = reached.flips;
+    air.0 = 0;
+    for (e, mut t, mut v, mut a, mut g_dir, children) in &mut player {
+        set_gravity_direction(
+            reached.gravity,
+            &mut g_dir,
+            &mut t,
+            children,
+            &mut aabb_colliders,
+            &mut rays,
+        );
+        t.translation = reached.position.extend(t.translation.z);
+        v.0 = Vec2::ZERO;
+        a.0 = Vec2::ZERO;
+        commands.entity(e).insert(Invulnerable(Timer::from_seconds(
+            INVULNERABLE_SECONDS,
+            TimerMode::Once,
+        )));
+    }
+
+    for e in &falling_blocks {
+        commands.entity(e).despawn_recursive();
+    }
+    for block in &active.falling_blocks {
+        let e = spawn_falling_block(&mut commands, block.sprite.clone(), block.transform);
+        commands.entity(block.layer).add_child(e);
+    }
+
+    // ones that moved, fell out or were stomped since are all replaced
+    for e in &bodies {
+        commands.entity(e).despawn_recursive();
+    }
+    for body in &active.bodies {
+        let e = match &body.walker {
+            Some(instance) => spawn_walker(
+                &mut commands,
+                body.transform,
+                body.gravity,
+                instance.clone(),
+            ),
+            None => spawn_crate(&mut commands, body.transform, body.gravity),
+        };
+        commands.entity(body.layer).add_child(e);
+    }
+
+    // the goal is a new entity, so it isn't brought back again on the next death
+    for goal in &mut active.goals {
+        if goals.contains(goal.entity) {
+            continue;
+        }
+        goal.entity = spawn_goal(&mut commands, goal.transform, goal.instance.clone());
+        commands.entity(goal.layer).add_child(goal.entity);
+    }
+}
@@ -1,12 +1,13 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
-use crate::physics::{Direction, PhysicsSet};
+use crate::physics::{Direction, GroundRay, PhysicsSet};
 use bevy::{
     math::Vec3Swizzles,
     prelude::{
-        App, Bundle, Color, Component, CoreSchedule, CoreSet, Entity, GlobalTransform,
-        IntoSystemConfig, IntoSystemConfigs, IntoSystemSetConfig, IntoSystemSetConfigs, Parent,
-        Plugin, Query, ResMut, Schedule, SpatialBundle, SystemSet, Transform, Vec2, Without,
+        App, Bundle, Color, Component, CoreSchedule, CoreSet, Entity, EventWriter, GlobalTransform,
+        IVec2, IntoSystemConfig, IntoSystemConfigs, IntoSystemSetConfig, IntoSystemSetConfigs, Or,
+        Parent, Plugin, Query, Res, ResMut, Resource, Schedule, SpatialBundle, SystemSet,
+        Transform, Vec2, Vec3, With, Without,
     },
     transform::{
         systems::{propagate_transforms, sync_simple_transforms},
@@ -36,6 +37,8 @@ where
                 (
                     CollisionSets::TransformPropagateBefore,
                     CollisionSets::Produce,
+                    CollisionSets::PreSolve,
+                    CollisionSets::Dispatch,
                     CollisionSets::Consume,
                     CollisionSets::TransformPropagateAfter,
                 )
@@ -50,17 +53,23 @@ where
                 )
                     .in_set(CollisionSets::TransformPropagateBefore),
             )
+            .add_system(build_broad_phase.in_set(CollisionSets::Produce))
+            .add_system(apply_contact_responses::<T>.in_set(CollisionSets::PreSolve))
+            .add_system(send_collided::<T>.in_set(CollisionSets::Dispatch))
             .add_systems(
                 (
                     check_ray_to_box_collisions::<T>,
                     check_box_to_box_collisions::<T>,
+                    check_circle_collisions::<T>,
                 )
-                    .in_set(CollisionSets::Produce),
+                    .in_set(CollisionSets::Produce)
+                    .after(build_broad_phase),
             );
     }
 
     /// this function shouold be considered to be on user side
     pub fn add_systems_to_post_update(app: &mut App) {
+        app.init_resource::<BroadPhase>().add_event::<Collided<T>>();
         app.edit_schedule(CoreSchedule::Main, |schedule| {
             Self::add_systems_to_schedule(schedule);
             schedule.configure_set(
@@ -72,6 +81,7 @@ where
     }
 
     pub fn add_systems_to_fixed_update(app: &mut App) {
+        app.init_resource::<BroadPhase>().add_event::<Collided<T>>();
         app.edit_schedule(CoreSchedule::Main, |schedule| {
             Self::add_systems_to_schedule(schedule);
             schedule.configure_set(Collision.after(PhysicsSet));
@@ -100,6 +110,13 @@ pub enum CollisionSets {
     TransformPropagateBefore,
     /// systems that produce the collision events
     Produce,
+    /// Systems that decide which contacts are solid this tick, before anything
+    /// resolves them. Mark contacts with [`CollisionEvents::ignore`], like a
+    /// one-way platform letting the player through from below, or change how
+    /// they respond with [`CollisionEvents::modify`].
+    PreSolve,
+    /// sends every buffered event as a [`Collided`] event
+    Dispatch,
     /// systems that consume the collision events,
     /// users systems that need to modify the transform
     /// should go here
@@ -202,6 +219,48 @@ impl Ray {
             Some(tmax)
         }
     }
+
+    /// Like `intersect_aabb` for a circle. Normals point out from the center
+    /// through the hit.
+    fn intersect_circle(
+        ray_origin: Vec2,
+        ray: &Ray,
+        center: Vec2,
+        radius: f32,
+    ) -> Option<RayIntersection> {
+        let length = ray.0.length();
+        if length == 0.0 {
+            return None;
+        }
+        let direction = ray.0 / length;
+        let to_center = center - ray_origin;
+        let along = to_center.dot(direction);
+        let miss_sq = to_center.length_squared() - along * along;
+        if miss_sq > radius * radius {
+            // ray misses circle completely
+            return None;
+        }
+        let half_chord = (radius * radius - miss_sq).sqrt();
+        let (enter, exit) = (along - half_chord, along + half_chord);
+
+        if exit < 0.0 // points away from circle
+        || (enter < 0.0 && exit > length) // contained inside circle
+        || enter >= length
+        // ends before circle
+        {
+            return None;
+        }
+        // from outside the circle it's hit on the way in, from inside on the way out
+        let toi = if enter >= 0.0 { enter } else { exit };
+        let point = ray_origin + toi * direction;
+        Some(RayIntersection {
+            toi,
+            point,
+            normal: (point - center) / radius,
+            ray_direction: ray.0,
+            ray_origin,
+        })
+    }
 }
 
 #[derive(Bundle, Default)]
@@ -222,10 +281,75 @@ impl RayBundle {
     }
 }
 
+/// Transform for a Circle is the center, the value is its radius.
+#[derive(Component, Default)]
+pub struct Circle(pub f32);
+impl Shape for Circle {}
+
+#[allow(dead_code)] // no round hazards are spawned yet
+#[derive(Bundle, Default)]
+pub struct CircleBundle {
+    circle: Circle,
+    spatial_bundle: SpatialBundle,
+}
+
+impl CircleBundle {
+    #[allow(dead_code)]
+    pub fn new(radius: f32) -> CircleBundle {
+        CircleBundle {
+            circle: Circle(radius),
+            spatial_bundle: SpatialBundle::default(),
+        }
+    }
+}
+
+/// shape of a collider that can be swept, read from its `Rect` or `Circle`
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColliderShape {
+    Rect(Vec2),
+    Circle(f32),
+}
+
+impl ColliderShape {
+    fn of(rect: Option<&Rect>, circle: Option<&Circle>) -> Option<ColliderShape> {
+        match (rect, circle) {
+            (Some(rect), _) => Some(ColliderShape::Rect(rect.0)),
+            (None, Some(circle)) => Some(ColliderShape::Circle(circle.0)),
+            (None, None) => None,
+        }
+    }
+
+    /// half the size of the box around the shape
+    fn half_extents(&self) -> Vec2 {
+        match *self {
+            ColliderShape::Rect(size) => size / 2.0,
+            ColliderShape::Circle(radius) => Vec2::splat(radius),
+        }
+    }
+
+    /// `self` at `a_pos` moving by `delta` against `other` standing still at `b_pos`
+    fn sweep(&self, a_pos: Vec2, other: ColliderShape, b_pos: Vec2, delta: Vec2) -> Option<Sweep> {
+        match (*self, other) {
+            (ColliderShape::Rect(a_size), ColliderShape::Rect(b_size)) => {
+                Rect::sweep_aabb(a_pos, a_size, b_pos, b_size, delta)
+            }
+            (ColliderShape::Rect(a_size), ColliderShape::Circle(radius)) => {
+                Rect::sweep_circle(a_pos, a_size, b_pos, radius, delta)
+            }
+            (ColliderShape::Circle(radius), ColliderShape::Rect(b_size)) => {
+                Circle::sweep_aabb(a_pos, radius, b_pos, b_size, delta)
+            }
+            (ColliderShape::Circle(a_radius), ColliderShape::Circle(b_radius)) => {
+                Circle::sweep_circle(a_pos, a_radius, b_pos, b_radius, delta)
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct AabbIntersection {
     /// penetration depth
-    delta: Vec2,
+    pub delta: Vec2,
     normal: Vec2,
     point: Vec2,
 }
@@ -316,8 +440,126 @@ impl Rect {
     }
 }
 
+impl Rect {
+    /// box at `a_pos` moving by `delta` against a still circle, the circle's
+    /// sweep against the box run backwards
+    pub fn sweep_circle(
+        a_pos: Vec2,
+        a_size: Vec2,
+        b_pos: Vec2,
+        radius: f32,
+        delta: Vec2,
+    ) -> Option<Sweep> {
+        let hit = Circle::sweep_aabb(b_pos, radius, a_pos, a_size, -delta)?;
+        Some(Sweep {
+            position: a_pos - (hit.position - b_pos),
+            time: hit.time,
+            normal: -hit.normal,
+        })
+    }
+}
+
+impl Circle {
+    /// Circle at `a_pos` moving by `delta` against a still box. It's swept
+    /// against the box grown by the radius, and hits in the grown box's corners
+    /// against a point on the box's corner so the circle rolls around it.
+    pub fn sweep_aabb(
+        a_pos: Vec2,
+        radius: f32,
+        b_pos: Vec2,
+        b_size: Vec2,
+        delta: Vec2,
+    ) -> Option<Sweep> {
+        let half = b_size / 2.0;
+        if delta == Vec2::ZERO {
+            return Circle::overlap_aabb(a_pos, radius, b_pos, half);
+        }
+
+        let grown = Rect(b_size + Vec2::splat(radius * 2.0));
+        let hit = Ray::intersect_aabb(a_pos, &Ray(delta), b_pos, &grown)?;
+        let local = hit.point - b_pos;
+        if local.x.abs() <= half.x || local.y.abs() <= half.y {
+            return Some(Sweep {
+                position: hit.point,
+                time: hit.toi,
+                normal: hit.normal,
+            });
+        }
+        let corner = b_pos + half * local.signum();
+        Circle::sweep_circle(a_pos, radius, corner, 0.0, delta)
+    }
+
+    /// circle at `a_pos` moving by `delta` against a still circle
+    pub fn sweep_circle(
+        a_pos: Vec2,
+        a_radius: f32,
+        b_pos: Vec2,
+        b_radius: f32,
+        delta: Vec2,
+    ) -> Option<Sweep> {
+        let radius = a_radius + b_radius;
+        if delta == Vec2::ZERO {
+            let d = a_pos - b_pos;
+            let distance = d.length();
+            if distance > radius {
+                return None;
+            }
+            // pushed up out of circles with the same center
+            let normal = if distance == 0.0 {
+                Vec2::Y
+            } else {
+                d / distance
+            };
+            return Some(Sweep {
+                position: b_pos + normal * radius,
+                time: 0.,
+                normal,
+            });
+        }
+
+        let hit = Ray::intersect_circle(a_pos, &Ray(delta), b_pos, radius)?;
+        Some(Sweep {
+            position: hit.point,
+            time: hit.toi,
+            normal: hit.normal,
+        })
+    }
+
+    /// pushes a still circle out of a box, along the shortest way out
+    fn overlap_aabb(a_pos: Vec2, radius: f32, b_pos: Vec2, half: Vec2) -> Option<Sweep> {
+        let local = a_pos - b_pos;
+        let closest = local.clamp(-half, half);
+        let d = local - closest;
+        if d == Vec2::ZERO {
+            // center inside the box, push out of the nearest side
+            let p = half + radius - local.abs();
+            let (normal, depth) = if p.x < p.y {
+                (Vec2::new(local.x.signum(), 0.0), p.x)
+            } else {
+                (Vec2::new(0.0, local.y.signum()), p.y)
+            };
+            return Some(Sweep {
+                position: a_pos + normal * depth,
+                time: 0.,
+                normal,
+            });
+        }
+
+        let distance = d.length();
+        if distance > radius {
+            return None;
+        }
+        let normal = d / distance;
+        Some(Sweep {
+            position: b_pos + closest + normal * radius,
+            time: 0.,
+            normal,
+        })
+    }
+}
+
 /// result for sweep aabb test
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Sweep {
     // position of `a` aabb for sweep test that keeps object outside of collider
     pub position: Vec2,
@@ -345,6 +587,37 @@ fn c_max(a: &RayIntersection, b: &RayIntersection) -> RayIntersection {
     }
 }
 
+/// Closest of `rects` hit by `ray` cast from `origin`, for queries made outside
+/// of the collision sets. Each rect is given with the entity that owns it.
+pub fn cast_ray<'a>(
+    origin: Vec2,
+    ray: Vec2,
+    rects: impl IntoIterator<Item = (Entity, &'a Rect, &'a GlobalTransform)>,
+) -> Option<(Entity, RayIntersection)> {
+    rects
+        .into_iter()
+        .filter_map(|(owner, rect, t)| {
+            Ray::intersect_aabb(origin, &Ray(ray), t.translation().xy(), rect)
+                .map(|hit| (owner, hit))
+        })
+        .min_by(|(_, a), (_, b)| a.toi.total_cmp(&b.toi))
+}
+
+/// First of `rects` containing `point`, for hit testing things like mouse
+/// clicks. Each rect is given with the entity that owns it.
+pub fn point_in_rects<'a>(
+    point: Vec2,
+    rects: impl IntoIterator<Item = (Entity, &'a Rect, &'a GlobalTransform)>,
+) -> Option<Entity> {
+    rects.into_iter().find_map(|(owner, rect, t)| {
+        let inside = (point - t.translation().xy())
+            .abs()
+            .cmple(rect.0 / 2.0)
+            .all();
+        inside.then_some(owner)
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct RayIntersection {
     /// distance until time of impact
@@ -357,23 +630,96 @@ pub struct RayIntersection {
     pub ray_direction: Vec2,
 }
 
+/// Which colliders a `Rect` or `Ray` is checked against. Two colliders are
+/// only checked if each one's `layer` has a bit in the other's `filter`.
+/// Colliders without this are on every layer and are checked against everything.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub layer: u32,
+    pub filter: u32,
+}
+
+impl CollisionLayers {
+    pub const ALL: CollisionLayers = CollisionLayers::new(u32::MAX, u32::MAX);
+
+    pub const fn new(layer: u32, filter: u32) -> Self {
+        CollisionLayers { layer, filter }
+    }
+
+    pub fn interacts(&self, other: &CollisionLayers) -> bool {
+        self.layer & other.filter != 0 && other.layer & self.filter != 0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        CollisionLayers::ALL
+    }
+}
+
+fn layers_interact(a: Option<&CollisionLayers>, b: Option<&CollisionLayers>) -> bool {
+    a.copied()
+        .unwrap_or_default()
+        .interacts(&b.copied().unwrap_or_default())
+}
+
 #[derive(Component, Default, Clone, Copy, Debug)]
 pub struct PositionDelta {
     pub origin: Vec2,
     pub ray: Vec2,
 }
 
+#[derive(Clone)]
 pub struct CollisionEvent<T> {
     // entity that was collided with
     pub entity: Entity,
     // type T that was stored on entity that was collided with
     pub user_type: T,
     pub data: CollisionData,
+    /// left out of resolution this tick, set in `CollisionSets::PreSolve`
+    pub ignored: bool,
+    /// how resolving this contact changes the entity's movement
+    pub response: ContactResponse,
+}
+
+/// How a contact pushes back. Put it on a collider's owner to use it for every
+/// contact with that entity, or change single contacts in
+/// `CollisionSets::PreSolve`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ContactResponse {
+    /// velocity of the surface itself, like a conveyor belt, carries what rests on it
+    pub surface_velocity: Vec2,
+    /// grip along the surface, 1 is full grip and lower slides like ice
+    pub friction: f32,
+    /// fraction of the speed into the surface that bounces back
+    pub restitution: f32,
+}
+
+impl ContactResponse {
+    pub const SOLID: ContactResponse = ContactResponse {
+        surface_velocity: Vec2::ZERO,
+        friction: 1.0,
+        restitution: 0.0,
+    };
 }
 
+impl Default for ContactResponse {
+    fn default() -> Self {
+        ContactResponse::SOLID
+    }
+}
+
+/// Bounciness of a collider's owner, like a rubber block or a ball. Contacts
+/// bounce as much as the bouncier of the two entities, see
+/// `ContactResponse::restitution`.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Restitution(pub f32);
+
 /// the enum is the type of collider that detected the event
+#[derive(Clone)]
 pub enum CollisionData {
     Ray(RayIntersection),
+    /// a box or a circle swept against another box or circle
     Aabb(Sweep),
 }
 
@@ -395,48 +741,208 @@ impl<T> CollisionEvents<T> {
     pub fn new() -> CollisionEvents<T> {
         CollisionEvents { buffer: Vec::new() }
     }
+
+    /// Events that should push things apart. Sensors like goals still see
+    /// every event in `buffer`.
+    pub fn solid(&self) -> impl Iterator<Item = &CollisionEvent<T>> {
+        self.buffer.iter().filter(|event| !event.ignored)
+    }
+
+    /// leave every contact with `entity` out of resolution this tick
+    pub fn ignore(&mut self, entity: Entity) {
+        self.modify(entity, |event| event.ignored = true);
+    }
+
+    /// change every contact with `entity` this tick
+    pub fn modify(&mut self, entity: Entity, mut f: impl FnMut(&mut CollisionEvent<T>)) {
+        for event in &mut self.buffer {
+            if event.entity == entity {
+                f(event);
+            }
+        }
+    }
+}
+
+/// A [`CollisionEvent`] sent as a bevy event, for systems that would rather
+/// read every collision with an `EventReader` than poll each entity's
+/// [`CollisionEvents`]. Sent after `CollisionSets::PreSolve`, so ignored
+/// contacts and changed responses are already set. Read them in
+/// `CollisionSets::Consume`.
+#[derive(Clone)]
+pub struct Collided<T> {
+    /// entity with the `CollisionEvents` the event was buffered in
+    pub receiver: Entity,
+    pub event: CollisionEvent<T>,
+}
+
+/// size of a `BroadPhase` cell, a few ground tiles across
+const BROAD_PHASE_CELL_SIZE: f32 = 64.0;
+
+/// Uniform grid of every `Rect`, rebuilt each tick before the collision checks
+/// so only rects that share a cell are swept against each other. Other
+/// systems can use it to find the rects near something without checking
+/// every one.
+#[derive(Resource, Default)]
+pub struct BroadPhase {
+    cells: HashMap<IVec2, Vec<Entity>>,
+}
+
+impl BroadPhase {
+    fn cells_between(min: Vec2, max: Vec2) -> impl Iterator<Item = IVec2> {
+        let min = (min / BROAD_PHASE_CELL_SIZE).floor().as_ivec2();
+        let max = (max / BROAD_PHASE_CELL_SIZE).floor().as_ivec2();
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// adds the rect entity `e` to every cell the box from `min` to `max` touches
+    pub fn insert(&mut self, e: Entity, min: Vec2, max: Vec2) {
+        for cell in Self::cells_between(min, max) {
+            self.cells.entry(cell).or_default().push(e);
+        }
+    }
+
+    /// rect entities that may overlap the box from `min` to `max`, each once
+    pub fn query(&self, min: Vec2, max: Vec2) -> Vec<Entity> {
+        let mut found: Vec<Entity> = Self::cells_between(min, max)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Pairs of rect entities that share a cell, each once. Sorted so
+    /// collision events come out in the same order every run.
+    pub fn pairs(&self) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+        for cell in self.cells.values() {
+            for (i, &a) in cell.iter().enumerate() {
+                for &b in &cell[i + 1..] {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
 }
 
+fn build_broad_phase(
+    mut broad_phase: ResMut<BroadPhase>,
+    shapes: Query<
+        (
+            Entity,
+            Option<&Rect>,
+            Option<&Circle>,
+            &GlobalTransform,
+            &Parent,
+        ),
+        Or<(With<Rect>, With<Circle>)>,
+    >,
+    deltas: Query<&PositionDelta>,
+) {
+    broad_phase.clear();
+    for (e, rect, circle, t, owner) in &shapes {
+        let Some(shape) = ColliderShape::of(rect, circle) else {
+            continue;
+        };
+        // a pixel of padding so shapes that only touch still share a cell
+        let half = shape.half_extents() + 1.0;
+        let center = t.translation().truncate();
+        let (mut min, mut max) = (center - half, center + half);
+        // moving rects are swept from where they started the tick
+        if let Ok(delta) = deltas.get(owner.get()) {
+            for point in [delta.origin, delta.origin + delta.ray] {
+                min = min.min(point - half);
+                max = max.max(point + half);
+            }
+        }
+        broad_phase.insert(e, min, max);
+    }
+}
+
+/// rays against boxes and circles
 pub fn check_ray_to_box_collisions<T>(
-    rays: Query<(&Ray, &GlobalTransform, &Parent), Without<Rect>>,
-    rects: Query<(&Rect, &GlobalTransform, &Parent), Without<Ray>>,
+    rays: Query<
+        (&Ray, &GlobalTransform, &Parent, Option<&CollisionLayers>),
+        (Without<Rect>, Without<Circle>),
+    >,
+    shapes: Query<
+        (
+            Option<&Rect>,
+            Option<&Circle>,
+            &GlobalTransform,
+            &Parent,
+            Option<&CollisionLayers>,
+        ),
+        Without<Ray>,
+    >,
+    broad_phase: Res<BroadPhase>,
     mut collision_takers: Query<&mut CollisionEvents<T>>,
     user_types: Query<&T>,
 ) where
     T: Component + Clone,
 {
     // TODO: need to apply the rotation from the `GlobalTransform` to the ray too. can probably just apply the full affine transformation?
-    rays.for_each(|(ray, ray_origin, ray_owner)| {
-        rects.for_each(|(rect, rect_center, rect_owner)| {
-            if let Ok(mut collision_events) = collision_takers.get_mut(ray_owner.get()) {
-                let collision = Ray::intersect_aabb(
-                    ray_origin.translation().xy(),
-                    ray,
-                    rect_center.translation().xy(),
-                    rect,
-                );
-                if let Some(collision) = collision {
-                    collision_events.buffer.push(CollisionEvent {
-                        entity: rect_owner.get(),
-                        user_type: user_types.get(rect_owner.get()).unwrap().clone(),
-                        data: CollisionData::Ray(collision),
-                    });
+    for (ray, ray_origin, ray_owner, ray_layers) in &rays {
+        let Ok(mut collision_events) = collision_takers.get_mut(ray_owner.get()) else {
+            continue;
+        };
+        let start = ray_origin.translation().xy();
+        let end = start + ray.0;
+        for e in broad_phase.query(start.min(end), start.max(end)) {
+            let Ok((rect, circle, rect_center, rect_owner, rect_layers)) = shapes.get(e) else {
+                continue;
+            };
+            // ground rays start on their owner's own hit box, which can be ground too, like a crate
+            if rect_owner.get() == ray_owner.get() || !layers_interact(ray_layers, rect_layers) {
+                continue;
+            }
+            let center = rect_center.translation().xy();
+            let collision = match ColliderShape::of(rect, circle) {
+                Some(ColliderShape::Rect(size)) => {
+                    Ray::intersect_aabb(start, ray, center, &Rect(size))
                 }
+                Some(ColliderShape::Circle(radius)) => {
+                    Ray::intersect_circle(start, ray, center, radius)
+                }
+                None => None,
+            };
+            if let Some(collision) = collision {
+                collision_events.buffer.push(CollisionEvent {
+                    entity: rect_owner.get(),
+                    user_type: user_types.get(rect_owner.get()).unwrap().clone(),
+                    data: CollisionData::Ray(collision),
+                    ignored: false,
+                    response: ContactResponse::SOLID,
+                });
             }
-        });
-    });
+        }
+    }
 }
 
-// todo: should only check for rects that are interactable? i.e. don't check ground-ground interactions somehow
-// maybe just need collision layers
 pub fn check_box_to_box_collisions<T>(
-    rects: Query<(&Rect, &GlobalTransform, &Parent)>,
+    rects: Query<(&Rect, &GlobalTransform, &Parent, Option<&CollisionLayers>)>,
+    broad_phase: Res<BroadPhase>,
     user_types: Query<&T>,
     mut collision_takers: Query<(&mut CollisionEvents<T>, Option<&PositionDelta>)>,
 ) where
     T: Component + Clone,
 {
-    for [(r1, t1, p1), (r2, t2, p2)] in rects.iter_combinations() {
+    for (a, b) in broad_phase.pairs() {
+        let Ok([(r1, t1, p1, l1), (r2, t2, p2, l2)]) = rects.get_many([a, b]) else {
+            continue;
+        };
+        if !layers_interact(l1, l2) {
+            continue;
+        }
         if let Ok((mut collision_events, d)) = collision_takers.get_mut(p1.get()) {
             let PositionDelta { origin, ray } = d.copied().unwrap_or(PositionDelta {
                 origin: t1.translation().truncate(),
@@ -448,6 +954,8 @@ pub fn check_box_to_box_collisions<T>(
                     entity: p2.get(),
                     user_type: user_types.get(p2.get()).unwrap().clone(),
                     data: CollisionData::Aabb(collision),
+                    ignored: false,
+                    response: ContactResponse::SOLID,
                 });
             }
         }
@@ -464,12 +972,112 @@ pub fn check_box_to_box_collisions<T>(
                     entity: p1.get(),
                     user_type: user_types.get(p1.get()).unwrap().clone(),
                     data: CollisionData::Aabb(collision),
+                    ignored: false,
+                    response: ContactResponse::SOLID,
                 });
             }
         }
     }
 }
 
+/// pairs with at least one circle, boxes against boxes are in
+/// `check_box_to_box_collisions`
+pub fn check_circle_collisions<T>(
+    shapes: Query<
+        (
+            Option<&Rect>,
+            Option<&Circle>,
+            &GlobalTransform,
+            &Parent,
+            Option<&CollisionLayers>,
+        ),
+        Or<(With<Rect>, With<Circle>)>,
+    >,
+    broad_phase: Res<BroadPhase>,
+    user_types: Query<&T>,
+    mut collision_takers: Query<(&mut CollisionEvents<T>, Option<&PositionDelta>)>,
+) where
+    T: Component + Clone,
+{
+    for (a, b) in broad_phase.pairs() {
+        let Ok([(r1, c1, t1, p1, l1), (r2, c2, t2, p2, l2)]) = shapes.get_many([a, b]) else {
+            continue;
+        };
+        if c1.is_none() && c2.is_none() {
+            continue;
+        }
+        let (Some(s1), Some(s2)) = (ColliderShape::of(r1, c1), ColliderShape::of(r2, c2)) else {
+            continue;
+        };
+        if !layers_interact(l1, l2) {
+            continue;
+        }
+
+        let sides = [
+            (s1, t1, p1.get(), s2, t2, p2.get()),
+            (s2, t2, p2.get(), s1, t1, p1.get()),
+        ];
+        for (shape, t, owner, other, other_t, other_owner) in sides {
+            let Ok((mut collision_events, d)) = collision_takers.get_mut(owner) else {
+                continue;
+            };
+            let PositionDelta { origin, ray } = d.copied().unwrap_or(PositionDelta {
+                origin: t.translation().truncate(),
+                ray: Vec2::ZERO,
+            });
+            if let Some(collision) =
+                shape.sweep(origin, other, other_t.translation().truncate(), ray)
+            {
+                collision_events.buffer.push(CollisionEvent {
+                    entity: other_owner,
+                    user_type: user_types.get(other_owner).unwrap().clone(),
+                    data: CollisionData::Aabb(collision),
+                    ignored: false,
+                    response: ContactResponse::SOLID,
+                });
+            }
+        }
+    }
+}
+
+/// gives contacts the response of the entity they're with, systems that change
+/// single contacts in `CollisionSets::PreSolve` should run after this
+pub fn apply_contact_responses<T>(
+    mut collision_takers: Query<(&mut CollisionEvents<T>, Option<&Restitution>)>,
+    responses: Query<&ContactResponse>,
+    restitutions: Query<&Restitution>,
+) where
+    T: Component + Clone,
+{
+    for (mut events, own_restitution) in &mut collision_takers {
+        for event in &mut events.buffer {
+            if let Ok(response) = responses.get(event.entity) {
+                event.response = *response;
+            }
+            let both = own_restitution
+                .into_iter()
+                .chain(restitutions.get(event.entity));
+            for Restitution(restitution) in both {
+                event.response.restitution = event.response.restitution.max(*restitution);
+            }
+        }
+    }
+}
+
+fn send_collided<T>(
+    collision_takers: Query<(Entity, &CollisionEvents<T>)>,
+    mut collided: EventWriter<Collided<T>>,
+) where
+    T: Component + Clone,
+{
+    for (receiver, events) in &collision_takers {
+        collided.send_batch(events.buffer.iter().map(|event| Collided {
+            receiver,
+            event: event.clone(),
+        }));
+    }
+}
+
 fn cleanup_buffers<T>(mut buffers: Query<&mut CollisionEvents<T>>)
 where
     T: Component + Clone,
@@ -479,38 +1087,153 @@ where
     }
 }
 
-pub struct CollisionDebugPlugin;
-impl Plugin for CollisionDebugPlugin {
+#[derive(Default)]
+pub struct CollisionDebugPlugin<T: DebugColor>(PhantomData<T>);
+impl<T> Plugin for CollisionDebugPlugin<T>
+where
+    T: DebugColor,
+{
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_plugin(DebugLinesPlugin::default()).add_system(
-            draw_collision_shapes
-                .in_base_set(CoreSet::PostUpdate)
-                .after(Collision),
-        );
+        app.add_plugin(DebugLinesPlugin::default())
+            .insert_resource(CollisionDebugSettings { enabled: true })
+            .add_system(
+                draw_collision_shapes::<T>
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(Collision)
+                    .run_if(|settings: Res<CollisionDebugSettings>| settings.enabled),
+            );
+    }
+}
+
+impl<T> CollisionDebugPlugin<T>
+where
+    T: DebugColor,
+{
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// whether [`CollisionDebugPlugin`] draws the collision shapes
+#[derive(Resource)]
+pub struct CollisionDebugSettings {
+    pub enabled: bool,
+}
+
+/// How [`CollisionDebugPlugin`] draws colliders owned by an entity with this
+/// component. Colliders without an owner are drawn red.
+pub trait DebugColor: Component {
+    fn debug_color(&self) -> Color;
+
+    /// sensors only detect overlaps and are drawn dashed
+    fn is_sensor(&self) -> bool {
+        false
+    }
+}
+
+const UNOWNED_COLOR: Color = Color::RED;
+const DASH_LENGTH: f32 = 4.0;
+/// size of the cross drawn at the end of ground rays
+const GROUND_RAY_TIP: f32 = 2.0;
+/// straight lines each circle collider is drawn with
+const CIRCLE_SEGMENTS: usize = 24;
+
+/// `T` on the collider itself or on the entity it's a child of
+fn collider_owner<'a, T: Component>(
+    entity: Entity,
+    parent: Option<&Parent>,
+    owners: &'a Query<&T>,
+) -> Option<&'a T> {
+    owners
+        .get(entity)
+        .ok()
+        .or_else(|| owners.get(parent?.get()).ok())
+}
+
+fn dashed_line(lines: &mut DebugLines, start: Vec3, end: Vec3, color: Color) {
+    let length = start.distance(end);
+    let dashes = (length / (DASH_LENGTH * 2.0)).ceil() as usize;
+    for i in 0..dashes {
+        let from = (i as f32 * DASH_LENGTH * 2.0) / length;
+        let to = ((i as f32 * DASH_LENGTH * 2.0 + DASH_LENGTH) / length).min(1.0);
+        lines.line_colored(start.lerp(end, from), start.lerp(end, to), 0.0, color);
     }
 }
 
-fn draw_collision_shapes(
+fn draw_collision_shapes<T: DebugColor>(
     mut lines: ResMut<DebugLines>,
     mut shapes: ResMut<DebugShapes>,
-    rays: Query<(&Ray, &GlobalTransform)>,
-    rects: Query<(&Rect, &GlobalTransform)>,
+    rays: Query<(
+        Entity,
+        &Ray,
+        &GlobalTransform,
+        Option<&Parent>,
+        Option<&GroundRay>,
+    )>,
+    rects: Query<(Entity, &Rect, &GlobalTransform, Option<&Parent>)>,
+    circles: Query<(Entity, &Circle, &GlobalTransform, Option<&Parent>)>,
+    owners: Query<&T>,
 ) {
-    for (r, t) in &rays {
-        lines.line_colored(
-            t.translation(),
-            t.translation() + r.0.extend(0.0),
-            0.0,
-            Color::RED,
-        );
+    for (e, r, t, parent, ground_ray) in &rays {
+        let color = collider_owner(e, parent, &owners).map_or(UNOWNED_COLOR, T::debug_color);
+        let start = t.translation();
+        let end = start + r.0.extend(0.0);
+        lines.line_colored(start, end, 0.0, color);
+        // ground rays decide if something is standing, mark where they end
+        if ground_ray.is_some() {
+            let tip = Vec3::new(GROUND_RAY_TIP, GROUND_RAY_TIP, 0.0);
+            let flipped = Vec3::new(-GROUND_RAY_TIP, GROUND_RAY_TIP, 0.0);
+            lines.line_colored(end - tip, end + tip, 0.0, Color::WHITE);
+            lines.line_colored(end - flipped, end + flipped, 0.0, Color::WHITE);
+        }
     }
 
-    for (size, t) in &rects {
-        shapes
-            .rect()
-            .size(size.0)
-            .position(t.translation())
-            .color(Color::RED);
+    for (e, size, t, parent) in &rects {
+        let owner = collider_owner(e, parent, &owners);
+        let color = owner.map_or(UNOWNED_COLOR, T::debug_color);
+        if !owner.is_some_and(T::is_sensor) {
+            shapes
+                .rect()
+                .size(size.0)
+                .position(t.translation())
+                .color(color);
+            continue;
+        }
+        let half = (size.0 / 2.0).extend(0.0);
+        let center = t.translation();
+        let corners = [
+            center + Vec3::new(-half.x, -half.y, 0.0),
+            center + Vec3::new(half.x, -half.y, 0.0),
+            center + Vec3::new(half.x, half.y, 0.0),
+            center + Vec3::new(-half.x, half.y, 0.0),
+        ];
+        for i in 0..corners.len() {
+            dashed_line(
+                &mut lines,
+                corners[i],
+                corners[(i + 1) % corners.len()],
+                color,
+            );
+        }
+    }
+
+    // sensors leave out every other segment
+    for (e, radius, t, parent) in &circles {
+        let owner = collider_owner(e, parent, &owners);
+        let color = owner.map_or(UNOWNED_COLOR, T::debug_color);
+        let step = if owner.is_some_and(T::is_sensor) {
+            2
+        } else {
+            1
+        };
+        let center = t.translation();
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (Vec2::from_angle(angle) * radius.0).extend(0.0)
+        };
+        for i in (0..CIRCLE_SEGMENTS).step_by(step) {
+            lines.line_colored(point(i), point(i + 1), 0.0, color);
+        }
     }
 }
 
@@ -651,4 +1374,224 @@ mod tests {
             assert!(result.is_none());
         }
     }
+
+    mod collision_layers {
+        use crate::collisions::CollisionLayers;
+
+        #[test]
+        fn needs_layer_in_both_filters() {
+            let ground = CollisionLayers::new(1, !1);
+            assert!(!ground.interacts(&ground));
+            assert!(ground.interacts(&CollisionLayers::ALL));
+            assert!(CollisionLayers::ALL.interacts(&ground));
+
+            // on a layer ground collides with, but only looking for other layers
+            let one_sided = CollisionLayers::new(2, 4);
+            assert!(!ground.interacts(&one_sided));
+        }
+    }
+
+    mod broad_phase {
+        use bevy::prelude::{Entity, Vec2};
+
+        use crate::collisions::BroadPhase;
+
+        #[test]
+        fn pairs_rects_sharing_a_cell() {
+            let (a, b, c) = (
+                Entity::from_raw(1),
+                Entity::from_raw(2),
+                Entity::from_raw(3),
+            );
+            let mut broad_phase = BroadPhase::default();
+            // a spans two cells and shares both with b
+            broad_phase.insert(a, Vec2::new(50., 0.), Vec2::new(70., 10.));
+            broad_phase.insert(b, Vec2::new(40., 0.), Vec2::new(80., 10.));
+            broad_phase.insert(c, Vec2::new(300., 300.), Vec2::new(310., 310.));
+            assert_eq!(broad_phase.pairs(), [(a, b)]);
+        }
+
+        #[test]
+        fn queries_each_rect_once() {
+            let (a, b) = (Entity::from_raw(1), Entity::from_raw(2));
+            let mut broad_phase = BroadPhase::default();
+            broad_phase.insert(a, Vec2::new(-10., -10.), Vec2::new(10., 10.));
+            broad_phase.insert(b, Vec2::new(200., 0.), Vec2::new(210., 10.));
+            assert_eq!(
+                broad_phase.query(Vec2::new(-5., -5.), Vec2::new(5., 5.)),
+                [a]
+            );
+            assert_eq!(
+                broad_phase.query(Vec2::new(0., 0.), Vec2::new(250., 0.)),
+                [a, b]
+            );
+        }
+    }
+
+    mod sweep_circle {
+        use bevy::prelude::Vec2;
+
+        use crate::collisions::{Circle, Rect, Sweep};
+
+        fn sweep(position: [f32; 2], time: f32, normal: [f32; 2]) -> Sweep {
+            Sweep {
+                position: Vec2::from_array(position),
+                time,
+                normal: Vec2::from_array(normal),
+            }
+        }
+
+        #[test]
+        fn circle_hits_box_side() {
+            let result = Circle::sweep_aabb(
+                Vec2::new(10., 0.),
+                2.,
+                Vec2::ZERO,
+                Vec2::new(6., 6.),
+                Vec2::new(-10., 0.),
+            );
+            assert_eq!(result, Some(sweep([5., 0.], 5., [1., 0.])));
+        }
+
+        #[test]
+        fn circle_rolls_around_box_corner() {
+            let result = Circle::sweep_aabb(
+                Vec2::new(10., 10.),
+                2.,
+                Vec2::ZERO,
+                Vec2::new(6., 6.),
+                Vec2::new(-10., -10.),
+            )
+            .unwrap();
+            let diagonal = Vec2::ONE.normalize();
+            assert!(result.normal.abs_diff_eq(diagonal, 1e-5));
+            assert!(result
+                .position
+                .abs_diff_eq(Vec2::splat(3.) + diagonal * 2., 1e-4));
+        }
+
+        #[test]
+        fn circle_misses_past_box_corner() {
+            // inside the grown box's corner but outside the rounded one
+            let result = Circle::sweep_aabb(
+                Vec2::new(6., 3.8),
+                2.,
+                Vec2::ZERO,
+                Vec2::new(6., 6.),
+                Vec2::new(-2., 2.),
+            );
+            assert_eq!(result, None);
+        }
+
+        #[test]
+        fn circle_hits_circle() {
+            let result =
+                Circle::sweep_circle(Vec2::new(0., 10.), 1., Vec2::ZERO, 2., Vec2::new(0., -10.));
+            assert_eq!(result, Some(sweep([0., 3.], 7., [0., 1.])));
+        }
+
+        #[test]
+        fn still_circle_is_pushed_out_of_box() {
+            let result = Circle::sweep_aabb(
+                Vec2::new(4., 0.),
+                2.,
+                Vec2::ZERO,
+                Vec2::new(6., 6.),
+                Vec2::ZERO,
+            );
+            assert_eq!(result, Some(sweep([5., 0.], 0., [1., 0.])));
+        }
+
+        #[test]
+        fn box_hits_circle() {
+            let result = Rect::sweep_circle(
+                Vec2::new(-10., 0.),
+                Vec2::new(4., 4.),
+                Vec2::ZERO,
+                2.,
+                Vec2::new(10., 0.),
+            );
+            assert_eq!(result, Some(sweep([-4., 0.], 6., [-1., 0.])));
+        }
+    }
+
+    mod collision_events {
+        use bevy::prelude::{Entity, Vec2};
+
+        use crate::collisions::{
+            CollisionData, CollisionEvent, CollisionEvents, ContactResponse, Sweep,
+        };
+
+        fn event(entity: Entity) -> CollisionEvent<()> {
+            CollisionEvent {
+                entity,
+                user_type: (),
+                data: CollisionData::Aabb(Sweep {
+                    position: Vec2::ZERO,
+                    time: 0.,
+                    normal: Vec2::Y,
+                }),
+                ignored: false,
+                response: ContactResponse::SOLID,
+            }
+        }
+
+        #[test]
+        fn ignored_contacts_are_not_solid() {
+            let (a, b) = (Entity::from_raw(1), Entity::from_raw(2));
+            let mut events = CollisionEvents::new();
+            events.buffer.extend([event(a), event(b), event(a)]);
+            events.ignore(a);
+            let solid: Vec<_> = events.solid().map(|event| event.entity).collect();
+            assert_eq!(solid, [b]);
+            assert_eq!(events.buffer.len(), 3);
+        }
+    }
+
+    mod point_in_rects {
+        use bevy::prelude::{Entity, GlobalTransform, Vec2, Vec3};
+
+        use crate::collisions::{point_in_rects, Rect};
+
+        #[test]
+        fn finds_rect_containing_point() {
+            let a = Entity::from_raw(1);
+            let b = Entity::from_raw(2);
+            let rects = [
+                (a, Rect(Vec2::new(10., 10.)), GlobalTransform::IDENTITY),
+                (
+                    b,
+                    Rect(Vec2::new(4., 4.)),
+                    GlobalTransform::from_translation(Vec3::new(20., 0., 0.)),
+                ),
+            ];
+            let hit = |point| point_in_rects(point, rects.iter().map(|(e, r, t)| (*e, r, t)));
+            assert_eq!(hit(Vec2::new(4., -4.)), Some(a));
+            assert_eq!(hit(Vec2::new(21., 2.)), Some(b));
+            assert_eq!(hit(Vec2::new(10., 0.)), None);
+        }
+    }
+
+    // tests for `CircleBundle`
+    mod circle_bundle {
+        use bevy::prelude::{GlobalTransform, Vec2, World};
+
+        use crate::collisions::{Circle, CircleBundle, ColliderShape};
+
+        #[test]
+        fn spawns_a_round_collider() {
+            let mut world = World::new();
+            let e = world.spawn(CircleBundle::new(4.)).id();
+            // the collision systems only see colliders with a `GlobalTransform`
+            let mut colliders = world.query::<(&Circle, &GlobalTransform)>();
+            let (circle, _) = colliders.get(&world, e).unwrap();
+            let shape = ColliderShape::of(None, Some(circle)).unwrap();
+            assert_eq!(shape, ColliderShape::Circle(4.));
+
+            // a box falling onto it stops at its top, not at a square's corner
+            let falling = ColliderShape::Rect(Vec2::new(2., 2.));
+            let hit = falling.sweep(Vec2::new(3., 10.), shape, Vec2::ZERO, Vec2::new(0., -10.));
+            assert!(hit.unwrap().position.y < 5.);
+        }
+    }
 }
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
+
+use crate::collisions::CollisionDebugSettings;
+
+pub struct ConfigPlugin;
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        // these are overridden by settings.game.ron
+        app.insert_resource(AudioConfig {
+            master: 1.0,
+            music: 0.6,
+            sfx: 1.0,
+            duck_amount: 0.5,
+            fade_seconds: 0.6,
+            max_sfx_per_frame: 4,
+            sfx_kinds: HashMap::new(),
+        })
+        .insert_resource(CameraConfig {
+            center: (360.0, 360.0),
+            zoom: 1.0,
+            rotation_speed: 6.0,
+        })
+        .insert_resource(AssistConfig {
+            game_speed: 1.0,
+            soft_respawn: false,
+            rotate_camera: false,
+            input_frame: InputFrame::Screen,
+            landing_lag: true,
+        })
+        .insert_resource(AccessibilitySettings {
+            mono_audio: false,
+            sound_cues: false,
+            reduced_motion: false,
+            screen_reader: false,
+            ui_scale: 1.0,
+        })
+        .insert_resource(DebugConfig {
+            show_colliders: true,
+            level_skip: true,
+            show_nav_paths: false,
+            mouse_tools: true,
+            detect_leaks: true,
+            show_input_stats: false,
+        })
+        .add_startup_system(load_config)
+        .add_system(monitor_config_changes)
+        .add_system(apply_game_speed.run_if(resource_changed::<AssistConfig>()))
+        .add_system(apply_debug_config.run_if(resource_changed::<DebugConfig>()));
+    }
+}
+
+/// Settings that aren't physics, split into a resource per section when loaded
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "a4e2c7d3-8b61-4f2a-9c0e-1d5b7f3e6a29"]
+pub struct GameConfig {
+    pub audio: AudioConfig,
+    pub camera: CameraConfig,
+    pub assist: AssistConfig,
+    pub accessibility: AccessibilitySettings,
+    pub debug: DebugConfig,
+}
+
+/// default bus volumes
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct AudioConfig {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    /// how much music is lowered during dialogs and menus, 0.0 to 1.0
+    pub duck_amount: f32,
+    /// how long music and sounds take to fade out when leaving a state or quitting
+    pub fade_seconds: f32,
+    /// most sound effects started in one frame, the lowest priority ones are dropped
+    pub max_sfx_per_frame: usize,
+    /// priority and voice limit for each kind of sound effect, keyed by `Sfx::kind`
+    #[serde(default)]
+    pub sfx_kinds: HashMap<String, SfxKindConfig>,
+}
+
+impl AudioConfig {
+    /// kinds missing from the config have priority 0 and no voice limit
+    pub fn sfx_kind(&self, kind: &str) -> SfxKindConfig {
+        self.sfx_kinds.get(kind).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct SfxKindConfig {
+    /// higher priority sounds are kept when too many play at once
+    pub priority: i32,
+    /// most sounds of this kind playing at the same time, a new one stops the oldest
+    pub max_voices: Option<usize>,
+}
+
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct CameraConfig {
+    /// world position the camera looks at
+    pub center: (f32, f32),
+    /// orthographic scale, larger values zoom out
+    pub zoom: f32,
+    /// how fast the camera turns to follow gravity, in radians per second
+    pub rotation_speed: f32,
+}
+
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct AssistConfig {
+    /// multiplier on the speed of time, lower values slow the game down
+    pub game_speed: f32,
+    /// put the player back on the last safe ground instead of restarting
+    /// the level when they fall out of it
+    pub soft_respawn: bool,
+    /// turn the camera so the player's gravity always points down on screen,
+    /// movement keys follow the screen
+    pub rotate_camera: bool,
+    pub input_frame: InputFrame,
+    /// slow the player for a moment after a hard landing, see
+    /// `PhysicsSettings::landing_lag_speed`
+    pub landing_lag: bool,
+}
+
+/// what the movement directions are relative to
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFrame {
+    /// up is up on screen, so when standing on a wall up and down move along it
+    Screen,
+    /// right is the player's right, so left and right always move along the floor
+    Gravity,
+}
+
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct AccessibilitySettings {
+    /// mix sound effects down to one channel
+    pub mono_audio: bool,
+    /// flash a caption on screen whenever a sound effect plays
+    pub sound_cues: bool,
+    /// turn off flashing, shaking and scrolling effects. Visual effects should
+    /// check this and either skip or snap straight to their end state
+    pub reduced_motion: bool,
+    /// read out menu items as they're selected, see `MenuLabel`
+    pub screen_reader: bool,
+    /// size of menus and other UI, 1.0 is normal and 2.0 doubles it
+    pub ui_scale: f32,
+}
+
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct DebugConfig {
+    pub show_colliders: bool,
+    /// allow skipping levels with the 0 key
+    pub level_skip: bool,
+    /// draw navigation paths from the player to each goal
+    pub show_nav_paths: bool,
+    /// click to teleport the player and drag colliders around with the mouse, right
+    /// click to inspect them when built with the `inspector` feature
+    pub mouse_tools: bool,
+    /// warn about level entities that outlive their level
+    pub detect_leaks: bool,
+    /// list the delay from pressing jump to jumping for the last few jumps
+    pub show_input_stats: bool,
+}
+
+#[derive(Resource)]
+#[allow(dead_code)] // keeps the config asset alive for hot reloading
+struct GameConfigHandle(pub Handle<GameConfig>);
+
+fn load_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load("settings.game.ron");
+    commands.insert_resource(GameConfigHandle(handle));
+}
+
+fn monitor_config_changes(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    configs: Res<Assets<GameConfig>>,
+) {
+    for e in &mut events {
+        match e {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                let config = configs.get(handle).unwrap();
+                commands.insert_resource(config.audio.clone());
+                commands.insert_resource(config.camera.clone());
+                commands.insert_resource(config.assist.clone());
+                commands.insert_resource(config.accessibility.clone());
+                commands.insert_resource(config.debug.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn apply_game_speed(assist: Res<AssistConfig>, mut time: ResMut<Time>) {
+    time.set_relative_speed(assist.game_speed.max(0.0));
+}
+
+fn apply_debug_config(
+    debug: Res<DebugConfig>,
+    mut collision_debug: ResMut<CollisionDebugSettings>,
+) {
+    collision_debug.enabled = debug.show_colliders;
+}
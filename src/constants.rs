@@ -1,10 +1,53 @@
 use bevy::prelude::*;
 
-pub const PLAYER_DIM: Vec2 = Vec2::new(30.0, 20.0);
+use crate::collisions::{CollisionLayers, DebugColor};
+
+/// `CollisionLayers` bit for the level's ground cells
+pub const STATIC_GROUND: u32 = 1;
+/// ground cells never move, so they don't need to be checked against each other
+pub const STATIC_GROUND_LAYERS: CollisionLayers =
+    CollisionLayers::new(STATIC_GROUND, !STATIC_GROUND);
 
 #[derive(Component, Clone, PartialEq, Eq)]
 pub enum CollisionTypes {
     Player,
     Goal,
     Ground,
+    JumpPad,
+    Enemy,
+    Pickup,
+    Door,
+    Checkpoint,
+    /// enemies that can be stomped, see `EnemyPlugin`
+    Walker,
+    /// hurt by landing on it, see `boss_contact`
+    Boss,
+}
+
+impl DebugColor for CollisionTypes {
+    fn debug_color(&self) -> Color {
+        match self {
+            CollisionTypes::Player => Color::GREEN,
+            CollisionTypes::Goal => Color::YELLOW,
+            CollisionTypes::Ground => Color::GRAY,
+            CollisionTypes::JumpPad => Color::CYAN,
+            CollisionTypes::Enemy => Color::RED,
+            CollisionTypes::Pickup => Color::ORANGE,
+            CollisionTypes::Door => Color::PURPLE,
+            CollisionTypes::Checkpoint => Color::TEAL,
+            CollisionTypes::Walker => Color::MAROON,
+            CollisionTypes::Boss => Color::CRIMSON,
+        }
+    }
+
+    fn is_sensor(&self) -> bool {
+        matches!(
+            self,
+            CollisionTypes::Goal
+                | CollisionTypes::JumpPad
+                | CollisionTypes::Pickup
+                | CollisionTypes::Door
+                | CollisionTypes::Checkpoint
+        )
+    }
 }
@@ -0,0 +1,157 @@
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    player::{JumpAction, MovementAction, Player},
+    preload::Preloaded,
+};
+
+/// how long a connect/disconnect notice stays on screen
+const NOTICE_SECONDS: f32 = 2.5;
+
+pub struct ControllerPlugin;
+impl Plugin for ControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClaimedGamepad>()
+            .add_startup_system(spawn_notice_container)
+            .add_systems((
+                connection_notices,
+                claim_gamepad.after(connection_notices),
+                fade_notices,
+                apply_claimed_gamepad.after(claim_gamepad),
+            ));
+    }
+}
+
+/// The gamepad the player's input is read from. Until a button is pressed on
+/// one, input is accepted from every connected gamepad.
+#[derive(Resource, Default)]
+pub struct ClaimedGamepad(pub Option<Gamepad>);
+
+/// column in the bottom left corner notices are added to
+#[derive(Component)]
+struct NoticeContainer;
+
+#[derive(Component)]
+struct ControllerNotice(Timer);
+
+fn spawn_notice_container(mut commands: Commands) {
+    commands.spawn((
+        NoticeContainer,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn connection_notices(
+    mut commands: Commands,
+    mut events: EventReader<GamepadConnectionEvent>,
+    mut claimed: ResMut<ClaimedGamepad>,
+    preloaded: Res<Preloaded>,
+    container: Query<Entity, With<NoticeContainer>>,
+) {
+    for event in &mut events {
+        let message = match &event.connection {
+            GamepadConnection::Connected(info) => {
+                format!("{} connected, press a button to use it", info.name)
+            }
+            GamepadConnection::Disconnected => {
+                if claimed.0 == Some(event.gamepad) {
+                    claimed.0 = None;
+                }
+                format!("Controller {} disconnected", event.gamepad.id)
+            }
+        };
+
+        let Ok(container) = container.get_single() else {
+            continue;
+        };
+        let notice = commands
+            .spawn((
+                ControllerNotice(Timer::from_seconds(NOTICE_SECONDS, TimerMode::Once)),
+                TextBundle::from_section(
+                    message,
+                    TextStyle {
+                        font: preloaded.get("font"),
+                        font_size: 18.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ),
+            ))
+            .id();
+        commands.entity(container).add_child(notice);
+    }
+}
+
+/// the last gamepad a button was pressed on takes over the player's input
+fn claim_gamepad(buttons: Res<Input<GamepadButton>>, mut claimed: ResMut<ClaimedGamepad>) {
+    let Some(button) = buttons.get_just_pressed().last() else {
+        return;
+    };
+    if claimed.0 != Some(button.gamepad) {
+        claimed.0 = Some(button.gamepad);
+    }
+}
+
+fn fade_notices(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notices: Query<(Entity, &mut ControllerNotice, &mut Text)>,
+) {
+    for (e, mut notice, mut text) in &mut notices {
+        if notice.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        for section in &mut text.sections {
+            section
+                .style
+                .color
+                .set_a(notice.0.percent_left().min(0.5) * 2.0);
+        }
+    }
+}
+
+/// point an input map at the claimed gamepad, or every gamepad if none is claimed
+fn assign_gamepad<A: Actionlike>(map: &mut InputMap<A>, claimed: &ClaimedGamepad) {
+    match claimed.0 {
+        Some(gamepad) => {
+            map.set_gamepad(gamepad);
+        }
+        None => {
+            map.clear_gamepad();
+        }
+    }
+}
+
+fn apply_claimed_gamepad(
+    claimed: Res<ClaimedGamepad>,
+    mut jump: Query<&mut InputMap<JumpAction>, With<Player>>,
+    mut movement: Query<&mut InputMap<MovementAction>, With<Player>>,
+) {
+    // newly spawned players need the claim applied too
+    for mut map in &mut jump {
+        if claimed.is_changed() || map.is_added() {
+            assign_gamepad(&mut map, &claimed);
+        }
+    }
+    for mut map in &mut movement {
+        if claimed.is_changed() || map.is_added() {
+            assign_gamepad(&mut map, &claimed);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    bounds::DespawnOutOfBounds,
+    character_controller::{KinematicBody, KinematicBodyBundle},
+    collisions::{CollisionData, CollisionEvents, CollisionSets, PositionDelta},
+    constants::CollisionTypes,
+    game_state::GameState,
+    physics::{
+        Direction, ExternalForces, Gravity, GravityDirection, OnGround, PhysicsSettings,
+        SizedCollider, Velocity,
+    },
+    player::Player,
+};
+
+/// fraction of the pusher's speed a crate slides at, so it feels heavy
+const PUSH_TRANSFER: f32 = 0.8;
+/// how fast a crate on the ground stops sliding once nothing pushes it, in pixels a second squared
+const SLIDE_FRICTION: f32 = 900.0;
+const CRATE_COLOR: Color = Color::rgb(0.55, 0.38, 0.2);
+
+/// ground tile sized, and falls off ledges it's pushed over
+const CRATE_BODY: KinematicBody = KinematicBody {
+    collider: SizedCollider::Ground,
+    ground_rays: true,
+};
+
+/// Crates are ground the player can stand on and shove sideways. They fall
+/// under gravity and rest on ground and on each other.
+pub struct CratePlugin;
+impl Plugin for CratePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<CrateBundle>("Crate")
+            .add_system(after_crate_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            // crates can also come back while playing, see `spawn_crate`
+            .add_system(after_crate_spawned.in_set(GameState::Playing))
+            .add_system(
+                slow_sliding
+                    .in_set(ExternalForces)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(push_crates.in_set(CollisionSets::Consume));
+    }
+}
+
+/// Slides along its floor when the player, or another crate, walks into its side.
+#[derive(Component, Default)]
+pub struct Pushable;
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct CrateBundle {
+    pushable: Pushable,
+    #[with(crate_body)]
+    body: KinematicBodyBundle,
+    sprite: SpriteBundle,
+    despawn_out_of_bounds: DespawnOutOfBounds,
+}
+
+fn crate_body(_: &EntityInstance) -> KinematicBodyBundle {
+    KinematicBodyBundle::new(CRATE_BODY)
+}
+
+/// a crate put back while playing, like by a checkpoint respawn
+pub fn spawn_crate(commands: &mut Commands, transform: Transform, g_dir: Direction) -> Entity {
+    commands
+        .spawn(CrateBundle {
+            body: KinematicBodyBundle {
+                g_dir: GravityDirection(g_dir),
+                ..KinematicBodyBundle::new(CRATE_BODY)
+            },
+            sprite: SpriteBundle {
+                transform,
+                ..default()
+            },
+            ..default()
+        })
+        .id()
+}
+
+fn after_crate_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Gravity, &mut Sprite), Added<Pushable>>,
+    settings: Res<PhysicsSettings>,
+) {
+    for (e, mut gravity, mut sprite) in &mut q {
+        gravity.0 = settings.gravity_unpressed;
+        sprite.custom_size = Some(settings.colliders.get(SizedCollider::Ground));
+        sprite.color = CRATE_COLOR;
+        // ground, so the player and other crates are stopped by it and can stand on it
+        commands.entity(e).insert(CollisionTypes::Ground);
+    }
+}
+
+/// Velocity a crate should slide at after a pusher moving at `attempted` hits
+/// the side of it with a contact `normal`. Sides are relative to the pusher's
+/// gravity, and the crate only slides along its own floor.
+fn push_velocity(
+    normal: Vec2,
+    pusher_gravity: Direction,
+    attempted: Vec2,
+    crate_gravity: Direction,
+) -> Option<Vec2> {
+    // landing on top or bumping the bottom doesn't push
+    if normal.dot(pusher_gravity.as_vec2()) != 0.0 {
+        return None;
+    }
+    let push = -normal;
+    let speed = attempted.dot(push);
+    let floor = GravityDirection(crate_gravity).forward().as_vec2();
+    let along = push.dot(floor);
+    (speed > 0.0 && along != 0.0).then_some(floor * along * speed * PUSH_TRANSFER)
+}
+
+/// Pushers are already stopped by the crate's side, so their speed is taken
+/// from how far they tried to move this tick.
+fn push_crates(
+    pushers: Query<
+        (
+            &CollisionEvents<CollisionTypes>,
+            &GravityDirection,
+            &PositionDelta,
+        ),
+        Or<(With<Player>, With<Pushable>)>,
+    >,
+    mut crates: Query<(&mut Velocity, &GravityDirection), With<Pushable>>,
+    time_step: Res<FixedTime>,
+) {
+    let dt = time_step.period.as_secs_f32();
+    for (events, g_dir, delta) in &pushers {
+        let attempted = delta.ray / dt;
+        for event in events.solid() {
+            let CollisionData::Aabb(ref sweep) = event.data else {
+                continue;
+            };
+            let Ok((mut v, crate_g_dir)) = crates.get_mut(event.entity) else {
+                continue;
+            };
+            let Some(push) = push_velocity(sweep.normal, g_dir.0, attempted, crate_g_dir.0) else {
+                continue;
+            };
+            // only ever speed it up, a slower pusher behind it doesn't hold it back
+            let floor = push.normalize();
+            let current = v.0.dot(floor);
+            if current < push.length() {
+                v.0 += floor * (push.length() - current);
+            }
+        }
+    }
+}
+
+fn slow_sliding(
+    mut crates: Query<(&mut Velocity, &GravityDirection, &OnGround), With<Pushable>>,
+    time_step: Res<FixedTime>,
+) {
+    let step = SLIDE_FRICTION * time_step.period.as_secs_f32();
+    for (mut v, g_dir, on_ground) in &mut crates {
+        if !on_ground.0 {
+            continue;
+        }
+        let floor = g_dir.forward().as_vec2();
+        let sliding = v.0.dot(floor);
+        let slowed = sliding.signum() * (sliding.abs() - step).max(0.0);
+        v.0 += floor * (slowed - sliding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_sides_relative_to_the_pusher_push() {
+        let walking_right = Vec2::new(200.0, 0.0);
+        // walking into the crate's left side
+        let push = push_velocity(Vec2::NEG_X, Direction::Down, walking_right, Direction::Down);
+        assert_eq!(push, Some(Vec2::new(200.0 * PUSH_TRANSFER, 0.0)));
+        // landing on top of it
+        let falling = Vec2::new(0.0, -200.0);
+        assert_eq!(
+            push_velocity(Vec2::Y, Direction::Down, falling, Direction::Down),
+            None
+        );
+        // with gravity to the left the same contact is on its underside
+        assert_eq!(
+            push_velocity(Vec2::NEG_X, Direction::Left, walking_right, Direction::Down),
+            None
+        );
+        // walking away doesn't pull it along
+        assert_eq!(
+            push_velocity(
+                Vec2::NEG_X,
+                Direction::Down,
+                -walking_right,
+                Direction::Down
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Right click a collider with the debug mouse tools on to see its owner's
+//! components in an egui panel. Needs the `inspector` feature.
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext, EguiPlugin},
+    bevy_inspector::ui_for_entity,
+    DefaultInspectorConfigPlugin,
+};
+
+use crate::{
+    collisions::Rect,
+    config::DebugConfig,
+    game_state::GameState,
+    mouse::{collider_owner_at, CursorWorldPos},
+};
+
+pub struct DebugInspectorPlugin;
+impl Plugin for DebugInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(EguiPlugin)
+            .add_plugin(DefaultInspectorConfigPlugin)
+            .init_resource::<Inspected>()
+            .add_system(
+                pick_entity
+                    .in_set(GameState::Playing)
+                    .run_if(|debug: Res<DebugConfig>| debug.mouse_tools),
+            )
+            .add_system(
+                inspector_ui
+                    .run_if(|debug: Res<DebugConfig>| debug.mouse_tools)
+                    .run_if(|inspected: Res<Inspected>| inspected.0.is_some()),
+            );
+    }
+}
+
+/// entity shown in the inspector panel
+#[derive(Resource, Default)]
+struct Inspected(Option<Entity>);
+
+/// right clicking empty space closes the panel
+fn pick_entity(
+    buttons: Res<Input<MouseButton>>,
+    cursor: Res<CursorWorldPos>,
+    colliders: Query<(Entity, &Rect, &GlobalTransform, Option<&Parent>)>,
+    mut inspected: ResMut<Inspected>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(cursor) = cursor.0 else {
+        return;
+    };
+    inspected.0 = collider_owner_at(cursor, &colliders);
+}
+
+/// Only components with registered reflection can be edited, others are
+/// listed by name.
+fn inspector_ui(world: &mut World) {
+    let Some(entity) = world.resource::<Inspected>().0 else {
+        return;
+    };
+    // picked entities can despawn, like goals being collected
+    if world.get_entity(entity).is_none() {
+        world.resource_mut::<Inspected>().0 = None;
+        return;
+    }
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    let mut open = true;
+    egui::Window::new(format!("Inspector {entity:?}"))
+        .id(egui::Id::new("inspector"))
+        .open(&mut open)
+        .default_width(320.0)
+        .show(egui_context.get_mut(), |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui_for_entity(world, entity, ui);
+            });
+        });
+    if !open {
+        world.resource_mut::<Inspected>().0 = None;
+    }
+}
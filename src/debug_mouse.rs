@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+use crate::{
+    collisions::Rect,
+    config::DebugConfig,
+    game_state::GameState,
+    mouse::{collider_owner_at, CursorWorldPos},
+    physics::{Acceleration, Velocity},
+    player::Player,
+};
+
+pub struct DebugMousePlugin;
+impl Plugin for DebugMousePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            mouse_tools
+                .in_set(GameState::Playing)
+                .run_if(|debug: Res<DebugConfig>| debug.mouse_tools),
+        );
+    }
+}
+
+/// collider owner being dragged and where it was grabbed relative to its center
+#[derive(Default)]
+struct Dragging(Option<(Entity, Vec2)>);
+
+/// Pressing on a collider drags whatever owns it, pressing anywhere else
+/// teleports the player there. Velocities are cleared so things stay put.
+fn mouse_tools(
+    buttons: Res<Input<MouseButton>>,
+    cursor: Res<CursorWorldPos>,
+    colliders: Query<(Entity, &Rect, &GlobalTransform, Option<&Parent>)>,
+    player: Query<Entity, With<Player>>,
+    mut movable: Query<(
+        &mut Transform,
+        Option<&mut Velocity>,
+        Option<&mut Acceleration>,
+    )>,
+    mut dragging: Local<Dragging>,
+) {
+    if buttons.just_released(MouseButton::Left) {
+        dragging.0 = None;
+        return;
+    }
+    let Some(cursor) = cursor.0 else {
+        return;
+    };
+
+    let (target, grab_offset) = if buttons.just_pressed(MouseButton::Left) {
+        match collider_owner_at(cursor, &colliders) {
+            Some(owner) => {
+                let Ok((t, _, _)) = movable.get(owner) else {
+                    return;
+                };
+                let grab = (owner, t.translation.truncate() - cursor);
+                dragging.0 = Some(grab);
+                grab
+            }
+            None => {
+                let Ok(player) = player.get_single() else {
+                    return;
+                };
+                (player, Vec2::ZERO)
+            }
+        }
+    } else if buttons.pressed(MouseButton::Left) {
+        let Some(dragged) = dragging.0 else {
+            return;
+        };
+        dragged
+    } else {
+        return;
+    };
+
+    let Ok((mut t, velocity, acceleration)) = movable.get_mut(target) else {
+        dragging.0 = None;
+        return;
+    };
+    t.translation = (cursor + grab_offset).extend(t.translation.z);
+    if let Some(mut velocity) = velocity {
+        velocity.0 = Vec2::ZERO;
+    }
+    if let Some(mut acceleration) = acceleration {
+        acceleration.0 = Vec2::ZERO;
+    }
+}
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    bounds::DespawnOutOfBounds,
+    character_controller::{KinematicBody, KinematicBodyBundle},
+    collisions::{CollisionData, CollisionEvents, CollisionSets},
+    constants::CollisionTypes,
+    game_state::GameState,
+    level_registry::field,
+    physics::{
+        Direction, ExternalForces, Gravity, GravityDirection, OnGround, PhysicsSettings,
+        SizedCollider, Velocity,
+    },
+    player::{DeathCause, Player, PlayerDeath},
+};
+
+/// pixels a second walkers patrol at when their `Speed` isn't set
+const DEFAULT_WALKER_SPEED: f32 = 40.0;
+/// fraction of a jump the player bounces up with after stomping a walker
+const STOMP_BOUNCE: f32 = 0.6;
+const WALKER_COLOR: Color = Color::rgb(0.7, 0.25, 0.3);
+
+const WALKER_BODY: KinematicBody = KinematicBody {
+    collider: SizedCollider::Walker,
+    ground_rays: true,
+};
+
+/// Enemies that walk back and forth along the ground under their own gravity.
+/// Touching one from the side kills the player, landing on one kills it.
+pub struct EnemyPlugin;
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<WalkerBundle>("Walker")
+            .add_system(after_walker_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            // walkers can also come back while playing, see `spawn_walker`
+            .add_system(after_walker_spawned.in_set(GameState::Playing))
+            .add_system(
+                patrol
+                    .in_set(ExternalForces)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems((turn_walkers, walker_contact).in_set(CollisionSets::Consume));
+    }
+}
+
+/// Walks at `speed` along the floor under its `GravityDirection`, turning
+/// around at walls and before walking off an edge.
+#[derive(Component)]
+pub struct Walker {
+    pub speed: f32,
+    /// 1.0 walks towards the gravity's forward direction, -1.0 away from it
+    heading: f32,
+}
+
+impl Default for Walker {
+    fn default() -> Self {
+        Walker {
+            speed: DEFAULT_WALKER_SPEED,
+            heading: 1.0,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct WalkerBundle {
+    #[with(walker_from_instance)]
+    walker: Walker,
+    #[with(walker_body)]
+    body: KinematicBodyBundle,
+    sprite: SpriteBundle,
+    despawn_out_of_bounds: DespawnOutOfBounds,
+}
+
+fn walker_from_instance(instance: &EntityInstance) -> Walker {
+    match field(&instance.field_instances, "Speed") {
+        Some(FieldValue::Float(Some(speed))) if *speed > 0.0 => Walker {
+            speed: *speed,
+            ..default()
+        },
+        _ => Walker::default(),
+    }
+}
+
+/// the `Gravity` field lets walkers patrol walls and ceilings
+fn walker_body(instance: &EntityInstance) -> KinematicBodyBundle {
+    let direction = match field(&instance.field_instances, "Gravity") {
+        Some(FieldValue::Enum(Some(direction))) => match direction.as_str() {
+            "Up" => Direction::Up,
+            "Left" => Direction::Left,
+            "Right" => Direction::Right,
+            _ => Direction::Down,
+        },
+        _ => Direction::Down,
+    };
+    KinematicBodyBundle {
+        g_dir: GravityDirection(direction),
+        ..KinematicBodyBundle::new(WALKER_BODY)
+    }
+}
+
+/// A walker put back while playing, like by a checkpoint respawn. Its speed
+/// comes from the ldtk `instance`.
+pub fn spawn_walker(
+    commands: &mut Commands,
+    transform: Transform,
+    g_dir: Direction,
+    instance: EntityInstance,
+) -> Entity {
+    commands
+        .spawn((
+            WalkerBundle {
+                walker: walker_from_instance(&instance),
+                body: KinematicBodyBundle {
+                    g_dir: GravityDirection(g_dir),
+                    ..KinematicBodyBundle::new(WALKER_BODY)
+                },
+                sprite: SpriteBundle {
+                    transform,
+                    ..default()
+                },
+                ..default()
+            },
+            instance,
+        ))
+        .id()
+}
+
+fn after_walker_spawned(
+    mut commands: Commands,
+    mut q: Query<
+        (
+            Entity,
+            &GravityDirection,
+            &mut Gravity,
+            &mut Transform,
+            &mut Sprite,
+        ),
+        Added<Walker>,
+    >,
+    settings: Res<PhysicsSettings>,
+) {
+    for (e, g_dir, mut gravity, mut t, mut sprite) in &mut q {
+        gravity.0 = settings.gravity_unpressed;
+        // standing on whichever surface its gravity points at
+        t.rotation = Quat::from_rotation_z(g_dir.angle());
+        sprite.custom_size = Some(settings.colliders.get(SizedCollider::Walker));
+        sprite.color = WALKER_COLOR;
+        commands.entity(e).insert(CollisionTypes::Walker);
+    }
+}
+
+/// keeps walkers at their speed along the floor, falling is left to gravity
+fn patrol(mut walkers: Query<(&Walker, &GravityDirection, &mut Velocity)>) {
+    for (walker, g_dir, mut v) in &mut walkers {
+        let forward = g_dir.forward().as_vec2();
+        let along = v.0.dot(forward);
+        v.0 += forward * (walker.heading * walker.speed - along);
+    }
+}
+
+/// Turns around on hitting a wall, or when the ground ray on the leading
+/// corner stops finding ground so the walker doesn't walk off the edge.
+fn turn_walkers(
+    mut walkers: Query<(
+        &mut Walker,
+        &Transform,
+        &OnGround,
+        &GravityDirection,
+        &CollisionEvents<CollisionTypes>,
+    )>,
+) {
+    for (mut walker, t, on_ground, g_dir, events) in &mut walkers {
+        let ahead = g_dir.forward().as_vec2() * walker.heading;
+        let position = t.translation.truncate();
+        let mut ground_ahead = false;
+        let mut wall_ahead = false;
+        for event in events.solid() {
+            if event.user_type != CollisionTypes::Ground {
+                continue;
+            }
+            match event.data {
+                CollisionData::Ray(ref ray) => {
+                    if (ray.ray_origin - position).dot(ahead) > 0.0
+                        && ray.ray_direction.dot(g_dir.as_vec2()) > 0.99
+                    {
+                        ground_ahead = true;
+                    }
+                }
+                CollisionData::Aabb(ref sweep) => {
+                    if sweep.normal.dot(-ahead) > 0.99 {
+                        wall_ahead = true;
+                    }
+                }
+            }
+        }
+        if wall_ahead || (on_ground.0 && !ground_ahead) {
+            walker.heading = -walker.heading;
+        }
+    }
+}
+
+/// Whether a contact with `normal`, pointing from the walker to the player,
+/// is the player landing on top of it. Top is relative to the player's
+/// gravity, not the walker's.
+fn is_stomp(normal: Vec2, player_velocity: Vec2, player_gravity: Direction) -> bool {
+    let up = player_gravity.reverse().as_vec2();
+    normal.dot(up) > 0.99 && player_velocity.dot(up) <= 0.0
+}
+
+fn walker_contact(
+    mut commands: Commands,
+    mut player: Query<
+        (
+            &CollisionEvents<CollisionTypes>,
+            &GravityDirection,
+            &mut Velocity,
+            &mut OnGround,
+        ),
+        With<Player>,
+    >,
+    settings: Res<PhysicsSettings>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    for (events, g_dir, mut v, mut on_ground) in &mut player {
+        for event in &events.buffer {
+            if event.user_type != CollisionTypes::Walker {
+                continue;
+            }
+            // the player's ground rays reaching a walker below don't count
+            let CollisionData::Aabb(ref sweep) = event.data else {
+                continue;
+            };
+            if is_stomp(sweep.normal, v.0, g_dir.0) {
+                commands.entity(event.entity).despawn_recursive();
+                let up = g_dir.reverse().as_vec2();
+                let rising = v.0.dot(up);
+                v.0 += up * (settings.initial_jump_speed * STOMP_BOUNCE - rising);
+                on_ground.0 = false;
+            } else {
+                deaths.send(PlayerDeath {
+                    cause: DeathCause::Enemy,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stomps_follow_the_players_gravity() {
+        let falling_down = Vec2::new(0.0, -200.0);
+        assert!(is_stomp(Vec2::Y, falling_down, Direction::Down));
+        // walking into its side is a hit
+        assert!(!is_stomp(Vec2::X, Vec2::new(-100.0, 0.0), Direction::Down));
+
+        // with gravity pointing left the walker's side is the top
+        let falling_left = Vec2::new(-200.0, 0.0);
+        assert!(is_stomp(Vec2::X, falling_left, Direction::Left));
+        assert!(!is_stomp(Vec2::Y, falling_left, Direction::Left));
+    }
+
+    #[test]
+    fn stomps_allow_float_error_in_the_normal() {
+        let normal = Vec2::new(1e-4, 1.0).normalize();
+        assert!(is_stomp(normal, Vec2::new(0.0, -200.0), Direction::Down));
+    }
+}
@@ -0,0 +1,177 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{
+    ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, GridCoords, LdtkEntity,
+};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+
+use crate::{
+    collisions::{CollisionEvents, CollisionSets, RectBundle},
+    constants::CollisionTypes,
+    game_state::GameState,
+    ground::{FallingGround, Ground},
+    ground_edit::{apply_ground_edits, GroundEdit},
+    level_registry::field,
+    render_layers::RenderLayer,
+};
+
+const DEFAULT_RADIUS: f32 = 48.0;
+const DEBRIS_COUNT: usize = 16;
+const DEBRIS_SPEED: f32 = 180.0;
+const DEBRIS_SECONDS: f32 = 0.6;
+
+pub struct ExplosionPlugin;
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Explosion>()
+            .register_ldtk_entity::<BombBundle>("Bomb")
+            .add_system(after_bomb_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(trigger_bombs.in_set(CollisionSets::Consume))
+            .add_system(
+                destroy_ground
+                    .before(apply_ground_edits)
+                    .in_set(GameState::Playing),
+            )
+            .add_systems((spawn_debris, fade_debris).in_set(GameState::Playing));
+    }
+}
+
+/// sent when something blows up, ground within `radius` of `position` is removed
+pub struct Explosion {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+/// pickup that explodes when the player touches it
+#[derive(Component)]
+pub struct Bomb {
+    pub radius: f32,
+}
+
+impl Default for Bomb {
+    fn default() -> Self {
+        Bomb {
+            radius: DEFAULT_RADIUS,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct BombBundle {
+    #[with(bomb_from_instance)]
+    bomb: Bomb,
+    #[sprite_bundle("bomb.png")]
+    sprite: SpriteBundle,
+}
+
+fn bomb_from_instance(instance: &EntityInstance) -> Bomb {
+    match field(&instance.field_instances, "Radius") {
+        Some(FieldValue::Float(Some(radius))) if *radius > 0.0 => Bomb { radius: *radius },
+        _ => Bomb::default(),
+    }
+}
+
+fn after_bomb_spawned(mut commands: Commands, q: Query<Entity, Added<Bomb>>) {
+    for e in &q {
+        commands
+            .entity(e)
+            .insert((
+                CollisionTypes::Pickup,
+                CollisionEvents::<CollisionTypes>::new(),
+            ))
+            .with_children(|children| {
+                children.spawn(RectBundle::new(Vec2::new(16.0, 16.0)));
+            });
+    }
+}
+
+fn trigger_bombs(
+    mut commands: Commands,
+    bombs: Query<(
+        Entity,
+        &Bomb,
+        &GlobalTransform,
+        &CollisionEvents<CollisionTypes>,
+    )>,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for (e, bomb, t, events) in &bombs {
+        if events
+            .buffer
+            .iter()
+            .any(|event| matches!(event.user_type, CollisionTypes::Player))
+        {
+            explosions.send(Explosion {
+                position: t.translation().truncate(),
+                radius: bomb.radius,
+            });
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn destroy_ground(
+    mut explosions: EventReader<Explosion>,
+    ground: Query<(&GridCoords, &GlobalTransform), (With<Ground>, Without<FallingGround>)>,
+    mut edits: EventWriter<GroundEdit>,
+) {
+    for explosion in &mut explosions {
+        for (&cell, t) in &ground {
+            if t.translation().truncate().distance(explosion.position) <= explosion.radius {
+                edits.send(GroundEdit::Remove(cell));
+            }
+        }
+    }
+}
+
+/// a bit of flying rubble from an explosion
+#[derive(Component)]
+struct Debris {
+    velocity: Vec2,
+    life: Timer,
+}
+
+fn spawn_debris(
+    mut commands: Commands,
+    mut explosions: EventReader<Explosion>,
+    mut rng: ResMut<GlobalRng>,
+) {
+    for explosion in &mut explosions {
+        for i in 0..DEBRIS_COUNT {
+            let angle = (i as f32 + rng.f32()) / DEBRIS_COUNT as f32 * TAU;
+            let speed = DEBRIS_SPEED * (0.5 + rng.f32());
+            commands.spawn((
+                Debris {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    life: Timer::from_seconds(DEBRIS_SECONDS, TimerMode::Once),
+                },
+                RenderLayer::Particles,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(1.0, 0.7, 0.3),
+                        custom_size: Some(Vec2::splat(4.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(explosion.position.extend(0.0)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+fn fade_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris: Query<(Entity, &mut Debris, &mut Transform, &mut Sprite)>,
+) {
+    for (e, mut debris, mut t, mut sprite) in &mut debris {
+        if debris.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        t.translation += (debris.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(debris.life.percent_left());
+    }
+}
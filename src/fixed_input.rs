@@ -0,0 +1,133 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::physics::PhysicsSet;
+
+/// Keeps presses seen in frames between fixed ticks until the next tick, so
+/// systems in `FixedUpdate` can't miss them. At high frame rates most frames
+/// have no tick, and at low ones a frame runs several ticks that should only
+/// see a press once.
+#[derive(Default)]
+pub struct FixedInputPlugin<A: Actionlike>(PhantomData<A>);
+
+impl<A: Actionlike> FixedInputPlugin<A> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Actionlike + PartialEq> Plugin for FixedInputPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_fixed_actions::<A>)
+            .add_system(buffer_presses::<A>.in_set(BufferInputs))
+            .add_system(
+                clear_presses::<A>
+                    .after(PhysicsSet)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+/// Runs once this frame's action states are final, anything pressing
+/// actions itself should run before it
+#[derive(SystemSet, Hash, Eq, PartialEq, Clone, Debug)]
+pub struct BufferInputs;
+
+/// actions pressed since the last fixed tick, read this in `FixedUpdate`
+/// instead of `ActionState::just_pressed`
+#[derive(Component)]
+pub struct FixedActions<A: Actionlike> {
+    just_pressed: Vec<A>,
+}
+
+impl<A: Actionlike> Default for FixedActions<A> {
+    fn default() -> Self {
+        FixedActions {
+            just_pressed: Vec::new(),
+        }
+    }
+}
+
+impl<A: Actionlike + PartialEq> FixedActions<A> {
+    pub fn just_pressed(&self, action: A) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    fn press(&mut self, action: A) {
+        if !self.just_pressed(action.clone()) {
+            self.just_pressed.push(action);
+        }
+    }
+}
+
+fn add_fixed_actions<A: Actionlike>(
+    mut commands: Commands,
+    q: Query<Entity, (With<ActionState<A>>, Without<FixedActions<A>>)>,
+) {
+    for e in &q {
+        commands.entity(e).insert(FixedActions::<A>::default());
+    }
+}
+
+fn buffer_presses<A: Actionlike + PartialEq>(
+    mut q: Query<(&ActionState<A>, &mut FixedActions<A>)>,
+) {
+    for (action_state, mut fixed) in &mut q {
+        for action in action_state.get_just_pressed() {
+            fixed.press(action);
+        }
+    }
+}
+
+/// the tick has had its chance to see the presses
+fn clear_presses<A: Actionlike>(mut q: Query<&mut FixedActions<A>>) {
+    for mut fixed in &mut q {
+        if !fixed.just_pressed.is_empty() {
+            fixed.just_pressed.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
+    enum TestAction {
+        Jump,
+    }
+
+    #[test]
+    fn press_between_ticks_is_kept_for_one_tick() {
+        let mut app = App::new();
+        app.add_plugin(FixedInputPlugin::<TestAction>::new());
+        let e = app.world.spawn(ActionState::<TestAction>::default()).id();
+        app.update();
+
+        let set = |app: &mut App, pressed: bool| {
+            let mut action_state = app.world.get_mut::<ActionState<TestAction>>(e).unwrap();
+            if pressed {
+                action_state.press(TestAction::Jump);
+            } else {
+                action_state.release(TestAction::Jump);
+            }
+        };
+        // pressed and let go again in frames without a fixed tick
+        set(&mut app, true);
+        app.update();
+        set(&mut app, false);
+        app.update();
+        let just_pressed = |app: &App| {
+            app.world
+                .get::<FixedActions<TestAction>>(e)
+                .unwrap()
+                .just_pressed(TestAction::Jump)
+        };
+        assert!(just_pressed(&app));
+
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+        assert!(!just_pressed(&app));
+    }
+}
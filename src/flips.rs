@@ -0,0 +1,319 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{
+    floating_text::FloatingText,
+    game_state::GameState,
+    level::LevelCompleted,
+    level_packs::LevelPacks,
+    level_registry::LevelRegistry,
+    physics::{GravityRotated, Landed, OutOfFlips},
+    player::Player,
+    preload::Preloaded,
+    run_mode::RunMode,
+    save::SaveData,
+};
+
+pub struct FlipCounterPlugin;
+impl Plugin for FlipCounterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlipCount>()
+            .init_resource::<AirFlips>()
+            .init_resource::<FlipLimit>()
+            .init_resource::<StyleResults>()
+            .add_systems(
+                (reset_flip_count, set_flip_limit, spawn_flip_text)
+                    .chain()
+                    .in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(reset_style_results.in_schedule(OnExit(GameState::StartMenu)))
+            .add_system(despawn_flip_text.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems(
+                (count_flips, land_flips, limit_flips, update_flip_text)
+                    .chain()
+                    .in_set(GameState::Playing),
+            )
+            // read completions before the level transition resets the count
+            .add_system(score_level.in_base_set(CoreSet::PostUpdate));
+    }
+}
+
+/// style points awarded for finishing a level within its par flips
+const STYLE_BONUS: u32 = 100;
+/// style points lost for every flip over par
+const STYLE_PENALTY: u32 = 25;
+
+/// gravity rotations the player has done in the current level attempt
+#[derive(Resource, Default)]
+pub struct FlipCount(pub u32);
+
+/// gravity rotations since the player last landed
+#[derive(Resource, Default)]
+pub struct AirFlips(pub u32);
+
+/// Flips allowed before the player can't rotate gravity. Once they're used up
+/// the player has to land, or restart the level for the par.
+#[derive(Resource, Default)]
+pub struct FlipLimit {
+    /// from the level's `Flip_Limit`, given back on landing
+    pub per_jump: Option<u32>,
+    /// the level's par in the minimal flips mode, for the whole attempt, only a
+    /// checkpoint gives some back
+    pub per_attempt: Option<u32>,
+}
+
+impl FlipLimit {
+    pub fn is_limited(&self) -> bool {
+        self.per_jump.is_some() || self.per_attempt.is_some()
+    }
+
+    pub fn remaining(&self, count: &FlipCount, air: &AirFlips) -> Option<u32> {
+        let jump = self.per_jump.map(|limit| limit.saturating_sub(air.0));
+        let attempt = self.per_attempt.map(|limit| limit.saturating_sub(count.0));
+        match (jump, attempt) {
+            (Some(jump), Some(attempt)) => Some(jump.min(attempt)),
+            (jump, attempt) => jump.or(attempt),
+        }
+    }
+}
+
+/// remaining flips, only shown in levels with a limit
+#[derive(Component)]
+struct FlipText;
+
+pub struct LevelStyle {
+    pub flips: u32,
+    /// minimum flips for the level, authored as `Par_Flips` in ldtk or
+    /// the best recorded run if the level doesn't have one
+    pub par: Option<u32>,
+}
+
+impl LevelStyle {
+    pub fn minimal(&self) -> bool {
+        self.par.is_some_and(|par| self.flips <= par)
+    }
+
+    pub fn score(&self) -> u32 {
+        match self.par {
+            Some(par) if self.flips <= par => STYLE_BONUS,
+            Some(par) => STYLE_BONUS.saturating_sub((self.flips - par) * STYLE_PENALTY),
+            None => 0,
+        }
+    }
+}
+
+/// style results for each level finished in the current run
+#[derive(Resource, Default)]
+pub struct StyleResults(pub Vec<LevelStyle>);
+
+impl StyleResults {
+    pub fn total_score(&self) -> u32 {
+        self.0.iter().map(|level| level.score()).sum()
+    }
+
+    pub fn minimal_clears(&self) -> usize {
+        self.0.iter().filter(|level| level.minimal()).count()
+    }
+}
+
+fn reset_flip_count(mut flips: ResMut<FlipCount>, mut air: ResMut<AirFlips>) {
+    flips.0 = 0;
+    air.0 = 0;
+}
+
+fn reset_style_results(mut results: ResMut<StyleResults>) {
+    results.0.clear();
+}
+
+fn set_flip_limit(
+    level: Res<LevelSelection>,
+    registry: Res<LevelRegistry>,
+    mode: Res<RunMode>,
+    packs: Res<LevelPacks>,
+    save: Res<SaveData>,
+    mut limit: ResMut<FlipLimit>,
+) {
+    *limit = FlipLimit::default();
+    let LevelSelection::Index(index) = *level else {
+        return;
+    };
+    let meta = registry.get(index);
+    limit.per_jump = meta.and_then(|meta| meta.flip_limit);
+    if *mode == RunMode::MinimalFlips {
+        let best_flips = match packs.active_name() {
+            Some(pack) => save.custom_levels.get(pack).map(|p| &p.best_flips),
+            None => Some(&save.best_flips),
+        };
+        let best = best_flips.and_then(|best| best.get(&index).copied());
+        // levels without a par yet only have their usual limit
+        limit.per_attempt = meta.and_then(|meta| meta.par_flips).or(best);
+    }
+}
+
+/// under the run timer
+fn spawn_flip_text(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    limit: Res<FlipLimit>,
+    existing: Query<Entity, With<FlipText>>,
+) {
+    for e in &existing {
+        commands.entity(e).despawn_recursive();
+    }
+    if !limit.is_limited() {
+        return;
+    }
+    commands.spawn((
+        FlipText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(58.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn despawn_flip_text(mut commands: Commands, text: Query<Entity, With<FlipText>>) {
+    for e in &text {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn count_flips(
+    mut events: EventReader<GravityRotated>,
+    players: Query<(), With<Player>>,
+    mut flips: ResMut<FlipCount>,
+    mut air: ResMut<AirFlips>,
+) {
+    for e in &mut events {
+        if players.contains(e.entity) {
+            flips.0 += 1;
+            air.0 += 1;
+        }
+    }
+}
+
+fn land_flips(
+    mut landed: EventReader<Landed>,
+    players: Query<(), With<Player>>,
+    mut air: ResMut<AirFlips>,
+) {
+    for e in &mut landed {
+        if players.contains(e.entity) {
+            air.0 = 0;
+        }
+    }
+}
+
+fn limit_flips(
+    mut commands: Commands,
+    flips: Res<FlipCount>,
+    air: Res<AirFlips>,
+    limit: Res<FlipLimit>,
+    players: Query<(Entity, Option<&OutOfFlips>), With<Player>>,
+) {
+    let out = limit.remaining(&flips, &air) == Some(0);
+    for (e, out_of_flips) in &players {
+        match (out, out_of_flips.is_some()) {
+            (true, false) => {
+                commands.entity(e).insert(OutOfFlips);
+            }
+            (false, true) => {
+                commands.entity(e).remove::<OutOfFlips>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_flip_text(
+    flips: Res<FlipCount>,
+    air: Res<AirFlips>,
+    limit: Res<FlipLimit>,
+    mut text: Query<&mut Text, With<FlipText>>,
+) {
+    if !flips.is_changed() && !air.is_changed() && !limit.is_changed() {
+        return;
+    }
+    let Some(remaining) = limit.remaining(&flips, &air) else {
+        return;
+    };
+    for mut text in &mut text {
+        text.sections[0].value = format!("Flips left: {remaining}");
+    }
+}
+
+fn score_level(
+    mut events: EventReader<LevelCompleted>,
+    flips: Res<FlipCount>,
+    registry: Res<LevelRegistry>,
+    mut results: ResMut<StyleResults>,
+    packs: Res<LevelPacks>,
+    mut save: ResMut<SaveData>,
+    player: Query<&GlobalTransform, With<Player>>,
+    mut popups: EventWriter<FloatingText>,
+) {
+    for e in &mut events {
+        let authored_par = registry.get(e.index).and_then(|meta| meta.par_flips);
+        let best_flips = save.best_flips_mut(packs.active_name());
+        let best = best_flips.get(&e.index).copied();
+
+        let style = LevelStyle {
+            flips: flips.0,
+            par: authored_par.or(best),
+        };
+        if let (Some(t), score @ 1..) = (player.iter().next(), style.score()) {
+            popups.send(FloatingText::new(
+                format!("+{score} style"),
+                t.translation().truncate(),
+            ));
+        }
+        results.0.push(style);
+
+        if best.map_or(true, |best| flips.0 < best) {
+            best_flips.insert(e.index, flips.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn landing_gives_back_a_levels_flips() {
+        let limit = FlipLimit {
+            per_jump: Some(2),
+            per_attempt: None,
+        };
+        let count = FlipCount(5);
+        assert_eq!(limit.remaining(&count, &AirFlips(2)), Some(0));
+        assert_eq!(limit.remaining(&count, &AirFlips(0)), Some(2));
+    }
+
+    #[test]
+    fn par_counts_the_whole_attempt() {
+        let limit = FlipLimit {
+            per_jump: Some(2),
+            per_attempt: Some(3),
+        };
+        assert_eq!(limit.remaining(&FlipCount(2), &AirFlips(0)), Some(1));
+        assert_eq!(limit.remaining(&FlipCount(3), &AirFlips(0)), Some(0));
+        assert_eq!(
+            FlipLimit::default().remaining(&FlipCount(9), &AirFlips(9)),
+            None
+        );
+    }
+}
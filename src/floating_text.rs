@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::AccessibilitySettings, game_state::GameState, preload::Preloaded,
+    render_layers::RenderLayer,
+};
+
+/// how long a popup stays on screen
+const RISE_SECONDS: f32 = 0.8;
+/// how far a popup moves up over its life
+const RISE_DISTANCE: f32 = 32.0;
+const FONT_SIZE: f32 = 18.0;
+
+/// Short text in the world that rises and fades, for score popups and the like.
+/// Send a `FloatingText` event from any system to show one.
+pub struct FloatingTextPlugin;
+impl Plugin for FloatingTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FloatingText>()
+            .init_resource::<FloatingTextPool>()
+            .add_systems((spawn_floating_text, rise_floating_text).chain())
+            .add_system(clear_floating_text.in_schedule(OnEnter(GameState::StartMenu)));
+    }
+}
+
+/// show `text` at the world position `position`
+pub struct FloatingText {
+    pub text: String,
+    pub position: Vec2,
+    pub color: Color,
+}
+
+impl FloatingText {
+    pub fn new(text: impl Into<String>, position: Vec2) -> Self {
+        FloatingText {
+            text: text.into(),
+            position,
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// Popups that have finished, reused instead of spawning new ones since
+/// pickups can send several a second.
+#[derive(Resource, Default)]
+struct FloatingTextPool(Vec<Entity>);
+
+#[derive(Component)]
+struct Rising {
+    start: Vec2,
+    timer: Timer,
+}
+
+fn popup_text(popup: &FloatingText, preloaded: &Preloaded) -> Text {
+    Text::from_section(
+        popup.text.clone(),
+        TextStyle {
+            font: preloaded.get("font"),
+            font_size: FONT_SIZE,
+            color: popup.color,
+        },
+    )
+    .with_alignment(TextAlignment::Center)
+}
+
+fn spawn_floating_text(
+    mut commands: Commands,
+    mut events: EventReader<FloatingText>,
+    mut pool: ResMut<FloatingTextPool>,
+    preloaded: Res<Preloaded>,
+    mut popups: Query<(&mut Text, &mut Transform, &mut Visibility, &mut Rising)>,
+) {
+    for popup in &mut events {
+        let rising = Rising {
+            start: popup.position,
+            timer: Timer::from_seconds(RISE_SECONDS, TimerMode::Once),
+        };
+        let transform = Transform::from_translation(popup.position.extend(0.0));
+
+        if let Some(e) = pool.0.pop() {
+            if let Ok((mut text, mut t, mut visibility, mut r)) = popups.get_mut(e) {
+                *text = popup_text(popup, &preloaded);
+                *t = transform;
+                *visibility = Visibility::Inherited;
+                *r = rising;
+                continue;
+            }
+        }
+        commands.spawn((
+            rising,
+            RenderLayer::WorldUi,
+            Text2dBundle {
+                text: popup_text(popup, &preloaded),
+                transform,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn rise_floating_text(
+    time: Res<Time>,
+    accessibility: Res<AccessibilitySettings>,
+    mut pool: ResMut<FloatingTextPool>,
+    mut q: Query<(
+        Entity,
+        &mut Rising,
+        &mut Transform,
+        &mut Visibility,
+        &mut Text,
+    )>,
+) {
+    for (e, mut rising, mut t, mut visibility, mut text) in &mut q {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        if rising.timer.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+            pool.0.push(e);
+            continue;
+        }
+
+        // reduced motion only fades the text out
+        let progress = rising.timer.percent();
+        if !accessibility.reduced_motion {
+            let offset = RISE_DISTANCE * (1.0 - (1.0 - progress).powi(2));
+            t.translation = (rising.start + Vec2::Y * offset).extend(t.translation.z);
+        }
+        for section in &mut text.sections {
+            section.style.color.set_a(1.0 - progress * progress);
+        }
+    }
+}
+
+/// Hide everything still showing when leaving to the menu. Popups are left
+/// running between levels so the last pickup's still shows.
+fn clear_floating_text(
+    mut pool: ResMut<FloatingTextPool>,
+    mut q: Query<(Entity, &mut Visibility), With<Rising>>,
+) {
+    for (e, mut visibility) in &mut q {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+            pool.0.push(e);
+        }
+    }
+}
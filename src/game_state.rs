@@ -1,12 +1,21 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 
+use crate::{collisions::Collision, physics::PhysicsSet};
+
 #[derive(States, SystemSet, Default, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GameState {
+    /// loading everything in the asset manifest, see `preload`
     #[default]
+    Preload,
     StartMenu,
+    /// picking a level to start from, see `LevelSelectPlugin`
+    LevelSelect,
     LoadLevel,
     SpawnLevel,
     Playing,
+    /// dropping level scoped assets on the way back to the start menu, see `preload`
     UnloadLevel,
     Respawn,
     WinScreen,
@@ -15,10 +24,45 @@ pub enum GameState {
 pub struct GameStatePlugin;
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<GameState>().configure_set(
-            GameState::Playing
-                .in_base_set(CoreSet::Update)
-                .run_if(in_state(GameState::Playing)),
-        );
+        app.add_state::<GameState>()
+            .init_resource::<ShouldSimulate>()
+            .configure_set(
+                GameState::Playing
+                    .in_base_set(CoreSet::Update)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            // everything that moves things or reacts to them touching lives in
+            // these sets, so this is the only place simulation needs gating
+            .configure_set(Collision.run_if(should_simulate));
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule.configure_set(PhysicsSet.run_if(should_simulate));
+        });
+    }
+}
+
+/// Whether physics and collisions run. Anything drawn over a level that should
+/// stop it, like a pause menu or photo mode, pauses here instead of adding its
+/// own state checks.
+#[derive(Resource, Default)]
+pub struct ShouldSimulate {
+    /// names of whatever is currently holding the simulation
+    paused_by: HashSet<&'static str>,
+}
+
+impl ShouldSimulate {
+    pub fn pause(&mut self, by: &'static str) {
+        self.paused_by.insert(by);
     }
+
+    pub fn resume(&mut self, by: &'static str) {
+        self.paused_by.remove(by);
+    }
+
+    pub fn running(&self) -> bool {
+        self.paused_by.is_empty()
+    }
+}
+
+pub fn should_simulate(simulate: Res<ShouldSimulate>) -> bool {
+    simulate.running()
 }
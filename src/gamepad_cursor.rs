@@ -0,0 +1,173 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    ui::{FocusPolicy, UiSystem},
+    window::PrimaryWindow,
+};
+
+use crate::{game_state::GameState, preload::Preloaded};
+
+/// pixels a second the cursor moves with the stick fully pushed
+const CURSOR_SPEED: f32 = 600.0;
+/// the paw is hidden after the stick has been left alone this long
+const IDLE_SECONDS: f32 = 3.0;
+const PAW_SIZE: f32 = 24.0;
+
+/// Moves the mouse cursor with the right stick on menu screens, so anything
+/// that works with the mouse works with a gamepad too. The right trigger or
+/// clicking the right stick clicks. South is left to the menus' own
+/// selection so the two don't both fire.
+pub struct GamepadCursorPlugin;
+impl Plugin for GamepadCursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadCursor>()
+            .add_system(spawn_paw.in_schedule(OnExit(GameState::Preload)))
+            // before the ui reads the cursor, so hovering and clicking
+            // happen in the same frame
+            .add_system(
+                move_cursor
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .before(UiSystem::Focus)
+                    .run_if(in_menu),
+            )
+            .add_system(update_paw);
+    }
+}
+
+/// seconds since the stick last moved the cursor, `None` if it hasn't been
+/// used since the last menu was opened
+#[derive(Resource, Default)]
+struct GamepadCursor(Option<f32>);
+
+impl GamepadCursor {
+    fn visible(&self) -> bool {
+        self.0.is_some_and(|idle| idle < IDLE_SECONDS)
+    }
+}
+
+#[derive(Component)]
+struct Paw;
+
+fn is_menu(state: &GameState) -> bool {
+    matches!(
+        state,
+        GameState::StartMenu | GameState::LevelSelect | GameState::WinScreen
+    )
+}
+
+fn in_menu(state: Res<State<GameState>>) -> bool {
+    is_menu(&state.0)
+}
+
+fn spawn_paw(mut commands: Commands, preloaded: Res<Preloaded>) {
+    commands.spawn((
+        Paw,
+        ImageBundle {
+            image: preloaded.get("paw_cursor").into(),
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Px(PAW_SIZE), Val::Px(PAW_SIZE)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            // clicks go to whatever is under the paw
+            focus_policy: FocusPolicy::Pass,
+            // over every menu and dialog
+            z_index: ZIndex::Global(30),
+            ..default()
+        },
+    ));
+}
+
+fn move_cursor(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut cursor: ResMut<GamepadCursor>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    let stick: Vec2 = gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis_type| {
+                axes.get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or_default()
+            };
+            Vec2::new(
+                axis(GamepadAxisType::RightStickX),
+                axis(GamepadAxisType::RightStickY),
+            )
+        })
+        .sum();
+    if let Some(idle) = &mut cursor.0 {
+        *idle += time.raw_delta_seconds();
+    }
+
+    if stick != Vec2::ZERO {
+        let size = Vec2::new(window.width(), window.height());
+        let start = window.cursor_position().unwrap_or(size / 2.0);
+        let step = stick.clamp_length_max(1.0) * CURSOR_SPEED * time.raw_delta_seconds();
+        // the ui reads the window's cursor, so moving it is enough for
+        // buttons to be hovered
+        window.set_cursor_position(Some((start + step).clamp(Vec2::ZERO, size)));
+        cursor.0 = Some(0.0);
+    }
+
+    if !cursor.visible() {
+        return;
+    }
+    let clicks = [
+        GamepadButtonType::RightTrigger2,
+        GamepadButtonType::RightThumb,
+    ];
+    let any = |check: &dyn Fn(GamepadButton) -> bool| {
+        gamepads.iter().any(|gamepad| {
+            clicks
+                .iter()
+                .any(|&button| check(GamepadButton::new(gamepad, button)))
+        })
+    };
+    if any(&|button| buttons.just_pressed(button)) {
+        mouse.press(MouseButton::Left);
+        cursor.0 = Some(0.0);
+    }
+    if any(&|button| buttons.just_released(button)) {
+        mouse.release(MouseButton::Left);
+    }
+}
+
+fn update_paw(
+    state: Res<State<GameState>>,
+    mut cursor: ResMut<GamepadCursor>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut paw: Query<(&mut Style, &mut Visibility), With<Paw>>,
+) {
+    // the paw only comes back once the stick is used in the next menu
+    if !is_menu(&state.0) {
+        cursor.0 = None;
+    }
+    let (Ok(window), Ok((mut style, mut visibility))) =
+        (windows.get_single(), paw.get_single_mut())
+    else {
+        return;
+    };
+
+    let Some(position) = window.cursor_position().filter(|_| cursor.visible()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    // ui positions are from the top left
+    style.position = UiRect {
+        left: Val::Px(position.x - PAW_SIZE / 2.0),
+        top: Val::Px(window.height() - position.y - PAW_SIZE / 2.0),
+        ..default()
+    };
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::{CameraRoll, MainCamera},
+    game_state::GameState,
+    goals::Goal,
+    preload::Preloaded,
+    render_layers::{LayerDepth, RenderLayer},
+};
+
+/// distance from the edge of the view the arrows are drawn at
+const EDGE_MARGIN: f32 = 20.0;
+/// how far off screen a goal has to be before its arrow is fully visible
+const FADE_DISTANCE: f32 = 60.0;
+
+pub struct GoalIndicatorPlugin;
+impl Plugin for GoalIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_indicators.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(update_indicators.in_base_set(CoreSet::PostUpdate));
+    }
+}
+
+/// arrow at the edge of the view pointing at a goal that's off screen
+#[derive(Component)]
+struct GoalIndicator {
+    goal: Entity,
+}
+
+fn spawn_indicators(
+    mut commands: Commands,
+    goals: Query<Entity, Added<Goal>>,
+    preloaded: Res<Preloaded>,
+) {
+    for goal in &goals {
+        commands.spawn((
+            GoalIndicator { goal },
+            // over floating text, they're the one thing that mustn't be hidden
+            RenderLayer::WorldUi,
+            LayerDepth(1.0),
+            SpriteBundle {
+                texture: preloaded.get("goal_arrow"),
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                    ..default()
+                },
+                transform: Transform::default(),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn update_indicators(
+    mut commands: Commands,
+    camera: Query<(&GlobalTransform, &OrthographicProjection), With<MainCamera>>,
+    roll: Res<CameraRoll>,
+    goals: Query<&GlobalTransform, With<Goal>>,
+    mut indicators: Query<(Entity, &GoalIndicator, &mut Transform, &mut Sprite)>,
+) {
+    let Ok((camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    // the camera can be mirrored, so only take the position and size of the view
+    let center = camera_transform.translation().truncate();
+    let half_size = (projection.area.max - projection.area.min).abs() / 2.0;
+    let inner = (half_size - EDGE_MARGIN).max(Vec2::ZERO);
+    // offsets are worked out in view space so arrows stay on screen when it's turned
+    let to_view = Vec2::from_angle(-roll.0);
+
+    for (e, indicator, mut t, mut sprite) in &mut indicators {
+        let Ok(goal) = goals.get(indicator.goal) else {
+            // goal was collected or the level was unloaded
+            commands.entity(e).despawn();
+            continue;
+        };
+
+        let offset = goal.translation().truncate() - center;
+        let view_offset = to_view.rotate(offset);
+        let outside = (view_offset.abs() - half_size).max(Vec2::ZERO).length();
+        sprite.color.set_a((outside / FADE_DISTANCE).min(1.0));
+
+        let edge = view_offset.clamp(-inner, inner);
+        t.translation = (center + Vec2::from_angle(roll.0).rotate(edge)).extend(t.translation.z);
+        t.rotation = Quat::from_rotation_z(offset.y.atan2(offset.x));
+    }
+}
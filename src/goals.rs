@@ -1,23 +1,40 @@
 use crate::collisions::{CollisionEvents, CollisionSets, RectBundle};
 use crate::constants::CollisionTypes;
 use bevy::prelude::*;
-use bevy_ecs_ldtk::{prelude::LdtkEntityAppExt, LdtkEntity};
+use bevy_ecs_ldtk::{prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
 use bevy_turborand::{DelegatedRng, GlobalRng};
 
-use crate::{game_state::GameState, sfx::SfxHandles};
+use crate::{
+    floating_text::FloatingText,
+    game_state::GameState,
+    mutators::{Mutators, FRAGILE_GOAL_SECONDS},
+    player::{DeathCause, PlayerDeath},
+    preload::Preloaded,
+    world_flags::{flag_set_by, WorldFlags},
+};
 
 pub struct GoalPlugin;
 impl Plugin for GoalPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(goal_collision_detection.in_set(CollisionSets::Consume))
+        app.add_event::<GoalCollected>()
+            .add_system(goal_collision_detection.in_set(CollisionSets::Consume))
             .add_system(after_goal_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
-            .add_startup_system(load_goal_images)
+            // goals can also come back while playing, see `spawn_goal`
+            .add_system(after_goal_spawned.in_set(GameState::Playing))
+            .init_resource::<FragileGoalTimer>()
+            .add_system(reset_fragile_timer.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(fragile_goals_expire.in_set(GameState::Playing))
             .register_ldtk_entity::<GoalBundle>("Goal");
     }
 }
 #[derive(Component, Default)]
 pub struct Goal;
 
+/// sent when the player picks up a goal
+pub struct GoalCollected {
+    pub position: Vec2,
+}
+
 #[derive(Bundle, LdtkEntity, Default)]
 pub struct GoalBundle {
     goal: Goal,
@@ -25,26 +42,24 @@ pub struct GoalBundle {
     sprite: SpriteBundle,
 }
 
+/// running once the first goal is collected with the fragile goals mutator
 #[derive(Resource, Default)]
-pub struct GoalHandles {
-    pub handles: Vec<Handle<Image>>,
-}
-impl GoalHandles {
-    fn handle_paths() -> Vec<String> {
-        vec![
-            "goal-ball-yellow.png".to_string(),
-            "goal-ball-blue.png".to_string(),
-            "goal-ball-red.png".to_string(),
-            "goal-mouse.png".to_string(),
-            "goal-fish.png".to_string(),
-        ]
-    }
-}
+pub struct FragileGoalTimer(pub Option<Timer>);
+
+/// asset manifest labels of the images goals pick from
+const GOAL_IMAGES: [&str; 5] = [
+    "goal_yellow",
+    "goal_blue",
+    "goal_red",
+    "goal_mouse",
+    "goal_fish",
+];
 
+/// goals already set up have a `CollisionTypes`, so running this again while playing skips them
 fn after_goal_spawned(
     mut commands: Commands,
-    mut q: Query<(Entity, &mut Handle<Image>), With<Goal>>,
-    goal_handles: Res<GoalHandles>,
+    mut q: Query<(Entity, &mut Handle<Image>), (Added<Goal>, Without<CollisionTypes>)>,
+    preloaded: Res<Preloaded>,
     mut rand: ResMut<GlobalRng>,
 ) {
     for (e, mut h) in &mut q {
@@ -59,33 +74,91 @@ fn after_goal_spawned(
             });
 
         // set a random image
-        let index = rand.u8(0..goal_handles.handles.len() as u8) as usize;
-        *h = goal_handles.handles[index].clone()
+        let index = rand.u8(0..GOAL_IMAGES.len() as u8) as usize;
+        *h = preloaded.get(GOAL_IMAGES[index]);
+    }
+}
+
+/// spawn a goal at `transform`, `instance` is the ldtk entity it was placed as
+pub fn spawn_goal(
+    commands: &mut Commands,
+    transform: Transform,
+    instance: Option<EntityInstance>,
+) -> Entity {
+    let mut goal = commands.spawn(GoalBundle {
+        sprite: SpriteBundle {
+            transform,
+            ..default()
+        },
+        ..default()
+    });
+    if let Some(instance) = instance {
+        goal.insert(instance);
     }
+    goal.id()
 }
 
 fn goal_collision_detection(
     mut commands: Commands,
-    mut goals: Query<(Entity, &mut CollisionEvents<CollisionTypes>), With<Goal>>,
-    audio: Res<Audio>,
-    sfx: Res<SfxHandles>,
+    mut goals: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut CollisionEvents<CollisionTypes>,
+            Option<&EntityInstance>,
+        ),
+        With<Goal>,
+    >,
+    mut collected: EventWriter<GoalCollected>,
+    mut popups: EventWriter<FloatingText>,
+    mutators: Res<Mutators>,
+    mut fragile_timer: ResMut<FragileGoalTimer>,
+    mut flags: ResMut<WorldFlags>,
 ) {
-    for (entity, mut collision_events) in &mut goals {
+    for (entity, t, mut collision_events, instance) in &mut goals {
         for event in collision_events.buffer.drain(..) {
             if event.user_type == CollisionTypes::Player {
                 commands.entity(entity).despawn_recursive();
-                audio.play(sfx.goal.clone());
+                collected.send(GoalCollected {
+                    position: t.translation().truncate(),
+                });
+                popups.send(
+                    FloatingText::new("+1", t.translation().truncate())
+                        .with_color(Color::rgb(1.0, 0.85, 0.3)),
+                );
+                if let Some(flag) = instance.and_then(flag_set_by) {
+                    flags.set(flag);
+                }
+
+                if mutators.fragile_goals && fragile_timer.0.is_none() {
+                    fragile_timer.0 =
+                        Some(Timer::from_seconds(FRAGILE_GOAL_SECONDS, TimerMode::Once));
+                }
             }
         }
     }
 }
 
-fn load_goal_images(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let mut handles = GoalHandles::default();
-    for path in GoalHandles::handle_paths() {
-        let handle = asset_server.load(path);
-        handles.handles.push(handle);
+fn reset_fragile_timer(mut fragile_timer: ResMut<FragileGoalTimer>) {
+    fragile_timer.0 = None;
+}
+
+/// kill the player if the remaining goals weren't collected in time
+fn fragile_goals_expire(
+    time: Res<Time>,
+    mut fragile_timer: ResMut<FragileGoalTimer>,
+    goals: Query<(), With<Goal>>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    let Some(timer) = fragile_timer.0.as_mut() else {
+        return;
+    };
+
+    if !timer.tick(time.delta()).just_finished() || goals.is_empty() {
+        return;
     }
 
-    commands.insert_resource(handles);
+    deaths.send(PlayerDeath {
+        cause: DeathCause::FragileGoals,
+    });
 }
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::level_registry::field;
+
+pub struct GravityZonePlugin;
+impl Plugin for GravityZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<GravityZoneBundle>("Gravity_Zone");
+    }
+}
+
+/// Area of a level that overrides the gravity rotation at the apex of a jump
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GravityZone {
+    /// gravity never rotates while inside
+    #[default]
+    Locked,
+    /// gravity always rotates at the apex, even after hitting a block during
+    /// the jump or once the level's flips have run out
+    Forced,
+}
+
+/// full size of the zone, centered on its transform
+#[derive(Component, Default)]
+pub struct ZoneSize(pub Vec2);
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct GravityZoneBundle {
+    #[with(zone_from_instance)]
+    zone: GravityZone,
+    #[with(size_from_instance)]
+    size: ZoneSize,
+}
+
+fn zone_from_instance(instance: &EntityInstance) -> GravityZone {
+    match field(&instance.field_instances, "Mode") {
+        Some(FieldValue::Enum(Some(mode))) if mode == "Forced" => GravityZone::Forced,
+        _ => GravityZone::Locked,
+    }
+}
+
+fn size_from_instance(instance: &EntityInstance) -> ZoneSize {
+    ZoneSize(Vec2::new(instance.width as f32, instance.height as f32))
+}
+
+/// the zone overriding gravity at `point`, locked zones win over forced ones when they overlap
+pub fn zone_at<'a>(
+    zones: impl IntoIterator<Item = (&'a GravityZone, &'a ZoneSize, &'a Transform)>,
+    point: Vec2,
+) -> Option<GravityZone> {
+    let mut found = None;
+    for (zone, size, t) in zones {
+        let offset = (point - t.translation.truncate()).abs();
+        if offset.x > size.0.x / 2.0 || offset.y > size.0.y / 2.0 {
+            continue;
+        }
+
+        if *zone == GravityZone::Locked {
+            return Some(GravityZone::Locked);
+        }
+        found = Some(*zone);
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    mod zone_at {
+        use bevy::prelude::{Transform, Vec2};
+
+        use crate::gravity_zones::{zone_at, GravityZone, ZoneSize};
+
+        fn zone(mode: GravityZone, x: f32) -> (GravityZone, ZoneSize, Transform) {
+            (
+                mode,
+                ZoneSize(Vec2::new(20.0, 10.0)),
+                Transform::from_xyz(x, 0.0, 0.0),
+            )
+        }
+
+        fn at(zones: &[(GravityZone, ZoneSize, Transform)], point: Vec2) -> Option<GravityZone> {
+            zone_at(zones.iter().map(|(z, s, t)| (z, s, t)), point)
+        }
+
+        #[test]
+        fn finds_the_zone_containing_the_point() {
+            let zones = [zone(GravityZone::Forced, 0.0)];
+            assert_eq!(at(&zones, Vec2::new(9.0, 4.0)), Some(GravityZone::Forced));
+            // edges count as inside
+            assert_eq!(at(&zones, Vec2::new(10.0, -5.0)), Some(GravityZone::Forced));
+            assert_eq!(at(&zones, Vec2::new(11.0, 0.0)), None);
+            assert_eq!(at(&zones, Vec2::new(0.0, 6.0)), None);
+        }
+
+        #[test]
+        fn locked_wins_where_zones_overlap() {
+            let point = Vec2::new(5.0, 0.0);
+            for zones in [
+                [
+                    zone(GravityZone::Locked, 0.0),
+                    zone(GravityZone::Forced, 10.0),
+                ],
+                [
+                    zone(GravityZone::Forced, 10.0),
+                    zone(GravityZone::Locked, 0.0),
+                ],
+            ] {
+                assert_eq!(at(&zones, point), Some(GravityZone::Locked));
+            }
+            // only the forced one reaches this far
+            let zones = [
+                zone(GravityZone::Locked, 0.0),
+                zone(GravityZone::Forced, 10.0),
+            ];
+            assert_eq!(at(&zones, Vec2::new(15.0, 0.0)), Some(GravityZone::Forced));
+        }
+    }
+}
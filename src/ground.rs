@@ -1,27 +1,68 @@
 use crate::{
-    collisions::{CollisionData, CollisionEvents, CollisionSets, RectBundle, PositionDelta},
-    constants::CollisionTypes,
-    physics::{Acceleration, Direction, Gravity, GravityDirection, OnGround, Velocity},
+    bounds::DespawnOutOfBounds,
+    character_controller::{KinematicBody, KinematicBodyBundle},
+    collisions::{
+        CollisionData, CollisionEvents, CollisionSets, ContactResponse, PositionDelta, RectBundle,
+        Restitution,
+    },
+    constants::{CollisionTypes, STATIC_GROUND_LAYERS},
+    physics::{
+        apply_velocity, Direction, Gravity, GravityDirection, OnGround, PhysicsSet,
+        PhysicsSettings, SizedCollider, Velocity,
+    },
     player::Player,
 };
 use bevy::prelude::*;
 use bevy_ecs_ldtk::{
-    prelude::{LdtkEntityAppExt, LdtkIntCellAppExt},
-    LdtkEntity, LdtkIntCell,
+    ldtk::FieldValue,
+    prelude::{LayerMetadata, LdtkEntityAppExt, LdtkIntCellAppExt},
+    EntityInstance, IntGridCell, LdtkEntity, LdtkIntCell,
 };
+use serde::Deserialize;
 
-use crate::game_state::GameState;
+use crate::{game_state::GameState, level_registry::field};
+
+/// pixels a second moving platforms move at when their `Speed` isn't set
+const DEFAULT_PLATFORM_SPEED: f32 = 48.0;
+
+/// Platforms IntGrid values spawned as ground, one for each `SurfaceMaterial`
+pub const GROUND_INT_CELLS: [i32; 4] = [1, 2, 3, 4];
+/// Platforms IntGrid value spawned as a one-way platform
+pub const ONE_WAY_INT_CELL: i32 = 5;
+
+/// whether the Platforms IntGrid `value` spawns anything solid
+pub fn is_solid_int_cell(value: i32) -> bool {
+    GROUND_INT_CELLS.contains(&value) || value == ONE_WAY_INT_CELL
+}
 
 pub struct GroundPlugin;
 impl Plugin for GroundPlugin {
     fn build(&self, app: &mut App) {
-        app.register_ldtk_int_cell::<GroundBundle>(1)
+        for value in GROUND_INT_CELLS {
+            app.register_ldtk_int_cell::<GroundBundle>(value);
+        }
+        app.add_event::<BlockFell>()
+            .register_ldtk_int_cell::<OneWayPlatformBundle>(ONE_WAY_INT_CELL)
             .register_ldtk_entity::<FallingGroundBundle>("Falling_Block")
-            .add_startup_system(load_falling_block_sprite)
+            .register_ldtk_entity::<MovingPlatformBundle>("Moving_Platform")
             .add_systems(
-                (after_ground_spawned, after_falling_ground_spawned)
+                (
+                    after_ground_spawned,
+                    after_falling_ground_spawned,
+                    after_moving_platform_spawned,
+                )
                     .in_schedule(OnEnter(GameState::SpawnLevel)),
             )
+            .add_system(
+                move_platforms
+                    .before(apply_velocity)
+                    .in_set(PhysicsSet)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            // blocks can also be dropped in and ground added while playing
+            .add_systems(
+                (after_ground_spawned, after_falling_ground_spawned).in_set(GameState::Playing),
+            )
             .add_system(fall_block_after_jump.in_set(CollisionSets::Consume));
     }
 }
@@ -30,21 +71,85 @@ impl Plugin for GroundPlugin {
 #[derive(Component, Default)]
 pub struct Ground;
 
+/// What ground is made of, changes footstep and landing sounds and dust.
+/// Set by the Platforms IntGrid value: 1 stone, 2 ice, 3 metal, 4 rubber.
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum SurfaceMaterial {
+    #[default]
+    Stone,
+    Ice,
+    Metal,
+    Rubber,
+}
+
+/// grip on ice, see `ContactResponse::friction`
+pub const ICE_FRICTION: f32 = 0.1;
+/// bounciness of rubber ground, see `Restitution`
+const RUBBER_RESTITUTION: f32 = 0.8;
+
+fn surface_material(cell: IntGridCell) -> SurfaceMaterial {
+    match cell.value {
+        2 => SurfaceMaterial::Ice,
+        3 => SurfaceMaterial::Metal,
+        4 => SurfaceMaterial::Rubber,
+        _ => SurfaceMaterial::Stone,
+    }
+}
+
 #[derive(Bundle, LdtkIntCell, Default)]
 pub struct GroundBundle {
     ground: Ground,
+    #[with(surface_material)]
+    material: SurfaceMaterial,
+}
+
+/// Ground that can be jumped up through and only holds up things landing on
+/// top of it, where the top is the side facing away from their gravity.
+/// Set by the Platforms IntGrid value 5, see `physics::lands_on_one_way`.
+#[derive(Component, Default)]
+pub struct OneWayPlatform;
+
+#[derive(Bundle, LdtkIntCell, Default)]
+pub struct OneWayPlatformBundle {
+    ground: Ground,
+    one_way: OneWayPlatform,
+    material: SurfaceMaterial,
 }
 
 fn after_ground_spawned(
     mut commands: Commands,
-    q: Query<Entity, (Added<Ground>, Without<FallingGround>)>,
+    q: Query<
+        (Entity, &SurfaceMaterial),
+        (
+            Added<Ground>,
+            Without<FallingGround>,
+            Without<MovingPlatform>,
+        ),
+    >,
+    settings: Res<PhysicsSettings>,
 ) {
-    for e in &q {
-        commands
-            .entity(e)
+    for (e, material) in &q {
+        let mut ground = commands.entity(e);
+        match material {
+            SurfaceMaterial::Ice => {
+                ground.insert(ContactResponse {
+                    friction: ICE_FRICTION,
+                    ..ContactResponse::SOLID
+                });
+            }
+            SurfaceMaterial::Rubber => {
+                ground.insert(Restitution(RUBBER_RESTITUTION));
+            }
+            SurfaceMaterial::Stone | SurfaceMaterial::Metal => {}
+        }
+        ground
             .insert(CollisionTypes::Ground)
             .with_children(|children| {
-                children.spawn(RectBundle::new(Vec2::new(24.0, 24.0)));
+                children.spawn((
+                    RectBundle::new(settings.colliders.get(SizedCollider::Ground)),
+                    SizedCollider::Ground,
+                    STATIC_GROUND_LAYERS,
+                ));
             });
     }
 }
@@ -58,67 +163,261 @@ pub struct FallingGroundBundle {
     ground: Ground,
     #[sprite_bundle("falling-block.png")]
     sprite: SpriteBundle,
-    g_dir: GravityDirection,
-    gravity: Gravity,
-    on_ground: OnGround,
-    velocity: Velocity,
-    acceleration: Acceleration,
+    #[with(falling_block_body)]
+    body: KinematicBodyBundle,
     player_contact: PlayerContact,
+    despawn_out_of_bounds: DespawnOutOfBounds,
 }
 
-#[derive(Component, Default)]
-struct PlayerContact {
-    pub is_in_contact: bool,
+/// falls when the player jumps off it instead of walking off ledges, see `fall_block_after_jump`
+const FALLING_BLOCK_BODY: KinematicBody = KinematicBody {
+    collider: SizedCollider::FallingBlock,
+    ground_rays: false,
+};
+
+fn falling_block_body(_: &EntityInstance) -> KinematicBodyBundle {
+    KinematicBodyBundle::new(FALLING_BLOCK_BODY)
 }
 
-#[derive(Resource)]
-pub struct FallingBlockSprite {
-    pub sprite: Handle<Image>,
+/// falling blocks with this start falling as soon as they're spawned
+#[derive(Component)]
+struct DropOnSpawn;
+
+/// spawn a falling block resting at `transform`, like the ones placed in ldtk
+pub fn spawn_falling_block(
+    commands: &mut Commands,
+    sprite: Handle<Image>,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn(FallingGroundBundle {
+            sprite: SpriteBundle {
+                texture: sprite,
+                transform,
+                ..default()
+            },
+            body: KinematicBodyBundle::new(FALLING_BLOCK_BODY),
+            ..default()
+        })
+        .id()
 }
 
-fn load_falling_block_sprite(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(FallingBlockSprite {
-        sprite: asset_server.load("falling-block.png"),
-    });
+/// spawn a falling block at `position` that immediately falls along `direction`
+pub fn drop_falling_block(
+    commands: &mut Commands,
+    sprite: Handle<Image>,
+    position: Vec3,
+    direction: Direction,
+) -> Entity {
+    commands
+        .spawn((
+            FallingGroundBundle {
+                sprite: SpriteBundle {
+                    texture: sprite,
+                    transform: Transform::from_translation(position),
+                    ..default()
+                },
+                body: KinematicBodyBundle {
+                    g_dir: GravityDirection(direction),
+                    ..KinematicBodyBundle::new(FALLING_BLOCK_BODY)
+                },
+                ..default()
+            },
+            DropOnSpawn,
+        ))
+        .id()
 }
 
-fn after_falling_ground_spawned(
+/// Ground that moves back and forth along `path`, carrying anything standing on
+/// it. The ldtk entity's `Path` points are the waypoints after its own
+/// position, `Speed` is in pixels a second.
+#[derive(Component)]
+pub struct MovingPlatform {
+    /// positions visited in order, then in reverse
+    pub path: Vec<Vec2>,
+    pub speed: f32,
+    /// index in `path` being moved towards
+    target: usize,
+    /// heading back towards the start of the path
+    returning: bool,
+}
+
+impl Default for MovingPlatform {
+    fn default() -> Self {
+        MovingPlatform {
+            path: Vec::new(),
+            speed: DEFAULT_PLATFORM_SPEED,
+            target: 0,
+            returning: false,
+        }
+    }
+}
+
+impl MovingPlatform {
+    /// step to the next waypoint, turning around at either end of the path
+    fn next_target(&mut self) {
+        if self.path.len() < 2 {
+            return;
+        }
+        if self.target == 0 || self.target == self.path.len() - 1 {
+            self.returning = self.target != 0;
+        }
+        self.target = if self.returning {
+            self.target - 1
+        } else {
+            self.target + 1
+        };
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct MovingPlatformBundle {
+    #[with(moving_platform_from_instance)]
+    platform: MovingPlatform,
+    ground: Ground,
+    #[with(metal)]
+    material: SurfaceMaterial,
+    sprite: SpriteBundle,
+    velocity: Velocity,
+}
+
+fn moving_platform_from_instance(instance: &EntityInstance) -> MovingPlatform {
+    match field(&instance.field_instances, "Speed") {
+        Some(FieldValue::Float(Some(speed))) if *speed > 0.0 => MovingPlatform {
+            speed: *speed,
+            ..default()
+        },
+        _ => MovingPlatform::default(),
+    }
+}
+
+fn metal(_: &EntityInstance) -> SurfaceMaterial {
+    SurfaceMaterial::Metal
+}
+
+/// The path is in cells of the entity's layer, which is only known once the
+/// platform has been put in it.
+fn after_moving_platform_spawned(
     mut commands: Commands,
-    mut q: Query<(Entity, &Transform, &mut Gravity, &mut GravityDirection, &mut OnGround), Added<FallingGround>>,
+    mut q: Query<
+        (
+            Entity,
+            &EntityInstance,
+            &Transform,
+            &Parent,
+            &mut MovingPlatform,
+            &mut Sprite,
+        ),
+        Added<MovingPlatform>,
+    >,
+    layers: Query<&LayerMetadata>,
 ) {
-    for (e, t, mut g, mut g_dir, mut on_ground) in &mut q {
-        g.0 = 200.0;
-        on_ground.0 = true;
-        g_dir.0 = Direction::Down;
+    for (e, instance, t, parent, mut platform, mut sprite) in &mut q {
+        let size = Vec2::new(instance.width as f32, instance.height as f32);
+        sprite.custom_size = Some(size);
+        sprite.color = Color::rgb(0.5, 0.52, 0.58);
+
+        platform.path = vec![t.translation.truncate()];
+        let path = field(&instance.field_instances, "Path");
+        if let (Ok(layer), Some(FieldValue::Points(points))) = (layers.get(parent.get()), path) {
+            let grid_size = layer.grid_size as f32;
+            // ldtk cells count down from the top, the layer's y goes up
+            platform.path.extend(points.iter().flatten().map(|point| {
+                Vec2::new(point.x as f32 + 0.5, (layer.c_hei - point.y) as f32 - 0.5) * grid_size
+            }));
+        }
+        platform.next_target();
+
         commands
             .entity(e)
             .insert((
                 CollisionTypes::Ground,
-                CollisionEvents::<CollisionTypes>::new(),
                 PositionDelta {
                     origin: t.translation.truncate(),
                     ray: Vec2::ZERO,
                 },
             ))
             .with_children(|children| {
-                children.spawn(RectBundle::new(Vec2::new(71.0, 71.0)));
+                children.spawn(RectBundle::new(size));
             });
     }
 }
 
+/// Sets each platform's velocity to reach its next waypoint. The last step
+/// before a waypoint is shortened so it stops right on it.
+fn move_platforms(
+    mut platforms: Query<(&mut MovingPlatform, &Transform, &mut Velocity)>,
+    time_step: Res<FixedTime>,
+) {
+    let dt = time_step.period.as_secs_f32();
+    for (mut platform, t, mut v) in &mut platforms {
+        let Some(&target) = platform.path.get(platform.target) else {
+            v.0 = Vec2::ZERO;
+            continue;
+        };
+        let to_target = target - t.translation.truncate();
+        if to_target.length() <= platform.speed * dt {
+            v.0 = to_target / dt;
+            platform.next_target();
+        } else {
+            v.0 = to_target.normalize() * platform.speed;
+        }
+    }
+}
+
+/// sent when a falling block starts to fall after the player jumps off it
+pub struct BlockFell {
+    pub position: Vec2,
+}
+
+#[derive(Component, Default)]
+struct PlayerContact {
+    pub is_in_contact: bool,
+}
+
+fn after_falling_ground_spawned(
+    mut commands: Commands,
+    mut q: Query<
+        (
+            Entity,
+            &mut Gravity,
+            &mut GravityDirection,
+            &mut OnGround,
+            Option<&DropOnSpawn>,
+        ),
+        Added<FallingGround>,
+    >,
+) {
+    for (e, mut g, mut g_dir, mut on_ground, drop) in &mut q {
+        g.0 = 200.0;
+        on_ground.0 = drop.is_none();
+        if drop.is_none() {
+            g_dir.0 = Direction::Down;
+        }
+        commands.entity(e).insert(CollisionTypes::Ground);
+    }
+}
+
 fn fall_block_after_jump(
     player_collisions: Query<(&OnGround, &GravityDirection, &CollisionEvents<CollisionTypes>), With<Player>>,
     mut falling_blocks: Query<
-        (&mut OnGround, &mut GravityDirection, &mut PlayerContact),
+        (
+            &mut OnGround,
+            &mut GravityDirection,
+            &mut PlayerContact,
+            &GlobalTransform,
+        ),
         (With<FallingGround>, Without<Player>),
     >,
     mut last_in_contact: Local<Vec<Entity>>,
+    mut fell: EventWriter<BlockFell>,
 ) {
     let mut in_contact = Vec::with_capacity(10);
     if let Ok((on_ground, player_g_dir, player_collisions)) = player_collisions.get_single() {
         if on_ground.0 {
             for collision in &player_collisions.buffer {
-                if let Ok((_, mut g_dir, mut player_contact)) = falling_blocks.get_mut(collision.entity)
+                if let Ok((_, mut g_dir, mut player_contact, _)) =
+                    falling_blocks.get_mut(collision.entity)
                 {
                     in_contact.push(collision.entity);
     
@@ -135,8 +434,13 @@ fn fall_block_after_jump(
 
     for e in &last_in_contact {
         if !in_contact.contains(e) {
-            if let Ok((mut on_ground, _, mut player_contact)) = falling_blocks.get_mut(*e) {
+            if let Ok((mut on_ground, _, mut player_contact, t)) = falling_blocks.get_mut(*e) {
                 player_contact.is_in_contact = false;
+                if on_ground.0 {
+                    fell.send(BlockFell {
+                        position: t.translation().truncate(),
+                    });
+                }
                 on_ground.0 = false;
             }
         }
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::LayerMetadata, GridCoords, IntGridCell, LdtkAsset};
+use bevy_ecs_tilemap::tiles::{TileFlip, TilePos, TileStorage, TileTextureIndex, TileVisible};
+
+use crate::{
+    auto_tiling::AutoTiler,
+    collisions::{ContactResponse, Restitution},
+    constants::CollisionTypes,
+    game_state::GameState,
+    ground::{FallingGround, Ground, OneWayPlatform},
+    level_packs::LevelPacks,
+    navigation::NavGrid,
+    preload::Preloaded,
+};
+
+/// the int grid layer ground cells are painted on in ldtk
+const GROUND_LAYER: &str = "Platforms";
+
+/// Removes ground cells while playing. Send a `GroundEdit` event and the
+/// cell's tile, collider and nav grid cell are updated together, then the
+/// tiles around it are picked again with the level's auto tiling rules.
+pub struct GroundEditPlugin;
+impl Plugin for GroundEditPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GroundEdit>()
+            .add_system(apply_ground_edits.in_set(GameState::Playing))
+            // after the edits' commands have been applied
+            .add_system(
+                retile_edited_ground
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// change to a ground cell, `GridCoords` are the ground layer's cells
+pub enum GroundEdit {
+    Remove(GridCoords),
+}
+
+impl GroundEdit {
+    pub fn cell(&self) -> GridCoords {
+        match *self {
+            GroundEdit::Remove(cell) => cell,
+        }
+    }
+}
+
+fn tile_pos(cell: GridCoords, storage: &TileStorage) -> Option<TilePos> {
+    let in_map = cell.x >= 0
+        && cell.y >= 0
+        && (cell.x as u32) < storage.size.x
+        && (cell.y as u32) < storage.size.y;
+    in_map.then(|| TilePos::new(cell.x as u32, cell.y as u32))
+}
+
+/// Ground cells are the ground layer's tiles, so a removed cell keeps its tile
+/// entity and only loses its collider. That keeps the tilemap's storage valid.
+pub fn apply_ground_edits(
+    mut commands: Commands,
+    mut edits: EventReader<GroundEdit>,
+    ground: Query<(Entity, &GridCoords), (With<Ground>, Without<FallingGround>)>,
+    layers: Query<(&LayerMetadata, &TileStorage)>,
+    mut tiles: Query<&mut TileVisible>,
+    mut nav: ResMut<NavGrid>,
+) {
+    for edit in &mut edits {
+        match *edit {
+            GroundEdit::Remove(cell) => {
+                let Some((e, _)) = ground.iter().find(|(_, &coords)| coords == cell) else {
+                    continue;
+                };
+                commands
+                    .entity(e)
+                    .remove::<(
+                        Ground,
+                        OneWayPlatform,
+                        CollisionTypes,
+                        ContactResponse,
+                        Restitution,
+                    )>()
+                    .despawn_descendants();
+                hide_tiles(&layers, &mut tiles, cell);
+                nav.set_cell(cell.into(), false);
+            }
+        }
+    }
+}
+
+/// hides the tile over `cell` in every tilemap of the ground layer
+fn hide_tiles(
+    layers: &Query<(&LayerMetadata, &TileStorage)>,
+    tiles: &mut Query<&mut TileVisible>,
+    cell: GridCoords,
+) {
+    for (metadata, storage) in layers {
+        if metadata.identifier != GROUND_LAYER {
+            continue;
+        }
+        let Some(e) = tile_pos(cell, storage).and_then(|pos| storage.get(&pos)) else {
+            continue;
+        };
+        if let Ok(mut tile) = tiles.get_mut(e) {
+            tile.0 = false;
+        }
+    }
+}
+
+/// Picks the tiles around edited cells again, so edges and corners follow the
+/// ground as it changes.
+fn retile_edited_ground(
+    mut edits: EventReader<GroundEdit>,
+    packs: Res<LevelPacks>,
+    preloaded: Res<Preloaded>,
+    ldtks: Res<Assets<LdtkAsset>>,
+    ground: Query<(&GridCoords, &IntGridCell), (With<Ground>, Without<FallingGround>)>,
+    layers: Query<(&LayerMetadata, &TileStorage)>,
+    mut tiles: Query<(&mut TileTextureIndex, &mut TileFlip, &mut TileVisible)>,
+) {
+    let edited: Vec<IVec2> = edits.iter().map(|edit| edit.cell().into()).collect();
+    if edited.is_empty() {
+        return;
+    }
+    let Some(layer) = ldtks.get(&packs.ldtk(&preloaded)).and_then(|ldtk| {
+        ldtk.project
+            .defs
+            .layers
+            .iter()
+            .find(|layer| layer.identifier == GROUND_LAYER)
+    }) else {
+        return;
+    };
+    let Some((_, storage)) = layers
+        .iter()
+        .find(|(metadata, _)| metadata.identifier == GROUND_LAYER)
+    else {
+        return;
+    };
+
+    let tiler = AutoTiler::from_layer(layer);
+    let values: HashMap<IVec2, i32> = ground
+        .iter()
+        .map(|(&coords, cell)| (coords.into(), cell.value))
+        .collect();
+    let value = |cell: IVec2| {
+        tile_pos(cell.into(), storage)?;
+        Some(values.get(&cell).copied().unwrap_or(0))
+    };
+
+    let reach = tiler.reach();
+    let mut retiled = HashSet::new();
+    for cell in edited {
+        for x in -reach..=reach {
+            for y in -reach..=reach {
+                let cell = cell + IVec2::new(x, y);
+                if !retiled.insert(cell) {
+                    continue;
+                }
+                let Some(e) = tile_pos(cell.into(), storage).and_then(|pos| storage.get(&pos))
+                else {
+                    continue;
+                };
+                let Ok((mut index, mut flip, mut visible)) = tiles.get_mut(e) else {
+                    continue;
+                };
+                // only ground has tiles, so empty cells are left hidden
+                let tile = values
+                    .contains_key(&cell)
+                    .then(|| tiler.resolve(cell, value))
+                    .flatten();
+                visible.0 = tile.is_some();
+                if let Some(tile) = tile {
+                    index.0 = tile.index;
+                    flip.x = tile.flip_x;
+                    flip.y = tile.flip_y;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{
+    ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity, LevelSelection,
+};
+
+use crate::{
+    collisions::{CollisionEvents, CollisionSets, RectBundle},
+    constants::CollisionTypes,
+    game_state::GameState,
+    level_packs::LevelPacks,
+    level_registry::field,
+    save::SaveData,
+};
+
+const DOOR_SIZE: Vec2 = Vec2::new(24.0, 36.0);
+const DOOR_COLOR: Color = Color::rgb(0.55, 0.35, 0.2);
+/// doors to levels that have been finished
+const FINISHED_DOOR_COLOR: Color = Color::rgb(0.35, 0.7, 0.4);
+
+/// A level with the `Hub` field set is a hub. Runs start there instead of the
+/// first level, and its doors lead to the other levels. Finishing a level
+/// returns to the hub instead of going on to the next one.
+pub struct HubPlugin;
+impl Plugin for HubPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HubReturn>()
+            .register_ldtk_entity::<DoorBundle>("Door")
+            .add_system(leave_hub.in_schedule(OnExit(GameState::StartMenu)))
+            .add_system(after_door_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(enter_door.in_set(CollisionSets::Consume))
+            .add_system(color_doors.in_set(GameState::Playing));
+    }
+}
+
+/// hub the current level was entered from, finishing the level goes back to it
+#[derive(Resource, Default)]
+pub struct HubReturn(pub Option<usize>);
+
+/// leads to the level `level` when the player touches it
+#[derive(Component, Default)]
+pub struct Door {
+    pub level: usize,
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct DoorBundle {
+    #[with(door_from_instance)]
+    door: Door,
+    sprite: SpriteBundle,
+}
+
+fn door_from_instance(instance: &EntityInstance) -> Door {
+    match field(&instance.field_instances, "Level") {
+        Some(FieldValue::Int(Some(level))) => Door {
+            level: (*level).max(0) as usize,
+        },
+        _ => {
+            warn!("door at {} has no Level to lead to", instance.px);
+            Door::default()
+        }
+    }
+}
+
+fn leave_hub(mut hub: ResMut<HubReturn>) {
+    hub.0 = None;
+}
+
+fn after_door_spawned(mut commands: Commands, mut q: Query<(Entity, &mut Sprite), Added<Door>>) {
+    for (e, mut sprite) in &mut q {
+        sprite.custom_size = Some(DOOR_SIZE);
+        commands
+            .entity(e)
+            .insert((
+                CollisionTypes::Door,
+                CollisionEvents::<CollisionTypes>::new(),
+            ))
+            .with_children(|children| {
+                children.spawn(RectBundle::new(DOOR_SIZE));
+            });
+    }
+}
+
+fn enter_door(
+    mut doors: Query<(&Door, &mut CollisionEvents<CollisionTypes>)>,
+    mut hub: ResMut<HubReturn>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    for (door, mut events) in &mut doors {
+        if !events
+            .buffer
+            .drain(..)
+            .any(|event| event.user_type == CollisionTypes::Player)
+        {
+            continue;
+        }
+        let LevelSelection::Index(current) = *level_selection else {
+            continue;
+        };
+        hub.0 = Some(current);
+        *level_selection = LevelSelection::Index(door.level);
+        state.set(GameState::SpawnLevel);
+        return;
+    }
+}
+
+/// finished levels are remembered in the save, so doors stay marked between sessions
+fn color_doors(
+    save: Res<SaveData>,
+    packs: Res<LevelPacks>,
+    mut doors: Query<(&Door, &mut Sprite)>,
+) {
+    for (door, mut sprite) in &mut doors {
+        let color = if save.is_completed(packs.active_name(), door.level) {
+            FINISHED_DOOR_COLOR
+        } else {
+            DOOR_COLOR
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
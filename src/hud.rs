@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{
+    game_state::GameState, goals::Goal, physics::GravityDirection, player::Player,
+    preload::Preloaded,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const ARROW_SIZE: f32 = 24.0;
+
+/// current level, goals left and which way gravity pulls, along the bottom left while playing
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_hud.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(despawn_hud.in_schedule(OnExit(GameState::Playing)))
+            .add_systems(
+                (update_level_text, update_goals_text, update_gravity_arrow)
+                    .in_set(GameState::Playing),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Component)]
+struct GoalsText;
+
+#[derive(Component)]
+struct GravityArrow;
+
+fn level_text(level: &LevelSelection) -> String {
+    match level {
+        LevelSelection::Index(index) => format!("Level {}", index + 1),
+        _ => String::new(),
+    }
+}
+
+fn spawn_hud(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    level: Res<LevelSelection>,
+    goals: Query<(), With<Goal>>,
+    player: Query<&GravityDirection, With<Player>>,
+) {
+    let style = TextStyle {
+        font: preloaded.get("font"),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+    let gravity = player.get_single().map_or(0.0, |g| g.angle());
+
+    commands
+        .spawn((
+            Hud,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(10.0),
+                        left: Val::Px(10.0),
+                        ..default()
+                    },
+                    align_items: AlignItems::Center,
+                    gap: Size::width(Val::Px(16.0)),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LevelText,
+                TextBundle::from_section(level_text(&level), style.clone()),
+            ));
+            parent.spawn((
+                GoalsText,
+                TextBundle::from_section(goals_text(goals.iter().len()), style),
+            ));
+            parent.spawn((
+                GravityArrow,
+                ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(ARROW_SIZE), Val::Px(ARROW_SIZE)),
+                        ..default()
+                    },
+                    image: preloaded.get("goal_arrow").into(),
+                    transform: Transform::from_rotation(arrow_rotation(gravity)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn despawn_hud(mut commands: Commands, q: Query<Entity, With<Hud>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn goals_text(remaining: usize) -> String {
+    format!("Goals left: {remaining}")
+}
+
+/// The arrow image points right. UI y points down the screen, so turning
+/// the other way to world sprites.
+fn arrow_rotation(gravity_angle: f32) -> Quat {
+    Quat::from_rotation_z(std::f32::consts::FRAC_PI_2 - gravity_angle)
+}
+
+fn update_level_text(level: Res<LevelSelection>, mut q: Query<&mut Text, With<LevelText>>) {
+    if !level.is_changed() {
+        return;
+    }
+    for mut text in &mut q {
+        text.sections[0].value = level_text(&level);
+    }
+}
+
+fn update_goals_text(
+    added: Query<(), Added<Goal>>,
+    mut removed: RemovedComponents<Goal>,
+    goals: Query<(), With<Goal>>,
+    mut q: Query<&mut Text, With<GoalsText>>,
+) {
+    // read them all so they don't count again next frame
+    let removed = removed.iter().count();
+    if added.is_empty() && removed == 0 {
+        return;
+    }
+    for mut text in &mut q {
+        text.sections[0].value = goals_text(goals.iter().len());
+    }
+}
+
+fn update_gravity_arrow(
+    player: Query<&GravityDirection, (With<Player>, Changed<GravityDirection>)>,
+    mut arrows: Query<&mut Transform, With<GravityArrow>>,
+) {
+    let Ok(g) = player.get_single() else {
+        return;
+    };
+    for mut t in &mut arrows {
+        t.rotation = arrow_rotation(g.angle());
+    }
+}
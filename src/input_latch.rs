@@ -0,0 +1,61 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::game_state::GameState;
+
+/// Ignores actions that were already held when the game state changed or the
+/// entity reading them was spawned, until they're let go and pressed again.
+/// Stops a jump held through a level transition firing as soon as the player
+/// respawns.
+#[derive(Default)]
+pub struct InputLatchPlugin<A: Actionlike>(PhantomData<A>);
+
+impl<A: Actionlike> InputLatchPlugin<A> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Actionlike> Plugin for InputLatchPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_system(latch_actions::<A>.in_set(LatchInputs));
+    }
+}
+
+/// Runs before anything reads the player's actions
+#[derive(SystemSet, Hash, Eq, PartialEq, Clone, Debug)]
+pub struct LatchInputs;
+
+/// Only entities with an `InputMap` are latched, anything driving an
+/// `ActionState` itself, like the shadow cat, is left alone.
+fn latch_actions<A: Actionlike>(
+    state: Res<State<GameState>>,
+    mut latched: Local<HashMap<Entity, Vec<A>>>,
+    mut q: Query<(Entity, &mut ActionState<A>, Ref<InputMap<A>>)>,
+) {
+    latched.retain(|e, _| q.contains(*e));
+
+    for (e, mut action_state, input_map) in &mut q {
+        if state.is_changed() || input_map.is_added() {
+            latched.insert(e, A::variants().collect());
+        }
+        let Some(actions) = latched.get_mut(&e) else {
+            continue;
+        };
+
+        // the input manager presses held actions again every frame, so they're
+        // released here every frame until the input is actually let go
+        actions.retain(|action| {
+            let held = action_state.pressed(action.clone());
+            if held {
+                action_state.release(action.clone());
+            }
+            held
+        });
+        if actions.is_empty() {
+            latched.remove(&e);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{core::FrameCount, prelude::*};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    config::DebugConfig,
+    fixed_input::BufferInputs,
+    player::{InputProcessing, JumpAction, Player, PlayerJumped},
+    preload::Preloaded,
+};
+
+/// how many of the latest jumps are listed
+const SHOWN_JUMPS: usize = 8;
+
+/// Lists the delay from pressing jump to jumping for the last few jumps, to
+/// check the fixed tick buffering does what it should. Turned on with the
+/// debug config's `show_input_stats`.
+pub struct InputStatsPlugin;
+impl Plugin for InputStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JumpStats>()
+            .add_system(
+                record_presses
+                    .in_base_set(InputProcessing)
+                    .after(BufferInputs)
+                    .run_if(show_input_stats),
+            )
+            .add_systems((record_jumps.run_if(show_input_stats), update_overlay).chain());
+    }
+}
+
+fn show_input_stats(debug: Res<DebugConfig>) -> bool {
+    debug.show_input_stats
+}
+
+/// one jump, measured from the frame its press was seen
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct JumpSample {
+    delay: Duration,
+    frames: u32,
+    /// the press was kept over frames without a fixed tick until one ran
+    buffered: bool,
+}
+
+#[derive(Resource, Default)]
+struct JumpStats {
+    /// real time and frame of the latest press that hasn't jumped yet
+    pressed: Option<(Duration, u32)>,
+    jumps: VecDeque<JumpSample>,
+}
+
+impl JumpStats {
+    fn press(&mut self, now: Duration, frame: u32) {
+        self.pressed = Some((now, frame));
+    }
+
+    /// Pairs the jump with the press that caused it, jumps only happen on the
+    /// first tick after a press so it's always the latest one.
+    fn jump(&mut self, now: Duration, frame: u32) {
+        let Some((pressed_at, pressed_frame)) = self.pressed.take() else {
+            return;
+        };
+        let frames = frame.wrapping_sub(pressed_frame);
+        self.jumps.push_front(JumpSample {
+            delay: now.saturating_sub(pressed_at),
+            frames,
+            buffered: frames > 0,
+        });
+        self.jumps.truncate(SHOWN_JUMPS);
+    }
+}
+
+#[derive(Component)]
+struct InputStatsText;
+
+fn record_presses(
+    time: Res<Time>,
+    frame: Res<FrameCount>,
+    mut stats: ResMut<JumpStats>,
+    player: Query<&ActionState<JumpAction>, With<Player>>,
+) {
+    let Ok(action_state) = player.get_single() else {
+        return;
+    };
+    if action_state.just_pressed(JumpAction::Jump) {
+        stats.press(time.raw_elapsed(), frame.0);
+    }
+}
+
+/// jumps are sent from the fixed ticks, which run before this in the same frame
+fn record_jumps(
+    time: Res<Time>,
+    frame: Res<FrameCount>,
+    mut stats: ResMut<JumpStats>,
+    mut jumped: EventReader<PlayerJumped>,
+) {
+    for _ in &mut jumped {
+        stats.jump(time.raw_elapsed(), frame.0);
+    }
+}
+
+fn stats_text(stats: &JumpStats) -> String {
+    let mut text = String::from("press to jump");
+    for jump in &stats.jumps {
+        text.push_str(&format!(
+            "\n{:.1} ms, frame +{}{}",
+            jump.delay.as_secs_f64() * 1000.0,
+            jump.frames,
+            if jump.buffered { "  buffered" } else { "" },
+        ));
+    }
+    text
+}
+
+/// spawns and despawns the overlay as the debug config changes
+fn update_overlay(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    debug: Res<DebugConfig>,
+    stats: Res<JumpStats>,
+    mut q: Query<(Entity, &mut Text), With<InputStatsText>>,
+) {
+    if !debug.show_input_stats {
+        for (e, _) in &q {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    if q.is_empty() {
+        commands.spawn((
+            InputStatsText,
+            TextBundle::from_section(
+                stats_text(&stats),
+                TextStyle {
+                    font: preloaded.get("font"),
+                    font_size: 16.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        ));
+        return;
+    }
+    if !stats.is_changed() {
+        return;
+    }
+    for (_, mut text) in &mut q {
+        text.sections[0].value = stats_text(&stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_jumps_with_their_press() {
+        let mut stats = JumpStats::default();
+        // a jump pressed before the overlay was turned on isn't counted
+        stats.jump(Duration::from_millis(10), 1);
+        assert!(stats.jumps.is_empty());
+
+        stats.press(Duration::from_millis(100), 5);
+        stats.jump(Duration::from_millis(100), 5);
+        stats.press(Duration::from_millis(200), 8);
+        stats.jump(Duration::from_millis(216), 9);
+        assert_eq!(
+            stats.jumps.front(),
+            Some(&JumpSample {
+                delay: Duration::from_millis(16),
+                frames: 1,
+                buffered: true,
+            })
+        );
+        assert!(!stats.jumps[1].buffered);
+    }
+}
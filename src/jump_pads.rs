@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    collisions::{CollisionEvents, CollisionSets, Ray, Rect, RectBundle},
+    constants::CollisionTypes,
+    game_state::GameState,
+    level_registry::field,
+    physics::{
+        set_gravity_direction, Acceleration, Direction, GravityDirection, JumpState, OnGround,
+        PhysicsSettings, Velocity,
+    },
+    player::Player,
+    sfx::{PlaySfx, Sfx},
+};
+
+/// how long the spring animation plays, the pad can't fire again until it's done
+const LAUNCH_SECONDS: f32 = 0.3;
+const LAUNCH_FRAMES: [usize; 4] = [1, 2, 3, 0];
+
+pub struct JumpPadPlugin;
+impl Plugin for JumpPadPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<JumpPadBundle>("Jump_Pad")
+            .add_system(after_jump_pad_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(launch_player.in_set(CollisionSets::Consume))
+            .add_system(animate_jump_pads);
+    }
+}
+
+/// launches the player in `direction` no matter which way gravity points
+#[derive(Component)]
+pub struct JumpPad {
+    pub direction: Direction,
+    /// also point the player's gravity opposite `direction`, back at the
+    /// surface the pad launches away from, for running along ceilings and walls
+    pub flip_gravity: bool,
+    launch: Timer,
+}
+
+impl Default for JumpPad {
+    fn default() -> Self {
+        let mut launch = Timer::from_seconds(LAUNCH_SECONDS, TimerMode::Once);
+        // start finished so the pad is ready to fire
+        launch.tick(launch.duration());
+        JumpPad {
+            direction: Direction::Up,
+            flip_gravity: false,
+            launch,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct JumpPadBundle {
+    #[with(jump_pad_from_instance)]
+    jump_pad: JumpPad,
+    #[sprite_sheet_bundle("jump-pad.png", 24.0, 24.0, 4, 1, 0.0, 0.0, 0)]
+    sprite_sheet: SpriteSheetBundle,
+}
+
+fn jump_pad_from_instance(instance: &EntityInstance) -> JumpPad {
+    let fields = &instance.field_instances;
+    let direction = match field(fields, "Direction") {
+        Some(FieldValue::Enum(Some(direction))) => match direction.as_str() {
+            "Down" => Direction::Down,
+            "Left" => Direction::Left,
+            "Right" => Direction::Right,
+            _ => Direction::Up,
+        },
+        _ => Direction::Up,
+    };
+    let flip_gravity = matches!(field(fields, "Flip_Gravity"), Some(FieldValue::Bool(true)));
+
+    JumpPad {
+        direction,
+        flip_gravity,
+        ..default()
+    }
+}
+
+fn after_jump_pad_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &JumpPad, &mut Transform), Added<JumpPad>>,
+) {
+    for (e, pad, mut t) in &mut q {
+        // the sprite points up, gravity pointing down is unrotated
+        t.rotation = Quat::from_rotation_z(pad.direction.reverse().angle());
+        commands
+            .entity(e)
+            .insert((
+                CollisionTypes::JumpPad,
+                CollisionEvents::<CollisionTypes>::new(),
+            ))
+            .with_children(|children| {
+                children.spawn(RectBundle::new(Vec2::new(24., 24.)));
+            });
+    }
+}
+
+fn launch_player(
+    mut pads: Query<(
+        &mut JumpPad,
+        &GlobalTransform,
+        &mut CollisionEvents<CollisionTypes>,
+    )>,
+    mut player: Query<
+        (
+            &mut Velocity,
+            &mut Acceleration,
+            &mut OnGround,
+            &mut JumpState,
+            &mut GravityDirection,
+            &mut Transform,
+            &Children,
+        ),
+        With<Player>,
+    >,
+    mut aabb_colliders: Query<&mut Rect>,
+    mut rays: Query<&mut Ray>,
+    settings: Res<PhysicsSettings>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for (mut pad, pad_transform, mut collision_events) in &mut pads {
+        let touched = collision_events
+            .buffer
+            .drain(..)
+            .any(|event| event.user_type == CollisionTypes::Player);
+        if !touched || !pad.launch.finished() {
+            continue;
+        }
+
+        for (mut v, mut a, mut on_ground, mut jump_state, mut g_dir, mut t, children) in &mut player
+        {
+            if pad.flip_gravity {
+                set_gravity_direction(
+                    pad.direction.reverse(),
+                    &mut g_dir,
+                    &mut t,
+                    children,
+                    &mut aabb_colliders,
+                    &mut rays,
+                );
+            }
+            v.0 = pad.direction.as_vec2() * settings.jump_pad_speed;
+            a.0 = Vec2::ZERO;
+            on_ground.0 = false;
+            jump_state.turned_this_jump = false;
+        }
+
+        pad.launch.reset();
+        sfx.send(PlaySfx::at(
+            Sfx::JumpPad,
+            pad_transform.translation().truncate(),
+        ));
+    }
+}
+
+fn animate_jump_pads(time: Res<Time>, mut q: Query<(&mut JumpPad, &mut TextureAtlasSprite)>) {
+    for (mut pad, mut sprite) in &mut q {
+        if pad.launch.finished() {
+            continue;
+        }
+
+        pad.launch.tick(time.delta());
+        let frame = (pad.launch.percent() * LAUNCH_FRAMES.len() as f32) as usize;
+        sprite.index = LAUNCH_FRAMES[frame.min(LAUNCH_FRAMES.len() - 1)];
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use bevy::{
+    input::keyboard::{KeyboardInput, ScanCode},
+    prelude::*,
+};
+use leafwing_input_manager::user_input::InputKind;
+
+use crate::{
+    game_state::GameState,
+    preload::Preloaded,
+    settings::{Bindable, PlayerSettings},
+    start_menu::MenuMarker,
+};
+
+/// Physical keys for the default movement bindings, these are where W, A, S
+/// and D sit on a qwerty keyboard no matter what layout is active.
+#[cfg(target_os = "macos")]
+mod scan_codes {
+    use bevy::input::keyboard::ScanCode;
+
+    pub const UP: ScanCode = ScanCode(0x0d);
+    pub const LEFT: ScanCode = ScanCode(0x00);
+    pub const DOWN: ScanCode = ScanCode(0x01);
+    pub const RIGHT: ScanCode = ScanCode(0x02);
+}
+
+/// Physical keys for the default movement bindings, these are where W, A, S
+/// and D sit on a qwerty keyboard no matter what layout is active.
+#[cfg(not(target_os = "macos"))]
+mod scan_codes {
+    use bevy::input::keyboard::ScanCode;
+
+    pub const UP: ScanCode = ScanCode(0x11);
+    pub const LEFT: ScanCode = ScanCode(0x1e);
+    pub const DOWN: ScanCode = ScanCode(0x1f);
+    pub const RIGHT: ScanCode = ScanCode(0x20);
+}
+
+/// One of the default wasd movement keys
+#[derive(Clone, Copy, Debug)]
+pub struct WasdKey {
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    scan_code: ScanCode,
+    /// the key on a qwerty layout
+    qwerty: KeyCode,
+}
+
+pub const UP: WasdKey = WasdKey {
+    scan_code: scan_codes::UP,
+    qwerty: KeyCode::W,
+};
+pub const LEFT: WasdKey = WasdKey {
+    scan_code: scan_codes::LEFT,
+    qwerty: KeyCode::A,
+};
+pub const DOWN: WasdKey = WasdKey {
+    scan_code: scan_codes::DOWN,
+    qwerty: KeyCode::S,
+};
+pub const RIGHT: WasdKey = WasdKey {
+    scan_code: scan_codes::RIGHT,
+    qwerty: KeyCode::D,
+};
+
+impl WasdKey {
+    /// bound by physical key so it works on any layout
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn input(self) -> InputKind {
+        InputKind::KeyLocation(self.scan_code)
+    }
+
+    /// On the web winit reports the DOM `keyCode` as the scan code, which
+    /// follows the layout instead of the key's position (0x20 is Space), so
+    /// the keys are bound by name there.
+    #[cfg(target_arch = "wasm32")]
+    pub fn input(self) -> InputKind {
+        InputKind::Keyboard(self.qwerty)
+    }
+
+    /// the key on the active layout, falls back to the qwerty key until the
+    /// key has been pressed once
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn key_code(self, layout: &KeyLayout) -> KeyCode {
+        layout
+            .0
+            .get(&self.scan_code.0)
+            .copied()
+            .unwrap_or(self.qwerty)
+    }
+
+    /// the keys are bound by name on the web, see `input`
+    #[cfg(target_arch = "wasm32")]
+    pub fn key_code(self, _layout: &KeyLayout) -> KeyCode {
+        self.qwerty
+    }
+}
+
+pub struct KeyboardLayoutPlugin;
+impl Plugin for KeyboardLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyLayout>()
+            .add_system(learn_layout)
+            .add_system(spawn_controls_hint.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_system(update_controls_hint.run_if(
+                resource_changed::<KeyLayout>().or_else(resource_changed::<PlayerSettings>()),
+            ));
+    }
+}
+
+/// Which logical key each physical key produced the last time it was pressed.
+/// There's no way to ask for the active layout, so it's learned from key presses.
+#[derive(Resource, Default)]
+pub struct KeyLayout(HashMap<u32, KeyCode>);
+
+fn learn_layout(mut events: EventReader<KeyboardInput>, mut layout: ResMut<KeyLayout>) {
+    for event in &mut events {
+        let Some(key_code) = event.key_code else {
+            continue;
+        };
+        if layout.0.get(&event.scan_code) != Some(&key_code) {
+            layout.0.insert(event.scan_code, key_code);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ControlsHint;
+
+fn controls_text(layout: &KeyLayout, settings: &PlayerSettings) -> String {
+    let key = |action| settings.bindings.display_name(action, layout);
+    format!(
+        "Move: {} {} {} {} / Arrows\nJump: {}\nGrab: E",
+        key(Bindable::Up),
+        key(Bindable::Left),
+        key(Bindable::Down),
+        key(Bindable::Right),
+        key(Bindable::Jump),
+    )
+}
+
+fn spawn_controls_hint(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    layout: Res<KeyLayout>,
+    settings: Res<PlayerSettings>,
+) {
+    commands.spawn((
+        MenuMarker,
+        ControlsHint,
+        TextBundle::from_section(
+            controls_text(&layout, &settings),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            // above the run modes listed in the same corner
+            position: UiRect {
+                bottom: Val::Px(150.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn update_controls_hint(
+    layout: Res<KeyLayout>,
+    settings: Res<PlayerSettings>,
+    mut q: Query<&mut Text, With<ControlsHint>>,
+) {
+    for mut text in &mut q {
+        text.sections[0].value = controls_text(&layout, &settings);
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    collisions::{cast_ray, Rect},
+    constants::CollisionTypes,
+    game_state::GameState,
+    level_registry::field,
+    physics::Direction,
+    player::{DeathCause, Player, PlayerDeath},
+};
+
+/// furthest a beam reaches when nothing blocks it
+const MAX_BEAM_LENGTH: f32 = 1000.0;
+const BEAM_WIDTH: f32 = 4.0;
+
+pub struct LaserPlugin;
+impl Plugin for LaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<LaserBundle>("Laser")
+            .add_system(after_laser_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_systems(
+                (cycle_lasers, fire_lasers)
+                    .chain()
+                    .in_set(GameState::Playing),
+            );
+    }
+}
+
+/// Casts a beam along `direction` until it hits ground, the player dies if
+/// they touch it while it's on.
+#[derive(Component)]
+pub struct Laser {
+    pub direction: Direction,
+    /// switches and triggers can turn the beam on and off through this
+    pub on: bool,
+    /// turns the beam on and off on its own when set from the entity fields
+    cycle: Option<LaserCycle>,
+}
+
+struct LaserCycle {
+    on_seconds: f32,
+    off_seconds: f32,
+    timer: Timer,
+}
+
+impl Default for Laser {
+    fn default() -> Self {
+        Laser {
+            direction: Direction::Up,
+            on: true,
+            cycle: None,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct LaserBundle {
+    #[with(laser_from_instance)]
+    laser: Laser,
+    #[sprite_bundle("laser.png")]
+    sprite: SpriteBundle,
+}
+
+fn laser_from_instance(instance: &EntityInstance) -> Laser {
+    let fields = &instance.field_instances;
+    let direction = match field(fields, "Direction") {
+        Some(FieldValue::Enum(Some(direction))) => match direction.as_str() {
+            "Down" => Direction::Down,
+            "Left" => Direction::Left,
+            "Right" => Direction::Right,
+            _ => Direction::Up,
+        },
+        _ => Direction::Up,
+    };
+    let seconds = |name| match field(fields, name) {
+        Some(FieldValue::Float(Some(seconds))) if *seconds > 0.0 => Some(*seconds),
+        _ => None,
+    };
+    // only cycles when both fields are set
+    let cycle =
+        seconds("On_Seconds")
+            .zip(seconds("Off_Seconds"))
+            .map(|(on_seconds, off_seconds)| LaserCycle {
+                on_seconds,
+                off_seconds,
+                timer: Timer::from_seconds(on_seconds, TimerMode::Once),
+            });
+
+    Laser {
+        direction,
+        cycle,
+        ..default()
+    }
+}
+
+/// the visible beam, a child of the laser
+#[derive(Component)]
+struct LaserBeam;
+
+fn after_laser_spawned(mut commands: Commands, q: Query<Entity, Added<Laser>>) {
+    for e in &q {
+        commands.entity(e).with_children(|children| {
+            children.spawn((
+                LaserBeam,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(1.0, 0.25, 0.4),
+                        custom_size: Some(Vec2::ZERO),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+fn cycle_lasers(time: Res<Time>, mut lasers: Query<&mut Laser>) {
+    for mut laser in &mut lasers {
+        let laser = &mut *laser;
+        let Some(cycle) = &mut laser.cycle else {
+            continue;
+        };
+        if cycle.timer.tick(time.delta()).just_finished() {
+            laser.on = !laser.on;
+            let seconds = if laser.on {
+                cycle.on_seconds
+            } else {
+                cycle.off_seconds
+            };
+            cycle.timer = Timer::from_seconds(seconds, TimerMode::Once);
+        }
+    }
+}
+
+fn fire_lasers(
+    lasers: Query<(&Laser, &GlobalTransform, &Children)>,
+    mut beams: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<LaserBeam>>,
+    rects: Query<(&Rect, &GlobalTransform, &Parent)>,
+    types: Query<&CollisionTypes>,
+    player: Query<Entity, With<Player>>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    let player = player.get_single().ok();
+
+    for (laser, t, children) in &lasers {
+        let direction = laser.direction.as_vec2();
+        // start at the edge of the emitter so it doesn't hit the block it's on
+        let origin = t.translation().truncate() + direction * 6.0;
+
+        let length = if laser.on {
+            let ground = rects.iter().filter_map(|(rect, t, parent)| {
+                matches!(types.get(parent.get()), Ok(CollisionTypes::Ground)).then_some((
+                    parent.get(),
+                    rect,
+                    t,
+                ))
+            });
+            cast_ray(origin, direction * MAX_BEAM_LENGTH, ground)
+                .map_or(MAX_BEAM_LENGTH, |(_, hit)| hit.toi)
+        } else {
+            0.0
+        };
+
+        for child in children {
+            let Ok((mut sprite, mut beam_t, mut visibility)) = beams.get_mut(*child) else {
+                continue;
+            };
+            *visibility = if laser.on {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            sprite.custom_size = Some(if direction.x != 0.0 {
+                Vec2::new(length, BEAM_WIDTH)
+            } else {
+                Vec2::new(BEAM_WIDTH, length)
+            });
+            beam_t.translation = (direction * (6.0 + length / 2.0)).extend(-0.1);
+        }
+
+        let Some(player) = player else {
+            continue;
+        };
+        if length <= 0.0 {
+            continue;
+        }
+        let player_rects = rects
+            .iter()
+            .filter(|(_, _, parent)| parent.get() == player)
+            .map(|(rect, t, parent)| (parent.get(), rect, t));
+        if cast_ray(origin, direction * length, player_rects).is_some() {
+            deaths.send(PlayerDeath {
+                cause: DeathCause::Hazard,
+            });
+        }
+    }
+}
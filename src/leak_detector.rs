@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    boss::FromAttack,
+    collisions::{Ray, Rect},
+    config::DebugConfig,
+    game_state::GameState,
+    goals::Goal,
+    ground::{FallingGround, Ground},
+    player::Player,
+    turrets::Projectile,
+};
+
+/// frames to wait after a level is left before checking, ldtk despawns the
+/// old level over the next few frames
+const SETTLE_FRAMES: u32 = 5;
+
+pub struct LeakDetectorPlugin;
+impl Plugin for LeakDetectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeakCheck>()
+            .add_system(
+                snapshot_level_entities
+                    .in_schedule(OnExit(GameState::Playing))
+                    .run_if(detect_leaks),
+            )
+            .add_system(arm_leak_check.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(arm_leak_check.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_system(arm_leak_check.in_schedule(OnEnter(GameState::WinScreen)))
+            .add_system(check_leaks.run_if(detect_leaks));
+    }
+}
+
+fn detect_leaks(debug: Res<DebugConfig>) -> bool {
+    debug.detect_leaks
+}
+
+/// Level entities that existed when the player last left `Playing`. Any still
+/// around once the next level or menu has settled were leaked. Sounds aren't
+/// entities in this version of bevy, so they aren't checked.
+#[derive(Resource, Default)]
+struct LeakCheck {
+    snapshot: Vec<(Entity, &'static str)>,
+    frames_left: Option<u32>,
+}
+
+fn snapshot_level_entities(
+    mut check: ResMut<LeakCheck>,
+    colliders: Query<Entity, With<Rect>>,
+    rays: Query<Entity, With<Ray>>,
+    players: Query<Entity, With<Player>>,
+    goals: Query<Entity, With<Goal>>,
+    ground: Query<Entity, (With<Ground>, Without<FallingGround>)>,
+    blocks: Query<Entity, With<FallingGround>>,
+    projectiles: Query<Entity, With<Projectile>>,
+    attacks: Query<Entity, With<FromAttack>>,
+) {
+    check.snapshot = colliders
+        .iter()
+        .map(|e| (e, "collider"))
+        .chain(rays.iter().map(|e| (e, "ray")))
+        .chain(players.iter().map(|e| (e, "player")))
+        .chain(goals.iter().map(|e| (e, "goal")))
+        .chain(ground.iter().map(|e| (e, "ground")))
+        .chain(blocks.iter().map(|e| (e, "falling block")))
+        .chain(projectiles.iter().map(|e| (e, "projectile")))
+        .chain(attacks.iter().map(|e| (e, "boss attack")))
+        .collect();
+}
+
+fn arm_leak_check(mut check: ResMut<LeakCheck>) {
+    if !check.snapshot.is_empty() {
+        check.frames_left = Some(SETTLE_FRAMES);
+    }
+}
+
+fn check_leaks(
+    mut check: ResMut<LeakCheck>,
+    state: Res<State<GameState>>,
+    entities: Query<Entity>,
+) {
+    let Some(frames_left) = check.frames_left else {
+        return;
+    };
+    if frames_left > 0 {
+        check.frames_left = Some(frames_left - 1);
+        return;
+    }
+    check.frames_left = None;
+
+    let mut leaked = BTreeMap::<&str, usize>::new();
+    for (e, kind) in check.snapshot.drain(..) {
+        if entities.contains(e) {
+            *leaked.entry(kind).or_default() += 1;
+        }
+    }
+    if !leaked.is_empty() {
+        warn!(
+            "level entities survived into {:?}: {}",
+            state.0,
+            leaked
+                .iter()
+                .map(|(kind, count)| format!("{count} {kind}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
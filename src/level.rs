@@ -1,29 +1,91 @@
-use crate::{game_state::GameState, goals::Goal};
+use crate::{
+    boss::Boss, config::DebugConfig, game_state::GameState, goals::Goal, hub::HubReturn,
+    level_packs::LevelPacks, level_registry::LevelRegistry, preload::Preloaded,
+};
 use bevy::{asset::LoadState, prelude::*};
 use bevy_ecs_ldtk::{LdtkAsset, LdtkWorldBundle, LevelSelection};
 
 pub struct LevelPlugin;
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(LevelSelection::Index(0));
+        app.insert_resource(LevelSelection::Index(0))
+            .init_resource::<LevelSequence>()
+            .add_event::<LevelCompleted>();
 
         app.add_system(
             restart.run_if(in_state(GameState::Playing).or_else(in_state(GameState::WinScreen))),
         );
 
-        app.add_system(setup_ldtk.in_schedule(OnExit(GameState::StartMenu)))
+        app.add_systems((start_run, setup_ldtk).in_schedule(OnExit(GameState::StartMenu)))
             .add_system(check_load_status.run_if(in_state(GameState::LoadLevel)));
 
         app.add_system(spawn_done.run_if(in_state(GameState::SpawnLevel)));
         app.add_systems(
-            (level_complete, skip_level).distributive_run_if(in_state(GameState::Playing)),
+            (
+                level_complete,
+                skip_level.run_if(|debug: Res<DebugConfig>| debug.level_skip),
+            )
+                .distributive_run_if(in_state(GameState::Playing)),
         );
     }
 }
 
-fn setup_ldtk(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// sent when all the goals in a level have been collected and any boss beaten
+pub struct LevelCompleted {
+    pub index: usize,
+}
+
+/// Order levels are played in during a run. When `levels` is empty every
+/// level in the ldtk file is played in order.
+#[derive(Resource, Default)]
+pub struct LevelSequence {
+    pub levels: Vec<usize>,
+    /// position in the sequence of the current level
+    pub position: usize,
+}
+
+impl LevelSequence {
+    pub fn first(&self) -> usize {
+        self.levels.first().copied().unwrap_or(0)
+    }
+
+    /// continue the sequence from `index`, used when a run starts part way through
+    pub fn start_at(&mut self, index: usize) {
+        self.position = if self.levels.is_empty() {
+            index
+        } else {
+            self.levels
+                .iter()
+                .position(|&level| level == index)
+                .unwrap_or(0)
+        };
+    }
+
+    /// step to the next level, returns `None` when the sequence is finished
+    pub fn advance(&mut self, level_count: usize) -> Option<usize> {
+        self.position += 1;
+        if self.levels.is_empty() {
+            (self.position < level_count).then_some(self.position)
+        } else {
+            self.levels.get(self.position).copied()
+        }
+    }
+}
+
+fn start_run(
+    mut sequence: ResMut<LevelSequence>,
+    mut level: ResMut<LevelSelection>,
+    registry: Res<LevelRegistry>,
+) {
+    sequence.position = 0;
+    // runs with their own sequence, like the daily challenge, skip the hub
+    let hub = registry.hub().filter(|_| sequence.levels.is_empty());
+    *level = LevelSelection::Index(hub.unwrap_or_else(|| sequence.first()));
+}
+
+fn setup_ldtk(mut commands: Commands, preloaded: Res<Preloaded>, packs: Res<LevelPacks>) {
     commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("levels/levels.ldtk"),
+        ldtk_handle: packs.ldtk(&preloaded),
         ..default()
     });
 }
@@ -31,12 +93,17 @@ fn setup_ldtk(mut commands: Commands, asset_server: Res<AssetServer>) {
 fn check_load_status(
     ldtk_handle: Query<&Handle<LdtkAsset>>,
     asset_server: Res<AssetServer>,
+    preloaded: Res<Preloaded>,
     mut state: ResMut<NextState<GameState>>,
 ) {
     let handle = ldtk_handle.single();
     if asset_server.get_load_state(handle.clone()) != LoadState::Loaded {
         return;
     }
+    // level scoped assets dropped in the menus are loading again
+    if !preloaded.is_loaded(&asset_server) {
+        return;
+    }
 
     state.set(GameState::SpawnLevel);
 }
@@ -47,13 +114,16 @@ fn spawn_done(mut state: ResMut<NextState<GameState>>) {
 
 fn level_complete(
     mut commands: Commands,
-    q: Query<(), With<Goal>>,
+    q: Query<(), Or<(With<Goal>, With<Boss>)>>,
     mut ldtk_events: EventReader<AssetEvent<LdtkAsset>>,
     mut state: ResMut<NextState<GameState>>,
-    ldtk_entity: Query<(Entity, &Handle<LdtkAsset>)>,
-    ldtks: Res<Assets<LdtkAsset>>,
+    ldtk_entity: Query<Entity, With<Handle<LdtkAsset>>>,
+    registry: Res<LevelRegistry>,
     mut level_selection: ResMut<LevelSelection>,
+    mut sequence: ResMut<LevelSequence>,
     mut skip_level_done: Local<bool>,
+    mut completed: EventWriter<LevelCompleted>,
+    mut hub: ResMut<HubReturn>,
 ) {
     for e in &mut ldtk_events {
         if let AssetEvent::Modified { handle: _ } = e {
@@ -64,19 +134,20 @@ fn level_complete(
     }
     if q.is_empty() && !*skip_level_done {
         if let LevelSelection::Index(index) = *level_selection {
-            let (e, h) = ldtk_entity.single();
-            let ldtk = ldtks.get(h).unwrap(); // TODO: this line panics on escape sometimes
-
-            let (length, _) = ldtk.iter_levels().size_hint();
-            if index + 1 < length {
-                // go to next level
-                state.set(GameState::SpawnLevel);
-                *level_selection = LevelSelection::Index(index + 1);
-            } else {
-                // no more levels
-                commands.entity(e).despawn_recursive();
-                state.set(GameState::WinScreen);
+            // hubs have nothing to collect, they're left through their doors
+            if registry.is_hub(index) {
+                return;
             }
+            completed.send(LevelCompleted { index });
+            advance_level(
+                &mut commands,
+                &mut state,
+                &ldtk_entity,
+                &registry,
+                &mut level_selection,
+                &mut sequence,
+                &mut hub,
+            );
         } else {
             panic!("Only LevelSelection::Index is supported");
         }
@@ -85,16 +156,40 @@ fn level_complete(
     }
 }
 
+/// Move to the next level in the sequence or to the win screen if there are no
+/// more levels. Levels entered from a hub go back to the hub instead.
+fn advance_level(
+    commands: &mut Commands,
+    state: &mut NextState<GameState>,
+    ldtk_entity: &Query<Entity, With<Handle<LdtkAsset>>>,
+    registry: &LevelRegistry,
+    level_selection: &mut LevelSelection,
+    sequence: &mut LevelSequence,
+    hub: &mut HubReturn,
+) {
+    let e = ldtk_entity.single();
+    if let Some(hub) = hub.0.take() {
+        state.set(GameState::SpawnLevel);
+        *level_selection = LevelSelection::Index(hub);
+    } else if let Some(next) = sequence.advance(registry.len()) {
+        // go to next level
+        state.set(GameState::SpawnLevel);
+        *level_selection = LevelSelection::Index(next);
+    } else {
+        // no more levels
+        commands.entity(e).despawn_recursive();
+        state.set(GameState::WinScreen);
+    }
+}
+
 fn restart(
     mut commands: Commands,
     keyboard: Res<Input<KeyCode>>,
     mut state: ResMut<NextState<GameState>>,
-    mut level: ResMut<LevelSelection>,
     ldtk: Query<Entity, With<Handle<LdtkAsset>>>,
 ) {
     if keyboard.pressed(KeyCode::Escape) {
-        state.set(GameState::StartMenu);
-        *level = LevelSelection::Index(0);
+        state.set(GameState::UnloadLevel);
         if !ldtk.is_empty() {
             commands.entity(ldtk.single()).despawn_recursive();
         }
@@ -105,25 +200,23 @@ fn skip_level(
     mut commands: Commands,
     keyboard: Res<Input<KeyCode>>,
     mut state: ResMut<NextState<GameState>>,
-    ldtk_entity: Query<(Entity, &Handle<LdtkAsset>)>,
-    ldtks: Res<Assets<LdtkAsset>>,
+    ldtk_entity: Query<Entity, With<Handle<LdtkAsset>>>,
+    registry: Res<LevelRegistry>,
     mut level_selection: ResMut<LevelSelection>,
+    mut sequence: ResMut<LevelSequence>,
+    mut hub: ResMut<HubReturn>,
 ) {
     if keyboard.just_pressed(KeyCode::Key0) {
-        if let LevelSelection::Index(index) = *level_selection {
-            let (e, h) = ldtk_entity.single();
-            let ldtk = ldtks.get(h).unwrap();
-
-            let (length, _) = ldtk.iter_levels().size_hint();
-            if index + 1 < length {
-                // go to next level
-                state.set(GameState::SpawnLevel);
-                *level_selection = LevelSelection::Index(index + 1);
-            } else {
-                // no more levels
-                commands.entity(e).despawn_recursive();
-                state.set(GameState::WinScreen);
-            }
+        if let LevelSelection::Index(_) = *level_selection {
+            advance_level(
+                &mut commands,
+                &mut state,
+                &ldtk_entity,
+                &registry,
+                &mut level_selection,
+                &mut sequence,
+                &mut hub,
+            );
         } else {
             panic!("Only LevelSelection::Index is supported");
         }
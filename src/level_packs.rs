@@ -0,0 +1,237 @@
+use bevy::{asset::LoadState, prelude::*};
+use bevy_ecs_ldtk::LdtkAsset;
+
+use crate::{
+    game_state::GameState, level_registry::LevelMetaOverlay, level_validation::pack_problems,
+    news::news_closed, preload::Preloaded, save::slot_selected, settings::settings_closed,
+    start_menu::MenuMarker,
+};
+
+/// problems listed per broken pack on the start menu, the rest are only logged
+const SHOWN_PROBLEMS: usize = 4;
+
+/// folder scanned for level packs, relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+const PACK_DIR: &str = "mods/levels";
+
+/// Loads user made level packs from `mods/levels` and any `--level-pack=<path>`
+/// arguments. A pack is an ldtk file with an optional `<name>.meta.ron` next
+/// to it, the same as the built in levels.
+pub struct LevelPacksPlugin;
+impl Plugin for LevelPacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelPacks>()
+            .add_startup_system(scan_level_packs)
+            .add_system(validate_packs)
+            .add_system(spawn_pack_text.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems(
+                (cycle_pack, update_pack_text)
+                    .chain()
+                    .distributive_run_if(in_state(GameState::StartMenu))
+                    .distributive_run_if(slot_selected)
+                    .distributive_run_if(news_closed)
+                    .distributive_run_if(settings_closed),
+            );
+    }
+}
+
+pub struct LevelPack {
+    /// file name without the extension, also keys the pack's progress in the save
+    pub name: String,
+    ldtk: Handle<LdtkAsset>,
+    meta: Option<Handle<LevelMetaOverlay>>,
+    /// why the pack can't be played, checked whenever it's loaded
+    problems: Vec<String>,
+}
+
+/// every pack that was found and the one runs are started with
+#[derive(Resource, Default)]
+pub struct LevelPacks {
+    packs: Vec<LevelPack>,
+    /// `None` plays the built in levels
+    active: Option<usize>,
+}
+
+impl LevelPacks {
+    pub fn active(&self) -> Option<&LevelPack> {
+        self.packs.get(self.active?)
+    }
+
+    /// name of the pack being played, `None` for the built in levels
+    pub fn active_name(&self) -> Option<&str> {
+        self.active().map(|pack| pack.name.as_str())
+    }
+
+    /// ldtk file levels are spawned from
+    pub fn ldtk(&self, preloaded: &Preloaded) -> Handle<LdtkAsset> {
+        match self.active() {
+            Some(pack) => pack.ldtk.clone(),
+            None => preloaded.get("levels"),
+        }
+    }
+
+    /// overrides for the active levels, packs without a meta file have none
+    pub fn meta(&self, preloaded: &Preloaded) -> Option<Handle<LevelMetaOverlay>> {
+        match self.active() {
+            Some(pack) => pack.meta.clone(),
+            None => Some(preloaded.get("level_meta")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn pack_paths() -> Vec<std::path::PathBuf> {
+    let mut paths: Vec<_> = std::fs::read_dir(PACK_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ldtk"))
+        .collect();
+    paths.sort();
+    paths.extend(
+        std::env::args().filter_map(|arg| arg.strip_prefix("--level-pack=").map(Into::into)),
+    );
+    paths
+}
+
+/// Packs live outside the assets folder, so they're loaded by absolute path.
+/// They're loaded in the background and can be picked once they've finished.
+#[cfg(not(target_arch = "wasm32"))]
+fn scan_level_packs(asset_server: Res<AssetServer>, mut packs: ResMut<LevelPacks>) {
+    for path in pack_paths() {
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("could not read level pack {}: {e}", path.display());
+                continue;
+            }
+        };
+        let Some(name) = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+        let meta = path.with_file_name(format!("{name}.meta.ron"));
+        info!("found level pack {name}");
+        packs.packs.push(LevelPack {
+            ldtk: asset_server.load(path),
+            meta: meta.exists().then(|| asset_server.load(meta)),
+            name,
+            problems: Vec::new(),
+        });
+    }
+}
+
+/// the browser can't read folders, so only the built in levels are playable
+#[cfg(target_arch = "wasm32")]
+fn scan_level_packs() {}
+
+/// Broken packs can't be picked, so a broken level is never spawned. Runs
+/// again when a pack is edited.
+fn validate_packs(
+    mut events: EventReader<AssetEvent<LdtkAsset>>,
+    ldtks: Res<Assets<LdtkAsset>>,
+    mut packs: ResMut<LevelPacks>,
+) {
+    for event in &mut events {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        let Some(ldtk) = ldtks.get(handle) else {
+            continue;
+        };
+        let Some(pack) = packs.packs.iter_mut().find(|pack| pack.ldtk == *handle) else {
+            continue;
+        };
+        pack.problems = pack_problems(ldtk);
+        for problem in &pack.problems {
+            warn!("level pack {}: {problem}", pack.name);
+        }
+    }
+}
+
+#[derive(Component)]
+struct PackText;
+
+fn pack_text(packs: &LevelPacks) -> String {
+    let name = packs.active_name().unwrap_or("Main Levels");
+    let mut text = format!("Custom Levels\nL: < {name} >");
+    for pack in packs.packs.iter().filter(|pack| !pack.problems.is_empty()) {
+        text.push_str(&format!("\n\n{} can't be played:", pack.name));
+        for problem in pack.problems.iter().take(SHOWN_PROBLEMS) {
+            text.push_str(&format!("\n{problem}"));
+        }
+        if pack.problems.len() > SHOWN_PROBLEMS {
+            text.push_str(&format!(
+                "\n...and {} more",
+                pack.problems.len() - SHOWN_PROBLEMS
+            ));
+        }
+    }
+    text
+}
+
+fn spawn_pack_text(mut commands: Commands, preloaded: Res<Preloaded>, packs: Res<LevelPacks>) {
+    if packs.packs.is_empty() {
+        return;
+    }
+    commands.spawn((
+        MenuMarker,
+        PackText,
+        TextBundle::from_section(
+            pack_text(&packs),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                // under the news hint
+                top: Val::Px(50.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+/// steps through the built in levels and every pack that loaded without problems
+fn cycle_pack(
+    keyboard: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut packs: ResMut<LevelPacks>,
+) {
+    if !keyboard.just_pressed(KeyCode::L) || packs.packs.is_empty() {
+        return;
+    }
+
+    // the built in levels sit at the end of the cycle
+    let count = packs.packs.len() + 1;
+    let mut next = packs.active.map_or(0, |active| active + 1);
+    for _ in 0..count {
+        let Some(pack) = packs.packs.get(next) else {
+            packs.active = None;
+            return;
+        };
+        let loaded = asset_server.get_load_state(&pack.ldtk) == LoadState::Loaded;
+        if loaded && pack.problems.is_empty() {
+            packs.active = Some(next);
+            return;
+        }
+        next = (next + 1) % count;
+    }
+}
+
+fn update_pack_text(packs: Res<LevelPacks>, mut texts: Query<&mut Text, With<PackText>>) {
+    if !packs.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = pack_text(&packs);
+    }
+}
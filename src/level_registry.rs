@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use bevy_ecs_ldtk::{
+    ldtk::{FieldInstance, FieldValue},
+    LdtkAsset,
+};
+
+use crate::{level_packs::LevelPacks, preload::Preloaded};
+
+pub struct LevelRegistryPlugin;
+impl Plugin for LevelRegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelRegistry>()
+            .add_system(rebuild_registry);
+    }
+}
+
+/// Per level data, read from the level fields in ldtk and then overridden
+/// by anything set in `levels/levels.meta.ron`.
+// not every field has a consumer yet
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct LevelMeta {
+    pub index: usize,
+    /// ldtk identifier, i.e. `Level_0`
+    pub identifier: String,
+    /// display name, `Name` field in ldtk
+    pub name: String,
+    /// target completion time in seconds, `Par_Time` field in ldtk
+    pub par_time: Option<f32>,
+    /// minimum gravity flips to finish, `Par_Flips` field in ldtk
+    pub par_flips: Option<u32>,
+    /// most gravity flips allowed in one attempt, `Flip_Limit` field in ldtk
+    pub flip_limit: Option<u32>,
+    /// `Theme` field in ldtk
+    pub theme: Option<String>,
+    /// `Tutorial` field in ldtk
+    pub tutorial: bool,
+    /// number of secrets hidden in the level, `Secrets` field in ldtk
+    pub secrets: u32,
+    /// levels are entered from this one's doors, `Hub` field in ldtk. See `HubPlugin`
+    pub hub: bool,
+    /// edges leaving the level come back in on the other side, `Wrap_Horizontal`
+    /// and `Wrap_Vertical` fields in ldtk. See `WrapPlugin`
+    pub wrap: LevelWrap,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelWrap {
+    /// left and right edges
+    pub horizontal: bool,
+    /// top and bottom edges
+    pub vertical: bool,
+}
+
+/// optional overrides for a level, every field left as `None` keeps the ldtk value
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LevelMetaOverride {
+    pub name: Option<String>,
+    pub par_time: Option<f32>,
+    pub par_flips: Option<u32>,
+    pub flip_limit: Option<u32>,
+    pub theme: Option<String>,
+    pub tutorial: Option<bool>,
+    pub secrets: Option<u32>,
+    pub hub: Option<bool>,
+    pub wrap_horizontal: Option<bool>,
+    pub wrap_vertical: Option<bool>,
+}
+
+/// contents of `levels.meta.ron`, keyed by ldtk level identifier
+#[derive(serde::Deserialize, TypeUuid, Debug, Clone, Default)]
+#[uuid = "5c3b9a51-2f0e-4d8b-b6a4-7a1f0d93c2e8"]
+pub struct LevelMetaOverlay {
+    pub levels: HashMap<String, LevelMetaOverride>,
+}
+
+#[derive(Resource, Default)]
+pub struct LevelRegistry {
+    levels: Vec<LevelMeta>,
+}
+
+impl LevelRegistry {
+    pub fn get(&self, index: usize) -> Option<&LevelMeta> {
+        self.levels.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LevelMeta> {
+        self.levels.iter()
+    }
+
+    /// index of the first hub level, runs start there when there is one
+    pub fn hub(&self) -> Option<usize> {
+        self.levels
+            .iter()
+            .find(|meta| meta.hub)
+            .map(|meta| meta.index)
+    }
+
+    pub fn is_hub(&self, index: usize) -> bool {
+        self.get(index).is_some_and(|meta| meta.hub)
+    }
+}
+
+/// value of the ldtk field named `identifier`
+pub fn field<'a>(fields: &'a [FieldInstance], identifier: &str) -> Option<&'a FieldValue> {
+    fields
+        .iter()
+        .find(|field| field.identifier == identifier)
+        .map(|field| &field.value)
+}
+
+fn level_meta(index: usize, identifier: &str, fields: &[FieldInstance]) -> LevelMeta {
+    let mut meta = LevelMeta {
+        index,
+        identifier: identifier.to_string(),
+        name: format!("Level {}", index + 1),
+        ..default()
+    };
+
+    if let Some(FieldValue::String(Some(name))) = field(fields, "Name") {
+        meta.name = name.clone();
+    }
+    if let Some(FieldValue::Float(par_time)) = field(fields, "Par_Time") {
+        meta.par_time = *par_time;
+    }
+    if let Some(FieldValue::Int(Some(par_flips))) = field(fields, "Par_Flips") {
+        meta.par_flips = Some((*par_flips).max(0) as u32);
+    }
+    if let Some(FieldValue::Int(Some(flip_limit))) = field(fields, "Flip_Limit") {
+        meta.flip_limit = Some((*flip_limit).max(0) as u32);
+    }
+    if let Some(FieldValue::String(theme)) = field(fields, "Theme") {
+        meta.theme = theme.clone();
+    }
+    if let Some(FieldValue::Bool(tutorial)) = field(fields, "Tutorial") {
+        meta.tutorial = *tutorial;
+    }
+    if let Some(FieldValue::Int(Some(secrets))) = field(fields, "Secrets") {
+        meta.secrets = (*secrets).max(0) as u32;
+    }
+    if let Some(FieldValue::Bool(hub)) = field(fields, "Hub") {
+        meta.hub = *hub;
+    }
+    if let Some(FieldValue::Bool(wrap)) = field(fields, "Wrap_Horizontal") {
+        meta.wrap.horizontal = *wrap;
+    }
+    if let Some(FieldValue::Bool(wrap)) = field(fields, "Wrap_Vertical") {
+        meta.wrap.vertical = *wrap;
+    }
+
+    meta
+}
+
+fn apply_override(meta: &mut LevelMeta, o: &LevelMetaOverride) {
+    if let Some(name) = &o.name {
+        meta.name = name.clone();
+    }
+    if o.par_time.is_some() {
+        meta.par_time = o.par_time;
+    }
+    if o.par_flips.is_some() {
+        meta.par_flips = o.par_flips;
+    }
+    if o.flip_limit.is_some() {
+        meta.flip_limit = o.flip_limit;
+    }
+    if o.theme.is_some() {
+        meta.theme = o.theme.clone();
+    }
+    if let Some(tutorial) = o.tutorial {
+        meta.tutorial = tutorial;
+    }
+    if let Some(secrets) = o.secrets {
+        meta.secrets = secrets;
+    }
+    if let Some(hub) = o.hub {
+        meta.hub = hub;
+    }
+    if let Some(wrap) = o.wrap_horizontal {
+        meta.wrap.horizontal = wrap;
+    }
+    if let Some(wrap) = o.wrap_vertical {
+        meta.wrap.vertical = wrap;
+    }
+}
+
+/// rebuild whenever the ldtk file or the overlay is loaded or hot reloaded, or
+/// a different level pack is picked
+fn rebuild_registry(
+    mut ldtk_events: EventReader<AssetEvent<LdtkAsset>>,
+    mut overlay_events: EventReader<AssetEvent<LevelMetaOverlay>>,
+    preloaded: Res<Preloaded>,
+    packs: Res<LevelPacks>,
+    ldtks: Res<Assets<LdtkAsset>>,
+    overlays: Res<Assets<LevelMetaOverlay>>,
+    mut registry: ResMut<LevelRegistry>,
+) {
+    let ldtk_changed = ldtk_events.iter().count() > 0;
+    let overlay_changed = overlay_events.iter().count() > 0;
+    if !ldtk_changed && !overlay_changed && !packs.is_changed() {
+        return;
+    }
+    let Some(ldtk) = ldtks.get(&packs.ldtk(&preloaded)) else {
+        return;
+    };
+    let overlay = packs.meta(&preloaded).and_then(|meta| overlays.get(&meta));
+
+    registry.levels = ldtk
+        .iter_levels()
+        .enumerate()
+        .map(|(index, level)| {
+            let mut meta = level_meta(index, &level.identifier, &level.field_instances);
+            if let Some(o) = overlay.and_then(|overlay| overlay.levels.get(&level.identifier)) {
+                apply_override(&mut meta, o);
+            }
+            meta
+        })
+        .collect();
+}
@@ -0,0 +1,309 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{LdtkAsset, LevelSelection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::MenuLabel,
+    game_state::GameState,
+    level::{LevelCompleted, LevelSequence},
+    level_packs::LevelPacks,
+    level_registry::LevelRegistry,
+    news::news_closed,
+    preload::Preloaded,
+    save::{slot_selected, SaveData},
+    settings::settings_closed,
+    timer::{format_time, RunTimer},
+};
+
+const ROW_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+const SELECTED_ROW_COLOR: Color = Color::rgb(0.95, 0.95, 0.95);
+const LOCKED_ROW_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+/// Press L on the start menu to pick which level to start from. Levels are
+/// unlocked by reaching them or finishing the one before.
+pub struct LevelSelectPlugin;
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelUnlocks>()
+            .init_resource::<LevelCursor>()
+            .add_system(
+                open_level_select
+                    .run_if(in_state(GameState::StartMenu))
+                    .run_if(slot_selected)
+                    .run_if(news_closed)
+                    .run_if(settings_closed),
+            )
+            .add_system(spawn_level_list.in_schedule(OnEnter(GameState::LevelSelect)))
+            .add_systems(
+                (choose_level, color_rows)
+                    .chain()
+                    .distributive_run_if(in_state(GameState::LevelSelect)),
+            )
+            .add_system(despawn_level_list.in_schedule(OnExit(GameState::LevelSelect)))
+            .add_system(unlock_entered_level.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_systems((unlock_next_level, sync_level_unlocks).chain());
+    }
+}
+
+/// Levels the player can start from on the level select, kept in the save.
+/// The first level and finished levels are always unlocked.
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LevelUnlocks {
+    levels: HashSet<usize>,
+    /// levels of user made packs, keyed by pack name
+    packs: HashMap<String, HashSet<usize>>,
+}
+
+impl LevelUnlocks {
+    fn levels(&self, pack: Option<&str>) -> Option<&HashSet<usize>> {
+        match pack {
+            Some(pack) => self.packs.get(pack),
+            None => Some(&self.levels),
+        }
+    }
+
+    pub fn is_unlocked(&self, pack: Option<&str>, index: usize) -> bool {
+        self.levels(pack)
+            .is_some_and(|levels| levels.contains(&index))
+    }
+
+    pub fn unlock(&mut self, pack: Option<&str>, index: usize) {
+        match pack {
+            Some(pack) => self.packs.entry(pack.to_string()).or_default(),
+            None => &mut self.levels,
+        }
+        .insert(index);
+    }
+}
+
+/// position of the highlighted row
+#[derive(Resource, Default)]
+struct LevelCursor(usize);
+
+#[derive(Component)]
+struct LevelList;
+
+#[derive(Component)]
+struct LevelRow {
+    /// row number from the top
+    position: usize,
+    index: usize,
+    unlocked: bool,
+}
+
+fn open_level_select(keyboard: Res<Input<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if keyboard.just_pressed(KeyCode::L) {
+        state.set(GameState::LevelSelect);
+    }
+}
+
+/// Hubs aren't listed, runs that start from one already begin there. Starts
+/// on the level the game was last closed in, if it's unlocked.
+fn spawn_level_list(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    registry: Res<LevelRegistry>,
+    unlocks: Res<LevelUnlocks>,
+    save: Res<SaveData>,
+    packs: Res<LevelPacks>,
+    mut cursor: ResMut<LevelCursor>,
+) {
+    let pack = packs.active_name();
+    let font = preloaded.get("font");
+    let levels: Vec<_> = registry.iter().filter(|meta| !meta.hub).collect();
+    let is_unlocked = |position: usize, index: usize| {
+        position == 0 || unlocks.is_unlocked(pack, index) || save.is_completed(pack, index)
+    };
+    // only recorded for the built in levels
+    let last_level = save.last_level.filter(|_| pack.is_none());
+    cursor.0 = levels
+        .iter()
+        .enumerate()
+        .position(|(position, meta)| {
+            Some(meta.index) == last_level && is_unlocked(position, meta.index)
+        })
+        .unwrap_or(0);
+
+    commands
+        .spawn((
+            LevelList,
+            NodeBundle {
+                style: Style {
+                    size: Size::width(Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Select a Level",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: SELECTED_ROW_COLOR,
+                },
+            ));
+            for (position, meta) in levels.iter().enumerate() {
+                let unlocked = is_unlocked(position, meta.index);
+                let name = if meta.name.is_empty() {
+                    &meta.identifier
+                } else {
+                    &meta.name
+                };
+                let text = match (unlocked, save.best_time(pack, meta.index)) {
+                    (false, _) => format!("{}. Locked", meta.index + 1),
+                    (true, Some(best)) => format!(
+                        "{}. {name}  {}",
+                        meta.index + 1,
+                        format_time(Duration::from_secs_f32(best))
+                    ),
+                    (true, None) => format!("{}. {name}", meta.index + 1),
+                };
+                parent.spawn((
+                    LevelRow {
+                        position,
+                        index: meta.index,
+                        unlocked,
+                    },
+                    MenuLabel::new(text.clone(), position == cursor.0),
+                    TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: ROW_COLOR,
+                        },
+                    ),
+                ));
+            }
+        });
+}
+
+/// Up and down move between levels, space or enter starts the highlighted one
+/// and escape goes back to the start menu.
+fn choose_level(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    rows: Query<&LevelRow>,
+    mut cursor: ResMut<LevelCursor>,
+    mut level: ResMut<LevelSelection>,
+    mut sequence: ResMut<LevelSequence>,
+    mut timer: ResMut<RunTimer>,
+    mut state: ResMut<NextState<GameState>>,
+    ldtk: Query<Entity, With<Handle<LdtkAsset>>>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButtonType| {
+        keyboard.just_pressed(key)
+            || gamepads
+                .iter()
+                .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+
+    if pressed(KeyCode::Escape, GamepadButtonType::East) {
+        // the level file started loading when the start menu closed
+        for e in &ldtk {
+            commands.entity(e).despawn_recursive();
+        }
+        state.set(GameState::UnloadLevel);
+        return;
+    }
+
+    let count = rows.iter().count();
+    if count == 0 {
+        return;
+    }
+    if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+    if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+
+    let start =
+        pressed(KeyCode::Return, GamepadButtonType::South) || keyboard.just_pressed(KeyCode::Space);
+    let Some(row) = rows.iter().find(|row| row.position == cursor.0) else {
+        return;
+    };
+    if !start || !row.unlocked {
+        return;
+    }
+    *level = LevelSelection::Index(row.index);
+    sequence.start_at(row.index);
+    // the run timer started when the start menu closed
+    timer.elapsed = Duration::ZERO;
+    timer.level_start = Duration::ZERO;
+    state.set(GameState::LoadLevel);
+}
+
+fn color_rows(cursor: Res<LevelCursor>, mut rows: Query<(&LevelRow, &mut Text, &mut MenuLabel)>) {
+    for (row, mut text, mut label) in &mut rows {
+        let selected = row.position == cursor.0;
+        if label.selected != selected {
+            label.selected = selected;
+        }
+        let color = if row.position == cursor.0 {
+            SELECTED_ROW_COLOR
+        } else if row.unlocked {
+            ROW_COLOR
+        } else {
+            LOCKED_ROW_COLOR
+        };
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn despawn_level_list(mut commands: Commands, list: Query<Entity, With<LevelList>>) {
+    for e in &list {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn unlock_entered_level(
+    level: Res<LevelSelection>,
+    packs: Res<LevelPacks>,
+    mut unlocks: ResMut<LevelUnlocks>,
+) {
+    let LevelSelection::Index(index) = *level else {
+        return;
+    };
+    if !unlocks.is_unlocked(packs.active_name(), index) {
+        unlocks.unlock(packs.active_name(), index);
+    }
+}
+
+fn unlock_next_level(
+    mut events: EventReader<LevelCompleted>,
+    registry: Res<LevelRegistry>,
+    packs: Res<LevelPacks>,
+    mut unlocks: ResMut<LevelUnlocks>,
+) {
+    for event in &mut events {
+        let next = event.index + 1;
+        if next < registry.len() && !unlocks.is_unlocked(packs.active_name(), next) {
+            unlocks.unlock(packs.active_name(), next);
+        }
+    }
+}
+
+/// copied to and from the save like `WorldFlags`
+fn sync_level_unlocks(mut unlocks: ResMut<LevelUnlocks>, mut save: ResMut<SaveData>) {
+    if unlocks.is_changed() && save.level_unlocks != *unlocks {
+        save.level_unlocks = unlocks.clone();
+    } else if save.is_changed() && save.level_unlocks != *unlocks {
+        *unlocks = save.level_unlocks.clone();
+    }
+}
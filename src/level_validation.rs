@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, LdtkAsset};
+
+use crate::ground::is_solid_int_cell;
+
+/// Everything in `ldtk` that would stop one of its levels being played, each
+/// prefixed with the level it's in. Empty when every level is playable.
+pub fn pack_problems(ldtk: &LdtkAsset) -> Vec<String> {
+    let mut problems: Vec<String> = ldtk
+        .iter_levels()
+        .flat_map(|level| {
+            level_problems(level)
+                .into_iter()
+                .map(|problem| format!("{}: {problem}", level.identifier))
+        })
+        .collect();
+    if ldtk.iter_levels().next().is_none() {
+        problems.push("has no levels".to_string());
+    }
+    problems
+}
+
+/// Checks for what the level systems expect: a single spawn point, something
+/// to finish the level with, and nothing placed outside the level or stuck in
+/// the ground.
+pub fn level_problems(level: &Level) -> Vec<String> {
+    let layers = level.layer_instances.as_deref().unwrap_or_default();
+    let entities = || layers.iter().flat_map(|layer| &layer.entity_instances);
+    let count = |identifier: &str| {
+        entities()
+            .filter(|entity| entity.identifier == identifier)
+            .count()
+    };
+    let size = IVec2::new(level.px_wid, level.px_hei);
+
+    let mut problems = Vec::new();
+    match count("Spawn_Point") {
+        0 => problems.push("has no Spawn_Point".to_string()),
+        1 => {}
+        n => problems.push(format!("has {n} Spawn_Points, only one is allowed")),
+    }
+    // a boss level is finished by beating the boss, goals are optional there
+    if count("Goal") == 0 && count("Boss") == 0 {
+        problems.push("has no Goal to collect".to_string());
+    }
+
+    for entity in entities() {
+        if !matches!(entity.identifier.as_str(), "Spawn_Point" | "Goal") {
+            continue;
+        }
+        if entity.px.cmplt(IVec2::ZERO).any() || entity.px.cmpge(size).any() {
+            problems.push(format!(
+                "{} at {} is outside the level",
+                entity.identifier, entity.px
+            ));
+        } else if entity.identifier == "Goal" && in_ground(level, entity.px) {
+            problems.push(format!("Goal at {} is inside the ground", entity.px));
+        }
+    }
+    problems
+}
+
+/// whether any int grid layer has ground or a platform in the cell under the pixel `px`
+fn in_ground(level: &Level, px: IVec2) -> bool {
+    let layers = level.layer_instances.as_deref().unwrap_or_default();
+    layers
+        .iter()
+        .filter(|layer| !layer.int_grid_csv.is_empty() && layer.grid_size > 0)
+        .any(|layer| {
+            let cell = px / layer.grid_size;
+            let index = (cell.y * layer.c_wid + cell.x) as usize;
+            (cell.x < layer.c_wid && cell.y < layer.c_hei)
+                && layer
+                    .int_grid_csv
+                    .get(index)
+                    .is_some_and(|&value| is_solid_int_cell(value))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_ecs_ldtk::ldtk::{EntityInstance, LayerInstance, Level, Type};
+
+    use super::level_problems;
+
+    fn entity(identifier: &str, px: IVec2) -> EntityInstance {
+        EntityInstance {
+            identifier: identifier.to_string(),
+            px,
+            ..default()
+        }
+    }
+
+    /// a 3x3 cell layer, `LayerInstance` has no `Default` to fill in the rest
+    fn layer(
+        layer_instance_type: Type,
+        int_grid_csv: Vec<i32>,
+        entity_instances: Vec<EntityInstance>,
+    ) -> LayerInstance {
+        LayerInstance {
+            c_hei: 3,
+            c_wid: 3,
+            grid_size: 24,
+            identifier: String::new(),
+            opacity: 1.0,
+            px_total_offset_x: 0,
+            px_total_offset_y: 0,
+            tileset_def_uid: None,
+            tileset_rel_path: None,
+            layer_instance_type,
+            auto_layer_tiles: Vec::new(),
+            entity_instances,
+            grid_tiles: Vec::new(),
+            iid: String::new(),
+            int_grid: None,
+            int_grid_csv,
+            layer_def_uid: 0,
+            level_id: 0,
+            optional_rules: Vec::new(),
+            override_tileset_uid: None,
+            px_offset_x: 0,
+            px_offset_y: 0,
+            seed: 0,
+            visible: true,
+        }
+    }
+
+    /// a 3x3 cell level with ground along the bottom row
+    fn level(entities: Vec<EntityInstance>) -> Level {
+        Level {
+            identifier: "Level_0".to_string(),
+            px_wid: 72,
+            px_hei: 72,
+            layer_instances: Some(vec![
+                layer(Type::IntGrid, vec![0, 0, 0, 0, 0, 0, 1, 1, 1], Vec::new()),
+                layer(Type::Entities, Vec::new(), entities),
+            ]),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn playable_level_has_no_problems() {
+        let level = level(vec![
+            entity("Spawn_Point", IVec2::new(12, 36)),
+            entity("Goal", IVec2::new(60, 36)),
+        ]);
+        assert!(level_problems(&level).is_empty());
+    }
+
+    #[test]
+    fn needs_exactly_one_spawn_point() {
+        let goal = entity("Goal", IVec2::new(60, 36));
+        assert_eq!(level_problems(&level(vec![goal.clone()])).len(), 1);
+
+        let spawn = entity("Spawn_Point", IVec2::new(12, 36));
+        let problems = level_problems(&level(vec![spawn.clone(), spawn, goal]));
+        assert_eq!(problems, ["has 2 Spawn_Points, only one is allowed"]);
+    }
+
+    #[test]
+    fn boss_levels_dont_need_goals() {
+        let spawn = entity("Spawn_Point", IVec2::new(12, 36));
+        assert_eq!(level_problems(&level(vec![spawn.clone()])).len(), 1);
+
+        let boss = entity("Boss", IVec2::new(60, 36));
+        assert!(level_problems(&level(vec![spawn, boss])).is_empty());
+    }
+
+    #[test]
+    fn goal_in_ground() {
+        let level = level(vec![
+            entity("Spawn_Point", IVec2::new(12, 36)),
+            entity("Goal", IVec2::new(36, 60)),
+        ]);
+        assert_eq!(
+            level_problems(&level),
+            ["Goal at [36, 60] is inside the ground"]
+        );
+    }
+
+    #[test]
+    fn goal_in_any_solid_cell() {
+        // rubber, one-way platform, then nothing along the bottom row
+        let goal_at = |x| Level {
+            layer_instances: Some(vec![
+                layer(Type::IntGrid, vec![0, 0, 0, 0, 0, 0, 4, 5, 0], Vec::new()),
+                layer(
+                    Type::Entities,
+                    Vec::new(),
+                    vec![
+                        entity("Spawn_Point", IVec2::new(12, 12)),
+                        entity("Goal", IVec2::new(x, 60)),
+                    ],
+                ),
+            ]),
+            ..level(Vec::new())
+        };
+        assert_eq!(level_problems(&goal_at(12)).len(), 1);
+        assert_eq!(level_problems(&goal_at(36)).len(), 1);
+        assert!(level_problems(&goal_at(60)).is_empty());
+    }
+
+    #[test]
+    fn outside_the_level() {
+        let level = level(vec![
+            entity("Spawn_Point", IVec2::new(-12, 36)),
+            entity("Goal", IVec2::new(36, 80)),
+        ]);
+        assert_eq!(level_problems(&level).len(), 2);
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+
+use crate::{
+    collisions::RectBundle,
+    config::AccessibilitySettings,
+    constants::{CollisionTypes, STATIC_GROUND_LAYERS},
+    game_state::GameState,
+    ground::SurfaceMaterial,
+    level_registry::field,
+    physics::{ExternalForces, Momentum, OnGround, Velocity},
+    player::Player,
+};
+
+const DEFAULT_RADIUS: f32 = 96.0;
+/// pixels per second squared right next to the magnet
+const DEFAULT_STRENGTH: f32 = 900.0;
+const ATTRACT_COLOR: Color = Color::rgb(0.85, 0.25, 0.3);
+const REPEL_COLOR: Color = Color::rgb(0.3, 0.45, 0.9);
+/// field line particles spawned per second for each magnet
+const PARTICLE_RATE: f32 = 12.0;
+const PARTICLE_SECONDS: f32 = 0.8;
+
+pub struct MagnetPlugin;
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<MagnetBundle>("Magnet")
+            .add_system(after_magnet_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(
+                pull_player
+                    .in_set(ExternalForces)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems((spawn_field_lines, move_field_lines));
+    }
+}
+
+/// Pulls the airborne player towards it, or pushes them away when `strength`
+/// is negative. The force fades out linearly to nothing at `radius`.
+#[derive(Component)]
+pub struct Magnet {
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Default for Magnet {
+    fn default() -> Self {
+        Magnet {
+            radius: DEFAULT_RADIUS,
+            strength: DEFAULT_STRENGTH,
+        }
+    }
+}
+
+impl Magnet {
+    /// acceleration on something at `offset` from the magnet's center
+    pub fn force(&self, offset: Vec2) -> Vec2 {
+        let distance = offset.length();
+        if distance >= self.radius || distance < f32::EPSILON {
+            return Vec2::ZERO;
+        }
+        -offset / distance * self.strength * (1.0 - distance / self.radius)
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct MagnetBundle {
+    #[with(magnet_from_instance)]
+    magnet: Magnet,
+    #[with(metal)]
+    material: SurfaceMaterial,
+    sprite: SpriteBundle,
+}
+
+fn magnet_from_instance(instance: &EntityInstance) -> Magnet {
+    let fields = &instance.field_instances;
+    let radius = match field(fields, "Radius") {
+        Some(FieldValue::Float(Some(radius))) if *radius > 0.0 => *radius,
+        _ => DEFAULT_RADIUS,
+    };
+    let strength = match field(fields, "Strength") {
+        Some(FieldValue::Float(Some(strength))) => strength.abs(),
+        _ => DEFAULT_STRENGTH,
+    };
+    let repel = matches!(field(fields, "Repel"), Some(FieldValue::Bool(true)));
+
+    Magnet {
+        radius,
+        strength: if repel { -strength } else { strength },
+    }
+}
+
+fn metal(_: &EntityInstance) -> SurfaceMaterial {
+    SurfaceMaterial::Metal
+}
+
+/// the magnet is a solid block, the field reaches out past it
+fn after_magnet_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &EntityInstance, &Magnet, &mut Sprite), Added<Magnet>>,
+) {
+    for (e, instance, magnet, mut sprite) in &mut q {
+        let size = Vec2::new(instance.width as f32, instance.height as f32);
+        sprite.custom_size = Some(size);
+        sprite.color = if magnet.strength < 0.0 {
+            REPEL_COLOR
+        } else {
+            ATTRACT_COLOR
+        };
+        commands
+            .entity(e)
+            .insert(CollisionTypes::Ground)
+            .with_children(|children| {
+                children.spawn((RectBundle::new(size), STATIC_GROUND_LAYERS));
+            });
+    }
+}
+
+fn pull_player(
+    magnets: Query<(&Magnet, &GlobalTransform)>,
+    mut player: Query<(&mut Velocity, &mut Momentum, &Transform, &OnGround), With<Player>>,
+    time_step: Res<FixedTime>,
+) {
+    let dt = time_step.period.as_secs_f32();
+    for (mut v, mut momentum, t, on_ground) in &mut player {
+        if on_ground.0 {
+            continue;
+        }
+        let position = t.translation.truncate();
+        for (magnet, magnet_t) in &magnets {
+            let force = magnet.force(position - magnet_t.translation().truncate());
+            momentum.push(&mut v, force * dt);
+        }
+    }
+}
+
+/// a speck drifting along the field, towards an attracting magnet or away from a repelling one
+#[derive(Component)]
+struct FieldLine {
+    velocity: Vec2,
+    life: Timer,
+}
+
+fn spawn_field_lines(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<GlobalRng>,
+    settings: Res<AccessibilitySettings>,
+    magnets: Query<(Entity, &Magnet, &Sprite)>,
+) {
+    if settings.reduced_motion {
+        return;
+    }
+    let chance = PARTICLE_RATE * time.delta_seconds();
+    for (e, magnet, sprite) in &magnets {
+        if rng.f32() > chance {
+            continue;
+        }
+        let direction = Vec2::from_angle(rng.f32() * std::f32::consts::TAU);
+        let speed = magnet.radius / PARTICLE_SECONDS;
+        // attracting lines start at the edge of the field and flow in
+        let (start, velocity) = if magnet.strength < 0.0 {
+            (Vec2::ZERO, direction * speed)
+        } else {
+            (direction * magnet.radius, -direction * speed)
+        };
+        let line = commands
+            .spawn((
+                FieldLine {
+                    velocity,
+                    life: Timer::from_seconds(PARTICLE_SECONDS, TimerMode::Once),
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: sprite.color,
+                        custom_size: Some(Vec2::new(4.0, 1.5)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(start.extend(-1.0))
+                        .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x))),
+                    ..default()
+                },
+            ))
+            .id();
+        // children of the magnet so they go away with the level
+        commands.entity(e).add_child(line);
+    }
+}
+
+fn move_field_lines(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lines: Query<(Entity, &mut FieldLine, &mut Transform, &mut Sprite)>,
+) {
+    for (e, mut line, mut t, mut sprite) in &mut lines {
+        if line.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        t.translation += (line.velocity * time.delta_seconds()).extend(0.0);
+        // fade in and out so they don't pop at either end
+        let percent = line.life.percent();
+        sprite
+            .color
+            .set_a((percent * (1.0 - percent) * 4.0).min(1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attracts_and_repels_inside_radius() {
+        let magnet = Magnet {
+            radius: 100.0,
+            strength: 1000.0,
+        };
+        let force = magnet.force(Vec2::new(50.0, 0.0));
+        assert!((force - Vec2::new(-500.0, 0.0)).length() < 1e-3);
+
+        let repel = Magnet {
+            strength: -1000.0,
+            ..magnet
+        };
+        assert!(repel.force(Vec2::new(0.0, 50.0)).y > 0.0);
+        assert_eq!(magnet.force(Vec2::new(0.0, 150.0)), Vec2::ZERO);
+    }
+}
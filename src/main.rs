@@ -1,33 +1,167 @@
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
 
+mod accessibility;
+mod audio_fade;
+mod auto_tiling;
+mod boss;
+mod bounds;
+mod bug_report;
+mod camera;
+mod camera_script;
+mod character_controller;
+mod checkpoints;
 mod collisions;
+mod config;
 mod constants;
+mod controllers;
+mod crates;
+#[cfg(feature = "inspector")]
+mod debug_inspector;
+mod debug_mouse;
+mod enemies;
+mod explosions;
+mod fixed_input;
+mod flips;
+mod floating_text;
 mod game_state;
+mod gamepad_cursor;
+mod goal_indicators;
 mod goals;
+mod gravity_zones;
 mod ground;
+mod ground_edit;
+mod hub;
+mod hud;
+mod input_latch;
+mod input_stats;
+mod jump_pads;
+mod keyboard_layout;
+mod lasers;
+mod leak_detector;
 mod level;
+mod level_packs;
+mod level_registry;
+mod level_select;
+mod level_validation;
+mod magnets;
+mod mouse;
+mod music;
+mod mutators;
+mod navigation;
+mod news;
+mod orientation;
+mod palette;
+#[cfg(all(test, feature = "perf_test"))]
+mod perf_test;
 mod physics;
 mod player;
+mod player_animation;
+mod post_processing;
+mod practice;
+mod preload;
+mod previews;
+mod quick_restart;
+mod quit_confirm;
+mod render_layers;
+mod run_mode;
+mod safe_position;
+mod save;
+mod save_migration;
+mod save_slots;
+mod settings;
 mod sfx;
+mod shadow_cat;
+#[cfg(test)]
+mod soak_test;
 mod start_menu;
+mod stuck;
+mod surfaces;
+mod swings;
+mod tasks;
+mod timer;
+mod turrets;
 mod win_screen;
+mod world_flags;
+mod wrapping;
 
 use crate::goals::GoalPlugin;
+use accessibility::AccessibilityPlugin;
+use audio_fade::AudioFadePlugin;
 use bevy::prelude::*;
 use bevy::window::WindowResolution;
 use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_ecs_ldtk::LdtkPlugin;
 use bevy_turborand::prelude::*;
+use boss::BossPlugin;
+use bounds::BoundsPlugin;
+use bug_report::BugReportPlugin;
+use camera::CameraPlugin;
+use camera_script::{CameraScriptPlugin, CameraScripts};
+use character_controller::CharacterControllerPlugin;
+use checkpoints::CheckpointPlugin;
 use collisions::{CollisionDebugPlugin, CollisionPlugin};
+use config::{ConfigPlugin, GameConfig};
 use constants::CollisionTypes;
-use game_state::GameStatePlugin;
+use controllers::ControllerPlugin;
+use crates::CratePlugin;
+#[cfg(feature = "inspector")]
+use debug_inspector::DebugInspectorPlugin;
+use debug_mouse::DebugMousePlugin;
+use enemies::EnemyPlugin;
+use explosions::ExplosionPlugin;
+use flips::FlipCounterPlugin;
+use floating_text::FloatingTextPlugin;
+use game_state::{GameState, GameStatePlugin};
+use gamepad_cursor::GamepadCursorPlugin;
+use goal_indicators::GoalIndicatorPlugin;
+use gravity_zones::GravityZonePlugin;
 use ground::GroundPlugin;
+use ground_edit::GroundEditPlugin;
+use hub::HubPlugin;
+use hud::HudPlugin;
+use input_stats::InputStatsPlugin;
+use jump_pads::JumpPadPlugin;
+use keyboard_layout::KeyboardLayoutPlugin;
+use lasers::LaserPlugin;
+use leak_detector::LeakDetectorPlugin;
 use level::LevelPlugin;
+use level_packs::LevelPacksPlugin;
+use level_registry::{LevelMetaOverlay, LevelRegistryPlugin};
+use level_select::LevelSelectPlugin;
+use magnets::MagnetPlugin;
+use mouse::MousePlugin;
+use music::MusicPlugin;
+use mutators::MutatorsPlugin;
+use navigation::NavigationPlugin;
+use news::{Changelog, NewsPlugin};
+use orientation::OrientationPlugin;
+use palette::{Background, PalettePlugin};
 use physics::{PhysicsPlugin, PhysicsSettings};
 use player::PlayerPlugin;
+use player_animation::PlayerAnimationPlugin;
+use post_processing::{PostProcessPlugin, PostProcessSettings};
+use practice::PracticePlugin;
+use preload::{AssetManifest, PreloadPlugin, Preloaded};
+use previews::LevelPreviewPlugin;
+use quick_restart::QuickRestartPlugin;
+use quit_confirm::QuitConfirmPlugin;
+use render_layers::{RenderLayer, RenderLayerPlugin};
+use run_mode::RunModePlugin;
+use safe_position::SafePositionPlugin;
+use save::SavePlugin;
+use save_slots::SaveSlotsPlugin;
+use settings::SettingsPlugin;
 use sfx::SfxPlugin;
+use shadow_cat::ShadowCatPlugin;
 use start_menu::StartMenuPlugin;
+use stuck::StuckPlugin;
+use surfaces::{SurfacePlugin, SurfaceTable};
+use swings::SwingPlugin;
+use timer::TimerPlugin;
+use turrets::TurretPlugin;
 use win_screen::WinScreenPlugin;
+use world_flags::WorldFlagsPlugin;
+use wrapping::WrapPlugin;
 
 fn main() {
     let mut app = App::new();
@@ -39,6 +173,8 @@ fn main() {
                     resolution: WindowResolution::new(720., 720.),
                     ..default()
                 }),
+                // closing may need confirming and fades the audio out, see `QuitConfirmPlugin`
+                close_when_requested: false,
                 ..default()
             })
             .set(AssetPlugin {
@@ -46,55 +182,122 @@ fn main() {
                 ..default()
             })
             .set(ImagePlugin::default_nearest()),
-    )
-    .add_plugin(RonAssetPlugin::<PhysicsSettings>::new(&["physics.ron"]))
-    .add_plugin(RngPlugin::default())
-    .insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+    );
+    add_game(&mut app);
+    // egui needs the window, so this is left out of the headless tests
+    #[cfg(feature = "inspector")]
+    app.add_plugin(DebugInspectorPlugin);
+
+    // #[cfg(debug_assertions)]
+    // bevy_mod_debugdump::print_main_schedule(&mut app);
+    // let dot = bevy_mod_debugdump::schedule_graph_dot(
+    //     &mut app,
+    //     CoreSchedule::FixedUpdate,
+    //     &bevy_mod_debugdump::schedule_graph::Settings::default(),
+    // );
+    // print!("{dot}");
+    app.run();
+}
+
+/// everything except the window and renderer setup, shared with headless tests
+fn add_game(app: &mut App) {
+    app.add_plugin(RonAssetPlugin::<PhysicsSettings>::new(&["physics.ron"]))
+        .add_plugin(RonAssetPlugin::<PostProcessSettings>::new(&["post.ron"]))
+        .add_plugin(RonAssetPlugin::<GameConfig>::new(&["game.ron"]))
+        .add_plugin(RonAssetPlugin::<LevelMetaOverlay>::new(&["meta.ron"]))
+        .add_plugin(RonAssetPlugin::<CameraScripts>::new(&["camera.ron"]))
+        .add_plugin(RonAssetPlugin::<Changelog>::new(&["news.ron"]))
+        .add_plugin(RonAssetPlugin::<SurfaceTable>::new(&["surface.ron"]))
+        .add_plugin(RonAssetPlugin::<AssetManifest>::new(&["manifest.ron"]))
+        .add_plugin(RngPlugin::default())
+        .insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
 
     app.add_plugin(LdtkPlugin);
 
     app.add_plugin(GameStatePlugin)
+        .add_plugin(PreloadPlugin)
+        .add_plugin(ConfigPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(SaveSlotsPlugin)
+        .add_plugin(WorldFlagsPlugin)
+        .add_plugin(WrapPlugin)
+        .add_plugin(CameraPlugin)
+        .add_plugin(CameraScriptPlugin)
+        .add_plugin(MousePlugin)
+        .add_plugin(PostProcessPlugin)
         .add_plugin(GroundPlugin)
+        .add_plugin(GroundEditPlugin)
+        .add_plugin(GravityZonePlugin)
+        .add_plugin(JumpPadPlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(TurretPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(CratePlugin)
+        .add_plugin(LaserPlugin)
+        .add_plugin(MagnetPlugin)
+        .add_plugin(SwingPlugin)
+        .add_plugin(ExplosionPlugin)
         .add_plugin(StartMenuPlugin)
+        .add_plugin(NewsPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(MutatorsPlugin)
+        .add_plugin(RunModePlugin)
+        .add_plugin(PracticePlugin)
+        .add_plugin(TimerPlugin)
+        .add_plugin(HudPlugin)
         .add_plugin(PhysicsPlugin)
+        .add_plugin(CharacterControllerPlugin)
         .add_plugin(GoalPlugin)
+        .add_plugin(GoalIndicatorPlugin)
+        .add_plugin(FlipCounterPlugin)
+        .add_plugin(FloatingTextPlugin)
         .add_plugin(LevelPlugin)
+        .add_plugin(HubPlugin)
+        .add_plugin(CheckpointPlugin)
+        .add_plugin(LevelPacksPlugin)
+        .add_plugin(QuickRestartPlugin)
+        .add_plugin(BoundsPlugin)
+        .add_plugin(NavigationPlugin)
+        .add_plugin(LevelRegistryPlugin)
+        .add_plugin(LevelSelectPlugin)
+        .add_plugin(PalettePlugin)
         .add_plugin(PlayerPlugin)
+        .add_plugin(PlayerAnimationPlugin)
+        .add_plugin(ShadowCatPlugin)
+        .add_plugin(KeyboardLayoutPlugin)
+        .add_plugin(ControllerPlugin)
+        .add_plugin(GamepadCursorPlugin)
+        .add_plugin(StuckPlugin)
+        .add_plugin(SafePositionPlugin)
+        .add_plugin(OrientationPlugin)
         .add_plugin(WinScreenPlugin)
         .add_plugin(SfxPlugin)
+        .add_plugin(AudioFadePlugin)
+        .add_plugin(QuitConfirmPlugin)
+        .add_plugin(RenderLayerPlugin)
+        .add_plugin(SurfacePlugin)
+        .add_plugin(AccessibilityPlugin)
+        .add_plugin(MusicPlugin)
         .add_plugin(CollisionPlugin::<CollisionTypes>::new())
-        .add_plugin(CollisionDebugPlugin)
-        .insert_resource(PhysicsSettings {
-            // these are overridden by the setting.ron
-            initial_jump_speed: 400.0,
-            gravity_pressed: 40.0,
-            gravity_unpressed: 200.0,
-            horizontal_speed: 200.0,
-            max_speed: 700.0,
-        })
-        .add_startup_system(setup);
-
-    // #[cfg(debug_assertions)]
-    // bevy_mod_debugdump::print_main_schedule(&mut app);
-    // let dot = bevy_mod_debugdump::schedule_graph_dot(
-    //     &mut app,
-    //     CoreSchedule::FixedUpdate,
-    //     &bevy_mod_debugdump::schedule_graph::Settings::default(),
-    // );
-    // print!("{dot}");
-    app.run();
+        .add_plugin(CollisionDebugPlugin::<CollisionTypes>::new())
+        .add_plugin(BugReportPlugin)
+        .add_plugin(DebugMousePlugin)
+        .add_plugin(LeakDetectorPlugin)
+        .add_plugin(InputStatsPlugin)
+        .add_plugin(LevelPreviewPlugin)
+        .init_resource::<PhysicsSettings>()
+        .add_system(setup.in_schedule(OnExit(GameState::Preload)));
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_xyz(360.0, 360.0, 1000.0),
-        ..default()
-    });
-
+fn setup(mut commands: Commands, preloaded: Res<Preloaded>) {
     // background
-    commands.spawn(SpriteBundle {
-        texture: asset_server.load("bg.png"),
-        transform: Transform::from_xyz(360., 360., 0.),
-        ..default()
-    });
+    commands.spawn((
+        Background,
+        RenderLayer::Background,
+        SpriteBundle {
+            texture: preloaded.get("background"),
+            transform: Transform::from_xyz(360., 360., 0.),
+            ..default()
+        },
+    ));
 }
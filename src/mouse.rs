@@ -0,0 +1,52 @@
+use bevy::{input::InputSystem, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    camera::MainCamera,
+    collisions::{point_in_rects, Rect},
+};
+
+pub struct MousePlugin;
+impl Plugin for MousePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorWorldPos>().add_system(
+            update_cursor_world_pos
+                .in_base_set(CoreSet::PreUpdate)
+                .after(InputSystem),
+        );
+    }
+}
+
+/// world position under the mouse cursor, `None` when it's outside the window
+#[derive(Resource, Default)]
+pub struct CursorWorldPos(pub Option<Vec2>);
+
+/// Owner of the collider under `point`. Colliders are usually children of
+/// the entity they belong to, so that's the parent if there is one.
+pub fn collider_owner_at(
+    point: Vec2,
+    colliders: &Query<(Entity, &Rect, &GlobalTransform, Option<&Parent>)>,
+) -> Option<Entity> {
+    point_in_rects(
+        point,
+        colliders
+            .iter()
+            .map(|(e, rect, t, parent)| (parent.map_or(e, Parent::get), rect, t)),
+    )
+}
+
+fn update_cursor_world_pos(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut cursor: ResMut<CursorWorldPos>,
+) {
+    let position = windows
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .zip(camera.get_single().ok())
+        .and_then(|(position, (camera, t))| camera.viewport_to_world(t, position))
+        .map(|ray| ray.origin.truncate());
+    if cursor.0 != position {
+        cursor.0 = position;
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    game_state::GameState, goals::Goal, physics::OnGround, player::Player, sfx::AudioBuses,
+};
+
+pub struct MusicPlugin;
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicMix>()
+            .add_startup_system(load_music)
+            .add_system(start_music)
+            .add_systems((set_layer_targets, mix_layers).chain());
+    }
+}
+
+/// how fast stems fade in and out, in volume per second
+const FADE_SPEED: f32 = 1.5;
+
+/// Stems that are layered on top of each other. All stems are the same length
+/// and are started on the same frame so they stay in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MusicLayer {
+    Base,
+    Airborne,
+    LastGoal,
+    Timed,
+}
+
+impl MusicLayer {
+    const ALL: [MusicLayer; 4] = [
+        MusicLayer::Base,
+        MusicLayer::Airborne,
+        MusicLayer::LastGoal,
+        MusicLayer::Timed,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            MusicLayer::Base => "music/base.wav",
+            MusicLayer::Airborne => "music/airborne.wav",
+            MusicLayer::LastGoal => "music/last_goal.wav",
+            MusicLayer::Timed => "music/timed.wav",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct LayerGain {
+    gain: f32,
+    target: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct MusicMix {
+    /// set by timed challenge modes to bring in the timed stem
+    pub timed_challenge: bool,
+    layers: [LayerGain; 4],
+}
+
+impl MusicMix {
+    pub fn gain(&self, layer: MusicLayer) -> f32 {
+        self.layers[layer as usize].gain
+    }
+
+    fn set_target(&mut self, layer: MusicLayer, target: f32) {
+        self.layers[layer as usize].target = target;
+    }
+}
+
+#[derive(Resource)]
+struct MusicHandles(Vec<(MusicLayer, Handle<AudioSource>)>);
+
+#[derive(Resource)]
+struct MusicSinks(Vec<(MusicLayer, Handle<AudioSink>)>);
+
+fn load_music(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = MusicLayer::ALL
+        .iter()
+        .map(|layer| (*layer, asset_server.load(layer.path())))
+        .collect();
+    commands.insert_resource(MusicHandles(handles));
+}
+
+/// wait for every stem to load before starting them so they start in sync
+fn start_music(
+    mut commands: Commands,
+    handles: Res<MusicHandles>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut started: Local<bool>,
+) {
+    if *started {
+        return;
+    }
+
+    let load_state = asset_server.get_group_load_state(handles.0.iter().map(|(_, h)| h.id()));
+    if load_state != LoadState::Loaded {
+        return;
+    }
+
+    let sinks = handles
+        .0
+        .iter()
+        .map(|(layer, handle)| {
+            let sink =
+                audio.play_with_settings(handle.clone(), PlaybackSettings::LOOP.with_volume(0.0));
+            (*layer, audio_sinks.get_handle(sink))
+        })
+        .collect();
+    commands.insert_resource(MusicSinks(sinks));
+    *started = true;
+}
+
+fn set_layer_targets(
+    mut mix: ResMut<MusicMix>,
+    state: Res<State<GameState>>,
+    player: Query<&OnGround, With<Player>>,
+    goals: Query<(), With<Goal>>,
+) {
+    let playing = state.0 == GameState::Playing;
+    let airborne = playing && player.iter().any(|on_ground| !on_ground.0);
+    let last_goal = playing && goals.iter().count() == 1;
+    let timed = playing && mix.timed_challenge;
+
+    mix.set_target(MusicLayer::Base, 1.0);
+    mix.set_target(MusicLayer::Airborne, if airborne { 1.0 } else { 0.0 });
+    mix.set_target(MusicLayer::LastGoal, if last_goal { 1.0 } else { 0.0 });
+    mix.set_target(MusicLayer::Timed, if timed { 1.0 } else { 0.0 });
+}
+
+fn mix_layers(
+    time: Res<Time>,
+    mut mix: ResMut<MusicMix>,
+    buses: Res<AudioBuses>,
+    sinks: Option<Res<MusicSinks>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    let step = FADE_SPEED * time.delta_seconds();
+    for layer in &mut mix.layers {
+        layer.gain += (layer.target - layer.gain).clamp(-step, step);
+    }
+
+    let Some(sinks) = sinks else {
+        return;
+    };
+    for (layer, handle) in &sinks.0 {
+        if let Some(sink) = audio_sinks.get(handle) {
+            sink.set_volume(mix.gain(*layer) * buses.music_volume());
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    flips::StyleResults,
+    game_state::GameState,
+    preload::Preloaded,
+    save::{CompletedRun, SaveData},
+    settings::settings_closed,
+    start_menu::MenuMarker,
+};
+
+pub struct MutatorsPlugin;
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mutators>()
+            .add_system(spawn_mutator_menu.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems(
+                (toggle_mutators, update_mutator_menu)
+                    .chain()
+                    .distributive_run_if(in_state(GameState::StartMenu))
+                    .distributive_run_if(settings_closed),
+            )
+            .add_system(record_completed_run.in_schedule(OnEnter(GameState::WinScreen)));
+    }
+}
+
+/// seconds the player has to collect the rest of the goals after picking up
+/// the first one when `fragile_goals` is on
+pub const FRAGILE_GOAL_SECONDS: f32 = 8.0;
+
+/// Optional rules that change how a run plays. These can be combined and are
+/// consulted by the systems they affect.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mutators {
+    /// level is shown mirrored horizontally and left/right input is swapped
+    pub mirrored: bool,
+    pub double_gravity: bool,
+    /// once one goal is collected the rest need to be collected within `FRAGILE_GOAL_SECONDS`
+    pub fragile_goals: bool,
+    /// horizontal movement accelerates and slides instead of stopping instantly
+    pub icy_floors: bool,
+}
+
+impl Mutators {
+    pub fn gravity_scale(&self) -> f32 {
+        if self.double_gravity {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    fn labels(&self) -> [(&'static str, bool); 4] {
+        [
+            ("1: Mirrored", self.mirrored),
+            ("2: Double Gravity", self.double_gravity),
+            ("3: Fragile Goals", self.fragile_goals),
+            ("4: Icy Floors", self.icy_floors),
+        ]
+    }
+}
+
+#[derive(Component)]
+struct MutatorText;
+
+fn spawn_mutator_menu(mut commands: Commands, preloaded: Res<Preloaded>, mutators: Res<Mutators>) {
+    commands.spawn((
+        MenuMarker,
+        MutatorText,
+        TextBundle::from_section(
+            mutator_text(&mutators),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(20.0),
+                left: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn mutator_text(mutators: &Mutators) -> String {
+    let mut text = "Mutators".to_string();
+    for (label, enabled) in mutators.labels() {
+        text.push_str(&format!(
+            "\n{} [{}]",
+            label,
+            if enabled { "x" } else { " " }
+        ));
+    }
+    text
+}
+
+fn toggle_mutators(keyboard: Res<Input<KeyCode>>, mut mutators: ResMut<Mutators>) {
+    if keyboard.just_pressed(KeyCode::Key1) {
+        mutators.mirrored = !mutators.mirrored;
+    }
+    if keyboard.just_pressed(KeyCode::Key2) {
+        mutators.double_gravity = !mutators.double_gravity;
+    }
+    if keyboard.just_pressed(KeyCode::Key3) {
+        mutators.fragile_goals = !mutators.fragile_goals;
+    }
+    if keyboard.just_pressed(KeyCode::Key4) {
+        mutators.icy_floors = !mutators.icy_floors;
+    }
+}
+
+fn update_mutator_menu(mutators: Res<Mutators>, mut q: Query<&mut Text, With<MutatorText>>) {
+    if !mutators.is_changed() {
+        return;
+    }
+
+    for mut text in &mut q {
+        text.sections[0].value = mutator_text(&mutators);
+    }
+}
+
+fn record_completed_run(
+    mutators: Res<Mutators>,
+    style: Res<StyleResults>,
+    mut save: ResMut<SaveData>,
+) {
+    save.completed_runs.push(CompletedRun {
+        mutators: *mutators,
+        style: style.total_score(),
+    });
+}
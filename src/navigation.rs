@@ -0,0 +1,309 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::{
+    bounds::{update_level_bounds, LevelBounds},
+    config::DebugConfig,
+    game_state::GameState,
+    goals::Goal,
+    ground::{FallingGround, Ground},
+    physics::{Direction, GravityDirection},
+    player::Player,
+};
+
+/// size of an int grid cell in pixels
+const CELL_SIZE: f32 = 24.0;
+/// Cost of changing gravity onto a wall, a jump and a turn at the apex
+/// takes longer than walking a cell.
+const TURN_COST: u32 = 3;
+
+pub struct NavigationPlugin;
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+            .add_system(
+                build_nav_grid
+                    .after(update_level_bounds)
+                    .in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(
+                draw_nav_paths
+                    .in_set(GameState::Playing)
+                    .run_if(|debug: Res<DebugConfig>| debug.show_nav_paths),
+            );
+    }
+}
+
+/// A place something can stand, an empty cell with solid ground next to it in
+/// the direction of its gravity. Walls and ceilings are surfaces too, since
+/// gravity can point at them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NavNode {
+    pub cell: IVec2,
+    pub gravity: Direction,
+}
+
+/// Walkable surfaces of the current level, built from the ground int grid.
+/// Falling blocks are left out since they don't stay put.
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    solid: HashSet<IVec2>,
+    size: IVec2,
+    /// world position of the bottom left corner of the grid
+    origin: Vec2,
+}
+
+fn step(direction: Direction) -> IVec2 {
+    direction.as_vec2().as_ivec2()
+}
+
+impl NavGrid {
+    fn in_grid(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()
+    }
+
+    fn is_solid(&self, cell: IVec2) -> bool {
+        self.solid.contains(&cell)
+    }
+
+    fn is_surface(&self, node: NavNode) -> bool {
+        self.in_grid(node.cell)
+            && !self.is_solid(node.cell)
+            && self.is_solid(node.cell + step(node.gravity))
+    }
+
+    /// for ground that's added or destroyed while playing
+    pub fn set_cell(&mut self, cell: IVec2, solid: bool) {
+        if solid {
+            self.solid.insert(cell);
+        } else {
+            self.solid.remove(&cell);
+        }
+    }
+
+    pub fn cell_at(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / CELL_SIZE).floor().as_ivec2()
+    }
+
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    /// surface under something at `position` with gravity pointing `gravity`
+    pub fn node_at(&self, position: Vec2, gravity: Direction) -> Option<NavNode> {
+        let node = NavNode {
+            cell: self.cell_at(position),
+            gravity,
+        };
+        self.is_surface(node).then_some(node)
+    }
+
+    /// nodes reachable in one move from `node` and what the move costs
+    fn neighbors(&self, node: NavNode) -> Vec<(NavNode, u32)> {
+        let down = step(node.gravity);
+        let mut out = Vec::with_capacity(4);
+        for side in [node.gravity.cw(), node.gravity.ccw()] {
+            let next = node.cell + step(side);
+            if self.is_solid(next) {
+                // a wall ahead, jump and let gravity turn onto it
+                out.push((
+                    NavNode {
+                        cell: node.cell,
+                        gravity: side,
+                    },
+                    TURN_COST,
+                ));
+                continue;
+            }
+            if !self.in_grid(next) {
+                continue;
+            }
+
+            let walked = NavNode {
+                cell: next,
+                gravity: node.gravity,
+            };
+            if self.is_surface(walked) {
+                out.push((walked, 1));
+                continue;
+            }
+
+            // off a ledge, fall until landing or leaving the level
+            let mut cell = next;
+            let mut fallen = 0;
+            while self.in_grid(cell + down) && !self.is_solid(cell + down) {
+                cell += down;
+                fallen += 1;
+            }
+            if self.is_solid(cell + down) {
+                out.push((
+                    NavNode {
+                        cell,
+                        gravity: node.gravity,
+                    },
+                    1 + fallen,
+                ));
+            }
+        }
+        out
+    }
+
+    /// Cheapest way from `start` to any surface in the `goal` cell, whatever
+    /// gravity is there. Includes both ends.
+    pub fn path(&self, start: NavNode, goal: IVec2) -> Option<Vec<NavNode>> {
+        let heuristic = |cell: IVec2| {
+            let d = (cell - goal).abs();
+            (d.x + d.y) as u32
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<NavNode, NavNode> = HashMap::new();
+        let mut cost: HashMap<NavNode, u32> = HashMap::new();
+        // the heap needs an order, the counter breaks ties in insertion order
+        let mut pushed = 0u32;
+        let mut nodes = vec![start];
+        cost.insert(start, 0);
+        open.push(Reverse((heuristic(start.cell), pushed, 0usize)));
+
+        while let Some(Reverse((_, _, index))) = open.pop() {
+            let node = nodes[index];
+            if node.cell == goal {
+                let mut path = vec![node];
+                let mut current = node;
+                while let Some(previous) = came_from.get(&current) {
+                    path.push(*previous);
+                    current = *previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let node_cost = cost[&node];
+            for (next, move_cost) in self.neighbors(node) {
+                let next_cost = node_cost + move_cost;
+                if cost.get(&next).is_some_and(|&c| c <= next_cost) {
+                    continue;
+                }
+                cost.insert(next, next_cost);
+                came_from.insert(next, node);
+                pushed += 1;
+                nodes.push(next);
+                open.push(Reverse((
+                    next_cost + heuristic(next.cell),
+                    pushed,
+                    nodes.len() - 1,
+                )));
+            }
+        }
+        None
+    }
+}
+
+fn build_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    bounds: Res<LevelBounds>,
+    ground: Query<&GridCoords, (With<Ground>, Without<FallingGround>)>,
+) {
+    *grid = NavGrid {
+        solid: ground.iter().map(|&coords| IVec2::from(coords)).collect(),
+        size: ((bounds.max - bounds.min) / CELL_SIZE).ceil().as_ivec2(),
+        origin: bounds.min,
+    };
+}
+
+/// draw the path from the player to every goal
+fn draw_nav_paths(
+    grid: Res<NavGrid>,
+    mut lines: ResMut<DebugLines>,
+    player: Query<(&Transform, &GravityDirection), With<Player>>,
+    goals: Query<&Transform, With<Goal>>,
+) {
+    let Ok((t, g)) = player.get_single() else {
+        return;
+    };
+    let Some(start) = grid.node_at(t.translation.truncate(), g.0) else {
+        return;
+    };
+
+    for goal in &goals {
+        let Some(path) = grid.path(start, grid.cell_at(goal.translation.truncate())) else {
+            continue;
+        };
+        for pair in path.windows(2) {
+            lines.line_colored(
+                grid.cell_center(pair[0].cell).extend(0.0),
+                grid.cell_center(pair[1].cell).extend(0.0),
+                0.0,
+                Color::CYAN,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{NavGrid, NavNode};
+    use crate::physics::Direction;
+
+    /// grid with solid cells drawn as `#`, the first row is the top
+    fn grid(rows: &[&str]) -> NavGrid {
+        let height = rows.len() as i32;
+        let mut solid = std::collections::HashSet::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    solid.insert(IVec2::new(x as i32, height - 1 - y as i32));
+                }
+            }
+        }
+        NavGrid {
+            solid,
+            size: IVec2::new(rows[0].len() as i32, height),
+            origin: Vec2::ZERO,
+        }
+    }
+
+    #[test]
+    fn walks_along_floor() {
+        let grid = grid(&["....", "####"]);
+        let start = NavNode {
+            cell: IVec2::new(0, 1),
+            gravity: Direction::Down,
+        };
+        let path = grid.path(start, IVec2::new(3, 1)).unwrap();
+        assert_eq!(path.len(), 4);
+        assert!(path.iter().all(|node| node.gravity == Direction::Down));
+    }
+
+    #[test]
+    fn climbs_onto_wall() {
+        let grid = grid(&["..#", "..#", "..#", "###"]);
+        let start = NavNode {
+            cell: IVec2::new(0, 1),
+            gravity: Direction::Down,
+        };
+        let path = grid.path(start, IVec2::new(1, 3)).unwrap();
+        let last = path.last().unwrap();
+        assert_eq!(last.cell, IVec2::new(1, 3));
+        assert_eq!(last.gravity, Direction::Right);
+    }
+
+    #[test]
+    fn no_path_off_the_bottom_of_the_level() {
+        let grid = grid(&["...", "#..", "..."]);
+        let start = NavNode {
+            cell: IVec2::new(0, 2),
+            gravity: Direction::Down,
+        };
+        // nothing to land on below the ledge
+        assert!(grid.path(start, IVec2::new(2, 0)).is_none());
+    }
+}
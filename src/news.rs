@@ -0,0 +1,223 @@
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
+
+use crate::{
+    game_state::GameState,
+    preload::Preloaded,
+    save::{slot_selected, SaveData},
+    start_menu::MenuMarker,
+};
+
+/// newest entries shown in the panel, older ones don't fit on screen
+const SHOWN_ENTRIES: usize = 3;
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const HIGHLIGHT_COLOR: Color = Color::rgb(1.0, 0.8, 0.4);
+
+pub struct NewsPlugin;
+impl Plugin for NewsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_changelog)
+            .add_system(spawn_news_hint.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems(
+                (show_new_changes.run_if(slot_selected), toggle_news)
+                    .chain()
+                    .distributive_run_if(in_state(GameState::StartMenu)),
+            );
+    }
+}
+
+/// Release notes loaded from changelog.news.ron. Entry text uses a small
+/// subset of markdown: `# ` headings, `- ` bullets and `**highlighted**` words.
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "2c9e5f0a-7b3d-4e18-a6c4-91d8b2f3e705"]
+pub struct Changelog {
+    /// newest first
+    pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChangelogEntry {
+    pub version: String,
+    pub date: String,
+    pub text: String,
+}
+
+#[derive(Resource)]
+struct ChangelogHandle(Handle<Changelog>);
+
+#[derive(Component)]
+pub struct NewsPanel;
+
+/// whether the news panel is closed, for start menu inputs it would otherwise swallow
+pub fn news_closed(panels: Query<(), With<NewsPanel>>) -> bool {
+    panels.is_empty()
+}
+
+fn load_changelog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ChangelogHandle(asset_server.load("changelog.news.ron")));
+}
+
+fn spawn_news_hint(mut commands: Commands, preloaded: Res<Preloaded>) {
+    commands.spawn((
+        MenuMarker,
+        TextBundle::from_section(
+            "N: What's New",
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(20.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+/// open the panel once for each new version, the first time a save sees it
+fn show_new_changes(
+    mut commands: Commands,
+    handle: Res<ChangelogHandle>,
+    changelogs: Res<Assets<Changelog>>,
+    mut save: ResMut<SaveData>,
+    panels: Query<(), With<NewsPanel>>,
+    preloaded: Res<Preloaded>,
+) {
+    let Some(changelog) = changelogs.get(&handle.0) else {
+        return;
+    };
+    let Some(latest) = changelog.entries.first() else {
+        return;
+    };
+    if save.seen_news.as_ref() == Some(&latest.version) {
+        return;
+    }
+    save.seen_news = Some(latest.version.clone());
+    if panels.is_empty() {
+        spawn_news_panel(&mut commands, changelog, preloaded.get("font"));
+    }
+}
+
+fn toggle_news(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    handle: Res<ChangelogHandle>,
+    changelogs: Res<Assets<Changelog>>,
+    panels: Query<Entity, With<NewsPanel>>,
+    preloaded: Res<Preloaded>,
+) {
+    match panels.get_single() {
+        Ok(panel) => {
+            // released rather than pressed so the same press doesn't also start the game
+            let close = keyboard.any_just_pressed([KeyCode::N, KeyCode::Escape])
+                || keyboard.any_just_released([KeyCode::Space, KeyCode::Return])
+                || gamepads.iter().any(|gamepad| {
+                    buttons.just_released(GamepadButton::new(gamepad, GamepadButtonType::South))
+                });
+            if close {
+                commands.entity(panel).despawn_recursive();
+            }
+        }
+        Err(_) => {
+            if !keyboard.just_pressed(KeyCode::N) {
+                return;
+            }
+            if let Some(changelog) = changelogs.get(&handle.0) {
+                spawn_news_panel(&mut commands, changelog, preloaded.get("font"));
+            }
+        }
+    }
+}
+
+/// text sections for a line, alternating plain and `**highlighted**` spans
+fn spans(line: &str, style: &TextStyle) -> Vec<TextSection> {
+    line.split("**")
+        .enumerate()
+        .filter(|(_, span)| !span.is_empty())
+        .map(|(i, span)| {
+            let mut style = style.clone();
+            if i % 2 == 1 {
+                style.color = HIGHLIGHT_COLOR;
+            }
+            TextSection::new(span, style)
+        })
+        .collect()
+}
+
+fn spawn_news_panel(commands: &mut Commands, changelog: &Changelog, font: Handle<Font>) {
+    let style = |font_size| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: TEXT_COLOR,
+    };
+    let line = |parent: &mut ChildBuilder, sections: Vec<TextSection>, margin: f32| {
+        parent.spawn((
+            MenuMarker,
+            TextBundle::from_sections(sections).with_style(Style {
+                margin: UiRect::top(Val::Px(margin)),
+                ..default()
+            }),
+        ));
+    };
+
+    commands
+        .spawn((
+            MenuMarker,
+            NewsPanel,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Percent(15.0),
+                        top: Val::Percent(10.0),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(70.0), Val::Percent(80.0)),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(24.0)),
+                    overflow: Overflow::Hidden,
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.08, 0.95).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            line(parent, spans("What's New", &style(36.0)), 0.0);
+            for entry in changelog.entries.iter().take(SHOWN_ENTRIES) {
+                let mut title = style(28.0);
+                title.color = HIGHLIGHT_COLOR;
+                line(
+                    parent,
+                    vec![TextSection::new(
+                        format!("{}  ({})", entry.version, entry.date),
+                        title,
+                    )],
+                    20.0,
+                );
+                for text in entry.text.lines().map(str::trim) {
+                    if let Some(heading) = text.strip_prefix("# ") {
+                        line(parent, spans(heading, &style(22.0)), 10.0);
+                    } else if let Some(bullet) = text.strip_prefix("- ") {
+                        line(
+                            parent,
+                            spans(&format!("  \u{2022} {bullet}"), &style(18.0)),
+                            2.0,
+                        );
+                    } else if !text.is_empty() {
+                        line(parent, spans(text, &style(18.0)), 6.0);
+                    }
+                }
+            }
+            line(parent, spans("N or Esc to close", &style(16.0)), 20.0);
+        });
+}
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+
+use crate::physics::{GravityDirection, Velocity};
+
+pub struct OrientationPlugin;
+impl Plugin for OrientationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(face_gravity);
+    }
+}
+
+/// Keeps a sprite facing the way it moves relative to its gravity, rotated so
+/// its feet point along gravity. Needs `Velocity`, `GravityDirection` and a `Sprite`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct GravityFacing;
+
+fn face_gravity(
+    mut q: Query<(&mut Sprite, &mut Transform, &Velocity, &GravityDirection), With<GravityFacing>>,
+) {
+    for (mut s, mut t, v, g) in &mut q {
+        t.rotation = Quat::from_rotation_z(g.angle());
+        let forward_speed = g.forward().as_vec2().dot(v.0);
+
+        if forward_speed > 0. {
+            s.flip_x = false;
+        } else if forward_speed < 0. {
+            s.flip_x = true;
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+use bevy_ecs_tilemap::tiles::TileColor;
+
+use crate::level_registry::LevelRegistry;
+
+pub struct PalettePlugin;
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PaletteTint>()
+            .add_system(select_palette_tint.run_if(
+                resource_changed::<LevelSelection>().or_else(resource_changed::<LevelRegistry>()),
+            ))
+            .add_system(blend_palette_tint.after(select_palette_tint))
+            .add_system(tint_new_tiles.after(blend_palette_tint));
+    }
+}
+
+/// marker for the background sprite so it gets tinted with the level tiles
+#[derive(Component)]
+pub struct Background;
+
+/// colors sampled across the level sequence, the first level uses the first entry
+/// and the last level the last entry. Colors in between are blended.
+const PALETTE_LUT: [Color; 4] = [
+    Color::rgb(1.0, 1.0, 1.0),    // morning
+    Color::rgb(1.0, 0.92, 0.78),  // afternoon
+    Color::rgb(0.96, 0.72, 0.66), // dusk
+    Color::rgb(0.66, 0.72, 0.96), // night
+];
+
+/// how fast the tint blends towards the target per second
+const BLEND_SPEED: f32 = 1.5;
+
+#[derive(Resource)]
+pub struct PaletteTint {
+    pub current: Color,
+    pub target: Color,
+}
+
+impl Default for PaletteTint {
+    fn default() -> Self {
+        PaletteTint {
+            current: PALETTE_LUT[0],
+            target: PALETTE_LUT[0],
+        }
+    }
+}
+
+/// sample the lut at `t` in 0..=1
+pub fn sample_lut(t: f32) -> Color {
+    let scaled = t.clamp(0.0, 1.0) * (PALETTE_LUT.len() - 1) as f32;
+    let index = (scaled.floor() as usize).min(PALETTE_LUT.len() - 2);
+    lerp_color(
+        PALETTE_LUT[index],
+        PALETTE_LUT[index + 1],
+        scaled - index as f32,
+    )
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let a = Vec4::from(a.as_rgba_f32());
+    let b = Vec4::from(b.as_rgba_f32());
+    let c = a.lerp(b, t);
+    Color::rgba(c.x, c.y, c.z, c.w)
+}
+
+fn select_palette_tint(
+    level_selection: Res<LevelSelection>,
+    registry: Res<LevelRegistry>,
+    mut tint: ResMut<PaletteTint>,
+) {
+    let LevelSelection::Index(index) = *level_selection else {
+        return;
+    };
+    let level_count = registry.len();
+
+    let t = if level_count > 1 {
+        index as f32 / (level_count - 1) as f32
+    } else {
+        0.0
+    };
+    tint.target = sample_lut(t);
+}
+
+fn blend_palette_tint(
+    time: Res<Time>,
+    mut tint: ResMut<PaletteTint>,
+    mut tiles: Query<&mut TileColor>,
+    mut background: Query<&mut Sprite, With<Background>>,
+) {
+    let current = Vec4::from(tint.current.as_rgba_f32());
+    let target = Vec4::from(tint.target.as_rgba_f32());
+    if current.abs_diff_eq(target, 0.001) {
+        return;
+    }
+
+    let t = (time.delta_seconds() * BLEND_SPEED).min(1.0);
+    tint.current = lerp_color(tint.current, tint.target, t);
+
+    for mut color in &mut tiles {
+        color.0 = tint.current;
+    }
+    for mut sprite in &mut background {
+        sprite.color = tint.current;
+    }
+}
+
+/// tiles spawned by a level load start white, match them to the current tint
+fn tint_new_tiles(tint: Res<PaletteTint>, mut tiles: Query<&mut TileColor, Added<TileColor>>) {
+    for mut color in &mut tiles {
+        color.0 = tint.current;
+    }
+}
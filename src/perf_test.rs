@@ -0,0 +1,121 @@
+//! Plays the smallest and then the largest level headless for a fixed number
+//! of ticks with scripted input, timing only the fixed update where the
+//! physics runs. Fails if the largest level's average tick is more than
+//! `MAX_SLOWDOWN` times the smallest's, so it doesn't depend on how fast the
+//! machine is. Only meaningful in release builds:
+//! `cargo test --release --features perf_test perf_test`
+use std::time::{Duration, Instant};
+
+use bevy::{input::ButtonState, prelude::*, time::TimeUpdateStrategy};
+use bevy_ecs_ldtk::{ldtk::Level, LdtkAsset, LevelSelection};
+
+use crate::{
+    game_state::GameState,
+    level_packs::LevelPacks,
+    preload::Preloaded,
+    soak_test::{headless_app, key, run_until, state},
+};
+
+const TICKS: u32 = 1000;
+/// Bigger levels have more to collide with, but anything slower than this
+/// grows faster than the level does.
+const MAX_SLOWDOWN: u32 = 10;
+/// ticks to let the level settle before timing starts
+const WARMUP_TICKS: u32 = 60;
+
+/// indices of the levels with the least and the most area in the active pack
+fn smallest_and_largest_levels(app: &App) -> Option<(usize, usize)> {
+    let handle = app
+        .world
+        .resource::<LevelPacks>()
+        .ldtk(app.world.resource::<Preloaded>());
+    let ldtk = app.world.resource::<Assets<LdtkAsset>>().get(&handle)?;
+    let area = |(_, level): &(usize, &Level)| level.px_wid * level.px_hei;
+    let smallest = ldtk.iter_levels().enumerate().min_by_key(area)?.0;
+    let largest = ldtk.iter_levels().enumerate().max_by_key(area)?.0;
+    Some((smallest, largest))
+}
+
+/// Runs back and forth, turning around every few seconds, and jumps often so
+/// gravity keeps rotating.
+fn scripted_input(app: &mut App, tick: u32) {
+    let (held, released) = if (tick / 200) % 2 == 0 {
+        (KeyCode::Right, KeyCode::Left)
+    } else {
+        (KeyCode::Left, KeyCode::Right)
+    };
+    if tick % 200 == 0 {
+        key(app, released, ButtonState::Released);
+        key(app, held, ButtonState::Pressed);
+    }
+    match tick % 45 {
+        0 => key(app, KeyCode::Space, ButtonState::Pressed),
+        5 => key(app, KeyCode::Space, ButtonState::Released),
+        _ => {}
+    }
+}
+
+/// Plays `level` from the start menu and returns to it, returning the average
+/// and slowest fixed update.
+fn time_level(app: &mut App, visited: &mut Vec<GameState>, level: usize) -> (Duration, Duration) {
+    *app.world.resource_mut::<LevelSelection>() = LevelSelection::Index(level);
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::LoadLevel);
+    run_until(app, visited, |app| state(app) == GameState::Playing);
+
+    // one fixed tick per update, however long the update took
+    let period = app.world.resource::<FixedTime>().period;
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(period));
+    for _ in 0..WARMUP_TICKS {
+        app.update();
+    }
+
+    // Updates without time passing run no fixed ticks, just the input and
+    // rendering, and the fixed update is then run on its own. Rendering is
+    // left out since CI only has a software renderer.
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    let mut total = Duration::ZERO;
+    let mut slowest = Duration::ZERO;
+    for tick in 0..TICKS {
+        scripted_input(app, tick);
+        app.update();
+        let start = Instant::now();
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+        let elapsed = start.elapsed();
+        total += elapsed;
+        slowest = slowest.max(elapsed);
+    }
+
+    app.insert_resource(TimeUpdateStrategy::Automatic);
+    for held in [KeyCode::Left, KeyCode::Right, KeyCode::Space] {
+        key(app, held, ButtonState::Released);
+    }
+    key(app, KeyCode::Escape, ButtonState::Pressed);
+    run_until(app, visited, |app| state(app) == GameState::StartMenu);
+    key(app, KeyCode::Escape, ButtonState::Released);
+    app.update();
+    (total / TICKS, slowest)
+}
+
+#[test]
+fn perf_test() {
+    assert!(
+        !cfg!(debug_assertions),
+        "tick times are only meaningful with --release"
+    );
+    let mut app = headless_app();
+    let mut visited = Vec::new();
+    run_until(&mut app, &mut visited, |app| {
+        state(app) == GameState::StartMenu && smallest_and_largest_levels(app).is_some()
+    });
+    let (smallest, largest) = smallest_and_largest_levels(&app).unwrap();
+
+    let (baseline, _) = time_level(&mut app, &mut visited, smallest);
+    let (average, slowest) = time_level(&mut app, &mut visited, largest);
+    assert!(
+        average < baseline * MAX_SLOWDOWN,
+        "average tick in level {largest} took {average:?}, over {MAX_SLOWDOWN} times the \
+         {baseline:?} of level {smallest} (slowest {slowest:?})"
+    );
+}
@@ -1,38 +1,76 @@
 use std::f32::consts::PI;
 
 use crate::{
-    collisions::{CollisionData, CollisionEvents, CollisionSets, PositionDelta, Ray, Rect},
+    character_controller::KinematicBody,
+    collisions::{
+        CollisionData, CollisionEvents, CollisionSets, ContactResponse, PositionDelta, Ray, Rect,
+        Sweep,
+    },
     constants::CollisionTypes,
+    gravity_zones::{zone_at, GravityZone, ZoneSize},
+    ground::{OneWayPlatform, SurfaceMaterial},
+    mutators::Mutators,
 };
 use bevy::{prelude::*, reflect::TypeUuid};
 
+/// bounces slower than this stop dead, so bouncy ground can be stood on
+const MIN_BOUNCE_SPEED: f32 = 40.0;
+
 pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<GravityRotated>();
+        app.add_event::<Landed>();
         app.add_systems(
             (
                 rotate_gravity,
                 apply_gravity,
                 apply_acceleration,
                 apply_velocity,
+                ride_moving_ground,
             )
                 .chain()
                 .in_set(PhysicsSet)
                 .in_schedule(CoreSchedule::FixedUpdate),
         );
+        app.edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+            schedule.configure_set(
+                ExternalForces
+                    .in_set(PhysicsSet)
+                    .after(apply_gravity)
+                    .before(apply_acceleration),
+            );
+        });
+        app.add_system(
+            cap_catch_up
+                .before(PhysicsSet)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+        app.add_system(pass_through_one_ways.in_set(CollisionSets::PreSolve));
         app.add_systems((ground_detection, falling_detection).in_set(CollisionSets::Consume));
         app.add_startup_system(load_physics);
         app.add_system(monitor_physics_changes);
+        app.add_system(resize_colliders.run_if(resource_changed::<PhysicsSettings>()));
     }
 }
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub struct PhysicsSet;
 
+/// Systems that push movers around besides gravity, like magnets, run here.
+/// It's after gravity and before velocity is clamped to `max_speed`.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct ExternalForces;
+
+/// Movers with this can't rotate gravity outside of forced zones. Added while
+/// the `FlipLimit` is used up.
+#[derive(Component)]
+pub struct OutOfFlips;
+
 #[derive(Component, Default)]
 pub struct Gravity(pub f32);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Direction {
     Up,
     Down,
@@ -80,6 +118,16 @@ impl Direction {
         }
     }
 
+    /// rotation of an entity whose gravity points this way, down is unrotated
+    pub fn angle(&self) -> f32 {
+        match self {
+            Direction::Down => 0.0,
+            Direction::Right => PI / 2.,
+            Direction::Up => PI,
+            Direction::Left => -PI / 2.,
+        }
+    }
+
     pub fn from_vec2(source: Vec2) -> Option<Self> {
         if source == Vec2::NEG_Y {
             Some(Direction::Down)
@@ -95,6 +143,54 @@ impl Direction {
     }
 }
 
+/// sent when an entity rotates its gravity at the apex of a jump
+pub struct GravityRotated {
+    pub entity: Entity,
+}
+
+/// sent when an entity touches the floor after being in the air
+pub struct Landed {
+    pub entity: Entity,
+    /// speed along gravity just before hitting the floor
+    pub speed: f32,
+    pub surface: SurfaceMaterial,
+}
+
+/// what the floor an entity is standing on is made of, kept while in the air
+#[derive(Component, Default, Clone, Copy)]
+pub struct GroundSurface(pub SurfaceMaterial);
+
+/// Grip of the floor an entity is standing on, from its `ContactResponse`.
+/// Kept while in the air, like `GroundSurface`.
+#[derive(Component, Clone, Copy)]
+pub struct GroundFriction(pub f32);
+
+impl Default for GroundFriction {
+    fn default() -> Self {
+        GroundFriction(1.0)
+    }
+}
+
+/// Velocity of the floor an entity is standing on, kept while in the air.
+/// Some of it is added to the entity's own velocity when it leaves the floor,
+/// see `PhysicsSettings::inherit_ground_velocity`.
+#[derive(Component, Default, Clone, Copy)]
+pub struct GroundVelocity(pub Vec2);
+
+/// Speed picked up from something besides walking, like letting go of a swing,
+/// a magnet or a moving floor. Walking sets the speed along the floor every
+/// frame, so `control_movement` adds this on top, fading it out in the air.
+#[derive(Component, Default, Clone, Copy)]
+pub struct Momentum(pub Vec2);
+
+impl Momentum {
+    /// adds `dv` to `v`, and to the momentum so walking doesn't take it away
+    pub fn push(&mut self, v: &mut Velocity, dv: Vec2) {
+        v.0 += dv;
+        self.0 += dv;
+    }
+}
+
 /// Direction gravity applies to for a specific object,
 /// Note: might be better for this to be a vector instead?
 #[derive(Component, Deref, DerefMut, Clone, Copy)]
@@ -151,11 +247,110 @@ pub struct PhysicsSettings {
     pub gravity_unpressed: f32,
     pub horizontal_speed: f32,
     pub max_speed: f32,
+    /// speed the player is launched at by a jump pad
+    pub jump_pad_speed: f32,
+    /// most fixed steps run in a single frame, time past that after a hitch is dropped
+    pub max_catch_up_steps: u32,
+    /// how much of a moving floor's velocity is kept when jumping or walking off it
+    pub inherit_ground_velocity: VelocityInheritance,
+    /// landing faster than this along gravity damps horizontal input for
+    /// `landing_lag_seconds`
+    pub landing_lag_speed: f32,
+    pub landing_lag_seconds: f32,
+    pub colliders: ColliderSizes,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        // these are overridden by the setting.ron
+        PhysicsSettings {
+            initial_jump_speed: 400.0,
+            gravity_pressed: 40.0,
+            gravity_unpressed: 200.0,
+            horizontal_speed: 200.0,
+            max_speed: 700.0,
+            jump_pad_speed: 600.0,
+            max_catch_up_steps: 4,
+            inherit_ground_velocity: VelocityInheritance::Full,
+            landing_lag_speed: 600.0,
+            landing_lag_seconds: 0.1,
+            colliders: ColliderSizes {
+                player: (30.0, 20.0),
+                ground: (24.0, 24.0),
+                falling_block: (71.0, 71.0),
+                walker: (24.0, 16.0),
+            },
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+pub enum VelocityInheritance {
+    Full,
+    /// fraction of the floor's velocity that's kept
+    Partial(f32),
+    None,
+}
+
+impl VelocityInheritance {
+    pub fn scale(self) -> f32 {
+        match self {
+            VelocityInheritance::Full => 1.0,
+            VelocityInheritance::Partial(fraction) => fraction,
+            VelocityInheritance::None => 0.0,
+        }
+    }
+}
+
+/// hit box sizes as `(width, height)` while gravity points down
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ColliderSizes {
+    pub player: (f32, f32),
+    pub ground: (f32, f32),
+    pub falling_block: (f32, f32),
+    pub walker: (f32, f32),
+}
+
+impl ColliderSizes {
+    pub fn get(&self, collider: SizedCollider) -> Vec2 {
+        let (width, height) = match collider {
+            SizedCollider::Player => self.player,
+            SizedCollider::Ground => self.ground,
+            SizedCollider::FallingBlock => self.falling_block,
+            SizedCollider::Walker => self.walker,
+        };
+        Vec2::new(width, height)
+    }
+}
+
+/// which of the `ColliderSizes` a `Rect` uses, so it follows changes to the settings
+#[derive(Component, Clone, Copy)]
+pub enum SizedCollider {
+    Player,
+    Ground,
+    FallingBlock,
+    Walker,
 }
 
 #[derive(Resource)]
 struct PhysicsSettingsHandle(pub Handle<PhysicsSettings>);
 
+/// Drop any backlog of fixed steps past `max_catch_up_steps` so a long frame
+/// doesn't move things far enough to tunnel through ground.
+/// Runs inside the fixed update loop, so one step has already been taken out.
+fn cap_catch_up(mut fixed_time: ResMut<FixedTime>, settings: Res<PhysicsSettings>) {
+    let max_backlog = fixed_time.period * settings.max_catch_up_steps.saturating_sub(1);
+    if fixed_time.accumulated() <= max_backlog {
+        return;
+    }
+
+    let mut dropped = 0;
+    while fixed_time.accumulated() > max_backlog && fixed_time.expend().is_ok() {
+        dropped += 1;
+    }
+    warn!("fixed update fell behind, dropped {dropped} steps");
+}
+
 fn apply_gravity(
     mut q: Query<(
         &mut Acceleration,
@@ -164,6 +359,7 @@ fn apply_gravity(
         &Gravity,
         &OnGround,
     )>,
+    mutators: Res<Mutators>,
 ) {
     for (mut a, mut v, dir, gravity, on_ground) in q.iter_mut() {
         if on_ground.0 {
@@ -172,11 +368,11 @@ fn apply_gravity(
             continue;
         }
 
-        a.0 += gravity.0 * dir.as_vec2();
+        a.0 += gravity.0 * mutators.gravity_scale() * dir.as_vec2();
     }
 }
 
-fn apply_velocity(
+pub fn apply_velocity(
     mut query: Query<(&mut Transform, &Velocity, Option<&mut PositionDelta>)>,
     time_step: Res<FixedTime>,
 ) {
@@ -190,6 +386,33 @@ fn apply_velocity(
     }
 }
 
+/// Moves entities standing on moving ground, like a moving platform, along
+/// with it. Ground moving into them is left to the collision pushing them out.
+fn ride_moving_ground(
+    mut riders: Query<(
+        &mut Transform,
+        &OnGround,
+        &GroundVelocity,
+        &GravityDirection,
+        Option<&mut PositionDelta>,
+    )>,
+    time_step: Res<FixedTime>,
+) {
+    for (mut t, on_ground, ground_velocity, g, delta) in &mut riders {
+        if !on_ground.0 || ground_velocity.0 == Vec2::ZERO {
+            continue;
+        }
+        let down = g.as_vec2();
+        let carried = ground_velocity.0 - down * ground_velocity.0.dot(down).min(0.0);
+        let step = carried * time_step.period.as_secs_f32();
+        t.translation += step.extend(0.);
+        // part of this tick's movement, so the sweep against the ground includes it
+        if let Some(mut delta) = delta {
+            delta.ray += step;
+        }
+    }
+}
+
 fn apply_acceleration(
     mut q: Query<(&mut Velocity, &Acceleration)>,
     time_step: Res<FixedTime>,
@@ -208,23 +431,26 @@ pub struct GroundRay;
 
 // if all ground rays are not on the ground then the entity should be falling
 fn falling_detection(
-    mut jumpers: Query<
-        (
-            &mut OnGround,
-            &CollisionEvents<CollisionTypes>,
-            &GravityDirection,
-        ),
-        With<JumpState>,
-    >,
+    mut jumpers: Query<(
+        &KinematicBody,
+        &mut OnGround,
+        &mut Velocity,
+        Option<&GroundVelocity>,
+        Option<&mut Momentum>,
+        &CollisionEvents<CollisionTypes>,
+        &GravityDirection,
+    )>,
+    settings: Res<PhysicsSettings>,
 ) {
-    for (mut on_ground, ev, g) in &mut jumpers {
-        if !on_ground.0 {
+    for (body, mut on_ground, mut v, ground_velocity, momentum, ev, g) in &mut jumpers {
+        // bodies without rays are told when they fall
+        if !on_ground.0 || !body.ground_rays {
             continue;
         }
 
         let mut touching_ground = false;
 
-        for event in &ev.buffer {
+        for event in ev.solid() {
             let CollisionData::Ray(ref ray_data) = event.data else { continue; };
             // check if ray points "down" and intersects a ground collision
             if event.user_type == CollisionTypes::Ground
@@ -237,6 +463,61 @@ fn falling_detection(
 
         if !touching_ground {
             on_ground.0 = false;
+            if let Some(ground_velocity) = ground_velocity {
+                let dv = settings.inherit_ground_velocity.scale() * ground_velocity.0;
+                match momentum {
+                    Some(mut momentum) => momentum.push(&mut v, dv),
+                    None => v.0 += dv,
+                }
+            }
+        }
+    }
+}
+
+/// One-way platforms only stop things moving onto their top, the side facing
+/// away from `gravity`, so which side that is turns with the gravity. Anything
+/// sunk in deeper than it fell this tick was already passing through and
+/// isn't caught.
+fn lands_on_one_way(
+    sweep: &Sweep,
+    position: Vec2,
+    velocity: Vec2,
+    gravity: Direction,
+    dt: f32,
+) -> bool {
+    let up = gravity.reverse().as_vec2();
+    let fall_speed = -velocity.dot(up);
+    sweep.normal.angle_between(up) == 0.0
+        && fall_speed > 0.0
+        && (sweep.position - position).dot(up) <= fall_speed * dt + 1.0
+}
+
+/// leaves one-way platforms out of resolution for anything not landing on them
+fn pass_through_one_ways(
+    mut movers: Query<(
+        &mut CollisionEvents<CollisionTypes>,
+        &Transform,
+        &Velocity,
+        &GravityDirection,
+    )>,
+    one_way: Query<(), With<OneWayPlatform>>,
+    time_step: Res<FixedTime>,
+) {
+    let dt = time_step.period.as_secs_f32();
+    for (mut ev, t, v, g) in &mut movers {
+        let passing: Vec<Entity> = ev
+            .buffer
+            .iter()
+            .filter(|event| one_way.contains(event.entity))
+            .filter_map(|event| match event.data {
+                CollisionData::Aabb(ref sweep) => Some((event.entity, sweep)),
+                CollisionData::Ray(_) => None,
+            })
+            .filter(|(_, sweep)| !lands_on_one_way(sweep, t.translation.truncate(), v.0, g.0, dt))
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in passing {
+            ev.ignore(entity);
         }
     }
 }
@@ -244,73 +525,138 @@ fn falling_detection(
 // if all ground rays are not on the ground then the entity should be falling
 pub fn ground_detection(
     mut jumpers: Query<(
+        Entity,
         &mut OnGround,
         &mut Transform,
         &mut Velocity,
         &mut Acceleration,
         Option<&mut JumpState>,
+        Option<&mut GroundSurface>,
+        Option<&mut GroundVelocity>,
+        Option<&mut GroundFriction>,
         &CollisionEvents<CollisionTypes>,
         &GravityDirection,
     )>,
+    surfaces: Query<&SurfaceMaterial>,
+    floors: Query<&PositionDelta>,
+    time_step: Res<FixedTime>,
+    mut landed: EventWriter<Landed>,
 ) {
-    for (mut on_ground, mut t, mut v, mut a, jump_state, ev, g) in &mut jumpers {
+    for (
+        entity,
+        mut on_ground,
+        mut t,
+        mut v,
+        mut a,
+        jump_state,
+        ground_surface,
+        ground_velocity,
+        ground_friction,
+        ev,
+        g,
+    ) in &mut jumpers
+    {
         let mut touching_ground = false;
-        let mut collision: Option<&crate::collisions::Sweep> = None;
-        for event in &ev.buffer {
+        let mut surface = SurfaceMaterial::default();
+        let mut floor_velocity = Vec2::ZERO;
+        let mut friction = 1.0;
+        let fall_speed = g.as_vec2().dot(v.0);
+        let dt = time_step.period.as_secs_f32();
+        let mut collision: Option<(&Sweep, ContactResponse)> = None;
+        for event in ev.solid() {
             // ignore other types of collision other than Aabb collisions
             let CollisionData::Aabb(ref sweep) = event.data else { continue; };
             if let CollisionTypes::Ground = event.user_type {
-                if collision.is_none() || sweep.time < collision.unwrap().time {
-                    collision = Some(sweep);
+                if collision.map_or(true, |(nearest, _)| sweep.time < nearest.time) {
+                    collision = Some((sweep, event.response));
                 }
 
                 // check if ground collision is a "floor"
                 if sweep.normal.angle_between(g.reverse().as_vec2()) == 0.0 {
                     touching_ground = true;
+                    surface = surfaces.get(event.entity).copied().unwrap_or_default();
+                    friction = event.response.friction;
+                    floor_velocity = event.response.surface_velocity;
+                    // only ground that moves, like falling blocks, tracks how far it moved
+                    if let Ok(delta) = floors.get(event.entity) {
+                        floor_velocity += delta.ray / dt;
+                    }
                 }
             }
         }
 
-        if let Some(collision) = collision {
+        let mut bounced_off_floor = false;
+        if let Some((collision, response)) = collision {
             // set position outside of ground
             // note: this would be incorrect if jumper is a child of another transform
             t.translation = (collision.position + collision.normal).extend(t.translation.z);
 
-            // set velocity and acceleration in direction ground to zero
-            if v.0.dot(Vec2::from_angle(PI).rotate(collision.normal)) > 0. {
-                v.0 *= Vec2::from_angle(PI / 2.).rotate(collision.normal).abs();
+            // set velocity in direction ground to zero, or send it back on bouncy ground
+            let into = v.0.dot(collision.normal);
+            if into < 0. {
+                let bounce = -into * response.restitution;
+                v.0 -= collision.normal * into;
+                if bounce >= MIN_BOUNCE_SPEED {
+                    v.0 += collision.normal * bounce;
+                    bounced_off_floor =
+                        collision.normal.angle_between(g.reverse().as_vec2()) == 0.0;
+                }
             }
             if a.0.dot(Vec2::from_angle(PI).rotate(collision.normal)) > 0. {
                 a.0 *= Vec2::from_angle(PI / 2.).rotate(collision.normal).abs();
             }
 
             if let Some(mut jump_state) = jump_state {
-                if Direction::from_vec2(collision.normal).unwrap() == g.0 {
+                if Direction::from_vec2(collision.normal) == Some(g.0) {
                     // skip rotation if we hit a block
                     jump_state.turned_this_jump = true;
                 }
             }
         }
 
-        if touching_ground {
+        // bouncing off the floor leaves the entity in the air
+        if touching_ground && bounced_off_floor {
+            on_ground.0 = false;
+        } else if touching_ground {
+            if !on_ground.0 {
+                landed.send(Landed {
+                    entity,
+                    speed: fall_speed,
+                    surface,
+                });
+            }
             on_ground.0 = true;
+            if let Some(mut ground_surface) = ground_surface {
+                ground_surface.0 = surface;
+            }
+            if let Some(mut ground_velocity) = ground_velocity {
+                ground_velocity.0 = floor_velocity;
+            }
+            if let Some(mut ground_friction) = ground_friction {
+                ground_friction.0 = friction;
+            }
         }
     }
 }
 
 fn rotate_gravity(
     mut movers: Query<(
+        Entity,
         &mut GravityDirection,
         &mut JumpState,
         &mut Acceleration,
         &mut Transform,
         &Velocity,
         &Children,
+        Option<&OutOfFlips>,
     )>,
     mut aabb_colliders: Query<&mut Rect>,
     mut rays: Query<&mut Ray>,
+    zones: Query<(&GravityZone, &ZoneSize, &Transform), Without<JumpState>>,
+    mut rotated: EventWriter<GravityRotated>,
 ) {
-    for (mut g_dir, mut jump_state, mut a, mut t, v, children) in &mut movers {
+    for (entity, mut g_dir, mut jump_state, mut a, mut t, v, children, out_of_flips) in &mut movers
+    {
         let v_speed = g_dir.as_vec2().dot(v.0);
         let current_v_direction = if v_speed > 0.0 {
             g_dir.0
@@ -329,9 +675,10 @@ fn rotate_gravity(
             jump_state.last_horizontal_movement_dir
         };
 
+        let zone = zone_at(&zones, t.translation.truncate());
         if current_v_direction != jump_state.last_vertical_movement_dir
             && current_v_direction == g_dir.0
-            && !jump_state.turned_this_jump
+            && turns_at_apex(zone, jump_state.turned_this_jump, out_of_flips.is_some())
         {
             a.0 = Vec2::ZERO;
             jump_state.turned_this_jump = true;
@@ -342,6 +689,7 @@ fn rotate_gravity(
                 t.rotate_z(PI / 2.);
                 g_dir.0 = g_dir.ccw();
             };
+            rotated.send(GravityRotated { entity });
 
             // rotate colliders
             for child in children {
@@ -362,6 +710,80 @@ fn rotate_gravity(
     }
 }
 
+/// Whether gravity rotates at the apex of a jump. Forced zones always rotate
+/// it, even after a block was hit this jump or the flips have run out, and
+/// locked zones never do.
+fn turns_at_apex(zone: Option<GravityZone>, turned_this_jump: bool, out_of_flips: bool) -> bool {
+    match zone {
+        Some(GravityZone::Forced) => true,
+        Some(GravityZone::Locked) => false,
+        None => !turned_this_jump && !out_of_flips,
+    }
+}
+
+/// point gravity in `direction`, turning the transform and child colliders to match
+pub fn set_gravity_direction(
+    direction: Direction,
+    g_dir: &mut GravityDirection,
+    t: &mut Transform,
+    children: &Children,
+    aabb_colliders: &mut Query<&mut Rect>,
+    rays: &mut Query<&mut Ray>,
+) {
+    if g_dir.0 == direction {
+        return;
+    }
+
+    let swap_axes = g_dir.as_vec2().x.abs() != direction.as_vec2().x.abs();
+    g_dir.0 = direction;
+    t.rotation = Quat::from_rotation_z(direction.angle());
+
+    for child in children {
+        if let Ok(mut rect) = aabb_colliders.get_mut(*child) {
+            if swap_axes {
+                rect.0 = Vec2::new(rect.0.y, rect.0.x);
+            }
+        }
+
+        if let Ok(mut ray) = rays.get_mut(*child) {
+            ray.0 = direction.as_vec2() * ray.0.length();
+        }
+    }
+}
+
+/// resize colliders that were spawned before the settings were last loaded
+fn resize_colliders(
+    settings: Res<PhysicsSettings>,
+    mut rects: Query<(&mut Rect, &SizedCollider, &Parent)>,
+    mut rays: Query<(&mut Transform, &Parent), With<Ray>>,
+    owners: Query<&GravityDirection>,
+    bodies: Query<&KinematicBody>,
+) {
+    for (mut rect, collider, parent) in &mut rects {
+        let size = settings.colliders.get(*collider);
+        // rects are axis aligned, so they're stored swapped while gravity is sideways
+        let sideways = owners
+            .get(parent.get())
+            .is_ok_and(|g_dir| g_dir.as_vec2().x != 0.0);
+        rect.0 = if sideways {
+            Vec2::new(size.y, size.x)
+        } else {
+            size
+        };
+
+        let has_rays = bodies.get(parent.get()).is_ok_and(|body| body.ground_rays);
+        if has_rays {
+            // ground rays sit on the bottom corners of the hit box
+            for (mut t, ray_parent) in &mut rays {
+                if ray_parent.get() == parent.get() {
+                    t.translation.x = t.translation.x.signum() * size.x / 2.;
+                    t.translation.y = -size.y / 2.;
+                }
+            }
+        }
+    }
+}
+
 fn load_physics(mut commands: Commands, asset_server: Res<AssetServer>) {
     let handle = asset_server.load("settings.physics.ron");
     commands.insert_resource(PhysicsSettingsHandle(handle));
@@ -382,3 +804,125 @@ fn monitor_physics_changes(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_way_top_turns_with_gravity() {
+        let dt = 1.0 / 60.0;
+        // landing on the top side while falling right
+        let sweep = Sweep {
+            position: Vec2::new(-2.0, 0.0),
+            time: 0.5,
+            normal: Vec2::NEG_X,
+        };
+        let falling_right = Vec2::new(300.0, 0.0);
+        assert!(lands_on_one_way(
+            &sweep,
+            Vec2::ZERO,
+            falling_right,
+            Direction::Right,
+            dt
+        ));
+        // the same contact with world down gravity is a side and passes through
+        assert!(!lands_on_one_way(
+            &sweep,
+            Vec2::ZERO,
+            falling_right,
+            Direction::Down,
+            dt
+        ));
+        // jumping up through it
+        assert!(!lands_on_one_way(
+            &sweep,
+            Vec2::ZERO,
+            -falling_right,
+            Direction::Right,
+            dt
+        ));
+        // already halfway through
+        assert!(!lands_on_one_way(
+            &sweep,
+            Vec2::new(12.0, 0.0),
+            falling_right,
+            Direction::Right,
+            dt
+        ));
+    }
+
+    mod rotate_gravity {
+        use super::*;
+
+        /// a mover just past the apex of a jump, falling and moving forward
+        fn world_at_apex(zone: Option<GravityZone>, turned_this_jump: bool) -> (World, Entity) {
+            let mut world = World::new();
+            world.init_resource::<Events<GravityRotated>>();
+            if let Some(zone) = zone {
+                world.spawn((zone, ZoneSize(Vec2::splat(100.0)), Transform::default()));
+            }
+            let mover = world
+                .spawn((
+                    GravityDirection(Direction::Down),
+                    JumpState {
+                        turned_this_jump,
+                        last_horizontal_movement_dir: Direction::Left,
+                        last_vertical_movement_dir: Direction::Up,
+                    },
+                    Acceleration::default(),
+                    Transform::default(),
+                    Velocity(Vec2::new(-10.0, -10.0)),
+                ))
+                .with_children(|children| {
+                    children.spawn(Rect(Vec2::new(10.0, 20.0)));
+                })
+                .id();
+            (world, mover)
+        }
+
+        fn run(world: &mut World, mover: Entity) -> Direction {
+            let mut schedule = Schedule::new();
+            schedule.add_system(crate::physics::rotate_gravity);
+            schedule.run(world);
+            world.get::<GravityDirection>(mover).unwrap().0
+        }
+
+        #[test]
+        fn turns_forward_outside_zones() {
+            let (mut world, mover) = world_at_apex(None, false);
+            assert_eq!(run(&mut world, mover), Direction::Left);
+            assert_eq!(world.resource::<Events<GravityRotated>>().len(), 1);
+        }
+
+        #[test]
+        fn out_of_flips_stops_it_outside_zones() {
+            let (mut world, mover) = world_at_apex(None, false);
+            world.entity_mut(mover).insert(OutOfFlips);
+            assert_eq!(run(&mut world, mover), Direction::Down);
+        }
+
+        #[test]
+        fn locked_zones_never_turn() {
+            let (mut world, mover) = world_at_apex(Some(GravityZone::Locked), false);
+            assert_eq!(run(&mut world, mover), Direction::Down);
+            assert!(world.resource::<Events<GravityRotated>>().is_empty());
+        }
+
+        #[test]
+        fn forced_zones_turn_after_hitting_a_block() {
+            let (mut world, mover) = world_at_apex(Some(GravityZone::Forced), true);
+            assert_eq!(run(&mut world, mover), Direction::Left);
+        }
+
+        #[test]
+        fn forced_zones_turn_when_out_of_flips() {
+            let (mut world, mover) = world_at_apex(Some(GravityZone::Forced), true);
+            world.entity_mut(mover).insert(OutOfFlips);
+            assert_eq!(run(&mut world, mover), Direction::Left);
+            // the collider is turned with it
+            let child = world.get::<Children>(mover).unwrap()[0];
+            assert_eq!(world.get::<Rect>(child).unwrap().0, Vec2::new(20.0, 10.0));
+        }
+    }
+}
@@ -3,14 +3,30 @@ use bevy_ecs_ldtk::{prelude::LdtkEntityAppExt, LdtkEntity, LdtkLevel, Respawn};
 use leafwing_input_manager::{prelude::*, user_input::InputKind};
 
 use crate::{
-    collisions::{CollisionEvents, PositionDelta, RayBundle, RectBundle},
-    constants::{CollisionTypes, PLAYER_DIM},
+    bounds::LevelBounds,
+    character_controller::{character_body, KinematicBodyBundle},
+    checkpoints::{ActiveCheckpoint, CheckpointRespawn},
+    config::{AccessibilitySettings, AssistConfig, InputFrame},
+    constants::CollisionTypes,
+    fixed_input::{BufferInputs, FixedActions, FixedInputPlugin},
     game_state::GameState,
+    ground::ICE_FRICTION,
+    input_latch::{InputLatchPlugin, LatchInputs},
+    keyboard_layout::{self, WasdKey},
+    mutators::Mutators,
+    orientation::GravityFacing,
     physics::{
-        Acceleration, Direction, Gravity, GravityDirection, JumpState, OnGround, PhysicsSettings,
-        Velocity,
+        Direction, Gravity, GravityDirection, GroundFriction, GroundVelocity, JumpState, Landed,
+        Momentum, OnGround, PhysicsSet, PhysicsSettings, Velocity,
     },
-    sfx::SfxHandles,
+    player_animation::PlayerAnimation,
+    render_layers::RenderLayer,
+    safe_position::{SafePositions, SoftRespawn},
+    save::SaveData,
+    settings::{KeyBindings, PlayerSettings},
+    stuck::StuckTicks,
+    swings::Swinging,
+    wrapping::ActiveWrap,
 };
 
 pub struct PlayerPlugin;
@@ -18,30 +34,56 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<JumpAction>::default())
             .add_plugin(InputManagerPlugin::<MovementAction>::default())
+            // movement is left alone, keeping a direction held into the next
+            // level is harmless
+            .add_plugin(InputLatchPlugin::<JumpAction>::new())
+            // jumps happen in fixed ticks, which a quick tap can fall between
+            .add_plugin(FixedInputPlugin::<JumpAction>::new())
             .configure_set(
                 InputProcessing
                     .after(CoreSet::PreUpdateFlush)
                     .before(CoreSet::FixedUpdate),
             )
+            .configure_set(
+                LatchInputs
+                    .in_base_set(InputProcessing)
+                    .before(BufferInputs)
+                    .before(control_movement),
+            )
+            .configure_set(BufferInputs.in_base_set(InputProcessing))
+            .add_event::<PlayerDeath>()
+            .add_event::<PlayerDied>()
+            .add_event::<PlayerJumped>()
             .add_system(after_player_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
-            .add_systems((control_jump, control_movement).in_base_set(InputProcessing))
-            .add_systems((sprite_orientation, player_dies).in_set(GameState::Playing))
-            .add_startup_system(load_player_handle)
+            .add_system(
+                control_jump
+                    .before(PhysicsSet)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (landing_lag, control_movement)
+                    .chain()
+                    .in_base_set(InputProcessing),
+            )
+            .add_systems((out_of_bounds, blink_invulnerable).in_set(GameState::Playing))
+            .add_system(handle_player_death.in_base_set(CoreSet::PostUpdate))
             .register_ldtk_entity::<PlayerBundle>("Spawn_Point");
     }
 }
 
 #[derive(SystemSet, Hash, Eq, PartialEq, Clone, Default, Debug)]
 #[system_set(base)]
-struct InputProcessing;
+pub struct InputProcessing;
 
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
-enum JumpAction {
+pub enum JumpAction {
     Jump,
+    /// hold on to a swing point in reach, see `SwingPlugin`
+    Grab,
 }
 
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
-enum MovementAction {
+pub enum MovementAction {
     Left,
     Right,
     Up,
@@ -51,104 +93,158 @@ enum MovementAction {
 #[derive(Component, Default)]
 pub struct Player;
 
-#[derive(Resource)]
-pub struct PlayerSprite {
-    pub handle: Handle<Image>,
+/// what killed the player
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    OutOfBounds,
+    FragileGoals,
+    Enemy,
+    Hazard,
+}
+
+impl DeathCause {
+    /// Whether this kills the player even while they're [`Invulnerable`].
+    /// Hazards, enemies and crushing should return false here.
+    fn ignores_invulnerability(&self) -> bool {
+        match self {
+            DeathCause::OutOfBounds | DeathCause::FragileGoals => true,
+            DeathCause::Enemy | DeathCause::Hazard => false,
+        }
+    }
+}
+
+/// the player can't be hurt while this is running, added on every spawn
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+/// Sent by anything that can kill the player. Only [`handle_player_death`]
+/// decides what happens next.
+pub struct PlayerDeath {
+    pub cause: DeathCause,
 }
 
+/// sent by [`handle_player_death`] once the player has died and is being respawned
+pub struct PlayerDied;
+
+/// sent when the player leaves the ground by jumping
+pub struct PlayerJumped;
+
+/// seconds left of damped movement after a hard landing
+#[derive(Component, Default)]
+pub struct LandingLag(f32);
+
+/// how far outside the level the player can fall before dying
+const KILL_MARGIN: f32 = 100.0;
+/// how long the player can't be hurt after respawning
+pub const INVULNERABLE_SECONDS: f32 = 1.5;
+/// how many times a second the sprite blinks while invulnerable
+const BLINK_RATE: f32 = 10.0;
+/// fraction of the normal walking speed during landing lag
+const LANDING_LAG_SPEED_SCALE: f32 = 0.3;
+
 #[derive(Bundle, LdtkEntity)]
 pub struct PlayerBundle {
     player: Player,
     #[sprite_bundle("pixel-cat.png")]
     sprite: SpriteBundle,
-    velocity: Velocity,
-    acceleration: Acceleration,
-    g_dir: GravityDirection,
-    gravity: Gravity,
-    on_ground: OnGround,
+    #[with(character_body)]
+    body: KinematicBodyBundle,
+    landing_lag: LandingLag,
     jump_state: JumpState,
+    facing: GravityFacing,
+    stuck_ticks: StuckTicks,
+    safe_positions: SafePositions,
+    animation: PlayerAnimation,
 }
 
-fn after_player_spawned(mut commands: Commands, q: Query<(Entity, &Transform), Added<Player>>) {
-    for (e, t) in &q {
-        commands
-            .entity(e)
-            .insert((
-                InputManagerBundle::<JumpAction> {
-                    action_state: ActionState::default(),
-                    input_map: InputMap::new([
-                        (InputKind::Keyboard(KeyCode::Space), JumpAction::Jump),
-                        (
-                            InputKind::GamepadButton(GamepadButtonType::South),
-                            JumpAction::Jump,
-                        ),
-                    ]),
-                },
-                InputManagerBundle::<MovementAction> {
-                    action_state: ActionState::default(),
-                    input_map: InputMap::new([
-                        // wasd
-                        (InputKind::Keyboard(KeyCode::A), MovementAction::Left),
-                        (InputKind::Keyboard(KeyCode::D), MovementAction::Right),
-                        (InputKind::Keyboard(KeyCode::W), MovementAction::Up),
-                        (InputKind::Keyboard(KeyCode::S), MovementAction::Down),
-                        // arrow keys
-                        (InputKind::Keyboard(KeyCode::Left), MovementAction::Left),
-                        (InputKind::Keyboard(KeyCode::Right), MovementAction::Right),
-                        (InputKind::Keyboard(KeyCode::Up), MovementAction::Up),
-                        (InputKind::Keyboard(KeyCode::Down), MovementAction::Down),
-                        // game pad
-                        (
-                            InputKind::GamepadButton(GamepadButtonType::DPadLeft),
-                            MovementAction::Left,
-                        ),
-                        (
-                            InputKind::GamepadButton(GamepadButtonType::DPadRight),
-                            MovementAction::Right,
-                        ),
-                        (
-                            InputKind::GamepadButton(GamepadButtonType::DPadUp),
-                            MovementAction::Up,
-                        ),
-                        (
-                            InputKind::GamepadButton(GamepadButtonType::DPadDown),
-                            MovementAction::Down,
-                        ),
-                    ]),
-                },
-                CollisionTypes::Player,
-                CollisionEvents::<CollisionTypes>::new(),
-                PositionDelta {
-                    origin: t.translation.truncate(),
-                    ray: Vec2::ZERO,
-                },
-            ))
-            .with_children(|children| {
-                // spawn some ray colliders
-                const RAY_LENGTH: f32 = 15.0;
-                // point down
-                children.spawn(RayBundle::new(
-                    Direction::Down.as_vec2() * RAY_LENGTH,
-                    Vec2::new(-PLAYER_DIM.x / 2., -PLAYER_DIM.y / 2.),
-                ));
-                children.spawn(RayBundle::new(
-                    Direction::Down.as_vec2() * RAY_LENGTH,
-                    Vec2::new(PLAYER_DIM.x / 2., -PLAYER_DIM.y / 2.),
-                ));
-
-                // spawn hit box used for player collisions with wall and goals
-                children.spawn(RectBundle::new(PLAYER_DIM));
-            });
+fn after_player_spawned(
+    mut commands: Commands,
+    q: Query<Entity, Added<Player>>,
+    player_settings: Res<PlayerSettings>,
+) {
+    for e in &q {
+        commands.entity(e).insert((
+            InputManagerBundle::<JumpAction> {
+                action_state: ActionState::default(),
+                input_map: jump_input_map(&player_settings.bindings),
+            },
+            InputManagerBundle::<MovementAction> {
+                action_state: ActionState::default(),
+                input_map: movement_input_map(&player_settings.bindings),
+            },
+            CollisionTypes::Player,
+            RenderLayer::Player,
+            Invulnerable(Timer::from_seconds(INVULNERABLE_SECONDS, TimerMode::Once)),
+        ));
     }
 }
 
-fn load_player_handle(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(PlayerSprite {
-        handle: asset_server.load("pixel-cat.png"),
-    });
+/// Space and the south button jump, `bindings.jump` replaces Space. E and
+/// the west button grab.
+pub fn jump_input_map(bindings: &KeyBindings) -> InputMap<JumpAction> {
+    let key = bindings.jump.unwrap_or(KeyCode::Space);
+    InputMap::new([
+        (InputKind::Keyboard(key), JumpAction::Jump),
+        (
+            InputKind::GamepadButton(GamepadButtonType::South),
+            JumpAction::Jump,
+        ),
+        (InputKind::Keyboard(KeyCode::E), JumpAction::Grab),
+        (
+            InputKind::GamepadButton(GamepadButtonType::West),
+            JumpAction::Grab,
+        ),
+    ])
+}
+
+/// Wasd, the arrow keys and the d-pad move. A key in `bindings` replaces
+/// the wasd key for that direction, the arrows always work.
+pub fn movement_input_map(bindings: &KeyBindings) -> InputMap<MovementAction> {
+    // wasd, bound by position where the platform allows it
+    let key = |binding: Option<KeyCode>, wasd: WasdKey| match binding {
+        Some(key) => InputKind::Keyboard(key),
+        None => wasd.input(),
+    };
+    InputMap::new([
+        (
+            key(bindings.left, keyboard_layout::LEFT),
+            MovementAction::Left,
+        ),
+        (
+            key(bindings.right, keyboard_layout::RIGHT),
+            MovementAction::Right,
+        ),
+        (key(bindings.up, keyboard_layout::UP), MovementAction::Up),
+        (
+            key(bindings.down, keyboard_layout::DOWN),
+            MovementAction::Down,
+        ),
+        // arrow keys
+        (InputKind::Keyboard(KeyCode::Left), MovementAction::Left),
+        (InputKind::Keyboard(KeyCode::Right), MovementAction::Right),
+        (InputKind::Keyboard(KeyCode::Up), MovementAction::Up),
+        (InputKind::Keyboard(KeyCode::Down), MovementAction::Down),
+        // game pad
+        (
+            InputKind::GamepadButton(GamepadButtonType::DPadLeft),
+            MovementAction::Left,
+        ),
+        (
+            InputKind::GamepadButton(GamepadButtonType::DPadRight),
+            MovementAction::Right,
+        ),
+        (
+            InputKind::GamepadButton(GamepadButtonType::DPadUp),
+            MovementAction::Up,
+        ),
+        (
+            InputKind::GamepadButton(GamepadButtonType::DPadDown),
+            MovementAction::Down,
+        ),
+    ])
 }
 
-fn control_jump(
+pub fn control_jump(
     mut q: Query<(
         &mut Velocity,
         &mut OnGround,
@@ -156,20 +252,41 @@ fn control_jump(
         &mut Gravity,
         &GravityDirection,
         &ActionState<JumpAction>,
+        &FixedActions<JumpAction>,
+        Option<&GroundVelocity>,
+        Option<&mut Momentum>,
+        Option<&Player>,
     )>,
     settings: Res<PhysicsSettings>,
-    audio: Res<Audio>,
-    sfx: Res<SfxHandles>,
+    mut jumped: EventWriter<PlayerJumped>,
 ) {
-    for (mut v, mut on_ground, mut jump_state, mut g, g_dir, action_state) in q.iter_mut() {
-        if action_state.just_pressed(JumpAction::Jump) {
-            if !on_ground.0 {
-                return;
-            }
+    for (
+        mut v,
+        mut on_ground,
+        mut jump_state,
+        mut g,
+        g_dir,
+        action_state,
+        fixed_actions,
+        ground_velocity,
+        momentum,
+        player,
+    ) in q.iter_mut()
+    {
+        if fixed_actions.just_pressed(JumpAction::Jump) && on_ground.0 {
             v.0 -= settings.initial_jump_speed * g_dir.as_vec2();
+            if let Some(ground_velocity) = ground_velocity {
+                let dv = settings.inherit_ground_velocity.scale() * ground_velocity.0;
+                match momentum {
+                    Some(mut momentum) => momentum.push(&mut v, dv),
+                    None => v.0 += dv,
+                }
+            }
             on_ground.0 = false;
             jump_state.turned_this_jump = false;
-            audio.play(sfx.jump.clone());
+            if player.is_some() {
+                jumped.send(PlayerJumped);
+            }
         }
 
         g.0 = if action_state.pressed(JumpAction::Jump) {
@@ -180,15 +297,52 @@ fn control_jump(
     }
 }
 
-fn control_movement(
-    mut q: Query<(
-        &mut Velocity,
-        &ActionState<MovementAction>,
-        &GravityDirection,
-    )>,
+/// Starts the lag when the player lands faster than `landing_lag_speed`.
+/// Uses the game time so slowed down time makes it last longer too.
+fn landing_lag(
+    time: Res<Time>,
     settings: Res<PhysicsSettings>,
+    assist: Res<AssistConfig>,
+    mut landed: EventReader<Landed>,
+    mut q: Query<&mut LandingLag>,
 ) {
-    for (mut v, action, dir) in &mut q {
+    for mut lag in &mut q {
+        lag.0 = (lag.0 - time.delta_seconds()).max(0.0);
+    }
+    for event in &mut landed {
+        if !assist.landing_lag || event.speed < settings.landing_lag_speed {
+            continue;
+        }
+        if let Ok(mut lag) = q.get_mut(event.entity) {
+            lag.0 = settings.landing_lag_seconds;
+        }
+    }
+}
+
+pub fn control_movement(
+    mut q: Query<
+        (
+            &mut Velocity,
+            &ActionState<MovementAction>,
+            &GravityDirection,
+            &OnGround,
+            Option<&LandingLag>,
+            Option<&GroundFriction>,
+            Option<&mut Momentum>,
+        ),
+        Without<Swinging>,
+    >,
+    settings: Res<PhysicsSettings>,
+    mutators: Res<Mutators>,
+    assist: Res<AssistConfig>,
+    time: Res<Time>,
+) {
+    // how quickly horizontal speed changes with no grip, scaled by friction
+    const SLIDE_ACCELERATION: f32 = 6000.0;
+    /// how quickly momentum fades in the air, per second
+    const MOMENTUM_DRAG: f32 = 2.0;
+
+    for (mut v, action, dir, on_ground, lag, friction, momentum) in &mut q {
         let mut temp_v = Vec2::ZERO;
         if action.pressed(MovementAction::Down) {
             temp_v.y -= 1.0;
@@ -202,49 +356,301 @@ fn control_movement(
         if action.pressed(MovementAction::Right) {
             temp_v.x += 1.0;
         }
+        if mutators.mirrored {
+            temp_v.x = -temp_v.x;
+        }
+        let temp_v = movement_input(temp_v, dir.0, assist.input_frame, assist.rotate_camera);
 
-        let val = dir.forward().as_vec2().dot(temp_v);
-        if val != 0.0 {
-            v.0 = v.0 * dir.as_vec2().abs()
-                + (dir.forward().as_vec2() * val).normalize() * settings.horizontal_speed;
+        let forward = dir.forward().as_vec2();
+        let val = forward.dot(temp_v);
+        let lag_scale = match lag {
+            Some(lag) if lag.0 > 0.0 => LANDING_LAG_SPEED_SCALE,
+            _ => 1.0,
+        };
+        let target_speed = if val != 0.0 {
+            val.signum() * settings.horizontal_speed * lag_scale
         } else {
-            v.0 *= dir.as_vec2().abs();
+            0.0
+        };
+        let mut friction = friction.map_or(1.0, |friction| friction.0);
+        if mutators.icy_floors {
+            friction = friction.min(ICE_FRICTION);
         }
+        let mut current = forward.dot(v.0);
+        let mut carried = 0.0;
+        if let Some(mut momentum) = momentum {
+            if on_ground.0 {
+                // the floor takes over, what's left slides off on ice
+                momentum.0 = Vec2::ZERO;
+            } else {
+                current -= forward.dot(momentum.0);
+                momentum.0 *= (-MOMENTUM_DRAG * time.delta_seconds()).exp();
+                carried = forward.dot(momentum.0);
+            }
+        }
+        // full grip changes speed right away
+        let speed = if friction < 1.0 {
+            let step = friction * SLIDE_ACCELERATION * time.delta_seconds();
+            current + (target_speed - current).clamp(-step, step)
+        } else {
+            target_speed
+        };
+        v.0 = v.0 * dir.as_vec2().abs() + forward * with_momentum(speed, carried);
     }
 }
 
-fn sprite_orientation(
-    mut player: Query<(&mut Sprite, &Velocity, &GravityDirection), With<Player>>,
-) {
-    for (mut s, v, g) in &mut player {
-        let forward_speed = g.forward().as_vec2().dot(v.0);
-        if forward_speed > 0. {
-            s.flip_x = false;
-        } else if forward_speed < 0. {
-            s.flip_x = true;
+/// Speed along the floor walking at `walk` while carried along at `carried`.
+/// Walking with the momentum can't add to it past walking speed, walking
+/// against it slows it down.
+fn with_momentum(walk: f32, carried: f32) -> f32 {
+    if walk * carried > 0.0 {
+        if walk.abs() > carried.abs() {
+            walk
+        } else {
+            carried
         }
+    } else {
+        walk + carried
     }
 }
 
-fn player_dies(
+/// Turn the pressed directions into a world direction. The screen is turned
+/// to match gravity with `rotate_camera`, so then both frames are the same.
+fn movement_input(input: Vec2, gravity: Direction, frame: InputFrame, rotate_camera: bool) -> Vec2 {
+    if frame == InputFrame::Gravity || rotate_camera {
+        Vec2::from_angle(gravity.angle()).rotate(input)
+    } else {
+        input
+    }
+}
+
+/// wrapping levels send the player back in instead, so only their other axis can kill
+fn out_of_bounds(
     player: Query<&Transform, With<Player>>,
+    bounds: Res<LevelBounds>,
+    wrap: Res<ActiveWrap>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    for t in &player {
+        if !wrap.contains(&bounds, t.translation.truncate(), KILL_MARGIN) {
+            deaths.send(PlayerDeath {
+                cause: DeathCause::OutOfBounds,
+            });
+        }
+    }
+}
+
+/// record the death and restart the level, or go back to the last checkpoint
+pub fn handle_player_death(
     mut commands: Commands,
-    audio: Res<Audio>,
-    sfx: Res<SfxHandles>,
+    mut deaths: EventReader<PlayerDeath>,
+    invulnerable: Query<(), (With<Player>, With<Invulnerable>)>,
     level: Query<Entity, With<Handle<LdtkLevel>>>,
     mut state: ResMut<NextState<GameState>>,
+    mut save: ResMut<SaveData>,
+    assist: Res<AssistConfig>,
+    safe_positions: Query<&SafePositions>,
+    mut soft_respawn: EventWriter<SoftRespawn>,
+    checkpoint: Res<ActiveCheckpoint>,
+    mut checkpoint_respawn: EventWriter<CheckpointRespawn>,
+    mut died: EventWriter<PlayerDied>,
 ) {
-    for t in &player {
-        if t.translation.y < -100.
-            || t.translation.y > 800.
-            || t.translation.x > 800.
-            || t.translation.x < -100.
-        {
-            audio.play(sfx.death.clone());
-            for e in &level {
-                commands.entity(e).insert(Respawn);
+    // more than one source can kill the player on the same frame, only die once
+    let is_invulnerable = !invulnerable.is_empty();
+    let Some(cause) = deaths
+        .iter()
+        .map(|death| death.cause)
+        .filter(|cause| !is_invulnerable || cause.ignores_invulnerability())
+        .last()
+    else {
+        return;
+    };
+
+    // falling off a ledge only puts the player back on solid ground with assist on
+    if assist.soft_respawn
+        && cause == DeathCause::OutOfBounds
+        && safe_positions.iter().any(|safe| !safe.is_empty())
+    {
+        soft_respawn.send(SoftRespawn);
+        return;
+    }
+
+    info!("player died: {cause:?}");
+    save.deaths += 1;
+    died.send(PlayerDied);
+    if checkpoint.is_reached() {
+        checkpoint_respawn.send(CheckpointRespawn);
+        return;
+    }
+    for e in &level {
+        commands.entity(e).insert(Respawn);
+    }
+    state.set(GameState::SpawnLevel);
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    time: Res<Time>,
+    accessibility: Res<AccessibilitySettings>,
+    mut q: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+) {
+    for (e, mut invulnerable, mut visibility) in &mut q {
+        if invulnerable.0.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(e).remove::<Invulnerable>();
+            continue;
+        }
+
+        // flashing is left out with reduced motion, the player just stays visible
+        let blink = !accessibility.reduced_motion
+            && (invulnerable.0.elapsed_secs() * BLINK_RATE) as u32 % 2 == 1;
+        *visibility = if blink {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // tests for `movement_input`
+    mod movement_input {
+        use bevy::prelude::Vec2;
+
+        use crate::{
+            config::InputFrame,
+            physics::{Direction, GravityDirection},
+            player::movement_input,
+        };
+
+        /// speed along the floor, -1, 0 or 1
+        fn along_floor(input: Vec2, gravity: Direction, frame: InputFrame) -> f32 {
+            let world = movement_input(input, gravity, frame, false);
+            let speed = gravity_forward(gravity).dot(world);
+            if speed.abs() < 0.5 {
+                0.0
+            } else {
+                speed.signum()
+            }
+        }
+
+        fn gravity_forward(gravity: Direction) -> Vec2 {
+            GravityDirection(gravity).forward().as_vec2()
+        }
+
+        #[test]
+        fn frames_match_on_the_floor() {
+            for frame in [InputFrame::Screen, InputFrame::Gravity] {
+                assert_eq!(along_floor(Vec2::X, Direction::Down, frame), -1.0);
+                assert_eq!(along_floor(Vec2::NEG_X, Direction::Down, frame), 1.0);
+                assert_eq!(along_floor(Vec2::Y, Direction::Down, frame), 0.0);
+            }
+        }
+
+        #[test]
+        fn screen_frame_uses_up_and_down_on_walls() {
+            let frame = InputFrame::Screen;
+            // on the left wall forward is up the screen, on the right wall it's down
+            for (gravity, up) in [(Direction::Left, 1.0), (Direction::Right, -1.0)] {
+                assert_eq!(along_floor(Vec2::Y, gravity, frame), up);
+                assert_eq!(along_floor(Vec2::NEG_Y, gravity, frame), -up);
+                assert_eq!(along_floor(Vec2::X, gravity, frame), 0.0);
+            }
+        }
+
+        #[test]
+        fn gravity_frame_uses_left_and_right_on_walls() {
+            let frame = InputFrame::Gravity;
+            // right always walks against forward, like it does on the floor
+            for gravity in [Direction::Left, Direction::Right, Direction::Up] {
+                assert_eq!(along_floor(Vec2::X, gravity, frame), -1.0);
+                assert_eq!(along_floor(Vec2::NEG_X, gravity, frame), 1.0);
+                assert_eq!(along_floor(Vec2::Y, gravity, frame), 0.0);
+            }
+        }
+
+        #[test]
+        fn gravity_frame_keeps_right_as_the_players_right() {
+            // the player is drawn turned to match gravity, so their right and
+            // up are the screen's turned the same way
+            for (gravity, right, up) in [
+                (Direction::Down, Vec2::X, Vec2::Y),
+                (Direction::Right, Vec2::Y, Vec2::NEG_X),
+                (Direction::Up, Vec2::NEG_X, Vec2::NEG_Y),
+                (Direction::Left, Vec2::NEG_Y, Vec2::X),
+            ] {
+                let world = |input| movement_input(input, gravity, InputFrame::Gravity, false);
+                assert!(world(Vec2::X).distance(right) < 1e-5, "{gravity:?}");
+                assert!(world(Vec2::Y).distance(up) < 1e-5, "{gravity:?}");
+            }
+        }
+
+        #[test]
+        fn turned_camera_makes_the_screen_frame_follow_gravity() {
+            for gravity in [
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+                Direction::Up,
+            ] {
+                let screen = movement_input(Vec2::X, gravity, InputFrame::Screen, true);
+                let gravity_frame = movement_input(Vec2::X, gravity, InputFrame::Gravity, true);
+                assert_eq!(screen, gravity_frame);
+            }
+        }
+    }
+
+    // tests for `with_momentum`
+    mod with_momentum {
+        use crate::player::with_momentum;
+
+        #[test]
+        fn walking_along_keeps_the_faster_speed() {
+            assert_eq!(with_momentum(200.0, 500.0), 500.0);
+            assert_eq!(with_momentum(-200.0, -50.0), -200.0);
+        }
+
+        #[test]
+        fn walking_against_slows_down() {
+            assert_eq!(with_momentum(-200.0, 500.0), 300.0);
+            assert_eq!(with_momentum(0.0, 500.0), 500.0);
+        }
+    }
+
+    // tests for the input maps built from the key bindings
+    mod input_maps {
+        use bevy::prelude::KeyCode;
+
+        use crate::{
+            keyboard_layout::KeyLayout,
+            player::{jump_input_map, movement_input_map, JumpAction},
+            settings::{Bindable, KeyBindings},
+        };
+
+        #[test]
+        fn rebound_movement_keys_dont_overlap_jump() {
+            let layout = KeyLayout::default();
+            let mut bindings = KeyBindings::default();
+            assert!(bindings
+                .rebind(Bindable::Up, KeyCode::Space, &layout)
+                .is_err());
+            bindings
+                .rebind(Bindable::Jump, KeyCode::J, &layout)
+                .unwrap();
+            assert!(bindings
+                .rebind(Bindable::Down, KeyCode::J, &layout)
+                .is_err());
+            let jump_map = jump_input_map(&bindings);
+            let jump = jump_map.get(JumpAction::Jump);
+            for (inputs, action) in movement_input_map(&bindings).iter() {
+                for input in inputs.iter() {
+                    assert!(
+                        !jump.contains(input),
+                        "{action:?} shares {input:?} with jump"
+                    );
+                }
             }
-            state.set(GameState::SpawnLevel);
         }
     }
 }
@@ -0,0 +1,331 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    config::AccessibilitySettings,
+    game_state::GameState,
+    physics::{GravityDirection, Landed, OnGround, Velocity},
+    player::{JumpAction, MovementAction, PlayerJumped},
+};
+
+/// size of pixel-cat.png, the sprite is drawn at this size when not animating
+const SPRITE_SIZE: Vec2 = Vec2::new(30.0, 30.0);
+/// shortest and longest time the cat sits still before doing something
+const IDLE_SECONDS: (f32, f32) = (4.0, 8.0);
+/// shortest and longest time between blinks
+const BLINK_INTERVAL: (f32, f32) = (2.0, 5.0);
+const BLINK_SECONDS: f32 = 0.12;
+/// how far the sprite stretches along gravity when jumping
+const JUMP_STRETCH: f32 = 0.25;
+/// how far the sprite squashes along gravity landing at `LAND_SPEED`
+const LAND_SQUASH: f32 = 0.3;
+const LAND_SPEED: f32 = 600.0;
+/// how quickly the squash settles and how fast it wobbles on the way
+const SQUASH_DAMPING: f32 = 8.0;
+const SQUASH_FREQUENCY: f32 = 25.0;
+
+pub struct PlayerAnimationPlugin;
+impl Plugin for PlayerAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (squash_and_stretch, animate_player)
+                .chain()
+                .in_set(GameState::Playing),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AnimState {
+    Idle,
+    Run,
+    Jump,
+    Fall,
+    Land,
+    /// bob up and down licking a paw
+    Groom,
+    /// flick back and forth a few times
+    TailFlick,
+}
+
+/// what a state looks like while it plays
+struct Clip {
+    /// how long the clip runs, `None` loops forever
+    seconds: Option<f32>,
+    /// sprite scale at a time into the clip
+    scale: fn(f32) -> Vec2,
+    /// flip the sprite back and forth this often
+    flick_interval: Option<f32>,
+}
+
+fn still(_: f32) -> Vec2 {
+    Vec2::ONE
+}
+
+fn clip(state: AnimState) -> Clip {
+    match state {
+        AnimState::Idle | AnimState::Jump | AnimState::Fall => Clip {
+            seconds: None,
+            scale: still,
+            flick_interval: None,
+        },
+        AnimState::Run => Clip {
+            seconds: None,
+            scale: |t| Vec2::new(1.0, 1.0 - 0.04 * (t * PI * 6.0).sin().abs()),
+            flick_interval: None,
+        },
+        AnimState::Land => Clip {
+            seconds: Some(0.1),
+            scale: still,
+            flick_interval: None,
+        },
+        AnimState::Groom => Clip {
+            seconds: Some(1.6),
+            scale: |t| {
+                let squash = 0.06 * (t * PI * 4.0).sin().abs();
+                Vec2::new(1.0 + squash, 1.0 - squash)
+            },
+            flick_interval: None,
+        },
+        AnimState::TailFlick => Clip {
+            seconds: Some(0.6),
+            scale: still,
+            flick_interval: Some(0.15),
+        },
+    }
+}
+
+/// what transition conditions get to look at
+struct AnimContext {
+    /// speed along the floor
+    forward_speed: f32,
+    /// speed along gravity, positive when falling
+    fall_speed: f32,
+    on_ground: bool,
+    /// whether any action is pressed
+    input: bool,
+    time_in_state: f32,
+    clip_finished: bool,
+    /// how long to sit still before an idle variant, rolled on entering a state
+    idle_after: f32,
+    /// random number in 0..1, rolled on entering a state
+    roll: f32,
+}
+
+struct Transition {
+    /// states this applies in, empty for every state
+    from: &'static [AnimState],
+    to: AnimState,
+    when: fn(&AnimContext) -> bool,
+}
+
+/// Checked in order every frame, the first one that matches is taken.
+/// New animations should only need a state, a clip and some rows here.
+fn player_transitions() -> Vec<Transition> {
+    use AnimState::*;
+    vec![
+        Transition {
+            from: &[Idle, Run, Land, Groom, TailFlick, Fall],
+            to: Jump,
+            when: |c| !c.on_ground && c.fall_speed < 0.0,
+        },
+        Transition {
+            from: &[Idle, Run, Land, Groom, TailFlick, Jump],
+            to: Fall,
+            when: |c| !c.on_ground && c.fall_speed >= 0.0,
+        },
+        Transition {
+            from: &[Jump, Fall],
+            to: Land,
+            when: |c| c.on_ground,
+        },
+        Transition {
+            from: &[Land, Idle, Groom, TailFlick],
+            to: Run,
+            when: |c| c.forward_speed != 0.0 && c.clip_finished,
+        },
+        Transition {
+            from: &[Groom, TailFlick],
+            to: Idle,
+            when: |c| c.input || c.clip_finished,
+        },
+        Transition {
+            from: &[Land, Run],
+            to: Idle,
+            when: |c| c.forward_speed == 0.0 && c.clip_finished,
+        },
+        Transition {
+            from: &[Idle],
+            to: Groom,
+            when: |c| !c.input && c.time_in_state >= c.idle_after && c.roll < 0.5,
+        },
+        Transition {
+            from: &[Idle],
+            to: TailFlick,
+            when: |c| !c.input && c.time_in_state >= c.idle_after && c.roll >= 0.5,
+        },
+        Transition {
+            from: &[Idle],
+            to: Idle,
+            when: |c| c.input,
+        },
+    ]
+}
+
+#[derive(Component)]
+pub struct PlayerAnimation {
+    state: AnimState,
+    time_in_state: f32,
+    idle_after: f32,
+    roll: f32,
+    /// facing when the state was entered, flicks are relative to it
+    flip_x: bool,
+    transitions: Vec<Transition>,
+    blink: Timer,
+    /// stretch along gravity when the squash started, negative squashes
+    squash: f32,
+    squash_time: f32,
+}
+
+impl Default for PlayerAnimation {
+    fn default() -> Self {
+        PlayerAnimation {
+            state: AnimState::Idle,
+            time_in_state: 0.0,
+            idle_after: IDLE_SECONDS.0,
+            roll: 0.0,
+            flip_x: false,
+            transitions: player_transitions(),
+            blink: Timer::from_seconds(BLINK_INTERVAL.0, TimerMode::Once),
+            squash: 0.0,
+            squash_time: 0.0,
+        }
+    }
+}
+
+fn random_between(rng: &mut GlobalRng, (min, max): (f32, f32)) -> f32 {
+    min + rng.f32() * (max - min)
+}
+
+impl PlayerAnimation {
+    /// springy scale from the last jump or landing, y is along gravity since
+    /// the sprite is rotated to face it
+    fn squash_scale(&self) -> Vec2 {
+        let t = self.squash_time;
+        let amount = self.squash * (-SQUASH_DAMPING * t).exp() * (SQUASH_FREQUENCY * t).cos();
+        // keep roughly the same area so it reads as squishy rather than growing
+        Vec2::new(1.0 - amount * 0.5, 1.0 + amount)
+    }
+}
+
+/// Start a squash or stretch on jumps and landings. Only changes how the sprite
+/// is drawn, colliders keep their size.
+fn squash_and_stretch(
+    time: Res<Time>,
+    settings: Res<AccessibilitySettings>,
+    mut jumped: EventReader<PlayerJumped>,
+    mut landed: EventReader<Landed>,
+    mut q: Query<&mut PlayerAnimation>,
+) {
+    let jump = !jumped.is_empty();
+    jumped.clear();
+    let landing = landed
+        .iter()
+        .filter(|l| q.contains(l.entity))
+        .map(|l| l.speed)
+        .reduce(f32::max);
+    if settings.reduced_motion {
+        // a squash playing when the setting is turned on would be stuck half way
+        for mut anim in &mut q {
+            if anim.squash != 0.0 {
+                anim.squash = 0.0;
+            }
+        }
+        return;
+    }
+
+    for mut anim in &mut q {
+        anim.squash_time += time.delta_seconds();
+        if let Some(speed) = landing {
+            anim.squash = -LAND_SQUASH * (speed / LAND_SPEED).clamp(0.3, 1.0);
+            anim.squash_time = 0.0;
+        }
+        if jump {
+            anim.squash = JUMP_STRETCH;
+            anim.squash_time = 0.0;
+        }
+    }
+}
+
+fn animate_player(
+    time: Res<Time>,
+    mut rng: ResMut<GlobalRng>,
+    mut q: Query<(
+        &mut PlayerAnimation,
+        &mut Sprite,
+        &Velocity,
+        &GravityDirection,
+        &OnGround,
+        &ActionState<JumpAction>,
+        &ActionState<MovementAction>,
+    )>,
+) {
+    for (mut anim, mut sprite, v, g, on_ground, jump, movement) in &mut q {
+        // blinking happens whatever state the cat is in
+        if anim.blink.tick(time.delta()).just_finished() {
+            let next = random_between(&mut rng, BLINK_INTERVAL);
+            anim.blink = Timer::from_seconds(next, TimerMode::Once);
+        }
+        sprite.color = if anim.blink.elapsed_secs() < BLINK_SECONDS {
+            Color::rgb(0.75, 0.75, 0.75)
+        } else {
+            Color::WHITE
+        };
+
+        anim.time_in_state += time.delta_seconds();
+        let current = clip(anim.state);
+        let context = AnimContext {
+            forward_speed: g.forward().as_vec2().dot(v.0),
+            fall_speed: g.as_vec2().dot(v.0),
+            on_ground: on_ground.0,
+            input: !jump.get_pressed().is_empty() || !movement.get_pressed().is_empty(),
+            time_in_state: anim.time_in_state,
+            clip_finished: current
+                .seconds
+                .map_or(true, |seconds| anim.time_in_state >= seconds),
+            idle_after: anim.idle_after,
+            roll: anim.roll,
+        };
+
+        let next = anim
+            .transitions
+            .iter()
+            .find(|t| (t.from.is_empty() || t.from.contains(&anim.state)) && (t.when)(&context))
+            .map(|t| t.to);
+        if let Some(next) = next {
+            if current.flick_interval.is_some() {
+                sprite.flip_x = anim.flip_x;
+            }
+            anim.state = next;
+            anim.time_in_state = 0.0;
+            anim.idle_after = random_between(&mut rng, IDLE_SECONDS);
+            anim.roll = rng.f32();
+            anim.flip_x = sprite.flip_x;
+        }
+
+        let playing = clip(anim.state);
+        let t = match playing.seconds {
+            Some(seconds) => anim.time_in_state.min(seconds),
+            None => anim.time_in_state,
+        };
+        let scale = (playing.scale)(t) * anim.squash_scale();
+        sprite.custom_size = (scale != Vec2::ONE).then_some(SPRITE_SIZE * scale);
+        if let Some(interval) = playing.flick_interval {
+            let flicked = (t / interval) as u32 % 2 == 1;
+            sprite.flip_x = anim.flip_x ^ flicked;
+        }
+    }
+}
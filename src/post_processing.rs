@@ -0,0 +1,244 @@
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+use crate::{camera::MainCamera, config::AccessibilitySettings, player::PlayerDied};
+
+/// size of the render target the game is drawn into, matches the window
+const TARGET_SIZE: u32 = 720;
+
+pub struct PostProcessPlugin;
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<PostProcessMaterial>::default())
+            .insert_resource(PostProcessSettings {
+                // these are overridden by settings.post.ron
+                enabled: false,
+                scanline_intensity: 0.15,
+                vignette_strength: 0.35,
+                death_aberration: 1.0,
+            })
+            .init_resource::<DeathAberration>()
+            .add_startup_system(setup_post_process)
+            .add_startup_system(load_post_process_settings)
+            .add_system(monitor_post_process_changes)
+            .add_system(
+                toggle_post_process
+                    .after(monitor_post_process_changes)
+                    .run_if(resource_changed::<PostProcessSettings>()),
+            )
+            .add_system(pulse_death_aberration)
+            .add_system(
+                update_post_process_material
+                    .after(toggle_post_process)
+                    .after(pulse_death_aberration),
+            );
+    }
+}
+
+#[derive(Resource, serde::Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "0f7a2e41-6c1b-4f0e-9a57-3d52c8b1e9a4"]
+pub struct PostProcessSettings {
+    pub enabled: bool,
+    pub scanline_intensity: f32,
+    pub vignette_strength: f32,
+    /// strength of the chromatic aberration pulse when the player dies
+    pub death_aberration: f32,
+}
+
+#[derive(Resource)]
+#[allow(dead_code)] // only held so the settings stay loaded
+struct PostProcessSettingsHandle(pub Handle<PostProcessSettings>);
+
+/// Current strength of the death chromatic aberration, decays back to 0.
+/// Set to 1.0 when the player dies.
+#[derive(Resource, Default)]
+struct DeathAberration(f32);
+
+#[derive(AsBindGroup, TypeUuid, Clone)]
+#[uuid = "b62bb455-a72c-4b56-87bb-81e0554e234f"]
+pub struct PostProcessMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    source_image: Handle<Image>,
+    /// x: scanline intensity, y: vignette strength, z: chromatic aberration, w: time
+    #[uniform(2)]
+    params: Vec4,
+}
+
+impl Material2d for PostProcessMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/post_process.wgsl".into()
+    }
+}
+
+/// image the main camera renders into while post processing is enabled
+#[derive(Resource)]
+struct PostProcessTarget(Handle<Image>);
+
+#[derive(Component)]
+struct PostProcessCamera;
+
+fn setup_post_process(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PostProcessMaterial>>,
+) {
+    let size = Extent3d {
+        width: TARGET_SIZE,
+        height: TARGET_SIZE,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image_handle = images.add(image);
+
+    // the post process pass lives on its own render layer so the main camera never sees it
+    let post_process_layer = RenderLayers::layer(1);
+
+    commands.spawn((
+        post_process_layer,
+        MaterialMesh2dBundle {
+            mesh: meshes
+                .add(Mesh::from(shape::Quad::new(Vec2::splat(
+                    TARGET_SIZE as f32,
+                ))))
+                .into(),
+            material: materials.add(PostProcessMaterial {
+                source_image: image_handle.clone(),
+                params: Vec4::ZERO,
+            }),
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        PostProcessCamera,
+        post_process_layer,
+        // ui is already drawn into the render target by the main camera
+        UiCameraConfig { show_ui: false },
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    commands.insert_resource(PostProcessTarget(image_handle));
+}
+
+fn load_post_process_settings(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handle = asset_server.load("settings.post.ron");
+    commands.insert_resource(PostProcessSettingsHandle(handle));
+}
+
+fn monitor_post_process_changes(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<PostProcessSettings>>,
+    settings: Res<Assets<PostProcessSettings>>,
+) {
+    for e in &mut events {
+        match e {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                let setting = settings.get(handle).unwrap();
+                commands.insert_resource(setting.clone())
+            }
+            _ => {}
+        }
+    }
+}
+
+/// redirect the main camera into the render target when post processing is enabled
+fn toggle_post_process(
+    settings: Res<PostProcessSettings>,
+    target: Res<PostProcessTarget>,
+    mut main_camera: Query<&mut Camera, (With<MainCamera>, Without<PostProcessCamera>)>,
+    mut post_camera: Query<&mut Camera, With<PostProcessCamera>>,
+) {
+    for mut camera in &mut main_camera {
+        camera.target = if settings.enabled {
+            RenderTarget::Image(target.0.clone())
+        } else {
+            RenderTarget::default()
+        };
+    }
+
+    for mut camera in &mut post_camera {
+        camera.is_active = settings.enabled;
+    }
+}
+
+fn pulse_death_aberration(
+    mut died: EventReader<PlayerDied>,
+    mut aberration: ResMut<DeathAberration>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    if !died.is_empty() {
+        died.clear();
+        if !accessibility.reduced_motion {
+            aberration.0 = 1.0;
+        }
+    }
+}
+
+fn update_post_process_material(
+    time: Res<Time>,
+    settings: Res<PostProcessSettings>,
+    accessibility: Res<AccessibilitySettings>,
+    mut aberration: ResMut<DeathAberration>,
+    mut materials: ResMut<Assets<PostProcessMaterial>>,
+    q: Query<&Handle<PostProcessMaterial>>,
+) {
+    aberration.0 = (aberration.0 - time.delta_seconds() * 2.0).max(0.0);
+
+    if !settings.enabled {
+        return;
+    }
+
+    // scanlines stay still instead of rolling with reduced motion
+    let scroll = if accessibility.reduced_motion {
+        0.0
+    } else {
+        time.elapsed_seconds_wrapped()
+    };
+    for handle in &q {
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+        material.params = Vec4::new(
+            settings.scanline_intensity,
+            settings.vignette_strength,
+            aberration.0 * settings.death_aberration,
+            scroll,
+        );
+    }
+}
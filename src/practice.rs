@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{
+    collisions::{Ray, Rect},
+    game_state::GameState,
+    physics::{
+        set_gravity_direction, Acceleration, Direction, GravityDirection, OnGround, Velocity,
+    },
+    player::Player,
+    render_layers::RenderLayer,
+    run_mode::RunMode,
+};
+
+const MARKER_COLOR: Color = Color::rgba(0.4, 0.9, 1.0, 0.6);
+
+pub struct PracticePlugin;
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeCheckpoints>()
+            .add_systems(
+                (place_checkpoint, respawn_at_checkpoint, update_marker)
+                    .in_set(GameState::Playing)
+                    .distributive_run_if(|mode: Res<RunMode>| *mode == RunMode::Practice),
+            )
+            .add_system(despawn_marker.in_schedule(OnEnter(GameState::StartMenu)));
+    }
+}
+
+/// Respawn points placed by the player in practice mode, keyed by level index.
+/// These only last for the session and are never written to the ldtk file.
+#[derive(Resource, Default)]
+pub struct PracticeCheckpoints(HashMap<usize, (Vec2, Direction)>);
+
+#[derive(Component)]
+struct CheckpointMarker;
+
+fn current_level(level: &LevelSelection) -> Option<usize> {
+    match level {
+        LevelSelection::Index(index) => Some(*index),
+        _ => None,
+    }
+}
+
+/// C places a respawn point where the player is standing, X removes it
+fn place_checkpoint(
+    keyboard: Res<Input<KeyCode>>,
+    level: Res<LevelSelection>,
+    player: Query<(&Transform, &OnGround, &GravityDirection), With<Player>>,
+    mut checkpoints: ResMut<PracticeCheckpoints>,
+) {
+    let Some(index) = current_level(&level) else {
+        return;
+    };
+    if keyboard.just_pressed(KeyCode::X) {
+        checkpoints.0.remove(&index);
+        return;
+    }
+    if !keyboard.just_pressed(KeyCode::C) {
+        return;
+    }
+    // only solid ground is a fair place to come back to
+    for (t, on_ground, g_dir) in &player {
+        if on_ground.0 {
+            checkpoints
+                .0
+                .insert(index, (t.translation.truncate(), g_dir.0));
+        }
+    }
+}
+
+/// move a freshly spawned player to the level's checkpoint
+fn respawn_at_checkpoint(
+    level: Res<LevelSelection>,
+    checkpoints: Res<PracticeCheckpoints>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut GravityDirection,
+            &Children,
+        ),
+        Added<Player>,
+    >,
+    mut aabb_colliders: Query<&mut Rect>,
+    mut rays: Query<&mut Ray>,
+) {
+    let Some(&(position, gravity)) = current_level(&level).and_then(|i| checkpoints.0.get(&i))
+    else {
+        return;
+    };
+    for (mut t, mut v, mut a, mut g_dir, children) in &mut player {
+        set_gravity_direction(
+            gravity,
+            &mut g_dir,
+            &mut t,
+            children,
+            &mut aabb_colliders,
+            &mut rays,
+        );
+        t.translation = position.extend(t.translation.z);
+        v.0 = Vec2::ZERO;
+        a.0 = Vec2::ZERO;
+    }
+}
+
+fn update_marker(
+    mut commands: Commands,
+    level: Res<LevelSelection>,
+    checkpoints: Res<PracticeCheckpoints>,
+    mut marker: Query<(Entity, &mut Transform), With<CheckpointMarker>>,
+) {
+    if !checkpoints.is_changed() && !level.is_changed() {
+        return;
+    }
+    let checkpoint = current_level(&level).and_then(|i| checkpoints.0.get(&i));
+    match (checkpoint, marker.get_single_mut()) {
+        (Some((position, _)), Ok((_, mut t))) => {
+            t.translation = position.extend(t.translation.z);
+        }
+        (Some((position, _)), Err(_)) => {
+            commands.spawn((
+                CheckpointMarker,
+                RenderLayer::WorldUi,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: MARKER_COLOR,
+                        custom_size: Some(Vec2::new(4.0, 24.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.extend(0.0)),
+                    ..default()
+                },
+            ));
+        }
+        (None, Ok((e, _))) => commands.entity(e).despawn_recursive(),
+        (None, Err(_)) => {}
+    }
+}
+
+fn despawn_marker(mut commands: Commands, marker: Query<Entity, With<CheckpointMarker>>) {
+    for e in &marker {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bevy::{
+    asset::{Asset, LoadState},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use crate::game_state::GameState;
+
+pub struct PreloadPlugin;
+impl Plugin for PreloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Preloaded>()
+            .init_resource::<ManifestPath>()
+            .add_startup_system(load_manifest)
+            .add_system(load_manifest_entries)
+            .add_system(spawn_loading_screen.in_schedule(OnEnter(GameState::Preload)))
+            .add_system(track_preload.run_if(in_state(GameState::Preload)))
+            .add_system(despawn_loading_screen.in_schedule(OnExit(GameState::Preload)))
+            .add_system(unload_level_assets.in_schedule(OnEnter(GameState::UnloadLevel)))
+            .add_system(reload_level_assets.in_schedule(OnExit(GameState::StartMenu)));
+    }
+}
+
+/// Every asset that's loaded up front, keyed by label. Read from
+/// `preload.manifest.ron`.
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "9d41f6b2-7a3c-4e58-b1d0-3c8e5a2f7b64"]
+pub struct AssetManifest {
+    pub assets: BTreeMap<String, String>,
+    /// labels of assets only used in levels, they're dropped on the way back
+    /// to the start menu and loaded again when leaving it
+    #[serde(default)]
+    pub level_scoped: BTreeSet<String>,
+}
+
+/// where the manifest is read from, tests point it at their own fixtures
+#[derive(Resource)]
+pub struct ManifestPath(pub &'static str);
+
+impl Default for ManifestPath {
+    fn default() -> Self {
+        ManifestPath("preload.manifest.ron")
+    }
+}
+
+/// Handles for everything in the manifest. Holding them here keeps the assets
+/// loaded for the whole session, except level scoped ones while in the menus.
+#[derive(Resource, Default)]
+pub struct Preloaded {
+    manifest: Handle<AssetManifest>,
+    handles: HashMap<String, HandleUntyped>,
+}
+
+impl Preloaded {
+    /// handle for the asset listed under `label` in the manifest
+    pub fn get<T: Asset>(&self, label: &str) -> Handle<T> {
+        match self.handles.get(label) {
+            Some(handle) => handle.clone().typed(),
+            None => {
+                warn!("{label} is not in the asset manifest, or is unloaded");
+                Handle::default()
+            }
+        }
+    }
+
+    /// whether everything held has finished loading, failures count as finished
+    pub fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        self.handles.values().all(|handle| {
+            matches!(
+                asset_server.get_load_state(handle),
+                LoadState::Loaded | LoadState::Failed
+            )
+        })
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+fn load_manifest(
+    asset_server: Res<AssetServer>,
+    path: Res<ManifestPath>,
+    mut preloaded: ResMut<Preloaded>,
+) {
+    preloaded.manifest = asset_server.load(path.0);
+}
+
+/// Starts loading every asset in the manifest at once, the asset server loads
+/// them in parallel on its io threads. Runs again when the manifest is edited.
+fn load_manifest_entries(
+    mut events: EventReader<AssetEvent<AssetManifest>>,
+    manifests: Res<Assets<AssetManifest>>,
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<Preloaded>,
+) {
+    for event in &mut events {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        if *handle != preloaded.manifest {
+            continue;
+        }
+        let Some(manifest) = manifests.get(handle) else {
+            continue;
+        };
+        preloaded.handles = manifest
+            .assets
+            .iter()
+            .map(|(label, path)| (label.clone(), asset_server.load_untyped(path.as_str())))
+            .collect();
+    }
+}
+
+/// The text starts without a font, the font comes from the manifest too and
+/// `track_preload` sets it once the manifest has been read.
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            LoadingScreen,
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.05, 0.05, 0.08).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LoadingScreen,
+                TextBundle::from_section(
+                    "Loading",
+                    TextStyle {
+                        font_size: 32.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn track_preload(
+    preloaded: Res<Preloaded>,
+    asset_server: Res<AssetServer>,
+    mut texts: Query<&mut Text, With<LoadingScreen>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    // the manifest itself hasn't loaded yet
+    if preloaded.handles.is_empty() {
+        return;
+    }
+
+    // the asset server already logs failures, missing assets shouldn't stop
+    // the game from starting
+    let done = preloaded
+        .handles
+        .values()
+        .filter(|handle| {
+            matches!(
+                asset_server.get_load_state(*handle),
+                LoadState::Loaded | LoadState::Failed
+            )
+        })
+        .count();
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Loading {done}/{}", preloaded.handles.len());
+        text.sections[0].style.font = preloaded.get("font");
+    }
+
+    if done == preloaded.handles.len() {
+        state.set(GameState::StartMenu);
+    }
+}
+
+fn despawn_loading_screen(mut commands: Commands, q: Query<Entity, With<LoadingScreen>>) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
+
+/// Drops the handles of level scoped assets so bevy frees them once the level
+/// entities using them are gone, then carries on to the start menu.
+fn unload_level_assets(
+    manifests: Res<Assets<AssetManifest>>,
+    mut preloaded: ResMut<Preloaded>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Some(manifest) = manifests.get(&preloaded.manifest) {
+        preloaded
+            .handles
+            .retain(|label, _| !manifest.level_scoped.contains(label));
+    }
+    state.set(GameState::StartMenu);
+}
+
+/// starts loading any level scoped assets that were dropped, `LoadLevel` waits for them
+fn reload_level_assets(
+    manifests: Res<Assets<AssetManifest>>,
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<Preloaded>,
+) {
+    let Some(manifest) = manifests.get(&preloaded.manifest) else {
+        return;
+    };
+    for label in &manifest.level_scoped {
+        let Some(path) = manifest.assets.get(label) else {
+            continue;
+        };
+        preloaded
+            .handles
+            .entry(label.clone())
+            .or_insert_with(|| asset_server.load_untyped(path.as_str()));
+    }
+}
@@ -0,0 +1,410 @@
+use std::{f32::consts::TAU, num::NonZeroU32};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
+        render_graph::{self, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        RenderApp,
+    },
+};
+use bevy_ecs_ldtk::LevelSelection;
+use bevy_prototype_debug_lines::DebugLines;
+use wgpu::Maintain;
+
+use crate::{
+    bounds::LevelBounds,
+    collisions::CollisionDebugSettings,
+    explosions::Bomb,
+    game_state::GameState,
+    level_registry::LevelRegistry,
+    player::Player,
+    tasks::{TaskCompleted, TaskPlugin, Tasks},
+    turrets::Turret,
+};
+
+/// Size of the thumbnails. Rows copied out of a texture have to be a multiple
+/// of 256 bytes, 256 pixels * 4 bytes keeps that simple.
+const THUMBNAIL_SIZE: u32 = 256;
+/// hitbox images are bigger so single colliders can be made out
+const HITBOX_SIZE: u32 = 1024;
+/// segments in the circles drawn for blast radiuses
+const CIRCLE_SEGMENTS: usize = 32;
+/// frames to let a level settle before requesting a capture
+const SETTLE_FRAMES: u32 = 10;
+/// frames between requesting a capture and reading it back, rendering is pipelined
+const READBACK_FRAMES: u32 = 3;
+
+/// Tool mode started with `--generate-previews` or `--export-hitboxes`. Loads
+/// every level, frames it with an offscreen camera, writes an image of each
+/// level and exits.
+pub struct LevelPreviewPlugin;
+impl Plugin for LevelPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(mode) = PreviewMode::from_args() else {
+            return;
+        };
+
+        if mode == PreviewMode::Hitboxes {
+            app.add_systems((show_colliders, draw_hazards).in_set(GameState::Playing));
+        }
+        app.insert_resource(mode)
+            .add_plugin(ExtractResourcePlugin::<PreviewCapture>::default())
+            .add_plugin(TaskPlugin::<ThumbnailWritten>::new())
+            .add_startup_system(setup_previews)
+            .add_system(skip_start_menu.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_system(frame_level.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(capture_previews.in_set(GameState::Playing))
+            .add_system(finish_previews);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node("level_preview_copy", PreviewCopyNode);
+        graph.add_node_edge(
+            bevy::render::main_graph::node::CAMERA_DRIVER,
+            "level_preview_copy",
+        );
+    }
+}
+
+/// what the tool writes for each level
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+enum PreviewMode {
+    /// thumbnails for the level select, `assets/previews/<level>.png`
+    Thumbnails,
+    /// the level with every collider, spawn point and hazard range drawn over
+    /// it, for reviewing levels outside the game. `hitboxes/<level>.png`
+    Hitboxes,
+}
+
+impl PreviewMode {
+    fn from_args() -> Option<Self> {
+        std::env::args().find_map(|arg| match arg.as_str() {
+            "--generate-previews" => Some(PreviewMode::Thumbnails),
+            "--export-hitboxes" => Some(PreviewMode::Hitboxes),
+            _ => None,
+        })
+    }
+
+    fn size(self) -> u32 {
+        match self {
+            PreviewMode::Thumbnails => THUMBNAIL_SIZE,
+            PreviewMode::Hitboxes => HITBOX_SIZE,
+        }
+    }
+
+    fn dir(self) -> &'static str {
+        match self {
+            PreviewMode::Thumbnails => "assets/previews",
+            PreviewMode::Hitboxes => "hitboxes",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct PreviewProgress {
+    level: usize,
+    frames: u32,
+    requested: bool,
+    /// thumbnails still being written
+    writing: usize,
+    /// where the player was when the level started
+    spawn: Option<Vec2>,
+}
+
+/// result of writing the thumbnail at `path`
+struct ThumbnailWritten {
+    path: String,
+    result: Result<(), String>,
+}
+
+/// shared with the render world, which copies `image` into `buffer` while `copy` is set
+#[derive(Resource, ExtractResource, Clone)]
+struct PreviewCapture {
+    image: Handle<Image>,
+    buffer: Buffer,
+    /// width and height of `image`
+    size: u32,
+    copy: bool,
+}
+
+#[derive(Component)]
+struct PreviewCamera;
+
+fn setup_previews(
+    mut commands: Commands,
+    mode: Res<PreviewMode>,
+    mut images: ResMut<Assets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    let capture_size = mode.size();
+    let size = Extent3d {
+        width: capture_size,
+        height: capture_size,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("level_preview_buffer"),
+        size: (capture_size * capture_size * 4) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    commands.spawn((
+        PreviewCamera,
+        UiCameraConfig { show_ui: false },
+        Camera2dBundle {
+            camera: Camera {
+                order: -1,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+    commands.insert_resource(PreviewCapture {
+        image,
+        buffer,
+        size: capture_size,
+        copy: false,
+    });
+    commands.init_resource::<PreviewProgress>();
+}
+
+/// levels are loaded as soon as the assets have preloaded
+fn skip_start_menu(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::LoadLevel);
+}
+
+/// center the preview camera on the level and zoom so the whole level fits
+fn frame_level(
+    bounds: Res<LevelBounds>,
+    capture: Res<PreviewCapture>,
+    mut progress: ResMut<PreviewProgress>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<PreviewCamera>>,
+    player: Query<&Transform, (With<Player>, Without<PreviewCamera>)>,
+) {
+    let center = (bounds.min + bounds.max) / 2.0;
+    let size = bounds.max - bounds.min;
+    for (mut t, mut projection) in &mut camera {
+        t.translation = center.extend(t.translation.z);
+        projection.scale = size.max_element() / capture.size as f32;
+    }
+    progress.frames = 0;
+    progress.requested = false;
+    progress.spawn = player.iter().next().map(|t| t.translation.truncate());
+}
+
+/// the debug config is read from settings, which could have colliders turned off
+fn show_colliders(mut settings: ResMut<CollisionDebugSettings>) {
+    if !settings.enabled {
+        settings.enabled = true;
+    }
+}
+
+/// Marks what the collider drawing doesn't show: where the player starts,
+/// what's in a bomb's blast and the line turrets fire along.
+fn draw_hazards(
+    mut lines: ResMut<DebugLines>,
+    progress: Res<PreviewProgress>,
+    bounds: Res<LevelBounds>,
+    bombs: Query<(&Bomb, &GlobalTransform)>,
+    turrets: Query<(&Turret, &GlobalTransform)>,
+) {
+    if let Some(spawn) = progress.spawn {
+        let spawn = spawn.extend(0.0);
+        for corner in [Vec3::new(8.0, 8.0, 0.0), Vec3::new(-8.0, 8.0, 0.0)] {
+            lines.line_colored(spawn - corner, spawn + corner, 0.0, Color::LIME_GREEN);
+        }
+    }
+
+    for (bomb, t) in &bombs {
+        let center = t.translation();
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + (Vec2::from_angle(angle) * bomb.radius).extend(0.0)
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            lines.line_colored(point(i), point(i + 1), 0.0, Color::ORANGE);
+        }
+    }
+
+    // shots fly until they hit something, the level edge is as far as they can go
+    let size = (bounds.max - bounds.min).max_element();
+    for (turret, t) in &turrets {
+        let start = t.translation();
+        let end = start + (turret.direction.as_vec2() * size).extend(0.0);
+        let end = end.truncate().clamp(bounds.min, bounds.max).extend(end.z);
+        lines.line_colored(start, end, 0.0, Color::ORANGE_RED);
+    }
+}
+
+fn capture_previews(
+    mut progress: ResMut<PreviewProgress>,
+    mut capture: ResMut<PreviewCapture>,
+    render_device: Res<RenderDevice>,
+    registry: Res<LevelRegistry>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut state: ResMut<NextState<GameState>>,
+    tasks: Res<Tasks<ThumbnailWritten>>,
+    mode: Res<PreviewMode>,
+) {
+    // every level has been captured, waiting on the writes
+    if progress.level >= registry.len() {
+        return;
+    }
+    progress.frames += 1;
+    if !progress.requested {
+        if progress.frames >= SETTLE_FRAMES {
+            capture.copy = true;
+            progress.requested = true;
+            progress.frames = 0;
+        }
+        return;
+    }
+
+    // only copy for a single frame so the buffer isn't written while it's mapped
+    capture.copy = false;
+    if progress.frames < READBACK_FRAMES {
+        return;
+    }
+
+    let name = registry
+        .get(progress.level)
+        .map(|meta| meta.identifier.clone())
+        .unwrap_or_else(|| format!("Level_{}", progress.level));
+    match read_buffer(&capture.buffer, &render_device) {
+        Some(data) => {
+            let (dir, size) = (mode.dir(), capture.size);
+            let path = format!("{dir}/{name}.png");
+            progress.writing += 1;
+            tasks.spawn(move || ThumbnailWritten {
+                result: save_thumbnail(data, size, dir, &path),
+                path,
+            });
+        }
+        None => error!("failed to read back the preview for {name}"),
+    }
+
+    progress.level += 1;
+    if progress.level >= registry.len() {
+        return;
+    }
+    *level_selection = LevelSelection::Index(progress.level);
+    state.set(GameState::SpawnLevel);
+}
+
+fn read_buffer(buffer: &Buffer, render_device: &RenderDevice) -> Option<Vec<u8>> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    render_device.poll(Maintain::Wait);
+    receiver.recv().ok()?.ok()?;
+
+    let data = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+    Some(data)
+}
+
+/// exits once every level is captured and all the thumbnails are written
+fn finish_previews(
+    mut written: EventReader<TaskCompleted<ThumbnailWritten>>,
+    mut progress: ResMut<PreviewProgress>,
+    registry: Res<LevelRegistry>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for TaskCompleted(written) in &mut written {
+        progress.writing -= 1;
+        match &written.result {
+            Ok(()) => info!("wrote {}", written.path),
+            Err(e) => error!("failed to write {}: {e}", written.path),
+        }
+    }
+    if !registry.is_empty() && progress.level >= registry.len() && progress.writing == 0 {
+        exit.send(AppExit);
+    }
+}
+
+fn save_thumbnail(data: Vec<u8>, size: u32, dir: &str, path: &str) -> Result<(), String> {
+    let image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            ..default()
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    std::fs::create_dir_all(dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| image.try_into_dynamic().map_err(|e| format!("{e:?}")))
+        .and_then(|image| image.save(path).map_err(|e| e.to_string()))
+}
+
+struct PreviewCopyNode;
+impl render_graph::Node for PreviewCopyNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let Some(capture) = world.get_resource::<PreviewCapture>() else {
+            return Ok(());
+        };
+        if !capture.copy {
+            return Ok(());
+        }
+        let gpu_images = world.resource::<RenderAssets<Image>>();
+        let Some(source) = gpu_images.get(&capture.image) else {
+            return Ok(());
+        };
+
+        render_context.command_encoder().copy_texture_to_buffer(
+            source.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &capture.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(capture.size * 4),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: capture.size,
+                height: capture.size,
+                ..default()
+            },
+        );
+        Ok(())
+    }
+}
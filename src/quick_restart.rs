@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{LdtkLevel, Respawn};
+
+use crate::{
+    game_state::GameState,
+    run_mode::RunMode,
+    timer::{LevelTimer, RunTimer},
+};
+
+/// how long the restart button has to be held, so a stray press doesn't throw away an attempt
+const HOLD_SECONDS: f32 = 0.4;
+const BAR_WIDTH: f32 = 200.0;
+
+pub struct QuickRestartPlugin;
+impl Plugin for QuickRestartPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            quick_restart
+                .in_set(GameState::Playing)
+                // restarting would hand out free lives in a roguelite run
+                .run_if(|mode: Res<RunMode>| *mode != RunMode::Roguelite),
+        )
+        .add_system(despawn_restart_bar.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(Component)]
+struct RestartBar;
+
+/// Holding R or select respawns the level and winds the run timer back to
+/// when the level was started, without counting a death.
+fn quick_restart(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    level: Query<Entity, With<Handle<LdtkLevel>>>,
+    mut bar: Query<(Entity, &mut Style), With<RestartBar>>,
+    mut timer: ResMut<RunTimer>,
+    mut level_timer: ResMut<LevelTimer>,
+    mut state: ResMut<NextState<GameState>>,
+    mut held: Local<f32>,
+) {
+    let holding = keyboard.pressed(KeyCode::R)
+        || gamepads
+            .iter()
+            .any(|gamepad| buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::Select)));
+    if !holding {
+        *held = 0.0;
+        for (e, _) in &bar {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    *held += time.raw_delta_seconds();
+    if *held < HOLD_SECONDS {
+        let width = Val::Px(BAR_WIDTH * *held / HOLD_SECONDS);
+        match bar.get_single_mut() {
+            Ok((_, mut style)) => style.size.width = width,
+            Err(_) => {
+                commands.spawn((
+                    RestartBar,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                bottom: Val::Px(40.0),
+                                left: Val::Percent(50.0),
+                                ..default()
+                            },
+                            margin: UiRect::left(Val::Px(-BAR_WIDTH / 2.0)),
+                            size: Size::new(width, Val::Px(6.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.9, 0.9, 0.9).into(),
+                        ..default()
+                    },
+                ));
+            }
+        }
+        return;
+    }
+
+    *held = 0.0;
+    for (e, _) in &bar {
+        commands.entity(e).despawn_recursive();
+    }
+    info!("quick restart");
+    timer.restart_level();
+    level_timer.restart();
+    for e in &level {
+        commands.entity(e).insert(Respawn);
+    }
+    state.set(GameState::SpawnLevel);
+}
+
+fn despawn_restart_bar(mut commands: Commands, bar: Query<Entity, With<RestartBar>>) {
+    for e in &bar {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::{prelude::*, window::WindowCloseRequested};
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{
+    accessibility::MenuLabel,
+    audio_fade::QuitRequested,
+    game_state::{GameState, ShouldSimulate},
+    level_packs::LevelPacks,
+    preload::Preloaded,
+    save::{queue_write, ActiveSlot, SaveData, SaveWritten},
+    sfx::DuckMusic,
+    tasks::Tasks,
+};
+
+const PAUSED_BY: &str = "quit confirm";
+
+pub struct QuitConfirmPlugin;
+impl Plugin for QuitConfirmPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((handle_close_requests, confirm_input).chain())
+            .add_system(close_dialog.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+/// asks before closing the window in the middle of a level
+#[derive(Component)]
+struct QuitDialog;
+
+/// Closing from a menu quits straight away. During a level the progress is
+/// saved and the player is asked first, a second close request skips asking.
+fn handle_close_requests(
+    mut commands: Commands,
+    mut close: EventReader<WindowCloseRequested>,
+    mut quit: EventWriter<QuitRequested>,
+    state: Res<State<GameState>>,
+    dialog: Query<(), With<QuitDialog>>,
+    level: Res<LevelSelection>,
+    packs: Res<LevelPacks>,
+    slot: Res<ActiveSlot>,
+    tasks: Res<Tasks<SaveWritten>>,
+    mut save: ResMut<SaveData>,
+    mut simulate: ResMut<ShouldSimulate>,
+    preloaded: Res<Preloaded>,
+) {
+    if close.is_empty() {
+        return;
+    }
+    close.clear();
+
+    if state.0 != GameState::Playing || !dialog.is_empty() {
+        quit.send(QuitRequested);
+        return;
+    }
+
+    // custom packs' indices don't match the built in levels
+    if let (LevelSelection::Index(index), None) = (&*level, packs.active_name()) {
+        save.last_level = Some(*index);
+    }
+    if let Some(slot) = slot.0 {
+        queue_write(&tasks, slot, &save);
+    }
+
+    simulate.pause(PAUSED_BY);
+    commands
+        .spawn((
+            QuitDialog,
+            DuckMusic,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::height(Val::Px(16.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.08, 0.85).into(),
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            let text = |size| TextStyle {
+                font: preloaded.get("font"),
+                font_size: size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            };
+            parent.spawn((
+                MenuLabel::new(
+                    "Quit the game? Your progress has been saved. Y to quit, N to keep playing",
+                    true,
+                ),
+                TextBundle::from_section("Quit the game?", text(40.0)),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Your progress has been saved.",
+                text(20.0),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Y: Quit   N: Keep Playing",
+                text(24.0),
+            ));
+        });
+}
+
+fn confirm_input(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    dialog: Query<Entity, With<QuitDialog>>,
+    mut quit: EventWriter<QuitRequested>,
+    mut simulate: ResMut<ShouldSimulate>,
+) {
+    let Ok(dialog) = dialog.get_single() else {
+        return;
+    };
+    let pad = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+
+    // escape already goes back to the start menu during a level, so it isn't
+    // used to cancel here
+    if keyboard.any_just_pressed([KeyCode::Y, KeyCode::Return]) || pad(GamepadButtonType::South) {
+        quit.send(QuitRequested);
+    } else if keyboard.just_pressed(KeyCode::N) || pad(GamepadButtonType::East) {
+        commands.entity(dialog).despawn_recursive();
+        simulate.resume(PAUSED_BY);
+    }
+}
+
+fn close_dialog(
+    mut commands: Commands,
+    dialog: Query<Entity, With<QuitDialog>>,
+    mut simulate: ResMut<ShouldSimulate>,
+) {
+    for e in &dialog {
+        commands.entity(e).despawn_recursive();
+    }
+    simulate.resume(PAUSED_BY);
+}
@@ -0,0 +1,162 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_ecs_ldtk::{ldtk::Type, prelude::LayerMetadata, EntityInstance};
+
+/// where the camera sits, it sees everything between here and z 0
+pub const CAMERA_Z: f32 = 1000.0;
+/// how far apart the bands are, room for ordering things within one
+const BAND_DEPTH: f32 = 100.0;
+
+/// Keeps sprites in named z bands so what draws in front of what is decided in
+/// one place. Ldtk entities and tile layers are put in theirs when they spawn,
+/// anything else gets a `RenderLayer` where it's spawned. Parts drawn just
+/// behind their parent, like laser beams and rope, keep a small local z instead.
+pub struct RenderLayerPlugin;
+impl Plugin for RenderLayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((layer_ldtk_entities, layer_ldtk_tiles))
+            .add_system(
+                keep_in_band
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Bands from back to front. An entity with one has its global z kept at the
+/// band's, whatever its parents are at.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderLayer {
+    Background,
+    Tiles,
+    Entities,
+    Player,
+    Particles,
+    /// text and markers drawn in the level, in front of everything in it
+    WorldUi,
+}
+
+impl RenderLayer {
+    pub fn z(self) -> f32 {
+        self as u8 as f32 * BAND_DEPTH
+    }
+}
+
+/// orders things within their `RenderLayer`, higher is in front
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LayerDepth(pub f32);
+
+/// the player puts itself in `RenderLayer::Player` when it spawns
+fn layer_ldtk_entities(
+    mut commands: Commands,
+    q: Query<Entity, (Added<EntityInstance>, Without<RenderLayer>)>,
+) {
+    for e in &q {
+        commands.entity(e).insert(RenderLayer::Entities);
+    }
+}
+
+/// tile layers keep ldtk's order between themselves
+fn layer_ldtk_tiles(
+    mut commands: Commands,
+    q: Query<(Entity, &LayerMetadata, &Transform), Added<LayerMetadata>>,
+) {
+    for (e, layer, t) in &q {
+        if layer.layer_instance_type != Type::Entities {
+            commands
+                .entity(e)
+                .insert((RenderLayer::Tiles, LayerDepth(t.translation.z)));
+        }
+    }
+}
+
+/// Sets the local z that puts an entity at its band once its parents' z is
+/// added on. Parents only turn around z, so their z just adds up.
+fn keep_in_band(
+    mut set: ParamSet<(
+        Query<
+            (Entity, &RenderLayer, Option<&LayerDepth>, Option<&Parent>),
+            Or<(
+                Changed<RenderLayer>,
+                Changed<LayerDepth>,
+                Changed<Transform>,
+                Changed<Parent>,
+            )>,
+        >,
+        Query<(&Transform, Option<&Parent>)>,
+        Query<&mut Transform>,
+    )>,
+) {
+    let changed: Vec<_> = set
+        .p0()
+        .iter()
+        .map(|(e, layer, depth, parent)| {
+            let target = layer.z() + depth.map_or(0.0, |d| d.0);
+            (e, target, parent.map(Parent::get))
+        })
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+
+    let targets: Vec<_> = {
+        let parents = set.p1();
+        changed
+            .into_iter()
+            .map(|(e, target, mut parent)| {
+                let mut parents_z = 0.0;
+                while let Some(Ok((t, next))) = parent.map(|p| parents.get(p)) {
+                    parents_z += t.translation.z;
+                    parent = next.map(Parent::get);
+                }
+                (e, target - parents_z)
+            })
+            .collect()
+    };
+
+    let mut transforms = set.p2();
+    for (e, z) in targets {
+        if let Ok(mut t) = transforms.get_mut(e) {
+            // only write when it moved, so this doesn't keep itself changed
+            if t.translation.z != z {
+                t.translation.z = z;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_land_in_their_band() {
+        let mut app = App::new();
+        app.add_plugin(RenderLayerPlugin);
+        // like an ldtk entity layer, which sits a few z above the level
+        let layer = app.world.spawn(Transform::from_xyz(0.0, 0.0, 3.0)).id();
+        let walker = app
+            .world
+            .spawn((RenderLayer::Entities, Transform::default()))
+            .set_parent(layer)
+            .id();
+        let dust = app
+            .world
+            .spawn((
+                RenderLayer::Particles,
+                LayerDepth(1.0),
+                Transform::from_xyz(5.0, 5.0, 0.0),
+            ))
+            .id();
+        app.update();
+
+        let z = |app: &App, e: Entity| app.world.get::<Transform>(e).unwrap().translation.z;
+        assert_eq!(z(&app, walker) + 3.0, RenderLayer::Entities.z());
+        assert_eq!(z(&app, dust), RenderLayer::Particles.z() + 1.0);
+        assert!(RenderLayer::WorldUi.z() < CAMERA_Z);
+
+        // moving it about doesn't take it out of its band
+        app.world.get_mut::<Transform>(dust).unwrap().translation = Vec3::new(8.0, 2.0, 0.0);
+        app.update();
+        assert_eq!(z(&app, dust), RenderLayer::Particles.z() + 1.0);
+    }
+}
@@ -0,0 +1,461 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{LdtkAsset, LevelSelection};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_state::GameState,
+    level::{LevelCompleted, LevelSequence},
+    level_registry::LevelRegistry,
+    music::MusicMix,
+    mutators::Mutators,
+    player::{handle_player_death, PlayerDied},
+    preload::Preloaded,
+    save::{slot_selected, SaveData},
+    settings::settings_closed,
+    start_menu::MenuMarker,
+    timer::{format_time, RunTimer},
+};
+
+pub struct RunModePlugin;
+impl Plugin for RunModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunMode>()
+            .init_resource::<DailyChallenge>()
+            .init_resource::<RogueliteRun>()
+            .init_resource::<RngSeed>()
+            .add_startup_system(seed_rng)
+            .add_systems(
+                (reset_run_mode, spawn_daily_text, despawn_lives_text)
+                    .in_schedule(OnEnter(GameState::StartMenu)),
+            )
+            .add_systems(
+                (
+                    start_daily,
+                    start_roguelite,
+                    start_practice,
+                    start_minimal_flips,
+                )
+                    .distributive_run_if(in_state(GameState::StartMenu))
+                    .distributive_run_if(slot_selected)
+                    .distributive_run_if(settings_closed),
+            )
+            .add_system(
+                update_daily_text
+                    .run_if(in_state(GameState::StartMenu))
+                    .run_if(resource_changed::<SaveData>()),
+            )
+            .add_system(plan_seeded_run.in_schedule(OnExit(GameState::LoadLevel)))
+            .add_system(record_daily_best.in_schedule(OnEnter(GameState::WinScreen)))
+            .add_system(escalate_mutators)
+            // runs after the death handling so ending the run overrides the respawn
+            .add_system(
+                spend_lives
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(handle_player_death),
+            )
+            .add_system(update_lives_text);
+    }
+}
+
+/// number of levels in a daily challenge
+const DAILY_LEVEL_COUNT: usize = 5;
+/// number of levels in a roguelite run
+const ROGUELITE_LEVEL_COUNT: usize = 8;
+/// lives shared across a whole roguelite run
+const ROGUELITE_LIVES: u32 = 9;
+/// a new mutator is added every this many cleared levels in a roguelite run
+const ROGUELITE_ESCALATION: u32 = 2;
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    #[default]
+    Standard,
+    Daily,
+    Roguelite,
+    /// levels in order, with respawn points the player places themselves
+    Practice,
+    /// levels in order, each allowed only its par flips
+    MinimalFlips,
+}
+
+#[derive(Resource, Default)]
+pub struct DailyChallenge {
+    /// days since the unix epoch, used as the seed for the challenge
+    pub day: u64,
+    /// mutators the player had selected before starting the daily, restored
+    /// when returning to the menu
+    stashed_mutators: Option<Mutators>,
+}
+
+#[derive(Resource, Default)]
+pub struct RogueliteRun {
+    pub seed: u64,
+    pub lives: u32,
+    pub cleared: u32,
+    rng: Option<GlobalRng>,
+}
+
+impl RogueliteRun {
+    pub fn new(seed: u64) -> Self {
+        RogueliteRun {
+            seed,
+            lives: ROGUELITE_LIVES,
+            cleared: 0,
+            rng: Some(GlobalRng::with_seed(seed)),
+        }
+    }
+}
+
+/// What `GlobalRng` was last seeded with, so a bug report can reproduce it in
+/// any run mode. Picked at startup, the daily challenge seeds it with the day.
+#[derive(Resource, Default)]
+pub struct RngSeed(pub u64);
+
+/// the rng plugin seeds from entropy with no way to read the seed back
+fn seed_rng(mut rng: ResMut<GlobalRng>, mut seed: ResMut<RngSeed>) {
+    seed.0 = rng.u64(..);
+    *rng = GlobalRng::with_seed(seed.0);
+}
+
+/// best time for the daily challenge of `day`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct DailyBest {
+    pub day: u64,
+    pub seconds: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn current_day() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn current_day() -> u64 {
+    (js_sys::Date::now() / 86_400_000.0) as u64
+}
+
+/// deterministically pick the mutators for a daily challenge
+fn daily_mutators(rng: &mut GlobalRng) -> Mutators {
+    Mutators {
+        mirrored: rng.bool(),
+        double_gravity: rng.bool(),
+        fragile_goals: rng.bool(),
+        icy_floors: rng.bool(),
+    }
+}
+
+/// turn on a random mutator that isn't already enabled
+fn add_random_mutator(mutators: &mut Mutators, rng: &mut GlobalRng) {
+    let mut disabled = Vec::with_capacity(4);
+    if !mutators.mirrored {
+        disabled.push(&mut mutators.mirrored);
+    }
+    if !mutators.double_gravity {
+        disabled.push(&mut mutators.double_gravity);
+    }
+    if !mutators.fragile_goals {
+        disabled.push(&mut mutators.fragile_goals);
+    }
+    if !mutators.icy_floors {
+        disabled.push(&mut mutators.icy_floors);
+    }
+
+    if !disabled.is_empty() {
+        let index = rng.usize(0..disabled.len());
+        *disabled[index] = true;
+    }
+}
+
+pub fn reset_run_mode(
+    mut mode: ResMut<RunMode>,
+    mut daily: ResMut<DailyChallenge>,
+    mut mutators: ResMut<Mutators>,
+    mut sequence: ResMut<LevelSequence>,
+    mut music: ResMut<MusicMix>,
+) {
+    *mode = RunMode::Standard;
+    if let Some(stashed) = daily.stashed_mutators.take() {
+        *mutators = stashed;
+    }
+    *sequence = LevelSequence::default();
+    music.timed_challenge = false;
+}
+
+#[derive(Component)]
+struct DailyText;
+
+fn daily_text(save: &SaveData) -> String {
+    let day = current_day();
+    let best = save
+        .daily_best
+        .filter(|best| best.day == day)
+        .map(|best| format_time(std::time::Duration::from_secs_f32(best.seconds)))
+        .unwrap_or_else(|| "--".to_string());
+    format!(
+        "D: Daily Challenge\nToday's best: {best}\nR: Roguelite Run\nP: Practice\nF: Minimal Flips"
+    )
+}
+
+fn spawn_daily_text(mut commands: Commands, preloaded: Res<Preloaded>, save: Res<SaveData>) {
+    commands.spawn((
+        MenuMarker,
+        DailyText,
+        TextBundle::from_section(
+            daily_text(&save),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(20.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+/// the save changes when a slot is picked after the menu was spawned
+fn update_daily_text(save: Res<SaveData>, mut texts: Query<&mut Text, With<DailyText>>) {
+    for mut text in &mut texts {
+        text.sections[0].value = daily_text(&save);
+    }
+}
+
+fn start_daily(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut mode: ResMut<RunMode>,
+    mut daily: ResMut<DailyChallenge>,
+    mut mutators: ResMut<Mutators>,
+    mut music: ResMut<MusicMix>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard.just_pressed(KeyCode::D) {
+        return;
+    }
+
+    let day = current_day();
+    let mut rng = GlobalRng::with_seed(day);
+    daily.day = day;
+    daily.stashed_mutators = Some(*mutators);
+    *mutators = daily_mutators(&mut rng);
+    // reseeding the global rng also makes goal sprites the same for everyone
+    commands.insert_resource(rng);
+    commands.insert_resource(RngSeed(day));
+
+    *mode = RunMode::Daily;
+    music.timed_challenge = true;
+    state.set(GameState::LoadLevel);
+}
+
+fn start_roguelite(
+    keyboard: Res<Input<KeyCode>>,
+    mut mode: ResMut<RunMode>,
+    mut global_rng: ResMut<GlobalRng>,
+    mut roguelite: ResMut<RogueliteRun>,
+    mut daily: ResMut<DailyChallenge>,
+    mut mutators: ResMut<Mutators>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard.just_pressed(KeyCode::R) {
+        return;
+    }
+
+    let seed = global_rng.u64(..);
+    *roguelite = RogueliteRun::new(seed);
+    // runs start without mutators and escalate as levels are cleared
+    daily.stashed_mutators = Some(*mutators);
+    *mutators = Mutators::default();
+
+    *mode = RunMode::Roguelite;
+    state.set(GameState::LoadLevel);
+}
+
+fn start_practice(
+    keyboard: Res<Input<KeyCode>>,
+    mut mode: ResMut<RunMode>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::P) {
+        *mode = RunMode::Practice;
+        state.set(GameState::LoadLevel);
+    }
+}
+
+fn start_minimal_flips(
+    keyboard: Res<Input<KeyCode>>,
+    mut mode: ResMut<RunMode>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::F) {
+        *mode = RunMode::MinimalFlips;
+        state.set(GameState::LoadLevel);
+    }
+}
+
+/// pick the level sequence for seeded run modes, the level count is only
+/// known once the ldtk file has loaded
+pub fn plan_seeded_run(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    mode: Res<RunMode>,
+    daily: Res<DailyChallenge>,
+    roguelite: Res<RogueliteRun>,
+    registry: Res<LevelRegistry>,
+    mut sequence: ResMut<LevelSequence>,
+    mut level: ResMut<LevelSelection>,
+) {
+    if !sequence.levels.is_empty() {
+        return;
+    }
+    let (seed, count, skip_tutorials) = match *mode {
+        RunMode::Standard | RunMode::Practice | RunMode::MinimalFlips => return,
+        RunMode::Daily => (daily.day, DAILY_LEVEL_COUNT, false),
+        RunMode::Roguelite => (roguelite.seed, ROGUELITE_LEVEL_COUNT, true),
+    };
+    if registry.is_empty() {
+        return;
+    }
+
+    // separate rng from the global one so the sequence doesn't depend on
+    // how many random numbers were pulled before the level loaded
+    let mut rng = GlobalRng::with_seed(seed.wrapping_mul(31).wrapping_add(7));
+    let mut pool: Vec<usize> = registry
+        .iter()
+        .filter(|meta| !skip_tutorials || !meta.tutorial)
+        .map(|meta| meta.index)
+        .collect();
+    let count = count.min(pool.len());
+    for i in 0..count {
+        let j = rng.usize(i..pool.len());
+        pool.swap(i, j);
+    }
+    pool.truncate(count);
+
+    sequence.levels = pool;
+    sequence.position = 0;
+    *level = LevelSelection::Index(sequence.first());
+
+    if *mode == RunMode::Roguelite {
+        spawn_lives_text(&mut commands, &preloaded, roguelite.lives);
+    }
+}
+
+fn record_daily_best(
+    mode: Res<RunMode>,
+    daily: Res<DailyChallenge>,
+    timer: Res<RunTimer>,
+    mut save: ResMut<SaveData>,
+) {
+    if *mode != RunMode::Daily {
+        return;
+    }
+
+    let seconds = timer.elapsed.as_secs_f32();
+    let improved = match save.daily_best {
+        Some(best) if best.day == daily.day => seconds < best.seconds,
+        _ => true,
+    };
+    if improved {
+        save.daily_best = Some(DailyBest {
+            day: daily.day,
+            seconds,
+        });
+    }
+}
+
+fn escalate_mutators(
+    mut events: EventReader<LevelCompleted>,
+    mode: Res<RunMode>,
+    mut roguelite: ResMut<RogueliteRun>,
+    mut mutators: ResMut<Mutators>,
+) {
+    for _ in &mut events {
+        if *mode != RunMode::Roguelite {
+            continue;
+        }
+
+        roguelite.cleared += 1;
+        if roguelite.cleared % ROGUELITE_ESCALATION == 0 {
+            if let Some(rng) = roguelite.rng.as_mut() {
+                add_random_mutator(&mut mutators, rng);
+            }
+        }
+    }
+}
+
+fn spend_lives(
+    mut commands: Commands,
+    mut events: EventReader<PlayerDied>,
+    mode: Res<RunMode>,
+    mut roguelite: ResMut<RogueliteRun>,
+    mut state: ResMut<NextState<GameState>>,
+    ldtk: Query<Entity, With<Handle<LdtkAsset>>>,
+) {
+    for _ in &mut events {
+        if *mode != RunMode::Roguelite || roguelite.lives == 0 {
+            continue;
+        }
+
+        roguelite.lives -= 1;
+        if roguelite.lives == 0 {
+            // out of lives, the run is over
+            state.set(GameState::UnloadLevel);
+            for e in &ldtk {
+                commands.entity(e).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct LivesText;
+
+fn spawn_lives_text(commands: &mut Commands, preloaded: &Preloaded, lives: u32) {
+    commands.spawn((
+        LivesText,
+        TextBundle::from_section(
+            format!("Lives: {lives}"),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn update_lives_text(roguelite: Res<RogueliteRun>, mut q: Query<&mut Text, With<LivesText>>) {
+    if !roguelite.is_changed() {
+        return;
+    }
+
+    for mut text in &mut q {
+        text.sections[0].value = format!("Lives: {}", roguelite.lives);
+    }
+}
+
+fn despawn_lives_text(mut commands: Commands, q: Query<Entity, With<LivesText>>) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    collisions::{CollisionEvents, CollisionSets, Ray, Rect},
+    constants::CollisionTypes,
+    ground::FallingGround,
+    physics::{
+        ground_detection, set_gravity_direction, Acceleration, Direction, GravityDirection,
+        OnGround, Velocity,
+    },
+    player::{handle_player_death, Player},
+    stuck::StuckTicks,
+};
+
+/// how many safe positions are remembered
+const SAFE_POSITION_COUNT: usize = 8;
+/// distance the player has to move before a new safe position is recorded
+const SAMPLE_DISTANCE: f32 = 24.0;
+/// how many samples back soft respawns go, the newest is often right at a ledge edge
+const RESPAWN_LOOKBACK: usize = 2;
+
+pub struct SafePositionPlugin;
+impl Plugin for SafePositionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoftRespawn>()
+            .add_system(
+                track_safe_positions
+                    .in_set(CollisionSets::Consume)
+                    .after(ground_detection),
+            )
+            .add_system(
+                soft_respawn
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(handle_player_death),
+            );
+    }
+}
+
+/// send to move the player back to a recent safe position instead of restarting the level
+pub struct SoftRespawn;
+
+/// Recent places the player stood on solid ground, newest last
+#[derive(Component, Default)]
+pub struct SafePositions(VecDeque<(Vec2, Direction)>);
+
+impl SafePositions {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn respawn_point(&self) -> Option<(Vec2, Direction)> {
+        let back = RESPAWN_LOOKBACK.min(self.0.len().saturating_sub(1));
+        self.0.iter().rev().nth(back).copied()
+    }
+}
+
+fn track_safe_positions(
+    mut player: Query<
+        (
+            &Transform,
+            &OnGround,
+            &GravityDirection,
+            &CollisionEvents<CollisionTypes>,
+            &StuckTicks,
+            &mut SafePositions,
+        ),
+        With<Player>,
+    >,
+    falling_ground: Query<(), With<FallingGround>>,
+) {
+    for (t, on_ground, g_dir, collisions, stuck, mut safe) in &mut player {
+        if !on_ground.0 || stuck.0 > 0 {
+            continue;
+        }
+        // falling blocks won't be there to stand on later
+        let on_falling_ground = collisions
+            .buffer
+            .iter()
+            .any(|event| falling_ground.contains(event.entity));
+        if on_falling_ground {
+            continue;
+        }
+
+        let position = t.translation.truncate();
+        if let Some((last, _)) = safe.0.back() {
+            if last.distance(position) < SAMPLE_DISTANCE {
+                continue;
+            }
+        }
+        if safe.0.len() == SAFE_POSITION_COUNT {
+            safe.0.pop_front();
+        }
+        safe.0.push_back((position, g_dir.0));
+    }
+}
+
+fn soft_respawn(
+    mut events: EventReader<SoftRespawn>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Acceleration,
+            &mut GravityDirection,
+            &SafePositions,
+            &Children,
+        ),
+        With<Player>,
+    >,
+    mut aabb_colliders: Query<&mut Rect>,
+    mut rays: Query<&mut Ray>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    for (mut t, mut v, mut a, mut g_dir, safe, children) in &mut player {
+        let Some((position, gravity)) = safe.respawn_point() else {
+            continue;
+        };
+        set_gravity_direction(
+            gravity,
+            &mut g_dir,
+            &mut t,
+            children,
+            &mut aabb_colliders,
+            &mut rays,
+        );
+        t.translation = position.extend(t.translation.z);
+        v.0 = Vec2::ZERO;
+        a.0 = Vec2::ZERO;
+    }
+}
@@ -0,0 +1,526 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AccessibilitySettings, AssistConfig, AudioConfig},
+    game_state::GameState,
+    level::LevelCompleted,
+    level_packs::LevelPacks,
+    level_select::LevelUnlocks,
+    mutators::Mutators,
+    preload::Preloaded,
+    run_mode::DailyBest,
+    save_migration::{migrate, SAVE_VERSION},
+    settings::PlayerSettings,
+    tasks::{TaskCompleted, TaskPlugin, Tasks},
+    world_flags::WorldFlags,
+};
+
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveData>()
+            .init_resource::<ActiveSlot>()
+            .add_event::<SaveNotice>()
+            .add_plugin(TaskPlugin::<SaveWritten>::new())
+            .add_system(record_completed_level)
+            .add_system(
+                auto_save
+                    .run_if(slot_selected)
+                    .after(record_completed_level),
+            )
+            .add_system(track_playtime.in_set(GameState::Playing))
+            .add_system(report_failed_writes)
+            .add_systems((show_save_notices, fade_save_notices));
+    }
+}
+
+pub const SLOT_COUNT: usize = 3;
+/// where saves were kept before there were slots, read as the first slot
+const LEGACY_SAVE_KEY: &str = "save.ron";
+/// how often playtime is added to the save, so it isn't written every frame
+const PLAYTIME_FLUSH_SECONDS: f32 = 10.0;
+/// longest unsaved progress is kept in memory without a level being finished
+const AUTO_SAVE_SECONDS: f32 = 30.0;
+/// last line of every save, lets a damaged file be told apart from a valid one
+const CHECKSUM_PREFIX: &str = "\n// checksum: ";
+/// how long a notice about a damaged save stays on screen
+const NOTICE_SECONDS: f32 = 6.0;
+
+/// Newest write started for each slot. Writes run in parallel, so one that
+/// gets to the file after a newer one was started is dropped.
+static NEWEST_WRITE: Mutex<[u64; SLOT_COUNT]> = Mutex::new([0; SLOT_COUNT]);
+/// held while a slot is being written so the temporary and backup files
+/// aren't touched by two writes at once
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Slot `SaveData` is loaded from and written to. Nothing is saved until a
+/// slot has been picked.
+#[derive(Resource, Default)]
+pub struct ActiveSlot(pub Option<usize>);
+
+/// whether a save slot has been picked, for inputs that need one
+pub fn slot_selected(slot: Res<ActiveSlot>) -> bool {
+    slot.0.is_some()
+}
+
+/// progress that persists between sessions
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct SaveData {
+    /// fewest gravity flips used to finish each level, keyed by level index
+    pub best_flips: HashMap<usize, u32>,
+    /// fastest finish of each level in seconds, keyed by level index
+    pub best_times: HashMap<usize, f32>,
+    pub completed_runs: Vec<CompletedRun>,
+    /// best time for the most recently played daily challenge
+    pub daily_best: Option<DailyBest>,
+    /// total number of times the player has died
+    pub deaths: u32,
+    /// indices of every level that has been finished
+    pub completed_levels: HashSet<usize>,
+    /// seconds spent playing levels
+    pub playtime: f32,
+    /// built in level the player was on when they last closed the game
+    /// mid-run, the level select starts on it
+    pub last_level: Option<usize>,
+    /// newest changelog version the player has been shown
+    pub seen_news: Option<String>,
+    /// progress in user made level packs, keyed by pack name. Kept apart
+    /// since their level indices don't match the built in levels.
+    pub custom_levels: HashMap<String, LevelProgress>,
+    /// flags and counters levels use to affect each other, see `WorldFlagsPlugin`
+    pub world_flags: WorldFlags,
+    /// levels that can be picked on the level select
+    pub level_unlocks: LevelUnlocks,
+    /// volumes and key bindings from the settings menu
+    pub settings: PlayerSettings,
+    /// format version the save was written with, see `save_migration`
+    pub version: u32,
+}
+
+/// progress through the levels of a level pack
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct LevelProgress {
+    pub best_flips: HashMap<usize, u32>,
+    pub best_times: HashMap<usize, f32>,
+    pub completed_levels: HashSet<usize>,
+}
+
+impl SaveData {
+    fn pack_progress(&mut self, pack: &str) -> &mut LevelProgress {
+        self.custom_levels.entry(pack.to_string()).or_default()
+    }
+
+    /// best flips for the levels of `pack`, or the built in levels for `None`
+    pub fn best_flips_mut(&mut self, pack: Option<&str>) -> &mut HashMap<usize, u32> {
+        match pack {
+            Some(pack) => &mut self.pack_progress(pack).best_flips,
+            None => &mut self.best_flips,
+        }
+    }
+
+    /// best times for the levels of `pack`, or the built in levels for `None`
+    pub fn best_times_mut(&mut self, pack: Option<&str>) -> &mut HashMap<usize, f32> {
+        match pack {
+            Some(pack) => &mut self.pack_progress(pack).best_times,
+            None => &mut self.best_times,
+        }
+    }
+
+    pub fn best_time(&self, pack: Option<&str>, index: usize) -> Option<f32> {
+        let best_times = match pack {
+            Some(pack) => &self.custom_levels.get(pack)?.best_times,
+            None => &self.best_times,
+        };
+        best_times.get(&index).copied()
+    }
+
+    /// finished levels of `pack`, or the built in levels for `None`
+    pub fn is_completed(&self, pack: Option<&str>, index: usize) -> bool {
+        let completed = match pack {
+            Some(pack) => self.custom_levels.get(pack).map(|p| &p.completed_levels),
+            None => Some(&self.completed_levels),
+        };
+        completed.is_some_and(|completed| completed.contains(&index))
+    }
+
+    pub fn completed_levels_mut(&mut self, pack: Option<&str>) -> &mut HashSet<usize> {
+        match pack {
+            Some(pack) => &mut self.pack_progress(pack).completed_levels,
+            None => &mut self.completed_levels,
+        }
+    }
+}
+
+/// message about a save problem the player should know about
+pub struct SaveNotice(pub String);
+
+/// a write started with `queue_write` finished
+pub struct SaveWritten {
+    slot: usize,
+    result: Result<(), String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompletedRun {
+    /// mutators that were active for the run
+    pub mutators: Mutators,
+    pub style: u32,
+}
+
+fn slot_key(slot: usize) -> String {
+    format!("save_{}.ron", slot + 1)
+}
+
+fn backup_key(key: &str) -> String {
+    format!("{key}.bak")
+}
+
+/// FNV-1a, only needs to catch truncated or garbled files
+fn checksum(body: &str) -> u64 {
+    body.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn encode(save: &SaveData) -> Result<String, String> {
+    let save = SaveData {
+        version: SAVE_VERSION,
+        ..save.clone()
+    };
+    let body = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    Ok(format!("{body}{CHECKSUM_PREFIX}{:016x}\n", checksum(&body)))
+}
+
+fn decode(contents: &str) -> Result<SaveData, String> {
+    // saves from before checksums were added are only checked by parsing them
+    let body = match contents.trim_end().rsplit_once(CHECKSUM_PREFIX) {
+        Some((body, expected)) => {
+            if u64::from_str_radix(expected, 16) != Ok(checksum(body)) {
+                return Err("checksum does not match".to_string());
+            }
+            body
+        }
+        None => contents,
+    };
+    migrate(body)
+}
+
+/// Save data in `slot`, `None` if the slot is empty. A damaged save is
+/// replaced with its backup, and the player is told about it either way.
+pub fn load_slot(slot: usize, notices: &mut EventWriter<SaveNotice>) -> Option<SaveData> {
+    let (key, contents) = [slot_key(slot)]
+        .into_iter()
+        .chain((slot == 0).then(|| LEGACY_SAVE_KEY.to_string()))
+        .find_map(|key| Some((key.clone(), read_save(&key)?)))?;
+    let error = match decode(&contents) {
+        Ok(data) => return Some(data),
+        Err(e) => e,
+    };
+    warn!("save slot {} is damaged: {error}", slot + 1);
+
+    // keep the damaged file around in case it can be fixed by hand
+    let damaged_key = format!("{key}.damaged");
+    if let Err(e) = persist_save(&damaged_key, &contents) {
+        warn!("could not keep the damaged save: {e}");
+    }
+    remove_save(&key);
+    match read_save(&backup_key(&key)).and_then(|backup| decode(&backup).ok()) {
+        Some(data) => {
+            notices.send(SaveNotice(format!(
+                "Save slot {} was damaged, restored it from a backup",
+                slot + 1
+            )));
+            write_slot(slot, &data);
+            Some(data)
+        }
+        None => {
+            notices.send(SaveNotice(format!(
+                "Save slot {} was damaged and has no backup, the damaged file was kept as {damaged_key}",
+                slot + 1
+            )));
+            None
+        }
+    }
+}
+
+/// Write `save` to `slot`, keeping the slot's previous contents as a backup.
+/// Blocks until the save is written, use `queue_write` during play.
+pub fn write_slot(slot: usize, save: &SaveData) {
+    let generation = start_write(slot);
+    if let Err(e) = encode(save).and_then(|contents| write_encoded(slot, generation, &contents)) {
+        warn!("could not write save data: {e}");
+    }
+}
+
+/// Like `write_slot` but writes on the io task pool, a `SaveNotice` is sent
+/// if it fails.
+pub fn queue_write(tasks: &Tasks<SaveWritten>, slot: usize, save: &SaveData) {
+    let generation = start_write(slot);
+    // encoded now so the task doesn't need its own copy of the save
+    let contents = encode(save);
+    tasks.spawn(move || SaveWritten {
+        slot,
+        result: contents.and_then(|contents| write_encoded(slot, generation, &contents)),
+    });
+}
+
+fn start_write(slot: usize) -> u64 {
+    let mut newest = NEWEST_WRITE.lock().unwrap();
+    newest[slot] += 1;
+    newest[slot]
+}
+
+fn write_encoded(slot: usize, generation: u64, contents: &str) -> Result<(), String> {
+    let _writing = WRITING.lock().unwrap();
+    if NEWEST_WRITE.lock().unwrap()[slot] != generation {
+        return Ok(());
+    }
+
+    let key = slot_key(slot);
+    // a damaged save would overwrite a good backup, so only back up valid ones
+    if let Some(previous) = read_save(&key).filter(|previous| decode(previous).is_ok()) {
+        persist_save(&backup_key(&key), &previous)?;
+    }
+    persist_save(&key, contents)
+}
+
+pub fn delete_slot(slot: usize) {
+    // drops any queued write that would bring the slot back
+    start_write(slot);
+    let _writing = WRITING.lock().unwrap();
+    let key = slot_key(slot);
+    remove_save(&key);
+    remove_save(&backup_key(&key));
+    if slot == 0 {
+        remove_save(LEGACY_SAVE_KEY);
+    }
+}
+
+/// Writes the save when a level is finished, a setting changes, the game
+/// closes, or it's gone unsaved for `AUTO_SAVE_SECONDS`.
+fn auto_save(
+    save: Res<SaveData>,
+    slot: Res<ActiveSlot>,
+    tasks: Res<Tasks<SaveWritten>>,
+    time: Res<Time>,
+    mut completed: EventReader<LevelCompleted>,
+    mut exit: EventReader<bevy::app::AppExit>,
+    audio: Res<AudioConfig>,
+    assist: Res<AssistConfig>,
+    accessibility: Res<AccessibilitySettings>,
+    player_settings: Res<PlayerSettings>,
+    mut unsaved: Local<Option<f32>>,
+) {
+    if save.is_changed() {
+        unsaved.get_or_insert(0.0);
+    }
+    let Some(unsaved_seconds) = unsaved.as_mut() else {
+        completed.clear();
+        exit.clear();
+        return;
+    };
+    *unsaved_seconds += time.raw_delta_seconds();
+
+    let level_completed = !completed.is_empty();
+    completed.clear();
+    let exiting = !exit.is_empty();
+    exit.clear();
+    let settings_changed = audio.is_changed()
+        || assist.is_changed()
+        || accessibility.is_changed()
+        || player_settings.is_changed();
+
+    if level_completed || exiting || settings_changed || *unsaved_seconds >= AUTO_SAVE_SECONDS {
+        if let Some(slot) = slot.0 {
+            // the app may be gone before a queued write finishes
+            if exiting {
+                write_slot(slot, &save);
+            } else {
+                queue_write(&tasks, slot, &save);
+            }
+        }
+        *unsaved = None;
+    }
+}
+
+fn record_completed_level(
+    mut events: EventReader<LevelCompleted>,
+    packs: Res<LevelPacks>,
+    mut save: ResMut<SaveData>,
+) {
+    for event in &mut events {
+        save.completed_levels_mut(packs.active_name())
+            .insert(event.index);
+    }
+}
+
+fn report_failed_writes(
+    mut written: EventReader<TaskCompleted<SaveWritten>>,
+    mut notices: EventWriter<SaveNotice>,
+) {
+    for TaskCompleted(written) in &mut written {
+        if let Err(e) = &written.result {
+            warn!("could not write save data: {e}");
+            notices.send(SaveNotice(format!(
+                "Could not save to slot {}, progress since the last save may be lost",
+                written.slot + 1
+            )));
+        }
+    }
+}
+
+fn track_playtime(time: Res<Time>, mut save: ResMut<SaveData>, mut unsaved: Local<f32>) {
+    *unsaved += time.raw_delta_seconds();
+    if *unsaved >= PLAYTIME_FLUSH_SECONDS {
+        save.playtime += *unsaved;
+        *unsaved = 0.0;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_save(key: &str) -> Option<String> {
+    std::fs::read_to_string(key).ok()
+}
+
+/// Writes to a temporary file and renames it over `key`, so a crash part way
+/// through leaves the old save in place instead of a half written one.
+#[cfg(not(target_arch = "wasm32"))]
+fn persist_save(key: &str, contents: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    let temp = format!("{key}.tmp");
+    std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp, key))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_save(key: &str) {
+    if let Err(e) = std::fs::remove_file(key) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("could not delete save data: {e}");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_save(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// local storage writes are already all or nothing
+#[cfg(target_arch = "wasm32")]
+fn persist_save(key: &str, contents: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("local storage is not available")?
+        .set_item(key, contents)
+        .map_err(|_| "could not write to local storage".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_save(key: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.remove_item(key).is_err() {
+            warn!("could not delete save data from local storage");
+        }
+    }
+}
+
+#[derive(Component)]
+struct SaveNoticeText(Timer);
+
+fn show_save_notices(
+    mut commands: Commands,
+    mut notices: EventReader<SaveNotice>,
+    preloaded: Res<Preloaded>,
+) {
+    for (i, notice) in notices.iter().enumerate() {
+        commands.spawn((
+            SaveNoticeText(Timer::from_seconds(NOTICE_SECONDS, TimerMode::Once)),
+            TextBundle {
+                // above the slot select screen
+                z_index: ZIndex::Global(20),
+                ..TextBundle::from_section(
+                    notice.0.clone(),
+                    TextStyle {
+                        font: preloaded.get("font"),
+                        font_size: 20.0,
+                        color: Color::rgb(1.0, 0.8, 0.4),
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(20.0 + 28.0 * i as f32),
+                        left: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+            },
+        ));
+    }
+}
+
+fn fade_save_notices(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notices: Query<(Entity, &mut SaveNoticeText, &mut Text)>,
+) {
+    for (e, mut notice, mut text) in &mut notices {
+        if notice.0.tick(time.raw_delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        for section in &mut text.sections {
+            section
+                .style
+                .color
+                .set_a(notice.0.percent_left().min(0.25) * 4.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{decode, encode, SaveData};
+    use crate::settings::{KeyBindings, PlayerSettings};
+
+    #[test]
+    fn saves_round_trip_enums() {
+        let save = SaveData {
+            deaths: 3,
+            settings: PlayerSettings {
+                bindings: KeyBindings {
+                    jump: Some(KeyCode::J),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        };
+        let loaded = decode(&encode(&save).unwrap()).unwrap();
+        assert_eq!(loaded.settings, save.settings);
+        assert_eq!(loaded.deaths, 3);
+    }
+}
@@ -0,0 +1,115 @@
+//! Upgrades saves written by older versions of the game to the current format.
+//!
+//! When `SaveData` changes in a way `#[serde(default)]` can't cover (a renamed
+//! or restructured field, or a new field that should be filled in from old
+//! data), append a migration to `MIGRATIONS` and add a test for it.
+//!
+//! Saves are always read straight into `SaveData`, migrations then fix it up.
+//! Only the version, and fields `SaveData` no longer has, are read from ron's
+//! untyped `Value`, it drops the names of enum variants.
+use ron::{Map, Value};
+
+use crate::save::SaveData;
+
+/// Each entry upgrades a save from version `index + 1` to the next version,
+/// given the save as it was written for fields `SaveData` no longer has.
+/// Saves written before versions were recorded are version 1.
+const MIGRATIONS: &[fn(&mut SaveData, &Map) -> Result<(), String>] = &[v1_to_v2];
+
+/// version saves are written with
+pub const SAVE_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+fn key(name: &str) -> Value {
+    Value::String(name.to_string())
+}
+
+fn field<'a>(save: &'a Map, name: &str) -> Option<&'a Value> {
+    let name = key(name);
+    save.iter()
+        .find_map(|(key, value)| (*key == name).then_some(value))
+}
+
+/// read a save written by any version up to `SAVE_VERSION`
+pub fn migrate(body: &str) -> Result<SaveData, String> {
+    let Value::Map(written) = ron::from_str(body).map_err(|e| e.to_string())? else {
+        return Err("save is not a struct".to_string());
+    };
+    let version = match field(&written, "version") {
+        None => 1,
+        Some(Value::Number(version)) => version
+            .as_i64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| format!("invalid save version {version:?}"))?,
+        Some(version) => return Err(format!("invalid save version {version:?}")),
+    };
+    if version > SAVE_VERSION {
+        return Err(format!(
+            "save is version {version}, newer than this game's version {SAVE_VERSION}"
+        ));
+    }
+
+    let mut save: SaveData = ron::from_str(body).map_err(|e| e.to_string())?;
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut save, &written)?;
+    }
+    save.version = SAVE_VERSION;
+    Ok(save)
+}
+
+/// Levels weren't tracked as completed before save slots, but any level with a
+/// best flip count was finished.
+fn v1_to_v2(save: &mut SaveData, _: &Map) -> Result<(), String> {
+    let finished: Vec<usize> = save.best_flips.keys().copied().collect();
+    save.completed_levels.extend(finished);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::KeyCode;
+
+    use super::{migrate, SAVE_VERSION};
+
+    #[test]
+    fn unversioned_saves_are_version_1() {
+        let save = migrate("(deaths: 4)").unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.deaths, 4);
+    }
+
+    #[test]
+    fn v1_to_v2_completes_levels_with_best_flips() {
+        let save = migrate("(best_flips: {2: 5, 7: 1}, completed_levels: [7, 9])").unwrap();
+        let mut completed: Vec<_> = save.completed_levels.into_iter().collect();
+        completed.sort();
+        assert_eq!(completed, vec![2, 7, 9]);
+        assert_eq!(save.best_flips.get(&2), Some(&5));
+    }
+
+    #[test]
+    fn current_saves_are_unchanged() {
+        let save = migrate(&format!(
+            "(version: {SAVE_VERSION}, deaths: 2, completed_levels: [1], playtime: 30.5)"
+        ))
+        .unwrap();
+        assert_eq!(save.deaths, 2);
+        assert_eq!(save.completed_levels.len(), 1);
+        assert_eq!(save.playtime, 30.5);
+    }
+
+    #[test]
+    fn old_saves_keep_rebound_keys() {
+        let save =
+            migrate("(deaths: 1, settings: (bindings: (jump: Some(J), left: Some(A))))").unwrap();
+        assert_eq!(save.settings.bindings.jump, Some(KeyCode::J));
+        assert_eq!(save.settings.bindings.left, Some(KeyCode::A));
+        assert_eq!(save.settings.bindings.right, None);
+    }
+
+    #[test]
+    fn newer_saves_are_rejected() {
+        assert!(migrate(&format!("(version: {})", SAVE_VERSION + 1)).is_err());
+        assert!(migrate("(version: 0)").is_err());
+    }
+}
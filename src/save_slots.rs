@@ -0,0 +1,235 @@
+use bevy::prelude::*;
+
+use crate::{
+    accessibility::MenuLabel,
+    game_state::GameState,
+    news::news_closed,
+    preload::Preloaded,
+    save::{
+        delete_slot, load_slot, slot_selected, write_slot, ActiveSlot, SaveData, SaveNotice,
+        SLOT_COUNT,
+    },
+    settings::settings_closed,
+    start_menu::MenuMarker,
+};
+
+pub struct SaveSlotsPlugin;
+impl Plugin for SaveSlotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SlotMenu>().add_systems(
+            (
+                show_slot_screen,
+                slot_screen_input.run_if(not(slot_selected)),
+                update_slot_screen,
+                change_slot
+                    .run_if(slot_selected)
+                    .run_if(news_closed)
+                    .run_if(settings_closed),
+            )
+                .chain()
+                .distributive_run_if(in_state(GameState::StartMenu)),
+        );
+    }
+}
+
+/// what the slot select screen is showing
+#[derive(Resource, Default)]
+struct SlotMenu {
+    selected: usize,
+    slots: [Option<SaveData>; SLOT_COUNT],
+    /// delete was pressed once, pressing it again deletes the selected slot
+    confirm_delete: bool,
+}
+
+impl SlotMenu {
+    fn refresh(&mut self, notices: &mut EventWriter<SaveNotice>) {
+        for (slot, data) in self.slots.iter_mut().enumerate() {
+            *data = load_slot(slot, notices);
+        }
+    }
+}
+
+/// root of the slot select screen, shown over the start menu until a slot is picked
+#[derive(Component)]
+struct SlotScreen;
+
+#[derive(Component)]
+struct SlotLabel(usize);
+
+#[derive(Component)]
+struct SlotHint;
+
+fn summary(data: &Option<SaveData>) -> String {
+    let Some(data) = data else {
+        return "Empty".to_string();
+    };
+    let seconds = data.playtime as u32;
+    format!(
+        "{} levels  {}:{:02}:{:02}  {} deaths",
+        data.completed_levels.len(),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        data.deaths
+    )
+}
+
+fn show_slot_screen(
+    mut commands: Commands,
+    slot: Res<ActiveSlot>,
+    mut menu: ResMut<SlotMenu>,
+    mut notices: EventWriter<SaveNotice>,
+    screens: Query<Entity, With<SlotScreen>>,
+    preloaded: Res<Preloaded>,
+) {
+    match (slot.0, screens.get_single()) {
+        (Some(_), Ok(screen)) => commands.entity(screen).despawn_recursive(),
+        (None, Err(_)) => {
+            menu.refresh(&mut notices);
+            menu.confirm_delete = false;
+            let text = |size| TextStyle {
+                font: preloaded.get("font"),
+                font_size: size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            };
+            commands
+                .spawn((
+                    MenuMarker,
+                    SlotScreen,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            gap: Size::height(Val::Px(16.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.05, 0.05, 0.08, 0.95).into(),
+                        z_index: ZIndex::Global(10),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        MenuMarker,
+                        TextBundle::from_section("Select a Save", text(40.0)),
+                    ));
+                    for slot in 0..SLOT_COUNT {
+                        parent.spawn((
+                            MenuMarker,
+                            SlotLabel(slot),
+                            MenuLabel::default(),
+                            TextBundle::from_section("", text(24.0)),
+                        ));
+                    }
+                    parent.spawn((
+                        MenuMarker,
+                        SlotHint,
+                        MenuLabel::default(),
+                        TextBundle::from_section("", text(18.0)),
+                    ));
+                });
+        }
+        _ => {}
+    }
+}
+
+fn slot_screen_input(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut menu: ResMut<SlotMenu>,
+    mut notices: EventWriter<SaveNotice>,
+    mut slot: ResMut<ActiveSlot>,
+    mut save: ResMut<SaveData>,
+) {
+    let pad = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+    // released rather than pressed so the same press doesn't also start the game
+    let pad_released = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_released(GamepadButton::new(gamepad, button)))
+    };
+
+    if keyboard.any_just_pressed([KeyCode::Up, KeyCode::W]) || pad(GamepadButtonType::DPadUp) {
+        menu.selected = (menu.selected + SLOT_COUNT - 1) % SLOT_COUNT;
+        menu.confirm_delete = false;
+    }
+    if keyboard.any_just_pressed([KeyCode::Down, KeyCode::S]) || pad(GamepadButtonType::DPadDown) {
+        menu.selected = (menu.selected + 1) % SLOT_COUNT;
+        menu.confirm_delete = false;
+    }
+
+    let selected = menu.selected;
+    if keyboard.just_pressed(KeyCode::C) || pad(GamepadButtonType::West) {
+        // copy into the first empty slot
+        if let (Some(data), Some(empty)) = (
+            menu.slots[selected].clone(),
+            menu.slots.iter().position(Option::is_none),
+        ) {
+            write_slot(empty, &data);
+            menu.refresh(&mut notices);
+        }
+    }
+    if keyboard.any_just_pressed([KeyCode::Delete, KeyCode::X]) || pad(GamepadButtonType::North) {
+        if menu.confirm_delete {
+            delete_slot(selected);
+            menu.refresh(&mut notices);
+            menu.confirm_delete = false;
+        } else if menu.slots[selected].is_some() {
+            menu.confirm_delete = true;
+        }
+    }
+
+    if keyboard.any_just_released([KeyCode::Return, KeyCode::Space])
+        || pad_released(GamepadButtonType::South)
+    {
+        *save = menu.slots[selected].clone().unwrap_or_default();
+        slot.0 = Some(selected);
+    }
+}
+
+/// the delete confirmation is read out with the slot labels
+fn update_slot_screen(
+    menu: Res<SlotMenu>,
+    mut labels: Query<(&SlotLabel, &mut Text, &mut MenuLabel), Without<SlotHint>>,
+    mut hints: Query<(&mut Text, &mut MenuLabel), With<SlotHint>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+    for (label, mut text, mut menu_label) in &mut labels {
+        let selected = label.0 == menu.selected;
+        let cursor = if selected { ">" } else { " " };
+        let slot = format!("Slot {}: {}", label.0 + 1, summary(&menu.slots[label.0]));
+        text.sections[0].value = format!("{cursor} {slot}");
+        let announced = MenuLabel::new(slot, selected);
+        if *menu_label != announced {
+            *menu_label = announced;
+        }
+    }
+    for (mut text, mut menu_label) in &mut hints {
+        text.sections[0].value = if menu.confirm_delete {
+            "Press Delete again to erase this slot".to_string()
+        } else {
+            "Enter: Play   C: Copy   Delete: Erase".to_string()
+        };
+        let announced = MenuLabel::new(text.sections[0].value.clone(), menu.confirm_delete);
+        if *menu_label != announced {
+            *menu_label = announced;
+        }
+    }
+}
+
+/// go back to the slot select screen from the start menu
+fn change_slot(keyboard: Res<Input<KeyCode>>, mut slot: ResMut<ActiveSlot>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        slot.0 = None;
+    }
+}
@@ -0,0 +1,510 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::MenuLabel,
+    config::AudioConfig,
+    game_state::GameState,
+    keyboard_layout::{self, KeyLayout},
+    news::news_closed,
+    player::{jump_input_map, movement_input_map, JumpAction, MovementAction, Player},
+    preload::Preloaded,
+    save::{slot_selected, SaveData},
+    start_menu::MenuMarker,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const SELECTED_COLOR: Color = Color::rgb(1.0, 0.8, 0.4);
+/// how much one press of left or right changes a volume
+const VOLUME_STEP: f32 = 0.1;
+/// keys that keep their action whatever the bindings are, see `movement_input_map`
+const FIXED_KEYS: [(KeyCode, &str); 5] = [
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::E, "Grab"),
+];
+
+/// Press O on the start menu for volume and key binding settings. They're
+/// kept in the save slot, see `PlayerSettings`.
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerSettings>()
+            .add_system(sync_player_settings)
+            .add_system(apply_key_bindings)
+            .add_system(spawn_settings_hint.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems(
+                (settings_input, update_settings_rows)
+                    .chain()
+                    .distributive_run_if(in_state(GameState::StartMenu))
+                    .distributive_run_if(slot_selected)
+                    .distributive_run_if(news_closed),
+            );
+    }
+}
+
+/// Settings changed in the settings menu. Anything left at `None` uses the
+/// default from settings.game.ron.
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PlayerSettings {
+    pub master_volume: Option<f32>,
+    pub sfx_volume: Option<f32>,
+    pub bindings: KeyBindings,
+}
+
+/// keys replacing the default keyboard binding of each action, the arrow keys
+/// and gamepad always work so the game can't be made unplayable
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub jump: Option<KeyCode>,
+    pub left: Option<KeyCode>,
+    pub right: Option<KeyCode>,
+    pub up: Option<KeyCode>,
+    pub down: Option<KeyCode>,
+}
+
+/// whether the settings panel is closed, for start menu inputs it would otherwise swallow
+pub fn settings_closed(panels: Query<(), With<SettingsPanel>>) -> bool {
+    panels.is_empty()
+}
+
+/// Kept in the save so each slot has its own settings. Changes made to
+/// either are copied to the other, like `WorldFlags`.
+fn sync_player_settings(mut settings: ResMut<PlayerSettings>, mut save: ResMut<SaveData>) {
+    if settings.is_changed() && save.settings != *settings {
+        save.settings = settings.clone();
+    } else if save.is_changed() && save.settings != *settings {
+        *settings = save.settings.clone();
+    }
+}
+
+/// rebuild the player's input maps when the bindings change, keeping the claimed gamepad
+fn apply_key_bindings(
+    settings: Res<PlayerSettings>,
+    mut jump: Query<&mut InputMap<JumpAction>, With<Player>>,
+    mut movement: Query<&mut InputMap<MovementAction>, With<Player>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut map in &mut jump {
+        let gamepad = map.gamepad();
+        *map = jump_input_map(&settings.bindings);
+        if let Some(gamepad) = gamepad {
+            map.set_gamepad(gamepad);
+        }
+    }
+    for mut map in &mut movement {
+        let gamepad = map.gamepad();
+        *map = movement_input_map(&settings.bindings);
+        if let Some(gamepad) = gamepad {
+            map.set_gamepad(gamepad);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bindable {
+    Jump,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsItem {
+    MasterVolume,
+    SfxVolume,
+    Key(Bindable),
+    ResetKeys,
+}
+
+const BINDABLE: [Bindable; 5] = [
+    Bindable::Jump,
+    Bindable::Left,
+    Bindable::Right,
+    Bindable::Up,
+    Bindable::Down,
+];
+
+const ITEMS: [SettingsItem; 8] = [
+    SettingsItem::MasterVolume,
+    SettingsItem::SfxVolume,
+    SettingsItem::Key(Bindable::Jump),
+    SettingsItem::Key(Bindable::Left),
+    SettingsItem::Key(Bindable::Right),
+    SettingsItem::Key(Bindable::Up),
+    SettingsItem::Key(Bindable::Down),
+    SettingsItem::ResetKeys,
+];
+
+impl KeyBindings {
+    fn get_mut(&mut self, action: Bindable) -> &mut Option<KeyCode> {
+        match action {
+            Bindable::Jump => &mut self.jump,
+            Bindable::Left => &mut self.left,
+            Bindable::Right => &mut self.right,
+            Bindable::Up => &mut self.up,
+            Bindable::Down => &mut self.down,
+        }
+    }
+
+    /// key bound to `action`, the default key on the active layout if unbound
+    pub fn key_code(&self, action: Bindable, layout: &KeyLayout) -> KeyCode {
+        let bound = match action {
+            Bindable::Jump => self.jump,
+            Bindable::Left => self.left,
+            Bindable::Right => self.right,
+            Bindable::Up => self.up,
+            Bindable::Down => self.down,
+        };
+        bound.unwrap_or_else(|| match action {
+            Bindable::Jump => KeyCode::Space,
+            Bindable::Left => keyboard_layout::LEFT.key_code(layout),
+            Bindable::Right => keyboard_layout::RIGHT.key_code(layout),
+            Bindable::Up => keyboard_layout::UP.key_code(layout),
+            Bindable::Down => keyboard_layout::DOWN.key_code(layout),
+        })
+    }
+
+    /// name of the key bound to `action`, see `key_code`
+    pub fn display_name(&self, action: Bindable, layout: &KeyLayout) -> String {
+        format!("{:?}", self.key_code(action, layout))
+    }
+
+    /// Binds `key` to `action`, unless another action already uses it. The
+    /// error is the name of that action.
+    pub fn rebind(
+        &mut self,
+        action: Bindable,
+        key: KeyCode,
+        layout: &KeyLayout,
+    ) -> Result<(), String> {
+        let action_name = format!("{action:?}");
+        if let Some((_, other)) = FIXED_KEYS
+            .iter()
+            .find(|(fixed, name)| *fixed == key && *name != action_name)
+        {
+            return Err(other.to_string());
+        }
+        if let Some(other) = BINDABLE
+            .into_iter()
+            .find(|other| *other != action && self.key_code(*other, layout) == key)
+        {
+            return Err(format!("{other:?}"));
+        }
+        *self.get_mut(action) = Some(key);
+        Ok(())
+    }
+}
+
+fn adjust(volume: Option<f32>, default: f32, step: f32) -> Option<f32> {
+    let volume = (volume.unwrap_or(default) + step).clamp(0.0, 1.0);
+    // rounded so repeated steps don't drift off the tenths
+    Some((volume * 10.0).round() / 10.0)
+}
+
+#[derive(Component)]
+pub struct SettingsPanel {
+    cursor: usize,
+    /// waiting for the key to bind to the selected action
+    listening: bool,
+    /// the last key pressed while listening and the action already using it
+    clash: Option<(KeyCode, String)>,
+}
+
+#[derive(Component)]
+struct SettingsRow(usize);
+
+fn spawn_settings_hint(mut commands: Commands, preloaded: Res<Preloaded>) {
+    commands.spawn((
+        MenuMarker,
+        TextBundle::from_section(
+            "O: Settings",
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(80.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn spawn_settings_panel(commands: &mut Commands, font: Handle<Font>) {
+    commands
+        .spawn((
+            MenuMarker,
+            SettingsPanel {
+                cursor: 0,
+                listening: false,
+                clash: None,
+            },
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Percent(20.0),
+                        top: Val::Percent(15.0),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(60.0), Val::Percent(70.0)),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(24.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.08, 0.95).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                MenuMarker,
+                TextBundle::from_section(
+                    "Settings",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 36.0,
+                        color: TEXT_COLOR,
+                    },
+                ),
+            ));
+            for i in 0..ITEMS.len() {
+                parent.spawn((
+                    MenuMarker,
+                    SettingsRow(i),
+                    // filled in by update_settings_rows
+                    MenuLabel::default(),
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 24.0,
+                            color: TEXT_COLOR,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(12.0)),
+                        ..default()
+                    }),
+                ));
+            }
+            parent.spawn((
+                MenuMarker,
+                TextBundle::from_section(
+                    "Up/Down: pick  Left/Right: volume  Enter: rebind  Esc: close",
+                    TextStyle {
+                        font,
+                        font_size: 18.0,
+                        color: TEXT_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::top(Val::Px(24.0)),
+                    ..default()
+                }),
+            ));
+        });
+}
+
+fn settings_input(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    audio: Res<AudioConfig>,
+    mut settings: ResMut<PlayerSettings>,
+    mut panels: Query<(Entity, &mut SettingsPanel)>,
+    preloaded: Res<Preloaded>,
+    layout: Res<KeyLayout>,
+) {
+    let Ok((e, mut panel)) = panels.get_single_mut() else {
+        if keyboard.just_pressed(KeyCode::O) {
+            spawn_settings_panel(&mut commands, preloaded.get("font"));
+        }
+        return;
+    };
+    let pressed = |key, button| {
+        keyboard.just_pressed(key)
+            || gamepads
+                .iter()
+                .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+
+    if panel.listening {
+        // released so the escape doesn't also close the panel
+        if keyboard.just_released(KeyCode::Escape) {
+            panel.listening = false;
+            panel.clash = None;
+        } else if let Some(&key) = keyboard
+            .get_just_pressed()
+            .find(|key| **key != KeyCode::Escape)
+        {
+            let SettingsItem::Key(action) = ITEMS[panel.cursor] else {
+                return;
+            };
+            // a key already in use keeps listening for another
+            let mut bindings = settings.bindings.clone();
+            match bindings.rebind(action, key, &layout) {
+                Ok(()) => {
+                    settings.bindings = bindings;
+                    panel.listening = false;
+                    panel.clash = None;
+                }
+                Err(other) => panel.clash = Some((key, other)),
+            }
+        }
+        return;
+    }
+
+    // released rather than pressed so the same escape doesn't go back to the slot screen
+    if keyboard.just_pressed(KeyCode::O)
+        || keyboard.just_released(KeyCode::Escape)
+        || pressed(KeyCode::Back, GamepadButtonType::East)
+    {
+        commands.entity(e).despawn_recursive();
+        return;
+    }
+    if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        panel.cursor = (panel.cursor + ITEMS.len() - 1) % ITEMS.len();
+    }
+    if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        panel.cursor = (panel.cursor + 1) % ITEMS.len();
+    }
+    let step = if pressed(KeyCode::Left, GamepadButtonType::DPadLeft) {
+        -VOLUME_STEP
+    } else if pressed(KeyCode::Right, GamepadButtonType::DPadRight) {
+        VOLUME_STEP
+    } else {
+        0.0
+    };
+    let confirm = pressed(KeyCode::Return, GamepadButtonType::South);
+    match ITEMS[panel.cursor] {
+        SettingsItem::MasterVolume if step != 0.0 => {
+            settings.master_volume = adjust(settings.master_volume, audio.master, step);
+        }
+        SettingsItem::SfxVolume if step != 0.0 => {
+            settings.sfx_volume = adjust(settings.sfx_volume, audio.sfx, step);
+        }
+        SettingsItem::Key(_) if confirm => panel.listening = true,
+        SettingsItem::ResetKeys if confirm => settings.bindings = KeyBindings::default(),
+        _ => {}
+    }
+}
+
+fn update_settings_rows(
+    panels: Query<&SettingsPanel>,
+    settings: Res<PlayerSettings>,
+    audio: Res<AudioConfig>,
+    layout: Res<KeyLayout>,
+    mut rows: Query<(&SettingsRow, &mut Text, &mut MenuLabel)>,
+) {
+    let Ok(panel) = panels.get_single() else {
+        return;
+    };
+    let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+    for (row, mut text, mut label) in &mut rows {
+        let selected = row.0 == panel.cursor;
+        let value = match ITEMS[row.0] {
+            SettingsItem::MasterVolume => format!(
+                "Master volume: {}",
+                percent(settings.master_volume.unwrap_or(audio.master))
+            ),
+            SettingsItem::SfxVolume => format!(
+                "Sound effects volume: {}",
+                percent(settings.sfx_volume.unwrap_or(audio.sfx))
+            ),
+            SettingsItem::Key(action) if selected && panel.listening => match &panel.clash {
+                Some((key, other)) => {
+                    format!("{action:?}: {key:?} is used by {other}, press another key")
+                }
+                None => format!("{action:?}: press a key, Esc to cancel"),
+            },
+            SettingsItem::Key(action) => {
+                format!(
+                    "{action:?}: {}",
+                    settings.bindings.display_name(action, &layout)
+                )
+            }
+            SettingsItem::ResetKeys => "Reset keys".to_string(),
+        };
+        if label.text != value || label.selected != selected {
+            *label = MenuLabel::new(value.clone(), selected);
+        }
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        let color = if selected { SELECTED_COLOR } else { TEXT_COLOR };
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_steps_from_the_default_and_clamps() {
+        assert_eq!(adjust(None, 0.6, VOLUME_STEP), Some(0.7));
+        assert_eq!(adjust(Some(1.0), 0.6, VOLUME_STEP), Some(1.0));
+        assert_eq!(adjust(Some(0.1), 0.6, -VOLUME_STEP), Some(0.0));
+    }
+
+    // tests for `KeyBindings::rebind`
+    mod rebind {
+        use super::*;
+
+        #[test]
+        fn rejects_a_key_another_action_uses() {
+            let layout = KeyLayout::default();
+            let mut bindings = KeyBindings::default();
+            assert_eq!(
+                bindings.rebind(Bindable::Up, KeyCode::Space, &layout),
+                Err("Jump".to_string())
+            );
+            assert_eq!(
+                bindings.rebind(Bindable::Jump, KeyCode::E, &layout),
+                Err("Grab".to_string())
+            );
+            assert_eq!(
+                bindings.rebind(Bindable::Jump, KeyCode::Down, &layout),
+                Err("Down".to_string())
+            );
+            assert_eq!(bindings, KeyBindings::default());
+        }
+
+        #[test]
+        fn allows_a_free_key_or_the_actions_own() {
+            let layout = KeyLayout::default();
+            let mut bindings = KeyBindings::default();
+            assert_eq!(
+                bindings.rebind(Bindable::Left, KeyCode::Left, &layout),
+                Ok(())
+            );
+            assert_eq!(bindings.rebind(Bindable::Jump, KeyCode::J, &layout), Ok(()));
+            // free now jump has moved off it
+            assert_eq!(
+                bindings.rebind(Bindable::Up, KeyCode::Space, &layout),
+                Ok(())
+            );
+            assert_eq!(bindings.up, Some(KeyCode::Space));
+        }
+    }
+}
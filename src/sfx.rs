@@ -1,25 +1,753 @@
-use bevy::prelude::*;
+use std::cmp::Reverse;
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Sample, Source},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypeUuid,
+};
+
+use crate::{
+    audio_fade::AudioFades,
+    camera::MainCamera,
+    config::{AccessibilitySettings, AudioConfig},
+    explosions::Explosion,
+    game_state::GameState,
+    goals::GoalCollected,
+    ground::{BlockFell, SurfaceMaterial},
+    player::{Player, PlayerDied, PlayerJumped},
+    preload::Preloaded,
+    settings::PlayerSettings,
+    surfaces::SurfaceEffects,
+};
+
+/// emitters closer than this to the listener play at full volume
+const FULL_VOLUME_DISTANCE: f32 = 150.0;
+/// emitters this far away or further play at `MIN_VOLUME`
+const MIN_VOLUME_DISTANCE: f32 = 720.0;
+const MIN_VOLUME: f32 = 0.2;
+/// emitters this far to the side of the listener or further are panned by `MAX_PAN`
+const FULL_PAN_DISTANCE: f32 = 480.0;
+/// how far sounds can be panned, 1.0 would leave the other ear silent
+const MAX_PAN: f32 = 0.6;
+/// how fast music ducks and restores, in volume per second
+const DUCK_SPEED: f32 = 2.0;
+/// voices are forgotten after this long, bevy's sinks can't tell when they've finished
+const MAX_VOICE_SECONDS: f32 = 4.0;
 
 pub struct SfxPlugin;
 impl Plugin for SfxPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup);
+        app.init_resource::<AudioBuses>()
+            .init_resource::<ActiveVoices>()
+            .add_audio_source::<MonoAudio>()
+            .add_audio_source::<PannedAudio>()
+            .add_event::<PlaySfx>()
+            .add_system(gameplay_sfx.in_base_set(CoreSet::PostUpdate))
+            .add_system(
+                play_sfx
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(gameplay_sfx),
+            )
+            .add_system(duck_music)
+            .add_system(apply_audio_config.run_if(
+                resource_changed::<AudioConfig>().or_else(resource_changed::<PlayerSettings>()),
+            ));
     }
 }
 
+/// volume multipliers for the different kinds of audio, final volume of a
+/// sound is its own volume * its bus * master
 #[derive(Resource)]
-pub struct SfxHandles {
-    pub jump: Handle<AudioSource>,
-    pub goal: Handle<AudioSource>,
-    pub death: Handle<AudioSource>,
+pub struct AudioBuses {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    /// how much the music is lowered while something with [`DuckMusic`] exists
+    pub duck_amount: f32,
+    /// current multiplier from ducking, fades between 1.0 and 1.0 - `duck_amount`
+    ducked: f32,
+    /// lowered to 0.0 while the game is quitting
+    pub fade: f32,
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let handles = SfxHandles {
-        jump: asset_server.load("sfx/jump.ogg"),
-        goal: asset_server.load("sfx/coin.ogg"),
-        death: asset_server.load("sfx/death.ogg"),
+impl Default for AudioBuses {
+    fn default() -> Self {
+        AudioBuses {
+            master: 1.0,
+            music: 0.6,
+            sfx: 1.0,
+            duck_amount: 0.5,
+            ducked: 1.0,
+            fade: 1.0,
+        }
+    }
+}
+
+impl AudioBuses {
+    pub fn music_volume(&self) -> f32 {
+        self.master * self.music * self.ducked * self.fade
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master * self.sfx * self.fade
+    }
+}
+
+/// Reset the buses to the defaults from the game config when it's (re)loaded,
+/// volumes set in the settings menu take precedence.
+fn apply_audio_config(
+    config: Res<AudioConfig>,
+    settings: Res<PlayerSettings>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    buses.master = settings.master_volume.unwrap_or(config.master);
+    buses.music = config.music;
+    buses.sfx = settings.sfx_volume.unwrap_or(config.sfx);
+    buses.duck_amount = config.duck_amount;
+}
+
+/// Add to dialogs, cutscenes and menus that should lower the music while they're open
+#[derive(Component, Default)]
+pub struct DuckMusic;
+
+fn duck_music(time: Res<Time>, mut buses: ResMut<AudioBuses>, duckers: Query<(), With<DuckMusic>>) {
+    let target = if duckers.is_empty() {
+        1.0
+    } else {
+        1.0 - buses.duck_amount.clamp(0.0, 1.0)
     };
+    let step = DUCK_SPEED * time.delta_seconds();
+    buses.ducked += (target - buses.ducked).clamp(-step, step);
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Sfx {
+    Jump,
+    Goal,
+    Death,
+    JumpPad,
+    BlockFell,
+    Explosion,
+    /// sounds for these come from the surface table
+    Footstep(SurfaceMaterial),
+    Land(SurfaceMaterial),
+}
+
+/// Send to play a sound effect. Sounds with a position get quieter the further
+/// they are from the player, and are panned toward the side of the screen
+/// they're on.
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    pub position: Option<Vec2>,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        PlaySfx {
+            sfx,
+            position: None,
+        }
+    }
+
+    pub fn at(sfx: Sfx, position: Vec2) -> Self {
+        PlaySfx {
+            sfx,
+            position: Some(position),
+        }
+    }
+}
+
+impl Sfx {
+    /// name used for priorities and voice limits in the audio config
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Sfx::Jump => "jump",
+            Sfx::Goal => "goal",
+            Sfx::Death => "death",
+            Sfx::JumpPad => "jump_pad",
+            Sfx::BlockFell => "block_fell",
+            Sfx::Explosion => "explosion",
+            Sfx::Footstep(_) => "footstep",
+            Sfx::Land(_) => "land",
+        }
+    }
+
+    /// sounds that aren't from the surface table are in the asset manifest
+    fn handle(
+        &self,
+        preloaded: &Preloaded,
+        surfaces: &SurfaceEffects,
+    ) -> Option<Handle<AudioSource>> {
+        let label = match self {
+            Sfx::Jump => "sfx_jump",
+            Sfx::Goal => "sfx_goal",
+            Sfx::Death => "sfx_death",
+            Sfx::JumpPad => "sfx_jump_pad",
+            Sfx::BlockFell => "sfx_block_fell",
+            Sfx::Explosion => "sfx_explosion",
+            Sfx::Footstep(material) => return surfaces.footstep(*material),
+            Sfx::Land(material) => return surfaces.land(*material),
+        };
+        Some(preloaded.get(label))
+    }
+}
+
+/// volume multiplier for a sound `distance` away from the listener
+fn attenuation(distance: f32) -> f32 {
+    let t = ((distance - FULL_VOLUME_DISTANCE) / (MIN_VOLUME_DISTANCE - FULL_VOLUME_DISTANCE))
+        .clamp(0.0, 1.0);
+    1.0 - t * (1.0 - MIN_VOLUME)
+}
+
+/// Stereo position for a sound `offset` to the listener's right, -1.0 is only
+/// the left ear and 1.0 only the right.
+fn pan(offset: f32) -> f32 {
+    (offset / FULL_PAN_DISTANCE).clamp(-1.0, 1.0) * MAX_PAN
+}
+
+/// pick the sound for each gameplay event
+fn gameplay_sfx(
+    mut jumped: EventReader<PlayerJumped>,
+    mut died: EventReader<PlayerDied>,
+    mut collected: EventReader<GoalCollected>,
+    mut fell: EventReader<BlockFell>,
+    mut explosions: EventReader<Explosion>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for _ in &mut jumped {
+        sfx.send(PlaySfx::new(Sfx::Jump));
+    }
+    for _ in &mut died {
+        sfx.send(PlaySfx::new(Sfx::Death));
+    }
+    for goal in &mut collected {
+        sfx.send(PlaySfx::at(Sfx::Goal, goal.position));
+    }
+    for block in &mut fell {
+        sfx.send(PlaySfx::at(Sfx::BlockFell, block.position));
+    }
+    for explosion in &mut explosions {
+        sfx.send(PlaySfx::at(Sfx::Explosion, explosion.position));
+    }
+}
+
+/// sound effects started by `play_sfx`, oldest first, kept for
+/// `MAX_VOICE_SECONDS` so each kind's voices can be counted
+#[derive(Resource, Default)]
+struct ActiveVoices(Vec<Voice>);
+
+struct Voice {
+    kind: &'static str,
+    started: f32,
+    sink: Handle<AudioSink>,
+}
+
+/// Takes the oldest voices of `kind` out of `voices` until at most `keep` are
+/// left, returning their sinks to be stopped. Stopping a finished sound does
+/// nothing, so voices that already ended are safe to steal.
+fn steal_voices(voices: &mut Vec<Voice>, kind: &str, keep: usize) -> Vec<Handle<AudioSink>> {
+    let playing = voices.iter().filter(|voice| voice.kind == kind).count();
+    let mut steal = playing.saturating_sub(keep);
+    let mut stolen = Vec::new();
+    voices.retain(|voice| {
+        if steal == 0 || voice.kind != kind {
+            return true;
+        }
+        steal -= 1;
+        stolen.push(voice.sink.clone());
+        false
+    });
+    stolen
+}
+
+/// a sound asked for this frame
+struct Mixed {
+    sfx: Sfx,
+    handle: Handle<AudioSource>,
+    falloff: f32,
+    pan: f32,
+}
+
+/// Picks which of this frame's sounds play. Sounds of a kind already asked for
+/// this frame are merged into one, keeping the loudest. Then the highest
+/// priority kinds are kept, up to `max_sfx_per_frame`. Kinds at their voice
+/// limit still play, `play_sfx` stops their oldest voice to make room.
+fn mix(requests: impl IntoIterator<Item = Mixed>, config: &AudioConfig) -> Vec<Mixed> {
+    let mut merged: Vec<Mixed> = Vec::new();
+    for request in requests {
+        match merged
+            .iter_mut()
+            .find(|m| m.sfx.kind() == request.sfx.kind())
+        {
+            Some(m) if request.falloff > m.falloff => *m = request,
+            Some(_) => {}
+            None => merged.push(request),
+        }
+    }
+    // stable, so sounds with the same priority play in the order they were sent
+    merged.sort_by_key(|m| Reverse(config.sfx_kind(m.sfx.kind()).priority));
+    merged.truncate(config.max_sfx_per_frame);
+    merged
+}
+
+/// what `play_sfx` plays sounds through, plain, panned or downmixed to mono
+#[derive(SystemParam)]
+struct SfxOutput<'w> {
+    audio: Res<'w, Audio>,
+    mono_audio: Res<'w, Audio<MonoAudio>>,
+    panned_audio: Res<'w, Audio<PannedAudio>>,
+    sources: Res<'w, Assets<AudioSource>>,
+    mono_sources: ResMut<'w, Assets<MonoAudio>>,
+    panned_sources: ResMut<'w, Assets<PannedAudio>>,
+    audio_sinks: Res<'w, Assets<AudioSink>>,
+}
+
+fn play_sfx(
+    mut events: EventReader<PlaySfx>,
+    mut output: SfxOutput,
+    mut fades: ResMut<AudioFades>,
+    mut voices: ResMut<ActiveVoices>,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    accessibility: Res<AccessibilitySettings>,
+    config: Res<AudioConfig>,
+    preloaded: Res<Preloaded>,
+    surfaces: Res<SurfaceEffects>,
+    buses: Res<AudioBuses>,
+    player: Query<&GlobalTransform, With<Player>>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+) {
+    // listen from the player, or the camera when there's no player in the level
+    let listener = player
+        .iter()
+        .chain(camera.iter())
+        .next()
+        .map(|t| t.translation().truncate());
+    // the camera turns with gravity, so the screen's right isn't always the world's
+    let right = camera
+        .iter()
+        .next()
+        .map_or(Vec2::X, |t| t.right().truncate().normalize_or_zero());
+
+    let now = time.elapsed_seconds();
+    voices
+        .0
+        .retain(|voice| now - voice.started < MAX_VOICE_SECONDS);
+
+    let requests = events.iter().filter_map(|event| {
+        // surface sounds are missing until the surface table loads
+        let handle = event.sfx.handle(&preloaded, &surfaces)?;
+        let (falloff, pan) = match (event.position, listener) {
+            (Some(position), Some(listener)) => (
+                attenuation(position.distance(listener)),
+                pan(right.dot(position - listener)),
+            ),
+            _ => (1.0, 0.0),
+        };
+        Some(Mixed {
+            sfx: event.sfx,
+            handle,
+            falloff,
+            pan,
+        })
+    });
+
+    for mixed in mix(requests, &config) {
+        let kind = mixed.sfx.kind();
+        if let Some(max) = config.sfx_kind(kind).max_voices {
+            // the newest sound is the one the player just caused, so it wins
+            for sink in steal_voices(&mut voices.0, kind, max.saturating_sub(1)) {
+                if let Some(sink) = output.audio_sinks.get(&sink) {
+                    sink.stop();
+                }
+            }
+        }
+        let volume = buses.sfx_volume() * mixed.falloff;
+        let settings = PlaybackSettings::ONCE.with_volume(volume);
+        let source = output.sources.get(&mixed.handle);
+        // panning is lost when downmixed anyway
+        let sink = match source {
+            Some(source) if accessibility.mono_audio => {
+                let mono = output.mono_sources.add(MonoAudio(source.clone()));
+                output.mono_audio.play_with_settings(mono, settings)
+            }
+            None if accessibility.mono_audio => continue,
+            Some(source) if mixed.pan != 0.0 => {
+                let panned = output.panned_sources.add(PannedAudio {
+                    source: source.clone(),
+                    pan: mixed.pan,
+                });
+                output.panned_audio.play_with_settings(panned, settings)
+            }
+            _ => output.audio.play_with_settings(mixed.handle, settings),
+        };
+        let sink = output.audio_sinks.get_handle(sink);
+        voices.0.push(Voice {
+            kind,
+            started: now,
+            sink: sink.clone(),
+        });
+        // so longer sounds don't get cut off when the level ends
+        fades.track(sink, state.0.clone(), volume);
+    }
+}
+
+/// An audio source that's downmixed to mono as it plays. Every channel gets the
+/// average of all channels so nothing is lost when only one ear can hear.
+#[derive(TypeUuid, Clone)]
+#[uuid = "e3b4d0a7-5c1f-4b9e-8f26-71d9a2c4e5b3"]
+struct MonoAudio(AudioSource);
+
+impl Decodable for MonoAudio {
+    type DecoderItem = <AudioSource as Decodable>::DecoderItem;
+    type Decoder = MonoSource<<AudioSource as Decodable>::Decoder>;
+
+    fn decoder(&self) -> Self::Decoder {
+        MonoSource::new(self.0.decoder())
+    }
+}
+
+struct MonoSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    inner: S,
+    /// current frame averaged over every channel
+    mixed: S::Item,
+    /// channels of the current frame left to output
+    remaining: u16,
+}
+
+impl<S> MonoSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn new(inner: S) -> Self {
+        MonoSource {
+            inner,
+            mixed: S::Item::zero_value(),
+            remaining: 0,
+        }
+    }
+}
 
-    commands.insert_resource(handles);
+impl<S> Iterator for MonoSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            let channels = self.inner.channels().max(1);
+            let mut mixed = S::Item::zero_value();
+            for _ in 0..channels {
+                let sample = self.inner.next()?;
+                mixed = mixed.saturating_add(sample.amplify(1.0 / channels as f32));
+            }
+            self.mixed = mixed;
+            self.remaining = channels;
+        }
+
+        self.remaining -= 1;
+        Some(self.mixed)
+    }
+}
+
+impl<S> Source for MonoSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// An audio source played louder on one side, see `pan`. Mono sounds are
+/// spread over two channels so they can be panned too.
+#[derive(TypeUuid, Clone)]
+#[uuid = "7f1c2a9e-3d6b-4c85-9e0a-b52f4d8c1e76"]
+struct PannedAudio {
+    source: AudioSource,
+    pan: f32,
+}
+
+impl Decodable for PannedAudio {
+    type DecoderItem = <AudioSource as Decodable>::DecoderItem;
+    type Decoder = PanSource<<AudioSource as Decodable>::Decoder>;
+
+    fn decoder(&self) -> Self::Decoder {
+        PanSource::new(self.source.decoder(), self.pan)
+    }
+}
+
+struct PanSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    inner: S,
+    /// gain of the left and right channels, further channels are left alone
+    gains: [f32; 2],
+    /// the inner source is mono, so each of its samples is played on both sides
+    upmix: bool,
+    /// right side of the current upmixed sample, left to output
+    pending: Option<S::Item>,
+    /// channel of the next sample in the current frame
+    channel: u16,
+}
+
+impl<S> PanSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn new(inner: S, pan: f32) -> Self {
+        // the far side gets quieter, the near side stays at full volume
+        let pan = pan.clamp(-1.0, 1.0);
+        PanSource {
+            upmix: inner.channels() == 1,
+            inner,
+            gains: [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)],
+            pending: None,
+            channel: 0,
+        }
+    }
+}
+
+impl<S> Iterator for PanSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(right) = self.pending.take() {
+            return Some(right);
+        }
+
+        let sample = self.inner.next()?;
+        if self.upmix {
+            self.pending = Some(sample.amplify(self.gains[1]));
+            return Some(sample.amplify(self.gains[0]));
+        }
+        let gain = self
+            .gains
+            .get(self.channel as usize)
+            .copied()
+            .unwrap_or(1.0);
+        self.channel = (self.channel + 1) % self.inner.channels().max(1);
+        Some(sample.amplify(gain))
+    }
+}
+
+impl<S> Source for PanSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.upmix {
+            let pending = usize::from(self.pending.is_some());
+            self.inner.current_frame_len().map(|len| len * 2 + pending)
+        } else {
+            self.inner.current_frame_len()
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        if self.upmix {
+            2
+        } else {
+            self.inner.channels()
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SfxKindConfig;
+
+    fn config(kinds: &[(&str, i32, Option<usize>)], max_sfx_per_frame: usize) -> AudioConfig {
+        AudioConfig {
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            duck_amount: 0.0,
+            fade_seconds: 0.0,
+            max_sfx_per_frame,
+            sfx_kinds: kinds
+                .iter()
+                .map(|&(kind, priority, max_voices)| {
+                    (
+                        kind.to_string(),
+                        SfxKindConfig {
+                            priority,
+                            max_voices,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn request(sfx: Sfx, falloff: f32) -> Mixed {
+        Mixed {
+            sfx,
+            handle: Handle::default(),
+            falloff,
+            pan: 0.0,
+        }
+    }
+
+    #[test]
+    fn merges_same_kind_keeping_loudest() {
+        let mixed = mix(
+            [
+                request(Sfx::Goal, 0.3),
+                request(Sfx::Goal, 0.9),
+                request(Sfx::Goal, 0.5),
+            ],
+            &config(&[], 4),
+        );
+        assert_eq!(mixed.len(), 1);
+        assert_eq!(mixed[0].falloff, 0.9);
+    }
+
+    #[test]
+    fn drops_low_priority_kinds() {
+        let config = config(
+            &[
+                ("death", 10, None),
+                ("goal", 5, Some(1)),
+                ("footstep", 1, None),
+            ],
+            2,
+        );
+        let mixed = mix(
+            [
+                request(Sfx::Footstep(SurfaceMaterial::default()), 1.0),
+                request(Sfx::Goal, 1.0),
+                request(Sfx::BlockFell, 1.0),
+                request(Sfx::Death, 1.0),
+            ],
+            &config,
+        );
+        let kinds: Vec<_> = mixed.iter().map(|m| m.sfx.kind()).collect();
+        assert_eq!(kinds, ["death", "goal"]);
+    }
+
+    fn voice(kind: &'static str, started: f32) -> Voice {
+        Voice {
+            kind,
+            started,
+            sink: Handle::default(),
+        }
+    }
+
+    #[test]
+    fn full_kinds_lose_their_oldest_voices() {
+        let mut voices = vec![
+            voice("jump", 0.0),
+            voice("footstep", 0.5),
+            voice("jump", 1.0),
+            voice("jump", 2.0),
+        ];
+        // room for the new jump with a limit of two
+        let stolen = steal_voices(&mut voices, "jump", 1);
+        assert_eq!(stolen.len(), 2);
+        let left: Vec<_> = voices.iter().map(|v| (v.kind, v.started)).collect();
+        assert_eq!(left, [("footstep", 0.5), ("jump", 2.0)]);
+
+        assert!(steal_voices(&mut voices, "footstep", 1).is_empty());
+    }
+
+    #[test]
+    fn pans_toward_the_side_the_sound_is_on() {
+        assert_eq!(pan(0.0), 0.0);
+        assert!(pan(100.0) > 0.0 && pan(100.0) < MAX_PAN);
+        assert_eq!(pan(-100.0), -pan(100.0));
+        assert_eq!(pan(FULL_PAN_DISTANCE * 3.0), MAX_PAN);
+    }
+
+    /// `left` full volume samples spread over `channels` channels
+    struct Samples {
+        channels: u16,
+        left: usize,
+    }
+
+    impl Iterator for Samples {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            self.left = self.left.checked_sub(1)?;
+            Some(1.0)
+        }
+    }
+
+    impl Source for Samples {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> u32 {
+            44_100
+        }
+
+        fn total_duration(&self) -> Option<std::time::Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn panning_quiets_the_far_channel() {
+        let stereo = Samples {
+            channels: 2,
+            left: 8,
+        };
+        let panned: Vec<f32> = PanSource::new(stereo, 0.5).collect();
+        assert_eq!(panned[..4], [0.5, 1.0, 0.5, 1.0]);
+
+        // mono sounds are spread over both sides first
+        let mono = Samples {
+            channels: 1,
+            left: 8,
+        };
+        let panned = PanSource::new(mono, -0.5);
+        assert_eq!(panned.channels(), 2);
+        let panned: Vec<f32> = panned.collect();
+        assert_eq!(panned.len(), 16);
+        assert_eq!(panned[..4], [1.0, 0.5, 1.0, 0.5]);
+    }
 }
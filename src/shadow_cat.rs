@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    bounds::DespawnOutOfBounds,
+    character_controller::{character_body, KinematicBodyBundle},
+    collisions::{CollisionEvents, CollisionSets},
+    constants::CollisionTypes,
+    fixed_input::BufferInputs,
+    game_state::GameState,
+    input_latch::LatchInputs,
+    level_registry::field,
+    orientation::GravityFacing,
+    physics::JumpState,
+    player::{
+        control_movement, DeathCause, InputProcessing, JumpAction, MovementAction, Player,
+        PlayerDeath,
+    },
+};
+
+/// inputs older than this are dropped, no shadow can lag further behind
+const MAX_DELAY: f32 = 10.0;
+const DEFAULT_DELAY: f32 = 2.0;
+
+pub struct ShadowCatPlugin;
+impl Plugin for ShadowCatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputHistory>()
+            .register_ldtk_entity::<ShadowCatBundle>("Shadow_Cat")
+            .add_systems(
+                (after_shadow_spawned, reset_history).in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_systems(
+                (
+                    record_inputs.run_if(in_state(GameState::Playing)),
+                    replay_inputs,
+                )
+                    .chain()
+                    .in_base_set(InputProcessing)
+                    .after(LatchInputs)
+                    .before(BufferInputs)
+                    .before(control_movement),
+            )
+            .add_system(shadow_contact.in_set(CollisionSets::Consume));
+    }
+}
+
+/// Follows the player by replaying their inputs `delay` seconds late through
+/// the same controls, touching it kills the player.
+#[derive(Component)]
+pub struct ShadowCat {
+    pub delay: f32,
+}
+
+impl Default for ShadowCat {
+    fn default() -> Self {
+        ShadowCat {
+            delay: DEFAULT_DELAY,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct ShadowCatBundle {
+    #[with(shadow_cat_from_instance)]
+    shadow: ShadowCat,
+    #[sprite_bundle("pixel-cat.png")]
+    sprite: SpriteBundle,
+    #[with(character_body)]
+    body: KinematicBodyBundle,
+    jump_state: JumpState,
+    facing: GravityFacing,
+    despawn_out_of_bounds: DespawnOutOfBounds,
+}
+
+fn shadow_cat_from_instance(instance: &EntityInstance) -> ShadowCat {
+    let delay = match field(&instance.field_instances, "Delay") {
+        Some(FieldValue::Float(Some(delay))) => delay.clamp(0.0, MAX_DELAY),
+        _ => DEFAULT_DELAY,
+    };
+    ShadowCat { delay }
+}
+
+/// what the player was pressing at a point in the level
+struct RecordedInput {
+    time: f32,
+    jump: bool,
+    movement: Vec<MovementAction>,
+}
+
+/// the player's inputs since the level started
+#[derive(Resource, Default)]
+struct InputHistory {
+    elapsed: f32,
+    inputs: VecDeque<RecordedInput>,
+}
+
+fn reset_history(mut history: ResMut<InputHistory>) {
+    *history = InputHistory::default();
+}
+
+fn after_shadow_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Sprite), Added<ShadowCat>>,
+) {
+    for (e, mut sprite) in &mut q {
+        sprite.color = Color::rgba(0.15, 0.1, 0.25, 0.85);
+        commands.entity(e).insert((
+            ActionState::<JumpAction>::default(),
+            ActionState::<MovementAction>::default(),
+            CollisionTypes::Enemy,
+        ));
+    }
+}
+
+fn record_inputs(
+    time: Res<Time>,
+    mut history: ResMut<InputHistory>,
+    player: Query<(&ActionState<JumpAction>, &ActionState<MovementAction>), With<Player>>,
+) {
+    let Ok((jump, movement)) = player.get_single() else {
+        return;
+    };
+
+    history.elapsed += time.delta_seconds();
+    let input = RecordedInput {
+        time: history.elapsed,
+        jump: jump.pressed(JumpAction::Jump),
+        movement: movement.get_pressed(),
+    };
+    history.inputs.push_back(input);
+    while history
+        .inputs
+        .front()
+        .is_some_and(|input| input.time < history.elapsed - MAX_DELAY)
+    {
+        history.inputs.pop_front();
+    }
+}
+
+fn replay_inputs(
+    history: Res<InputHistory>,
+    mut shadows: Query<(
+        &ShadowCat,
+        &mut ActionState<JumpAction>,
+        &mut ActionState<MovementAction>,
+    )>,
+) {
+    for (shadow, mut jump, mut movement) in &mut shadows {
+        let replay_time = history.elapsed - shadow.delay;
+        let input = history
+            .inputs
+            .iter()
+            .rev()
+            .find(|input| input.time <= replay_time);
+
+        let jump_pressed = input.is_some_and(|input| input.jump);
+        if jump_pressed {
+            jump.press(JumpAction::Jump);
+        } else {
+            jump.release(JumpAction::Jump);
+        }
+        for action in MovementAction::variants() {
+            if input.is_some_and(|input| input.movement.contains(&action)) {
+                movement.press(action);
+            } else {
+                movement.release(action);
+            }
+        }
+    }
+}
+
+fn shadow_contact(
+    player: Query<&CollisionEvents<CollisionTypes>, With<Player>>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    for events in &player {
+        if events
+            .buffer
+            .iter()
+            .any(|event| matches!(event.user_type, CollisionTypes::Enemy))
+        {
+            deaths.send(PlayerDeath {
+                cause: DeathCause::Enemy,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collisions::{CollisionData, CollisionEvent, ContactResponse, Sweep};
+
+    fn deaths_from_contact(user_type: CollisionTypes) -> usize {
+        let mut world = World::new();
+        world.init_resource::<Events<PlayerDeath>>();
+        let other = world.spawn_empty().id();
+        let mut events = CollisionEvents::new();
+        events.buffer.push(CollisionEvent {
+            entity: other,
+            user_type,
+            data: CollisionData::Aabb(Sweep {
+                position: Vec2::ZERO,
+                time: 0.0,
+                normal: Vec2::Y,
+            }),
+            ignored: false,
+            response: ContactResponse::SOLID,
+        });
+        world.spawn((Player, events));
+        let mut schedule = Schedule::new();
+        schedule.add_system(shadow_contact);
+        schedule.run(&mut world);
+        world.resource::<Events<PlayerDeath>>().len()
+    }
+
+    #[test]
+    fn only_enemies_kill_on_contact() {
+        assert_eq!(deaths_from_contact(CollisionTypes::Enemy), 1);
+        // landing on the boss is how it's beaten, see `boss_contact`
+        assert_eq!(deaths_from_contact(CollisionTypes::Boss), 0);
+        assert_eq!(deaths_from_contact(CollisionTypes::Walker), 0);
+    }
+}
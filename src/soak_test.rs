@@ -0,0 +1,182 @@
+//! Runs the whole game headless, from the start menu through every level to
+//! the win screen and back, to catch panics and entities left behind. Plays
+//! the tiny level set in `assets/test` with scripted input, so it doesn't
+//! break when the real levels change.
+use std::time::{Duration, Instant};
+
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    log::LogPlugin,
+    prelude::*,
+    render::pipelined_rendering::PipelinedRenderingPlugin,
+    winit::WinitPlugin,
+};
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{add_game, game_state::GameState, preload::ManifestPath};
+
+/// long enough to load the ldtk file on a slow machine
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Keys pressed and released in each fixture level, by frames since the level
+/// started. Anything still held when the level is finished is released.
+const LEVEL_SCRIPTS: [&[(u32, KeyCode, ButtonState)]; 2] = [
+    &[(10, KeyCode::Right, ButtonState::Pressed)],
+    &[(10, KeyCode::Left, ButtonState::Pressed)],
+];
+
+/// No window, but still a renderer since the ldtk tilemaps can't be added
+/// without one. Software renderers like mesa's llvmpipe are enough.
+pub(crate) fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                ..default()
+            })
+            .set(ImagePlugin::default_nearest())
+            // it needs `App::run` to start its thread, tests call `update`
+            .disable::<PipelinedRenderingPlugin>()
+            .disable::<WinitPlugin>()
+            .disable::<LogPlugin>(),
+    );
+    add_game(&mut app);
+    app
+}
+
+pub(crate) fn state(app: &App) -> GameState {
+    app.world.resource::<State<GameState>>().0.clone()
+}
+
+pub(crate) fn key(app: &mut App, key: KeyCode, state: ButtonState) {
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key),
+        state,
+    });
+}
+
+/// update until `done` returns true, panicking if it takes longer than `TIMEOUT`
+pub(crate) fn run_until(
+    app: &mut App,
+    visited: &mut Vec<GameState>,
+    mut done: impl FnMut(&mut App) -> bool,
+) {
+    let start = Instant::now();
+    while !done(app) {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "timed out in {:?} after visiting {visited:?}",
+            state(app)
+        );
+        app.update();
+        let current = state(app);
+        if visited.last() != Some(&current) {
+            visited.push(current);
+        }
+        // asset loading happens on other threads
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// update for `frames` frames so despawns and state changes settle
+fn settle(app: &mut App, visited: &mut Vec<GameState>, frames: u32) {
+    let mut remaining = frames;
+    run_until(app, visited, |_| {
+        remaining = remaining.saturating_sub(1);
+        remaining == 0
+    });
+}
+
+/// Plays every fixture level from the start menu, by their scripts, and goes
+/// back to the menu from the win screen. Returns the levels played.
+fn play_run(app: &mut App, visited: &mut Vec<GameState>) -> Vec<usize> {
+    // skip the save slot screen, so the test never writes saves
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::LoadLevel);
+    run_until(app, visited, |app| state(app) == GameState::Playing);
+
+    let mut level = 0;
+    let mut frame = 0;
+    let mut held = Vec::new();
+    let mut played = Vec::new();
+    run_until(app, visited, |app| {
+        let LevelSelection::Index(current) = *app.world.resource::<LevelSelection>() else {
+            panic!("fixture levels are selected by index");
+        };
+        let finished = state(app) == GameState::WinScreen;
+        if current != level || finished {
+            for k in held.drain(..) {
+                key(app, k, ButtonState::Released);
+            }
+            level = current;
+            frame = 0;
+        }
+        if finished {
+            return true;
+        }
+        if state(app) != GameState::Playing {
+            return false;
+        }
+
+        if played.last() != Some(&level) {
+            played.push(level);
+        }
+        for &(at, k, button) in LEVEL_SCRIPTS[level] {
+            if at != frame {
+                continue;
+            }
+            key(app, k, button);
+            match button {
+                ButtonState::Pressed => held.push(k),
+                ButtonState::Released => held.retain(|&h| h != k),
+            }
+        }
+        frame += 1;
+        false
+    });
+
+    key(app, KeyCode::Escape, ButtonState::Pressed);
+    run_until(app, visited, |app| state(app) == GameState::StartMenu);
+    key(app, KeyCode::Escape, ButtonState::Released);
+    settle(app, visited, 10);
+    played
+}
+
+#[test]
+fn full_game_loop() {
+    let mut app = headless_app();
+    app.insert_resource(ManifestPath("test/soak.manifest.ron"));
+    let mut visited = Vec::new();
+    run_until(&mut app, &mut visited, |app| {
+        state(app) == GameState::StartMenu
+    });
+    settle(&mut app, &mut visited, 10);
+
+    let run = [
+        GameState::LoadLevel,
+        GameState::SpawnLevel,
+        GameState::Playing,
+        GameState::SpawnLevel,
+        GameState::Playing,
+        GameState::WinScreen,
+        GameState::UnloadLevel,
+        GameState::StartMenu,
+    ];
+    assert_eq!(play_run(&mut app, &mut visited), [0, 1]);
+    assert_eq!(visited[..2], [GameState::Preload, GameState::StartMenu]);
+    assert_eq!(visited[2..], run);
+    // pools like the score popups' fill up during the first run
+    let menu_entities = app.world.entities().len();
+
+    visited.clear();
+    assert_eq!(play_run(&mut app, &mut visited), [0, 1]);
+    assert_eq!(visited, run);
+    assert_eq!(
+        app.world.entities().len(),
+        menu_entities,
+        "entities were left behind after a full run"
+    );
+}
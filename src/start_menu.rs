@@ -1,6 +1,15 @@
 use bevy::prelude::*;
 
-use crate::game_state::GameState;
+use crate::{
+    accessibility::MenuLabel,
+    game_state::GameState,
+    news::news_closed,
+    preload::Preloaded,
+    render_layers::{LayerDepth, RenderLayer},
+    save::slot_selected,
+    settings::settings_closed,
+    sfx::DuckMusic,
+};
 
 const NORMAL_BUTTON: Color = Color::rgba(0.15, 0.15, 0.15, 0.);
 const HOVERED_BUTTON: Color = Color::rgba(0.25, 0.25, 0.25, 0.);
@@ -10,7 +19,11 @@ impl Plugin for StartMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(spawn_menu.in_schedule(OnEnter(GameState::StartMenu)))
             .add_systems(
-                (input_start, button_system).distributive_run_if(in_state(GameState::StartMenu)),
+                (input_start, button_system)
+                    .distributive_run_if(in_state(GameState::StartMenu))
+                    .distributive_run_if(slot_selected)
+                    .distributive_run_if(news_closed)
+                    .distributive_run_if(settings_closed),
             )
             .add_system(despawn_menu.in_schedule(OnExit(GameState::StartMenu)));
     }
@@ -19,10 +32,11 @@ impl Plugin for StartMenuPlugin {
 #[derive(Component)]
 pub struct MenuMarker;
 
-fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_menu(mut commands: Commands, preloaded: Res<Preloaded>) {
     commands
         .spawn((
             MenuMarker,
+            DuckMusic,
             NodeBundle {
                 style: Style {
                     size: Size::width(Val::Percent(100.0)),
@@ -37,6 +51,10 @@ fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
             parent
                 .spawn((
                     MenuMarker,
+                    MenuLabel::new(
+                        "Start game. Press space to start, or L to pick a level",
+                        true,
+                    ),
                     ButtonBundle {
                         style: Style {
                             size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -56,7 +74,7 @@ fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         TextBundle::from_section(
                             "Click or Press Space to Start",
                             TextStyle {
-                                font: asset_server.load("Rubik-Light.ttf"),
+                                font: preloaded.get("font"),
                                 font_size: 40.0,
                                 color: Color::rgb(0.9, 0.9, 0.9),
                             },
@@ -67,9 +85,11 @@ fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
 
     commands.spawn((
         MenuMarker,
+        RenderLayer::Background,
+        LayerDepth(1.0),
         SpriteBundle {
-            texture: asset_server.load("start-screen.png"),
-            transform: Transform::from_xyz(360., 360., 1.0),
+            texture: preloaded.get("start_screen"),
+            transform: Transform::from_xyz(360., 360., 0.0),
             ..default()
         },
     ));
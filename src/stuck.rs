@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{
+    collisions::{CollisionSets, Rect},
+    constants::CollisionTypes,
+    physics::ground_detection,
+    player::Player,
+    safe_position::SoftRespawn,
+};
+
+/// overlap with ground deeper than this counts as being stuck, the resolver
+/// normally leaves the player 1px outside
+const STUCK_DEPTH: f32 = 2.0;
+/// ticks spent stuck before the player is pushed out
+const NUDGE_TICKS: u32 = 10;
+/// ticks spent stuck before giving up and moving the player to a safe position
+const RESPAWN_TICKS: u32 = 30;
+
+pub struct StuckPlugin;
+impl Plugin for StuckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            recover_from_stuck
+                .in_set(CollisionSets::Consume)
+                .after(ground_detection),
+        );
+    }
+}
+
+/// ticks in a row the player has been embedded in the ground
+#[derive(Component, Default)]
+pub struct StuckTicks(pub u32);
+
+fn recover_from_stuck(
+    mut player: Query<(&mut Transform, &mut StuckTicks, &Children), With<Player>>,
+    colliders: Query<(&Rect, &GlobalTransform, &Parent)>,
+    collision_types: Query<&CollisionTypes>,
+    mut respawn: EventWriter<SoftRespawn>,
+) {
+    for (mut t, mut stuck, children) in &mut player {
+        let Some(size) = children
+            .iter()
+            .find_map(|child| colliders.get(*child).ok())
+            .map(|(rect, _, _)| rect.0)
+        else {
+            continue;
+        };
+        let position = t.translation.truncate();
+
+        // deepest overlap with any piece of ground
+        let mut push = Vec2::ZERO;
+        for (rect, ground_t, parent) in &colliders {
+            if !matches!(
+                collision_types.get(parent.get()),
+                Ok(CollisionTypes::Ground)
+            ) {
+                continue;
+            }
+            let Some(hit) =
+                Rect::inter_aabb(ground_t.translation().truncate(), rect.0, position, size)
+            else {
+                continue;
+            };
+            if hit.delta.length() > STUCK_DEPTH && hit.delta.length() > push.length() {
+                push = hit.delta;
+            }
+        }
+
+        if push == Vec2::ZERO && !position.is_nan() {
+            stuck.0 = 0;
+            continue;
+        }
+
+        stuck.0 += 1;
+        if stuck.0 >= RESPAWN_TICKS || position.is_nan() {
+            warn!("player stuck at {position}, moving back to a safe position");
+            respawn.send(SoftRespawn);
+            stuck.0 = 0;
+        } else if stuck.0 >= NUDGE_TICKS {
+            t.translation += push.extend(0.0);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use serde::Deserialize;
+
+use crate::{
+    game_state::GameState,
+    ground::SurfaceMaterial,
+    physics::{GravityDirection, GroundSurface, Landed, OnGround, Velocity},
+    player::Player,
+    render_layers::RenderLayer,
+    sfx::{PlaySfx, Sfx},
+};
+
+/// distance the player runs between footsteps
+const STEP_DISTANCE: f32 = 28.0;
+/// landings slower than this don't thud or kick up dust
+const MIN_LAND_SPEED: f32 = 150.0;
+const DUST_COUNT: usize = 6;
+const DUST_SPEED: f32 = 60.0;
+const DUST_SECONDS: f32 = 0.35;
+
+pub struct SurfacePlugin;
+impl Plugin for SurfacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SurfaceEffects>()
+            .add_startup_system(load_surface_table)
+            .add_system(apply_surface_table)
+            .add_systems((footsteps, landings).in_set(GameState::Playing))
+            .add_system(fade_dust)
+            .add_system(clear_dust.in_schedule(OnEnter(GameState::StartMenu)));
+    }
+}
+
+/// Sounds and dust for each surface material, loaded from surfaces.surface.ron
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "5d8a1e3c-9f27-4b60-8c15-e2a7b4d90f61"]
+pub struct SurfaceTable {
+    pub materials: HashMap<SurfaceMaterial, SurfaceEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SurfaceEntry {
+    /// asset paths of the sounds
+    pub footstep: String,
+    pub land: String,
+    /// rgb color of dust kicked up when landing and running
+    pub dust: (f32, f32, f32),
+}
+
+#[derive(Resource)]
+#[allow(dead_code)] // the table is read through asset events, this just holds it
+struct SurfaceTableHandle(Handle<SurfaceTable>);
+
+/// loaded sounds and colors from the surface table
+#[derive(Resource, Default)]
+pub struct SurfaceEffects {
+    sounds: HashMap<SurfaceMaterial, (Handle<AudioSource>, Handle<AudioSource>)>,
+    dust: HashMap<SurfaceMaterial, Color>,
+}
+
+impl SurfaceEffects {
+    pub fn footstep(&self, material: SurfaceMaterial) -> Option<Handle<AudioSource>> {
+        self.sounds.get(&material).map(|(step, _)| step.clone())
+    }
+
+    pub fn land(&self, material: SurfaceMaterial) -> Option<Handle<AudioSource>> {
+        self.sounds.get(&material).map(|(_, land)| land.clone())
+    }
+
+    fn dust(&self, material: SurfaceMaterial) -> Color {
+        self.dust
+            .get(&material)
+            .copied()
+            .unwrap_or(Color::rgb(0.6, 0.6, 0.6))
+    }
+}
+
+fn load_surface_table(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SurfaceTableHandle(
+        asset_server.load("surfaces.surface.ron"),
+    ));
+}
+
+fn apply_surface_table(
+    mut events: EventReader<AssetEvent<SurfaceTable>>,
+    tables: Res<Assets<SurfaceTable>>,
+    asset_server: Res<AssetServer>,
+    mut effects: ResMut<SurfaceEffects>,
+) {
+    for event in &mut events {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        let Some(table) = tables.get(handle) else {
+            continue;
+        };
+        effects.sounds = table
+            .materials
+            .iter()
+            .map(|(material, entry)| {
+                let sounds = (
+                    asset_server.load(&entry.footstep),
+                    asset_server.load(&entry.land),
+                );
+                (*material, sounds)
+            })
+            .collect();
+        effects.dust = table
+            .materials
+            .iter()
+            .map(|(material, entry)| {
+                let (r, g, b) = entry.dust;
+                (*material, Color::rgb(r, g, b))
+            })
+            .collect();
+    }
+}
+
+#[derive(Component)]
+struct Dust {
+    velocity: Vec2,
+    life: Timer,
+}
+
+fn spawn_dust(
+    commands: &mut Commands,
+    rng: &mut GlobalRng,
+    position: Vec2,
+    gravity: Vec2,
+    color: Color,
+    count: usize,
+) {
+    for _ in 0..count {
+        // spread along the floor and a little up away from it
+        let along = gravity.perp() * (rng.f32() * 2.0 - 1.0);
+        let up = -gravity * rng.f32() * 0.5;
+        commands.spawn((
+            Dust {
+                velocity: (along + up) * DUST_SPEED,
+                life: Timer::from_seconds(DUST_SECONDS, TimerMode::Once),
+            },
+            RenderLayer::Particles,
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(3.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation((position + gravity * 12.0).extend(0.0)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn footsteps(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<GlobalRng>,
+    effects: Res<SurfaceEffects>,
+    player: Query<
+        (
+            &Transform,
+            &Velocity,
+            &OnGround,
+            &GravityDirection,
+            &GroundSurface,
+        ),
+        With<Player>,
+    >,
+    mut sfx: EventWriter<PlaySfx>,
+    mut travelled: Local<f32>,
+) {
+    for (t, v, on_ground, g_dir, surface) in &player {
+        if !on_ground.0 {
+            *travelled = 0.0;
+            continue;
+        }
+        let gravity = g_dir.as_vec2();
+        let running_speed = (v.0 - gravity * v.0.dot(gravity)).length();
+        *travelled += running_speed * time.delta_seconds();
+        if *travelled < STEP_DISTANCE {
+            continue;
+        }
+        *travelled = 0.0;
+        let position = t.translation.truncate();
+        sfx.send(PlaySfx::at(Sfx::Footstep(surface.0), position));
+        spawn_dust(
+            &mut commands,
+            &mut rng,
+            position,
+            gravity,
+            effects.dust(surface.0),
+            1,
+        );
+    }
+}
+
+fn landings(
+    mut commands: Commands,
+    mut rng: ResMut<GlobalRng>,
+    effects: Res<SurfaceEffects>,
+    mut landed: EventReader<Landed>,
+    player: Query<(&Transform, &GravityDirection), With<Player>>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for event in &mut landed {
+        if event.speed < MIN_LAND_SPEED {
+            continue;
+        }
+        let Ok((t, g_dir)) = player.get(event.entity) else {
+            continue;
+        };
+        let position = t.translation.truncate();
+        sfx.send(PlaySfx::at(Sfx::Land(event.surface), position));
+        spawn_dust(
+            &mut commands,
+            &mut rng,
+            position,
+            g_dir.as_vec2(),
+            effects.dust(event.surface),
+            DUST_COUNT,
+        );
+    }
+}
+
+fn fade_dust(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dust: Query<(Entity, &mut Dust, &mut Transform, &mut Sprite)>,
+) {
+    for (e, mut dust, mut t, mut sprite) in &mut dust {
+        if dust.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        t.translation += (dust.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(dust.life.percent_left());
+    }
+}
+
+/// dust kicked up just before the level ended would otherwise show over the menu
+fn clear_dust(mut commands: Commands, dust: Query<Entity, With<Dust>>) {
+    for e in &dust {
+        commands.entity(e).despawn_recursive();
+    }
+}
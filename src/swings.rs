@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    collisions::PositionDelta,
+    game_state::GameState,
+    level_registry::field,
+    mutators::Mutators,
+    physics::{
+        apply_velocity, Acceleration, ExternalForces, GravityDirection, Momentum, OnGround,
+        PhysicsSet, Velocity,
+    },
+    player::{JumpAction, Player},
+};
+
+const DEFAULT_REACH: f32 = 72.0;
+/// Pulls the player around the swing point. Constant instead of the jump
+/// gravity, which builds up the longer the player is in the air.
+const SWING_GRAVITY: f32 = 1200.0;
+/// a rope stretched past this many times its length was let go of, like by a respawn
+const SNAP_STRETCH: f32 = 1.5;
+const ROPE_COLOR: Color = Color::rgb(0.45, 0.6, 0.25);
+
+pub struct SwingPlugin;
+impl Plugin for SwingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<SwingPointBundle>("Swing_Point")
+            .add_system(after_swing_point_spawned.in_schedule(OnEnter(GameState::SpawnLevel)))
+            .add_system(grab_swing_points.in_set(GameState::Playing))
+            .add_systems(
+                (
+                    swing.in_set(ExternalForces),
+                    keep_rope_length.in_set(PhysicsSet).after(apply_velocity),
+                )
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(draw_ropes);
+    }
+}
+
+/// a vine the airborne player can grab with `JumpAction::Grab` from `reach` away
+#[derive(Component)]
+pub struct SwingPoint {
+    pub reach: f32,
+}
+
+impl Default for SwingPoint {
+    fn default() -> Self {
+        SwingPoint {
+            reach: DEFAULT_REACH,
+        }
+    }
+}
+
+/// Hanging from `anchor` on a rope `length` long. Added to the player while
+/// they hold on, their velocity is kept as `Momentum` when they let go.
+#[derive(Component)]
+pub struct Swinging {
+    pub anchor: Entity,
+    pub length: f32,
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct SwingPointBundle {
+    #[with(swing_point_from_instance)]
+    swing_point: SwingPoint,
+    sprite: SpriteBundle,
+}
+
+fn swing_point_from_instance(instance: &EntityInstance) -> SwingPoint {
+    match field(&instance.field_instances, "Reach") {
+        Some(FieldValue::Float(Some(reach))) if *reach > 0.0 => SwingPoint { reach: *reach },
+        _ => SwingPoint::default(),
+    }
+}
+
+/// the rope drawn from a swing point to the player, hidden while nobody holds on
+#[derive(Component)]
+struct Rope;
+
+fn after_swing_point_spawned(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Sprite), Added<SwingPoint>>,
+) {
+    for (e, mut sprite) in &mut q {
+        sprite.custom_size = Some(Vec2::splat(8.0));
+        sprite.color = ROPE_COLOR;
+        commands.entity(e).with_children(|children| {
+            children.spawn((
+                Rope,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: ROPE_COLOR,
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+/// grab the nearest swing point in reach while in the air, grab or jump again to let go
+fn grab_swing_points(
+    mut commands: Commands,
+    points: Query<(Entity, &SwingPoint, &Transform)>,
+    mut player: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &mut Momentum,
+            &OnGround,
+            &ActionState<JumpAction>,
+            Option<&Swinging>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (e, t, v, mut momentum, on_ground, action, swinging) in &mut player {
+        let position = t.translation.truncate();
+        if let Some(swinging) = swinging {
+            let stretched = points.get(swinging.anchor).map_or(true, |(_, _, anchor)| {
+                position.distance(anchor.translation.truncate()) > swinging.length * SNAP_STRETCH
+            });
+            if on_ground.0
+                || stretched
+                || action.just_pressed(JumpAction::Grab)
+                || action.just_pressed(JumpAction::Jump)
+            {
+                // walking would otherwise stop the swing dead
+                momentum.0 = v.0;
+                commands.entity(e).remove::<Swinging>();
+            }
+            continue;
+        }
+        if on_ground.0 || !action.just_pressed(JumpAction::Grab) {
+            continue;
+        }
+        let nearest = points
+            .iter()
+            .map(|(anchor, point, anchor_t)| {
+                let length = position.distance(anchor_t.translation.truncate());
+                (anchor, point, length)
+            })
+            .filter(|(_, point, length)| *length <= point.reach)
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+        if let Some((anchor, _, length)) = nearest {
+            // a rope of no length has no direction to swing in
+            commands.entity(e).insert(Swinging {
+                anchor,
+                length: length.max(1.0),
+            });
+        }
+    }
+}
+
+/// Pendulum step, gravity pulls along the swing and the rope takes away any
+/// speed away from the anchor. Replaces the usual falling acceleration.
+fn swing(
+    points: Query<&Transform, (With<SwingPoint>, Without<Swinging>)>,
+    mut swingers: Query<(
+        &Swinging,
+        &Transform,
+        &mut Velocity,
+        &mut Acceleration,
+        &GravityDirection,
+    )>,
+    mutators: Res<Mutators>,
+    time_step: Res<FixedTime>,
+) {
+    let dt = time_step.period.as_secs_f32();
+    for (swinging, t, mut v, mut a, g) in &mut swingers {
+        let Ok(anchor) = points.get(swinging.anchor) else {
+            continue;
+        };
+        a.0 = Vec2::ZERO;
+        v.0 += g.as_vec2() * SWING_GRAVITY * mutators.gravity_scale() * dt;
+        let out = (t.translation - anchor.translation)
+            .truncate()
+            .normalize_or_zero();
+        let outwards = v.0.dot(out);
+        // slack ropes let the player fall back in, only a taut one pulls
+        if outwards > 0.0 {
+            v.0 -= out * outwards;
+        }
+    }
+}
+
+/// Moving along the swing drifts a little outwards each step, put the
+/// player back on the end of the rope.
+fn keep_rope_length(
+    points: Query<&Transform, (With<SwingPoint>, Without<Swinging>)>,
+    mut swingers: Query<(&Swinging, &mut Transform, Option<&mut PositionDelta>)>,
+) {
+    for (swinging, mut t, delta) in &mut swingers {
+        let Ok(anchor) = points.get(swinging.anchor) else {
+            continue;
+        };
+        let anchor = anchor.translation.truncate();
+        let offset = t.translation.truncate() - anchor;
+        if offset.length() <= swinging.length {
+            continue;
+        }
+        let position = anchor + offset.normalize_or_zero() * swinging.length;
+        t.translation = position.extend(t.translation.z);
+        // part of this tick's movement, so the sweep against the ground includes it
+        if let Some(mut delta) = delta {
+            delta.ray = position - delta.origin;
+        }
+    }
+}
+
+fn draw_ropes(
+    points: Query<(&Transform, &Children), (With<SwingPoint>, Without<Rope>)>,
+    swingers: Query<(&Swinging, &Transform), Without<Rope>>,
+    mut ropes: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<Rope>>,
+) {
+    for (_, _, mut visibility) in &mut ropes {
+        *visibility = Visibility::Hidden;
+    }
+    for (swinging, t) in &swingers {
+        let Ok((anchor, children)) = points.get(swinging.anchor) else {
+            continue;
+        };
+        let offset = (t.translation - anchor.translation).truncate();
+        for &child in children {
+            let Ok((mut rope_t, mut sprite, mut visibility)) = ropes.get_mut(child) else {
+                continue;
+            };
+            *visibility = Visibility::Inherited;
+            sprite.custom_size = Some(Vec2::new(offset.length(), 2.0));
+            // behind the swing point, relative to it
+            rope_t.translation = (offset / 2.0).extend(-0.1);
+            rope_t.rotation = Quat::from_rotation_z(offset.y.atan2(offset.x));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AssistConfig, InputFrame},
+        physics::PhysicsSettings,
+        player::{control_movement, MovementAction},
+    };
+
+    #[test]
+    fn letting_go_mid_swing_keeps_the_speed() {
+        let mut world = World::new();
+        world.insert_resource(PhysicsSettings::default());
+        world.insert_resource(Mutators::default());
+        world.insert_resource(AssistConfig {
+            game_speed: 1.0,
+            soft_respawn: false,
+            rotate_camera: false,
+            input_frame: InputFrame::Screen,
+            landing_lag: false,
+        });
+        world.insert_resource(Time::default());
+        let anchor = world
+            .spawn((SwingPoint::default(), Transform::from_xyz(0.0, 50.0, 0.0)))
+            .id();
+        let mut let_go = ActionState::<JumpAction>::default();
+        let_go.press(JumpAction::Grab);
+        // the bottom of the swing, faster than walking
+        let swing_speed = PhysicsSettings::default().horizontal_speed * 2.0;
+        let player = world
+            .spawn((
+                Player,
+                Swinging {
+                    anchor,
+                    length: 50.0,
+                },
+                Transform::default(),
+                Velocity(Vec2::new(swing_speed, 0.0)),
+                Momentum::default(),
+                OnGround(false),
+                GravityDirection::default(),
+                let_go,
+                ActionState::<MovementAction>::default(),
+            ))
+            .id();
+
+        let mut grab = Schedule::new();
+        grab.add_system(grab_swing_points);
+        grab.run(&mut world);
+        assert!(world.get::<Swinging>(player).is_none());
+
+        // no keys held, walking alone would stop the player
+        let mut movement = Schedule::new();
+        movement.add_system(control_movement);
+        movement.run(&mut world);
+        let v = world.get::<Velocity>(player).unwrap();
+        assert_eq!(v.0.x, swing_speed);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Mutex,
+    },
+};
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+
+/// Runs blocking work, like writing files or network requests, on the io task
+/// pool and sends a `TaskCompleted<T>` event with the result once it's done.
+#[derive(Default)]
+pub struct TaskPlugin<T: Send + Sync + 'static>(PhantomData<T>);
+
+impl<T: Send + Sync + 'static> TaskPlugin<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for TaskPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tasks<T>>()
+            .add_event::<TaskCompleted<T>>()
+            // results are seen by everything in the frame after the task finishes
+            .add_system(send_completed::<T>.in_base_set(CoreSet::PreUpdate));
+    }
+}
+
+/// result of a task started with `Tasks::spawn`
+pub struct TaskCompleted<T>(pub T);
+
+/// Starts tasks whose results come back as `TaskCompleted<T>` events.
+#[derive(Resource)]
+pub struct Tasks<T> {
+    // the std channel halves aren't `Sync`, so they can't be put in a
+    // resource as they are
+    sender: Mutex<Sender<T>>,
+    receiver: Mutex<Receiver<T>>,
+}
+
+impl<T> Default for Tasks<T> {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl<T: Send + 'static> Tasks<T> {
+    /// Runs `task` off the main thread. Tasks run in parallel, so nothing is
+    /// promised about the order they finish in.
+    pub fn spawn(&self, task: impl FnOnce() -> T + Send + 'static) {
+        let sender = self.sender.lock().unwrap().clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                // the receiver is only gone once the app has shut down
+                let _ = sender.send(task());
+            })
+            .detach();
+    }
+}
+
+fn send_completed<T: Send + Sync + 'static>(
+    tasks: Res<Tasks<T>>,
+    mut completed: EventWriter<TaskCompleted<T>>,
+) {
+    let receiver = tasks.receiver.lock().unwrap();
+    completed.send_batch(receiver.try_iter().map(TaskCompleted));
+}
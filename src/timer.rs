@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    game_state::{GameState, ShouldSimulate},
+    level::LevelCompleted,
+    level_packs::LevelPacks,
+    preload::Preloaded,
+    save::SaveData,
+};
+
+pub struct TimerPlugin;
+impl Plugin for TimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunTimer>()
+            .init_resource::<LevelTimer>()
+            .add_system(start_run_timer.in_schedule(OnExit(GameState::StartMenu)))
+            .add_system(start_level_timer.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(stop_level_timer.in_schedule(OnExit(GameState::Playing)))
+            .add_system(stop_run_timer.in_schedule(OnEnter(GameState::WinScreen)))
+            .add_system(despawn_timer_text.in_schedule(OnEnter(GameState::StartMenu)))
+            .add_systems((tick_run_timer, update_timer_text).chain())
+            .add_systems((tick_level_timer, update_level_timer_text).chain())
+            .add_systems((record_best_time, mark_level_start).chain());
+    }
+}
+
+/// speedrun timer, runs from leaving the start menu until the win screen
+#[derive(Resource, Default)]
+pub struct RunTimer {
+    pub elapsed: Duration,
+    pub running: bool,
+    /// `elapsed` when the current level was started
+    pub level_start: Duration,
+}
+
+impl RunTimer {
+    /// throw away the time spent on the current level attempt
+    pub fn restart_level(&mut self) {
+        self.elapsed = self.level_start;
+    }
+}
+
+/// Time spent playing the current level. Only runs in `GameState::Playing`
+/// and stops while the simulation is paused.
+#[derive(Resource, Default)]
+pub struct LevelTimer {
+    pub elapsed: Duration,
+    pub running: bool,
+    /// level index and time for each level finished this run, in order
+    pub splits: Vec<(usize, Duration)>,
+}
+
+impl LevelTimer {
+    /// start the current level's time over
+    pub fn restart(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// format a duration as `m:ss.cc`
+pub fn format_time(duration: Duration) -> String {
+    let secs = duration.as_secs_f32();
+    let minutes = (secs / 60.0).floor();
+    format!("{}:{:05.2}", minutes as u32, secs - minutes * 60.0)
+}
+
+#[derive(Component)]
+struct TimerText;
+
+#[derive(Component)]
+struct LevelTimerText;
+
+fn start_run_timer(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    mut timer: ResMut<RunTimer>,
+    mut level_timer: ResMut<LevelTimer>,
+) {
+    timer.elapsed = Duration::ZERO;
+    timer.level_start = Duration::ZERO;
+    timer.running = true;
+    *level_timer = LevelTimer::default();
+
+    commands.spawn((
+        LevelTimerText,
+        TextBundle::from_section(
+            level_time_text(Duration::ZERO),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(34.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+    commands.spawn((
+        TimerText,
+        TextBundle::from_section(
+            format_time(Duration::ZERO),
+            TextStyle {
+                font: preloaded.get("font"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+/// Keeps the level's time as a split and a best time, then starts the next
+/// level's time from zero.
+fn record_best_time(
+    mut events: EventReader<LevelCompleted>,
+    mut level_timer: ResMut<LevelTimer>,
+    packs: Res<LevelPacks>,
+    mut save: ResMut<SaveData>,
+) {
+    for e in &mut events {
+        let elapsed = level_timer.elapsed;
+        level_timer.splits.push((e.index, elapsed));
+        level_timer.restart();
+        level_timer.running = false;
+        let time = elapsed.as_secs_f32();
+        let best_times = save.best_times_mut(packs.active_name());
+        if best_times.get(&e.index).map_or(true, |&best| time < best) {
+            best_times.insert(e.index, time);
+        }
+    }
+}
+
+fn mark_level_start(mut events: EventReader<LevelCompleted>, mut timer: ResMut<RunTimer>) {
+    if !events.is_empty() {
+        events.clear();
+        timer.level_start = timer.elapsed;
+    }
+}
+
+fn start_level_timer(mut timer: ResMut<LevelTimer>) {
+    timer.running = true;
+}
+
+fn stop_level_timer(mut timer: ResMut<LevelTimer>) {
+    timer.running = false;
+}
+
+fn tick_level_timer(time: Res<Time>, simulate: Res<ShouldSimulate>, mut timer: ResMut<LevelTimer>) {
+    if timer.running && simulate.running() {
+        timer.elapsed += time.delta();
+    }
+}
+
+fn level_time_text(elapsed: Duration) -> String {
+    format!("Level {}", format_time(elapsed))
+}
+
+fn update_level_timer_text(timer: Res<LevelTimer>, mut q: Query<&mut Text, With<LevelTimerText>>) {
+    if !timer.is_changed() {
+        return;
+    }
+
+    for mut text in &mut q {
+        text.sections[0].value = level_time_text(timer.elapsed);
+    }
+}
+
+fn stop_run_timer(mut timer: ResMut<RunTimer>) {
+    timer.running = false;
+}
+
+fn tick_run_timer(time: Res<Time>, mut timer: ResMut<RunTimer>) {
+    if timer.running {
+        timer.elapsed += time.delta();
+    }
+}
+
+fn update_timer_text(timer: Res<RunTimer>, mut q: Query<&mut Text, With<TimerText>>) {
+    if !timer.is_changed() {
+        return;
+    }
+
+    for mut text in &mut q {
+        text.sections[0].value = format_time(timer.elapsed);
+    }
+}
+
+fn despawn_timer_text(
+    mut commands: Commands,
+    q: Query<Entity, Or<(With<TimerText>, With<LevelTimerText>)>>,
+) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
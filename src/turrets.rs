@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, prelude::LdtkEntityAppExt, EntityInstance, LdtkEntity};
+
+use crate::{
+    bounds::DespawnOutOfBounds,
+    collisions::{
+        CollisionData, CollisionEvents, CollisionSets, PositionDelta, RectBundle, Restitution,
+    },
+    constants::CollisionTypes,
+    game_state::GameState,
+    level_registry::field,
+    physics::{Direction, Velocity},
+    player::{DeathCause, PlayerDeath},
+};
+
+pub const PROJECTILE_SPEED: f32 = 240.0;
+pub const PROJECTILE_SIZE: f32 = 8.0;
+const DEFAULT_FIRE_SECONDS: f32 = 2.0;
+const DEFAULT_TELEGRAPH_SECONDS: f32 = 0.4;
+
+pub struct TurretPlugin;
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<TurretBundle>("Turret")
+            .add_systems(
+                (clear_projectiles, after_turret_spawned)
+                    .in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(fire_turrets.in_set(GameState::Playing))
+            .add_system(projectile_hits.in_set(CollisionSets::Consume));
+    }
+}
+
+/// fires a projectile along `direction` every `fire` seconds
+#[derive(Component)]
+pub struct Turret {
+    pub direction: Direction,
+    fire: Timer,
+    /// how long the turret flashes before each shot
+    telegraph: f32,
+    /// times each shot bounces off the ground before breaking, `Ricochets` field in ldtk
+    ricochets: u32,
+}
+
+impl Default for Turret {
+    fn default() -> Self {
+        Turret {
+            direction: Direction::Up,
+            fire: Timer::from_seconds(DEFAULT_FIRE_SECONDS, TimerMode::Repeating),
+            telegraph: DEFAULT_TELEGRAPH_SECONDS,
+            ricochets: 0,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct TurretBundle {
+    #[with(turret_from_instance)]
+    turret: Turret,
+    #[sprite_bundle("turret.png")]
+    sprite: SpriteBundle,
+}
+
+fn turret_from_instance(instance: &EntityInstance) -> Turret {
+    let fields = &instance.field_instances;
+    let direction = match field(fields, "Direction") {
+        Some(FieldValue::Enum(Some(direction))) => match direction.as_str() {
+            "Down" => Direction::Down,
+            "Left" => Direction::Left,
+            "Right" => Direction::Right,
+            _ => Direction::Up,
+        },
+        _ => Direction::Up,
+    };
+    let fire_seconds = match field(fields, "Fire_Rate") {
+        Some(FieldValue::Float(Some(seconds))) if *seconds > 0.0 => *seconds,
+        _ => DEFAULT_FIRE_SECONDS,
+    };
+    let telegraph = match field(fields, "Telegraph") {
+        Some(FieldValue::Float(Some(seconds))) => seconds.clamp(0.0, fire_seconds),
+        _ => DEFAULT_TELEGRAPH_SECONDS.min(fire_seconds),
+    };
+    let ricochets = match field(fields, "Ricochets") {
+        Some(FieldValue::Int(Some(ricochets))) => (*ricochets).max(0) as u32,
+        _ => 0,
+    };
+
+    Turret {
+        direction,
+        fire: Timer::from_seconds(fire_seconds, TimerMode::Repeating),
+        telegraph,
+        ricochets,
+    }
+}
+
+/// Moves in a straight line, kills the player and breaks on the ground.
+/// Shots with ricochets left bounce off the ground instead.
+#[derive(Component)]
+pub struct Projectile {
+    ricochets: u32,
+}
+
+fn clear_projectiles(mut commands: Commands, q: Query<Entity, With<Projectile>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn after_turret_spawned(mut q: Query<(&Turret, &mut Transform), Added<Turret>>) {
+    for (turret, mut t) in &mut q {
+        // the sprite points up, gravity pointing down is unrotated
+        t.rotation = Quat::from_rotation_z(turret.direction.reverse().angle());
+    }
+}
+
+fn fire_turrets(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut turrets: Query<(&mut Turret, &mut Sprite, &Transform)>,
+) {
+    for (mut turret, mut sprite, t) in &mut turrets {
+        let fired = turret.fire.tick(time.delta()).just_finished();
+        let until_fire = turret.fire.duration().as_secs_f32() - turret.fire.elapsed_secs();
+        sprite.color = if until_fire <= turret.telegraph {
+            Color::rgb(2.0, 1.2, 1.2)
+        } else {
+            Color::WHITE
+        };
+        if !fired {
+            continue;
+        }
+
+        let direction = turret.direction.as_vec2();
+        // start at the end of the barrel so it doesn't hit the turret's own block
+        let start = t.translation.truncate() + direction * 12.0;
+        spawn_projectile(
+            &mut commands,
+            start.extend(t.translation.z),
+            direction * PROJECTILE_SPEED,
+            turret.ricochets,
+        );
+    }
+}
+
+/// shared with the boss's projectile attack
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    start: Vec3,
+    velocity: Vec2,
+    ricochets: u32,
+) -> Entity {
+    commands
+        .spawn((
+            Projectile { ricochets },
+            // shots keep their speed, bouncier ground can't add to it
+            Restitution(1.0),
+            Velocity(velocity),
+            PositionDelta {
+                origin: start.truncate(),
+                ray: Vec2::ZERO,
+            },
+            CollisionTypes::Enemy,
+            CollisionEvents::<CollisionTypes>::new(),
+            DespawnOutOfBounds,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(1.0, 0.45, 0.3),
+                    custom_size: Some(Vec2::splat(PROJECTILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(start),
+                ..default()
+            },
+        ))
+        .with_children(|children| {
+            children.spawn(RectBundle::new(Vec2::splat(PROJECTILE_SIZE)));
+        })
+        .id()
+}
+
+fn projectile_hits(
+    mut commands: Commands,
+    mut projectiles: Query<(
+        Entity,
+        &mut Projectile,
+        &mut Transform,
+        &mut Velocity,
+        &CollisionEvents<CollisionTypes>,
+    )>,
+    mut deaths: EventWriter<PlayerDeath>,
+) {
+    for (e, mut projectile, mut t, mut v, events) in &mut projectiles {
+        let mut hit = false;
+        let mut ricochet = None;
+        for event in events.solid() {
+            match (&event.user_type, &event.data) {
+                (CollisionTypes::Player, _) => {
+                    deaths.send(PlayerDeath {
+                        cause: DeathCause::Enemy,
+                    });
+                    hit = true;
+                }
+                (CollisionTypes::Ground, CollisionData::Aabb(sweep))
+                    if projectile.ricochets > 0 && event.response.restitution > 0.0 =>
+                {
+                    ricochet = Some((sweep.position + sweep.normal, sweep.normal, event.response));
+                }
+                (CollisionTypes::Ground, _) => hit = true,
+                _ => {}
+            }
+        }
+        if hit {
+            commands.entity(e).despawn_recursive();
+        } else if let Some((position, normal, response)) = ricochet {
+            projectile.ricochets -= 1;
+            t.translation = position.extend(t.translation.z);
+            let into = v.0.dot(normal);
+            if into < 0.0 {
+                v.0 -= normal * into * (1.0 + response.restitution);
+            }
+        }
+    }
+}
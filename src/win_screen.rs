@@ -1,31 +1,443 @@
+use std::f32::consts::TAU;
+
 use bevy::prelude::*;
+use bevy_turborand::{DelegatedRng, GlobalRng};
+
+use crate::{
+    audio_fade::QuitRequested,
+    config::AccessibilitySettings,
+    flips::StyleResults,
+    game_state::GameState,
+    level::LevelSequence,
+    level_registry::LevelRegistry,
+    preload::Preloaded,
+    render_layers::{LayerDepth, RenderLayer},
+    run_mode::RunMode,
+    save::slot_selected,
+    timer::{format_time, LevelTimer, RunTimer},
+};
 
-use crate::game_state::GameState;
+const NORMAL_BUTTON: Color = Color::rgba(0.15, 0.15, 0.15, 0.8);
+const SELECTED_BUTTON: Color = Color::rgba(0.35, 0.75, 0.35, 0.9);
+/// seconds between firework bursts
+const BURST_INTERVAL: f32 = 0.7;
+const BURST_SIZE: usize = 40;
+const CONFETTI_SPEED: f32 = 220.0;
+const CONFETTI_GRAVITY: f32 = 260.0;
+const CONFETTI_SECONDS: f32 = 2.5;
+const CONFETTI_COLORS: [Color; 5] = [
+    Color::rgb(1.0, 0.35, 0.35),
+    Color::rgb(1.0, 0.85, 0.3),
+    Color::rgb(0.4, 0.9, 0.45),
+    Color::rgb(0.4, 0.7, 1.0),
+    Color::rgb(0.85, 0.5, 1.0),
+];
 
 pub struct WinScreenPlugin;
 impl Plugin for WinScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(spawn_win_screen.in_schedule(OnEnter(GameState::WinScreen)))
-            .add_system(despawn_win_screen.in_schedule(OnExit(GameState::WinScreen)));
+        app.init_resource::<WinMenu>()
+            .init_resource::<PendingRun>()
+            .add_system(spawn_win_screen.in_schedule(OnEnter(GameState::WinScreen)))
+            .add_systems(
+                (
+                    launch_fireworks,
+                    move_confetti,
+                    win_menu_input,
+                    win_button_mouse,
+                    update_win_buttons,
+                )
+                    .distributive_run_if(in_state(GameState::WinScreen)),
+            )
+            .add_system(despawn_win_screen.in_schedule(OnExit(GameState::WinScreen)))
+            .add_system(
+                start_pending_run
+                    .run_if(in_state(GameState::StartMenu))
+                    .run_if(slot_selected),
+            );
     }
 }
 
 #[derive(Component)]
 struct WinMarker;
 
-fn spawn_win_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum WinButton {
+    PlayAgain,
+    LevelSelect,
+    Quit,
+}
+
+const BUTTONS: [WinButton; 3] = [
+    WinButton::PlayAgain,
+    WinButton::LevelSelect,
+    WinButton::Quit,
+];
+
+#[derive(Resource, Default)]
+struct WinMenu {
+    selected: usize,
+    /// level picked with left/right while level select is highlighted
+    level: usize,
+    /// mode of the run that was just finished
+    mode: RunMode,
+    bursts: Timer,
+}
+
+/// A run to start as soon as the start menu is reached. Runs are always
+/// started by leaving the start menu, so the win screen goes through it
+/// instead of jumping straight to loading.
+#[derive(Resource, Default)]
+struct PendingRun(Option<(RunMode, usize)>);
+
+#[derive(Component)]
+struct Confetti {
+    velocity: Vec2,
+    spin: f32,
+    life: Timer,
+}
+
+fn spawn_win_screen(
+    mut commands: Commands,
+    preloaded: Res<Preloaded>,
+    style: Res<StyleResults>,
+    timer: Res<RunTimer>,
+    level_timer: Res<LevelTimer>,
+    registry: Res<LevelRegistry>,
+    mode: Res<RunMode>,
+    mut menu: ResMut<WinMenu>,
+) {
+    *menu = WinMenu {
+        mode: *mode,
+        bursts: Timer::from_seconds(BURST_INTERVAL, TimerMode::Repeating),
+        ..default()
+    };
+    // fire the first burst straight away
+    let interval = menu.bursts.duration();
+    menu.bursts.set_elapsed(interval);
+
     commands.spawn((
         WinMarker,
+        // over the background, which is still drawn behind the menus
+        RenderLayer::Background,
+        LayerDepth(1.0),
         SpriteBundle {
-            texture: asset_server.load("end-screen.png"),
-            transform: Transform::from_xyz(360., 360., 1.0),
+            texture: preloaded.get("end_screen"),
+            transform: Transform::from_xyz(360., 360., 0.0),
             ..default()
         },
     ));
+
+    let text = |size| TextStyle {
+        font: preloaded.get("font"),
+        font_size: size,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    commands.spawn((
+        WinMarker,
+        TextBundle::from_section(
+            format!(
+                "Time: {}   Style: {}   Minimal flips: {}/{}",
+                format_time(timer.elapsed),
+                style.total_score(),
+                style.minimal_clears(),
+                style.0.len()
+            ),
+            text(24.0),
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(20.0),
+                left: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+
+    // time each level of the run took, in the order they were played
+    let splits: Vec<_> = level_timer
+        .splits
+        .iter()
+        .map(|&(index, time)| {
+            let name = registry
+                .get(index)
+                .map_or_else(|| format!("Level {}", index + 1), |meta| meta.name.clone());
+            TextSection::new(format!("{name}  {}\n", format_time(time)), text(18.0))
+        })
+        .collect();
+    commands.spawn((
+        WinMarker,
+        TextBundle::from_sections(splits).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(20.0),
+                right: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+
+    commands
+        .spawn((
+            WinMarker,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(70.0),
+                        left: Val::Px(20.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    gap: Size::height(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for button in BUTTONS {
+                parent
+                    .spawn((
+                        button,
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect {
+                                    left: Val::Px(12.0),
+                                    right: Val::Px(12.0),
+                                    top: Val::Px(6.0),
+                                    bottom: Val::Px(6.0),
+                                },
+                                ..default()
+                            },
+                            background_color: NORMAL_BUTTON.into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section("", text(24.0)));
+                    });
+            }
+        });
 }
 
 fn despawn_win_screen(mut commands: Commands, q: Query<Entity, With<WinMarker>>) {
     for e in &q {
-        commands.entity(e).despawn();
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn launch_fireworks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut menu: ResMut<WinMenu>,
+    mut rng: ResMut<GlobalRng>,
+    settings: Res<AccessibilitySettings>,
+) {
+    if settings.reduced_motion || !menu.bursts.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let center = Vec2::new(120.0 + rng.f32() * 480.0, 380.0 + rng.f32() * 240.0);
+    let color = CONFETTI_COLORS[rng.usize(0..CONFETTI_COLORS.len())];
+    for i in 0..BURST_SIZE {
+        let angle = (i as f32 + rng.f32()) / BURST_SIZE as f32 * TAU;
+        let speed = CONFETTI_SPEED * (0.4 + rng.f32() * 0.6);
+        // mostly the burst color with a few pieces of the others mixed in
+        let color = if rng.f32() < 0.25 {
+            CONFETTI_COLORS[rng.usize(0..CONFETTI_COLORS.len())]
+        } else {
+            color
+        };
+        commands.spawn((
+            WinMarker,
+            Confetti {
+                velocity: Vec2::from_angle(angle) * speed,
+                spin: (rng.f32() * 2.0 - 1.0) * TAU,
+                life: Timer::from_seconds(CONFETTI_SECONDS, TimerMode::Once),
+            },
+            RenderLayer::Particles,
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(4.0, 7.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(center.extend(0.0)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn move_confetti(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut confetti: Query<(Entity, &mut Confetti, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut piece, mut t, mut sprite) in &mut confetti {
+        if piece.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        // air resistance so pieces drift down instead of dropping
+        piece.velocity.y -= CONFETTI_GRAVITY * dt;
+        piece.velocity *= 1.0 - 1.5 * dt;
+        t.translation += (piece.velocity * dt).extend(0.0);
+        t.rotate_z(piece.spin * dt);
+        sprite.color.set_a(piece.life.percent_left().min(0.5) * 2.0);
+    }
+}
+
+fn win_menu_input(
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    registry: Res<LevelRegistry>,
+    mut menu: ResMut<WinMenu>,
+    mut pending: ResMut<PendingRun>,
+    mut state: ResMut<NextState<GameState>>,
+    mut quit: EventWriter<QuitRequested>,
+) {
+    let pad = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+    // released rather than pressed so the same press doesn't also start the game
+    let pad_released = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_released(GamepadButton::new(gamepad, button)))
+    };
+
+    if keyboard.any_just_pressed([KeyCode::Up, KeyCode::W]) || pad(GamepadButtonType::DPadUp) {
+        menu.selected = (menu.selected + BUTTONS.len() - 1) % BUTTONS.len();
+    }
+    if keyboard.any_just_pressed([KeyCode::Down, KeyCode::S]) || pad(GamepadButtonType::DPadDown) {
+        menu.selected = (menu.selected + 1) % BUTTONS.len();
+    }
+    if BUTTONS[menu.selected] == WinButton::LevelSelect && !registry.is_empty() {
+        if keyboard.any_just_pressed([KeyCode::Left, KeyCode::A])
+            || pad(GamepadButtonType::DPadLeft)
+        {
+            menu.level = (menu.level + registry.len() - 1) % registry.len();
+        }
+        if keyboard.any_just_pressed([KeyCode::Right, KeyCode::D])
+            || pad(GamepadButtonType::DPadRight)
+        {
+            menu.level = (menu.level + 1) % registry.len();
+        }
+    }
+
+    if keyboard.any_just_released([KeyCode::Return, KeyCode::Space])
+        || pad_released(GamepadButtonType::South)
+    {
+        let button = BUTTONS[menu.selected];
+        activate(button, &menu, &mut pending, &mut state, &mut quit);
+    }
+}
+
+fn win_button_mouse(
+    interactions: Query<(&Interaction, &WinButton), Changed<Interaction>>,
+    mut menu: ResMut<WinMenu>,
+    mut pending: ResMut<PendingRun>,
+    mut state: ResMut<NextState<GameState>>,
+    mut quit: EventWriter<QuitRequested>,
+) {
+    for (interaction, button) in &interactions {
+        match interaction {
+            Interaction::Clicked => activate(*button, &menu, &mut pending, &mut state, &mut quit),
+            Interaction::Hovered => {
+                menu.selected = BUTTONS.iter().position(|b| b == button).unwrap_or(0);
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+fn activate(
+    button: WinButton,
+    menu: &WinMenu,
+    pending: &mut PendingRun,
+    state: &mut NextState<GameState>,
+    quit: &mut EventWriter<QuitRequested>,
+) {
+    match button {
+        WinButton::PlayAgain => {
+            // seeded modes are set up from the start menu, so they're replayed
+            // as a standard run
+            let mode = match menu.mode {
+                RunMode::Practice => RunMode::Practice,
+                RunMode::MinimalFlips => RunMode::MinimalFlips,
+                _ => RunMode::Standard,
+            };
+            pending.0 = Some((mode, 0));
+            state.set(GameState::StartMenu);
+        }
+        WinButton::LevelSelect => {
+            pending.0 = Some((RunMode::Practice, menu.level));
+            state.set(GameState::StartMenu);
+        }
+        WinButton::Quit => quit.send(QuitRequested),
+    }
+}
+
+fn update_win_buttons(
+    menu: Res<WinMenu>,
+    registry: Res<LevelRegistry>,
+    mut buttons: Query<(&WinButton, &mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !menu.is_changed() && !registry.is_changed() {
+        return;
+    }
+    for (button, mut color, children) in &mut buttons {
+        let selected = BUTTONS[menu.selected] == *button;
+        *color = if selected {
+            SELECTED_BUTTON
+        } else {
+            NORMAL_BUTTON
+        }
+        .into();
+
+        let label = match button {
+            WinButton::PlayAgain => "Play Again".to_string(),
+            WinButton::LevelSelect => match registry.get(menu.level) {
+                Some(meta) if selected => format!("Level Select: < {} >", meta.name),
+                Some(meta) => format!("Level Select: {}", meta.name),
+                None => "Level Select".to_string(),
+            },
+            WinButton::Quit => "Quit".to_string(),
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+/// start the run picked on the win screen, run after the start menu has reset
+/// the run mode on entering
+fn start_pending_run(
+    mut pending: ResMut<PendingRun>,
+    mut mode: ResMut<RunMode>,
+    mut sequence: ResMut<LevelSequence>,
+    registry: Res<LevelRegistry>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let Some((next_mode, first_level)) = pending.0.take() else {
+        return;
+    };
+    *mode = next_mode;
+    if first_level > 0 {
+        sequence.levels = (first_level..registry.len()).collect();
     }
+    state.set(GameState::LoadLevel);
 }
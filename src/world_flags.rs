@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::FieldValue, EntityInstance};
+use serde::{Deserialize, Serialize};
+
+use crate::{level_registry::field, save::SaveData};
+
+/// Named flags and counters that carry over between levels, like a secret
+/// found in one level opening a door in another. Kept in the save.
+pub struct WorldFlagsPlugin;
+impl Plugin for WorldFlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldFlags>()
+            .add_system(sync_world_flags)
+            .add_system(check_spawn_conditions);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct WorldFlags {
+    flags: HashSet<String>,
+    counters: HashMap<String, i32>,
+}
+
+impl WorldFlags {
+    pub fn set(&mut self, flag: impl Into<String>) {
+        self.flags.insert(flag.into());
+    }
+
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    /// value of `counter`, 0 if it's never been added to
+    pub fn count(&self, counter: &str) -> i32 {
+        self.counters.get(counter).copied().unwrap_or_default()
+    }
+
+    /// Whether `condition` holds. Conditions are a flag name, `!flag` for a
+    /// flag that isn't set, or `counter>=n`.
+    pub fn check(&self, condition: &str) -> bool {
+        let condition = condition.trim();
+        if let Some((counter, n)) = condition.split_once(">=") {
+            return match n.trim().parse() {
+                Ok(n) => self.count(counter.trim()) >= n,
+                Err(_) => {
+                    warn!("world flag condition {condition:?} needs a whole number");
+                    false
+                }
+            };
+        }
+        match condition.strip_prefix('!') {
+            Some(flag) => !self.is_set(flag.trim()),
+            None => self.is_set(condition),
+        }
+    }
+}
+
+/// The flags live in the save so they're loaded and written with the rest of
+/// the slot. Changes made to either are copied to the other.
+fn sync_world_flags(mut flags: ResMut<WorldFlags>, mut save: ResMut<SaveData>) {
+    if flags.is_changed() && save.world_flags != *flags {
+        save.world_flags = flags.clone();
+    } else if save.is_changed() && save.world_flags != *flags {
+        *flags = save.world_flags.clone();
+    }
+}
+
+/// ldtk entities with a `Requires_Flag` field are only kept if it holds
+fn check_spawn_conditions(
+    mut commands: Commands,
+    flags: Res<WorldFlags>,
+    spawned: Query<(Entity, &EntityInstance), Added<EntityInstance>>,
+) {
+    for (e, instance) in &spawned {
+        let Some(FieldValue::String(Some(condition))) =
+            field(&instance.field_instances, "Requires_Flag")
+        else {
+            continue;
+        };
+        if !flags.check(condition) {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// the flag an entity sets when the player collects it, from its `Sets_Flag` field
+pub fn flag_set_by(instance: &EntityInstance) -> Option<&str> {
+    match field(&instance.field_instances, "Sets_Flag") {
+        Some(FieldValue::String(Some(flag))) => Some(flag),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorldFlags;
+
+    #[test]
+    fn checks_flags() {
+        let mut flags = WorldFlags::default();
+        assert!(!flags.check("found_secret"));
+        assert!(flags.check("!found_secret"));
+
+        flags.set("found_secret");
+        assert!(flags.check("found_secret"));
+        assert!(!flags.check(" ! found_secret "));
+    }
+
+    #[test]
+    fn checks_counters() {
+        let mut flags = WorldFlags::default();
+        flags.counters.insert("keys".to_string(), 2);
+        assert!(flags.check("keys >= 2"));
+        assert!(!flags.check("keys>=3"));
+        assert!(flags.check("doors>=0"));
+        assert!(!flags.check("keys>=lots"));
+    }
+}
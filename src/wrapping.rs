@@ -0,0 +1,241 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::{
+    bounds::{update_level_bounds, DespawnOutOfBounds, LevelBounds},
+    collisions::PositionDelta,
+    game_state::GameState,
+    ground::MovingPlatform,
+    level_registry::{LevelRegistry, LevelWrap},
+    physics::{apply_velocity, PhysicsSet, Velocity},
+};
+
+/// copies drawn on the far side of each wrapped axis, and one for the corner
+const GHOST_OFFSETS: [IVec2; 3] = [IVec2::new(1, 0), IVec2::new(0, 1), IVec2::new(1, 1)];
+
+/// Levels with `Wrap_Horizontal` or `Wrap_Vertical` set send anything moving
+/// out of one edge back in through the opposite one.
+pub struct WrapPlugin;
+impl Plugin for WrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveWrap>()
+            .add_system(
+                set_active_wrap
+                    .after(update_level_bounds)
+                    .in_schedule(OnEnter(GameState::SpawnLevel)),
+            )
+            .add_system(
+                wrap_movers
+                    .in_set(PhysicsSet)
+                    .after(apply_velocity)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems((mark_wrapping, spawn_ghosts).chain())
+            .add_system(
+                update_ghosts
+                    .in_base_set(CoreSet::PostUpdate)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// how the current level wraps, and its size in level space
+#[derive(Resource, Default)]
+pub struct ActiveWrap {
+    pub wrap: LevelWrap,
+    pub size: Vec2,
+}
+
+impl ActiveWrap {
+    /// Like `LevelBounds::contains`, but a wrapped axis has no edge to fall
+    /// out of so it always counts as inside.
+    pub fn contains(&self, bounds: &LevelBounds, point: Vec2, margin: f32) -> bool {
+        let inside = point.cmpge(bounds.min - margin) & point.cmple(bounds.max + margin);
+        (self.wrap.horizontal || inside.x) && (self.wrap.vertical || inside.y)
+    }
+
+    /// Moves `position` back into the level along the wrapped axes. Level space
+    /// starts at zero in the bottom left corner.
+    fn wrapped(&self, position: Vec2) -> Vec2 {
+        let mut position = position;
+        if self.wrap.horizontal && self.size.x > 0.0 {
+            position.x = position.x.rem_euclid(self.size.x);
+        }
+        if self.wrap.vertical && self.size.y > 0.0 {
+            position.y = position.y.rem_euclid(self.size.y);
+        }
+        position
+    }
+}
+
+/// Moves across the edges of a wrapping level. Added to anything with a
+/// velocity, except moving platforms that follow a path and things that are
+/// meant to be despawned once they leave.
+#[derive(Component)]
+pub struct Wraps;
+
+/// drawn as `source` shifted one level size along `offset` so it's seen
+/// coming in on the other side before it wraps
+#[derive(Component)]
+struct WrapGhost {
+    source: Entity,
+    offset: IVec2,
+}
+
+fn set_active_wrap(
+    level: Res<LevelSelection>,
+    registry: Res<LevelRegistry>,
+    bounds: Res<LevelBounds>,
+    mut active: ResMut<ActiveWrap>,
+) {
+    let wrap = match *level {
+        LevelSelection::Index(index) => registry.get(index).map(|meta| meta.wrap),
+        _ => None,
+    };
+    *active = ActiveWrap {
+        wrap: wrap.unwrap_or_default(),
+        size: bounds.max - bounds.min,
+    };
+}
+
+fn mark_wrapping(
+    mut commands: Commands,
+    movers: Query<
+        Entity,
+        (
+            Added<Velocity>,
+            Without<MovingPlatform>,
+            Without<DespawnOutOfBounds>,
+        ),
+    >,
+) {
+    for e in &movers {
+        commands.entity(e).insert(Wraps);
+    }
+}
+
+fn wrap_movers(
+    active: Res<ActiveWrap>,
+    mut movers: Query<(&mut Transform, Option<&mut PositionDelta>), With<Wraps>>,
+) {
+    if active.wrap == LevelWrap::default() {
+        return;
+    }
+    for (mut t, delta) in &mut movers {
+        let position = t.translation.truncate();
+        let shift = active.wrapped(position) - position;
+        if shift == Vec2::ZERO {
+            continue;
+        }
+        t.translation += shift.extend(0.0);
+        // keep this tick's sweep on the side it ended up on
+        if let Some(mut delta) = delta {
+            delta.origin += shift;
+        }
+    }
+}
+
+fn spawn_ghosts(
+    mut commands: Commands,
+    sources: Query<(Entity, &Parent, &Handle<Image>), (Added<Wraps>, With<Sprite>)>,
+) {
+    for (source, parent, texture) in &sources {
+        for offset in GHOST_OFFSETS {
+            let ghost = commands
+                .spawn((
+                    WrapGhost { source, offset },
+                    SpriteBundle {
+                        texture: texture.clone(),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ))
+                .id();
+            // next to the source so they go away with the level
+            commands.entity(parent.get()).add_child(ghost);
+        }
+    }
+}
+
+/// Shows a ghost while its source is close enough to an edge for its copy on
+/// the other side to be on screen.
+fn update_ghosts(
+    mut commands: Commands,
+    active: Res<ActiveWrap>,
+    sources: Query<(&Transform, &Sprite, &Visibility), (With<Wraps>, Without<WrapGhost>)>,
+    mut ghosts: Query<(
+        Entity,
+        &WrapGhost,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    for (e, ghost, mut t, mut sprite, mut visibility) in &mut ghosts {
+        let Ok((source_t, source_sprite, source_visibility)) = sources.get(ghost.source) else {
+            commands.entity(e).despawn_recursive();
+            continue;
+        };
+        let position = source_t.translation.truncate();
+        // half the sprite's size, so it's shown as soon as any of it would cross
+        let reach = source_sprite.custom_size.unwrap_or(Vec2::splat(32.0)) / 2.0;
+        let side = |wraps: bool, axis: f32, size: f32, reach: f32| -> Option<f32> {
+            if !wraps {
+                None
+            } else if axis < reach {
+                Some(size)
+            } else if axis > size - reach {
+                Some(-size)
+            } else {
+                None
+            }
+        };
+        let x = side(active.wrap.horizontal, position.x, active.size.x, reach.x);
+        let y = side(active.wrap.vertical, position.y, active.size.y, reach.y);
+        let shift = match (ghost.offset.x != 0, ghost.offset.y != 0, x, y) {
+            (true, false, Some(x), _) => Some(Vec2::new(x, 0.0)),
+            (false, true, _, Some(y)) => Some(Vec2::new(0.0, y)),
+            (true, true, Some(x), Some(y)) => Some(Vec2::new(x, y)),
+            _ => None,
+        };
+
+        let Some(shift) = shift else {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+            continue;
+        };
+        *visibility = *source_visibility;
+        *t = *source_t;
+        t.translation += shift.extend(0.0);
+        *sprite = source_sprite.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_wrapped_axes_come_back_in() {
+        let active = ActiveWrap {
+            wrap: LevelWrap {
+                horizontal: true,
+                vertical: false,
+            },
+            size: Vec2::new(720.0, 720.0),
+        };
+        assert_eq!(
+            active.wrapped(Vec2::new(-10.0, -10.0)),
+            Vec2::new(710.0, -10.0)
+        );
+        assert_eq!(
+            active.wrapped(Vec2::new(730.0, 100.0)),
+            Vec2::new(10.0, 100.0)
+        );
+
+        let bounds = LevelBounds::default();
+        assert!(active.contains(&bounds, Vec2::new(-500.0, 100.0), 0.0));
+        assert!(!active.contains(&bounds, Vec2::new(100.0, -500.0), 0.0));
+    }
+}